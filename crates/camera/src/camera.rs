@@ -418,7 +418,7 @@ fn move_horizontaly(
     horizontal_movement: Res<HorizontalMovement>,
     focus: Res<CameraFocus>,
     map_bounds: Res<MapBounds>,
-    time: Res<Time>,
+    time: Res<Time<Real>>,
     mut camera_query: Query<&mut Transform, With<Camera3d>>,
     mut event: EventWriter<FocusInvalidatedEvent>,
 ) {
@@ -448,7 +448,7 @@ fn move_horizontaly(
 fn zoom(
     conf: Res<Configuration>,
     desired_distance: Res<DesiredDistance>,
-    time: Res<Time>,
+    time: Res<Time<Real>>,
     mut focus: ResMut<CameraFocus>,
     mut camera_query: Query<&mut Transform, With<Camera3d>>,
 ) {
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use de_core::{gconfig::GameConfig, objects::Local, state::AppState};
 use de_messages::ToPlayers;
-use de_multiplayer::{NetEntities, NetRecvHealthEvent, ToPlayersEvent};
+use de_multiplayer::{NetEntities, NetRecvHealthEvent, NetRecvSnapshotEvent, ToPlayersEvent};
 use de_objects::Health;
 use de_signs::UpdateBarValueEvent;
 use de_spawner::{DespawnActiveLocalEvent, DespawnerSet};
@@ -22,6 +22,9 @@ impl Plugin for HealthPlugin {
                         update_remote_health
                             .run_if(on_event::<NetRecvHealthEvent>())
                             .before(update_health),
+                        reconcile_health
+                            .run_if(on_event::<NetRecvSnapshotEvent>())
+                            .before(update_health),
                         update_health.run_if(on_event::<UpdateHealthEvent>()),
                     )
                         .in_set(HealthSet::Update),
@@ -85,10 +88,12 @@ fn update_local_health(
         out_events.send(UpdateHealthEvent::new(event.entity, event.delta));
 
         if config.multiplayer() {
-            net_events.send(ToPlayersEvent::new(ToPlayers::ChangeHealth {
-                entity: net_entities.net_id(event.entity),
-                delta: event.delta.try_into().unwrap(),
-            }));
+            if let Some(entity) = net_entities.net_id(event.entity) {
+                net_events.send(ToPlayersEvent::new(ToPlayers::ChangeHealth {
+                    entity,
+                    delta: event.delta.try_into().unwrap(),
+                }));
+            }
         }
     }
 }
@@ -102,6 +107,36 @@ fn update_remote_health(
     }
 }
 
+/// Catches up health of replicas with health of the simulated entities.
+///
+/// Only health which did not change since the previous snapshot is
+/// reconciled. Health changes caused by different players might still be on
+/// their way otherwise.
+fn reconcile_health(
+    replicas: Query<&Health, Without<Local>>,
+    mut in_events: EventReader<NetRecvSnapshotEvent>,
+    mut out_events: EventWriter<UpdateHealthEvent>,
+) {
+    for event in in_events.read() {
+        for snapshot in event.entities() {
+            let Ok(health) = replicas.get(snapshot.local()) else {
+                continue;
+            };
+
+            let target = snapshot.state().health();
+            let stable = snapshot
+                .previous()
+                .is_some_and(|previous| previous.health() == target);
+            if stable && health.current() != target {
+                out_events.send(UpdateHealthEvent::new(
+                    snapshot.local(),
+                    target - health.current(),
+                ));
+            }
+        }
+    }
+}
+
 fn update_health(
     mut healths: Query<&mut Health>,
     mut health_events: EventReader<UpdateHealthEvent>,
@@ -2,7 +2,10 @@
 //! This module implements final (i.e. parsed and validated) game configuration
 //! objects and their building from persistent configuration.
 
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
 
 use anyhow::{ensure, Context, Error, Result};
 use async_std::path::Path;
@@ -22,6 +25,7 @@ pub struct MultiplayerConf {
     #[ensure(lobby.scheme() == "http", "Only `http` scheme is allowed for `lobby`.")]
     lobby: Url,
     connector: SocketAddr,
+    impairment: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Config, Debug, Clone)]
@@ -66,6 +70,27 @@ pub struct AudioConf {
     #[ensure(*music_volume <= 1., "`music_volume` must be smaller or equal to 1.0.")]
     music_volume: f32,
 }
+
+#[derive(Deserialize, Serialize, Config, Debug, Clone)]
+pub struct Replication {
+    #[is_finite]
+    #[ensure(*interpolation_delay >= 0., "`interpolation_delay` must be greater than or equal to 0.0.")]
+    #[ensure(*interpolation_delay <= 2., "`interpolation_delay` must be smaller or equal to 2.0.")]
+    interpolation_delay: f32,
+
+    #[is_finite]
+    #[ensure(*max_extrapolation >= 0., "`max_extrapolation` must be greater than or equal to 0.0.")]
+    #[ensure(*max_extrapolation <= 2., "`max_extrapolation` must be smaller or equal to 2.0.")]
+    max_extrapolation: f32,
+
+    #[is_finite]
+    #[ensure(*correction_rate > 0., "`correction_rate` must be positive.")]
+    correction_rate: f32,
+
+    #[is_finite]
+    #[ensure(*snap_distance > 0., "`snap_distance` must be positive.")]
+    snap_distance: f32,
+}
 // --------------------
 
 // ---- default implementations ----
@@ -75,6 +100,7 @@ impl Default for MultiplayerConf {
         Self {
             lobby: Url::parse("http://lobby.de-game.org:8080").unwrap(),
             connector: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(34, 159, 189, 173)), 8082),
+            impairment: None,
         }
     }
 }
@@ -103,6 +129,17 @@ impl Default for AudioConf {
     }
 }
 
+impl Default for Replication {
+    fn default() -> Self {
+        Self {
+            interpolation_delay: 0.6,
+            max_extrapolation: 0.75,
+            correction_rate: 4.,
+            snap_distance: 8.,
+        }
+    }
+}
+
 // --------------------
 
 // for this more complicated data structure, we need to
@@ -125,6 +162,19 @@ impl TryInto<CameraConf> for Camera {
     }
 }
 
+impl TryInto<ReplicationConf> for Replication {
+    type Error = Error;
+
+    fn try_into(self) -> Result<ReplicationConf> {
+        Ok(ReplicationConf {
+            interpolation_delay: Duration::from_secs_f32(self.interpolation_delay),
+            max_extrapolation: Duration::from_secs_f32(self.max_extrapolation),
+            correction_rate: self.correction_rate,
+            snap_distance: Metre::new(self.snap_distance),
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CameraConf {
     move_margin: LogicalPixel,
@@ -136,6 +186,14 @@ pub struct CameraConf {
     scroll_inverted: bool,
 }
 
+#[derive(Debug, Clone)]
+pub struct ReplicationConf {
+    interpolation_delay: Duration,
+    max_extrapolation: Duration,
+    correction_rate: f32,
+    snap_distance: Metre,
+}
+
 // ---- config impls ----
 
 impl CameraConf {
@@ -189,6 +247,38 @@ impl MultiplayerConf {
     pub fn connector(&self) -> SocketAddr {
         self.connector
     }
+
+    /// Specification of simulated degradation of network conditions of
+    /// multiplayer games. This is intended for testing only.
+    pub fn impairment(&self) -> Option<&str> {
+        self.impairment.as_deref()
+    }
+}
+
+impl ReplicationConf {
+    /// Remotely simulated objects are shown this much in the past so that
+    /// their state might be interpolated between received updates.
+    pub fn interpolation_delay(&self) -> Duration {
+        self.interpolation_delay
+    }
+
+    /// Maximum time by which the state of a remotely simulated object is
+    /// extrapolated beyond the latest received update.
+    pub fn max_extrapolation(&self) -> Duration {
+        self.max_extrapolation
+    }
+
+    /// Fraction of the error between a replica and its estimated state
+    /// corrected per second.
+    pub fn correction_rate(&self) -> f32 {
+        self.correction_rate
+    }
+
+    /// Replicas farther than this from their estimated state are moved
+    /// instantaneously instead of smooth correction.
+    pub fn snap_distance(&self) -> Metre {
+        self.snap_distance
+    }
 }
 
 impl AudioConf {
@@ -224,5 +314,6 @@ impl AudioConf {
 bundle_config!(
     camera: CameraConf: Camera, // Conf file -> Camera -> CameraConf
     multiplayer: MultiplayerConf: MultiplayerConf,  // Conf file -> MultiplayerConf
-    audio: AudioConf: AudioConf
+    audio: AudioConf: AudioConf,
+    replication: ReplicationConf: Replication
 );
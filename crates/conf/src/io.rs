@@ -24,7 +24,10 @@ pub(crate) async fn load_conf_text(path: &Path) -> Result<Option<String>> {
 
 #[cfg(test)]
 mod tests {
-    use std::net::{IpAddr, Ipv6Addr};
+    use std::{
+        net::{IpAddr, Ipv6Addr},
+        time::Duration,
+    };
 
     use async_std::{path::PathBuf, task};
     use de_uom::Metre;
@@ -53,5 +56,14 @@ mod tests {
         assert_eq!(conf.multiplayer().connector().port(), 8083);
         assert_eq!(conf.camera().min_distance(), Metre::new(12.5));
         assert_eq!(conf.camera().max_distance(), Metre::new(250.));
+        assert_eq!(
+            conf.replication().interpolation_delay(),
+            Duration::from_millis(250)
+        );
+        assert_eq!(
+            conf.replication().max_extrapolation(),
+            Duration::from_millis(750)
+        );
+        assert_eq!(conf.replication().snap_distance(), Metre::new(12.));
     }
 }
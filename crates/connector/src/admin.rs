@@ -0,0 +1,425 @@
+use std::{fmt::Write as _, net::SocketAddr, time::Duration};
+
+use async_std::{
+    future::timeout,
+    io::{self, prelude::*},
+    net::{TcpListener, TcpStream},
+    task,
+};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+    game::{GameMetrics, PlayerMetrics},
+    games::Games,
+    shutdown::{Shutdown, SHUTDOWN_CHECK_INTERVAL},
+};
+
+/// Maximum size of a request head (request line and headers).
+const MAX_HEAD_SIZE: usize = 8 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Local HTTP server exposing live metrics of all running games.
+///
+/// * `GET /games` – JSON encoded list of the games.
+/// * `GET /metrics` – the metrics in Prometheus text exposition format.
+pub(crate) struct AdminServer {
+    listener: TcpListener,
+    games: Games,
+    shutdown: Shutdown,
+}
+
+impl AdminServer {
+    pub(crate) async fn bind(
+        addr: SocketAddr,
+        games: Games,
+        shutdown: Shutdown,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        Ok(Self {
+            listener,
+            games,
+            shutdown,
+        })
+    }
+
+    pub(crate) async fn run(self) {
+        while !self.shutdown.is_triggered() {
+            let Ok(result) = timeout(SHUTDOWN_CHECK_INTERVAL, self.listener.accept()).await else {
+                continue;
+            };
+            let stream = match result {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    warn!("Failed to accept admin connection: {err:?}");
+                    continue;
+                }
+            };
+
+            let games = self.games.clone();
+            task::spawn(async move {
+                if let Err(err) = handle(stream, games).await {
+                    warn!("Admin request failed: {err:?}");
+                }
+            });
+        }
+    }
+}
+
+async fn handle(mut stream: TcpStream, games: Games) -> io::Result<()> {
+    let head = match timeout(REQUEST_TIMEOUT, read_head(&mut stream)).await {
+        Ok(head) => head?,
+        Err(_) => return Ok(()),
+    };
+
+    let response = match parse_request(&head) {
+        Some(("GET", "/games")) => Response::ok(
+            "application/json",
+            serde_json::to_string(&GamesResponse {
+                games: games.metrics().await,
+            })
+            .unwrap(),
+        ),
+        Some(("GET", "/metrics")) => Response::ok(
+            "text/plain; version=0.0.4",
+            prometheus(&games.metrics().await),
+        ),
+        Some(("GET", _)) => Response::error("404 Not Found"),
+        Some(_) => Response::error("405 Method Not Allowed"),
+        None => Response::error("400 Bad Request"),
+    };
+
+    info!(
+        "Admin request {:?}: {}",
+        head.lines().next(),
+        response.status
+    );
+    stream.write_all(response.to_string().as_bytes()).await?;
+    stream.flush().await
+}
+
+/// Reads the request until the end of its head. The request body, if any, is
+/// ignored.
+async fn read_head(stream: &mut TcpStream) -> io::Result<String> {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+
+    while !head.ends_with(b"\r\n\r\n") && !head.ends_with(b"\n\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+        if head.len() > MAX_HEAD_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request head is too large",
+            ));
+        }
+    }
+
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Returns method and path (without query) of a HTTP request.
+fn parse_request(head: &str) -> Option<(&str, &str)> {
+    let mut parts = head.lines().next()?.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+
+    let path = target.split_once('?').map_or(target, |(path, _)| path);
+    Some((method, path))
+}
+
+#[derive(Serialize)]
+struct GamesResponse {
+    games: Vec<GameMetrics>,
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: &'static str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain",
+            body: format!("{status}\n"),
+        }
+    }
+}
+
+impl std::fmt::Display for Response {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.content_type,
+            self.body.len(),
+            self.body
+        )
+    }
+}
+
+/// Name, type, help and value getter of a per game metric.
+type GameMetric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&GameMetrics) -> f64,
+);
+
+/// Renders the metrics in Prometheus text exposition format.
+fn prometheus(games: &[GameMetrics]) -> String {
+    let mut text = String::new();
+
+    metric(&mut text, "games", "gauge", "Number of running games.");
+    writeln!(text, "de_connector_games {}", games.len()).unwrap();
+
+    let game_metrics: [GameMetric; 12] = [
+        (
+            "game_max_players",
+            "gauge",
+            "Maximum number of players of a game.",
+            |game| game.max_players as f64,
+        ),
+        (
+            "game_locked",
+            "gauge",
+            "1 if a game is locked by its host, 0 otherwise.",
+            |game| if game.locked { 1. } else { 0. },
+        ),
+        (
+            "game_players",
+            "gauge",
+            "Number of players connected to a game.",
+            |game| game.players.len() as f64,
+        ),
+        (
+            "game_disconnected_players",
+            "gauge",
+            "Number of players who lost connection and might rejoin a game.",
+            |game| game.disconnected.len() as f64,
+        ),
+        (
+            "game_spectators",
+            "gauge",
+            "Number of spectators connected to a game.",
+            |game| game.spectators as f64,
+        ),
+        (
+            "game_received_packages_total",
+            "counter",
+            "Number of packages received from the clients of a game.",
+            |game| game.packages_received as f64,
+        ),
+        (
+            "game_relayed_packages_total",
+            "counter",
+            "Number of packages with player messages sent to the clients of a game.",
+            |game| game.packages_relayed as f64,
+        ),
+        (
+            "game_rejected_messages_total",
+            "counter",
+            "Number of dropped invalid player messages received from the clients of a game.",
+            |game| game.messages_rejected as f64,
+        ),
+        (
+            "game_received_bytes_total",
+            "counter",
+            "Payload bytes received from the clients of a game.",
+            |game| game.bytes_received as f64,
+        ),
+        (
+            "game_sent_bytes_total",
+            "counter",
+            "Payload bytes sent to the clients of a game.",
+            |game| game.bytes_sent as f64,
+        ),
+        (
+            "game_resends_total",
+            "counter",
+            "Number of re-sent reliable packages to the players of a game.",
+            |game| game.resends as f64,
+        ),
+        (
+            "game_connection_errors_total",
+            "counter",
+            "Number of lost client connections of a game.",
+            |game| game.connection_errors as f64,
+        ),
+    ];
+
+    for (name, kind, help, value) in game_metrics {
+        metric(&mut text, name, kind, help);
+        for game in games {
+            writeln!(
+                text,
+                "de_connector_{name}{{port=\"{}\"}} {}",
+                game.port,
+                value(game)
+            )
+            .unwrap();
+        }
+    }
+
+    metric(
+        &mut text,
+        "game_readiness",
+        "gauge",
+        "Readiness stage of a game, always 1.",
+    );
+    for game in games {
+        writeln!(
+            text,
+            "de_connector_game_readiness{{port=\"{}\",readiness=\"{}\"}} 1",
+            game.port, game.readiness
+        )
+        .unwrap();
+    }
+
+    metric(
+        &mut text,
+        "player_rtt_seconds",
+        "gauge",
+        "Smoothed round-trip time of a player connection.",
+    );
+    for game in games {
+        for player in &game.players {
+            if let Some(rtt) = player.rtt_ms {
+                writeln!(
+                    text,
+                    "de_connector_player_rtt_seconds{{{}}} {}",
+                    player_labels(game, player),
+                    rtt / 1000.
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    metric(
+        &mut text,
+        "player_loss",
+        "gauge",
+        "Estimated fraction of lost reliable packages sent to a player.",
+    );
+    metric(
+        &mut text,
+        "player_resends_total",
+        "counter",
+        "Number of re-sent reliable packages to a player.",
+    );
+    for game in games {
+        for player in &game.players {
+            let labels = player_labels(game, player);
+            writeln!(text, "de_connector_player_loss{{{labels}}} {}", player.loss).unwrap();
+            writeln!(
+                text,
+                "de_connector_player_resends_total{{{labels}}} {}",
+                player.resends
+            )
+            .unwrap();
+        }
+    }
+
+    text
+}
+
+fn player_labels(game: &GameMetrics, player: &PlayerMetrics) -> String {
+    format!("port=\"{}\",player=\"{}\"", game.port, player.player)
+}
+
+fn metric(text: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(
+        text,
+        "# HELP de_connector_{name} {help}\n# TYPE de_connector_{name} {kind}"
+    )
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!(
+            parse_request("GET /games?pretty HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+            Some(("GET", "/games"))
+        );
+        assert_eq!(
+            parse_request("POST /metrics HTTP/1.0\r\n\r\n"),
+            Some(("POST", "/metrics"))
+        );
+        assert_eq!(parse_request("GET /games\r\n\r\n"), None);
+        assert_eq!(parse_request("GET /games FTP\r\n\r\n"), None);
+        assert_eq!(parse_request(""), None);
+    }
+
+    #[test]
+    fn test_prometheus() {
+        let text = prometheus(&[GameMetrics {
+            port: 1234,
+            max_players: 4,
+            readiness: "NotReady".to_owned(),
+            locked: true,
+            players: vec![PlayerMetrics {
+                player: 1,
+                addr: "127.0.0.1:2000".parse().unwrap(),
+                readiness: "Ready".to_owned(),
+                rtt_ms: Some(25.),
+                loss: 0.5,
+                resends: 3,
+            }],
+            disconnected: vec![2],
+            spectators: 0,
+            packages_received: 10,
+            packages_relayed: 20,
+            messages_rejected: 2,
+            bytes_received: 100,
+            bytes_sent: 200,
+            resends: 3,
+            connection_errors: 1,
+        }]);
+
+        for line in [
+            "# TYPE de_connector_games gauge",
+            "de_connector_games 1",
+            "de_connector_game_locked{port=\"1234\"} 1",
+            "de_connector_game_players{port=\"1234\"} 1",
+            "de_connector_game_disconnected_players{port=\"1234\"} 1",
+            "# TYPE de_connector_game_relayed_packages_total counter",
+            "de_connector_game_relayed_packages_total{port=\"1234\"} 20",
+            "de_connector_game_rejected_messages_total{port=\"1234\"} 2",
+            "de_connector_game_connection_errors_total{port=\"1234\"} 1",
+            "de_connector_game_readiness{port=\"1234\",readiness=\"NotReady\"} 1",
+            "de_connector_player_rtt_seconds{port=\"1234\",player=\"1\"} 0.025",
+            "de_connector_player_loss{port=\"1234\",player=\"1\"} 0.5",
+            "# TYPE de_connector_player_resends_total counter",
+            "de_connector_player_resends_total{port=\"1234\",player=\"1\"} 3",
+            "de_connector_game_resends_total{port=\"1234\"} 3",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "missing `{line}` in:\n{text}"
+            );
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::{env, net::SocketAddr, path::PathBuf, time::Duration};
+
+use anyhow::Context;
+use de_net::Impairment;
+
+/// Port of the main server of a stand-alone connector.
+const PORT: u16 = 8082;
+const REPLAY_DIR_VAR_NAME: &str = "DE_REPLAY_DIR";
+const GAME_KEY_VAR_NAME: &str = "DE_GAME_KEY";
+const IMPAIRMENT_VAR_NAME: &str = "DE_NET_IMPAIRMENT";
+const ADMIN_ADDR_VAR_NAME: &str = "DE_ADMIN_ADDR";
+const CLIENT_TIMEOUT_VAR_NAME: &str = "DE_CLIENT_TIMEOUT";
+const LAN_ANNOUNCE_VAR_NAME: &str = "DE_LAN_ANNOUNCE";
+/// Default number of seconds after which a silent client is considered dead.
+const DEFAULT_CLIENT_TIMEOUT: u64 = 30;
+
+/// Configuration of a DE Connector.
+#[derive(Clone, Debug)]
+pub struct ConnectorConf {
+    port: u16,
+    replay_dir: Option<PathBuf>,
+    game_key: Option<String>,
+    impairment: Option<Impairment>,
+    admin_addr: Option<SocketAddr>,
+    lan_announce: Option<SocketAddr>,
+    client_timeout: Duration,
+}
+
+impl ConnectorConf {
+    /// Returns a configuration with all optional features (replays, game
+    /// authentication, network impairment, admin endpoint and LAN
+    /// announcements) disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `port` - port of the main server.
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            replay_dir: None,
+            game_key: None,
+            impairment: None,
+            admin_addr: None,
+            lan_announce: None,
+            client_timeout: Duration::from_secs(DEFAULT_CLIENT_TIMEOUT),
+        }
+    }
+
+    /// Returns the configuration of a stand-alone connector, optional
+    /// features are configured with environment variables.
+    pub(crate) fn from_env() -> anyhow::Result<Self> {
+        let mut conf = Self::new(PORT);
+
+        if let Ok(value) = env::var(IMPAIRMENT_VAR_NAME) {
+            conf.impairment = Some(
+                value
+                    .parse::<Impairment>()
+                    .with_context(|| format!("Invalid {IMPAIRMENT_VAR_NAME}"))?,
+            );
+        }
+
+        conf.replay_dir = env::var_os(REPLAY_DIR_VAR_NAME).map(PathBuf::from);
+        conf.game_key = env::var(GAME_KEY_VAR_NAME).ok();
+
+        if let Ok(value) = env::var(CLIENT_TIMEOUT_VAR_NAME) {
+            let client_timeout = value
+                .parse::<u64>()
+                .with_context(|| format!("Invalid {CLIENT_TIMEOUT_VAR_NAME}"))?;
+            if client_timeout == 0 {
+                anyhow::bail!("{CLIENT_TIMEOUT_VAR_NAME} must be positive");
+            }
+            conf.client_timeout = Duration::from_secs(client_timeout);
+        }
+
+        if let Ok(addr) = env::var(ADMIN_ADDR_VAR_NAME) {
+            conf.admin_addr = Some(
+                addr.parse()
+                    .with_context(|| format!("Invalid {ADMIN_ADDR_VAR_NAME}"))?,
+            );
+        }
+
+        if let Ok(addr) = env::var(LAN_ANNOUNCE_VAR_NAME) {
+            conf.lan_announce = Some(
+                addr.parse()
+                    .with_context(|| format!("Invalid {LAN_ANNOUNCE_VAR_NAME}"))?,
+            );
+        }
+
+        Ok(conf)
+    }
+
+    /// Announce the connector and its open games to the local network.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - address the announcements are sent to. It is usually a
+    ///   broadcast address.
+    pub fn with_lan_announce(mut self, target: SocketAddr) -> Self {
+        self.lan_announce = Some(target);
+        self
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub(crate) fn replay_dir(&self) -> Option<&PathBuf> {
+        self.replay_dir.as_ref()
+    }
+
+    pub(crate) fn game_key(&self) -> Option<&str> {
+        self.game_key.as_deref()
+    }
+
+    pub(crate) fn impairment(&self) -> Option<&Impairment> {
+        self.impairment.as_ref()
+    }
+
+    pub(crate) fn admin_addr(&self) -> Option<SocketAddr> {
+        self.admin_addr
+    }
+
+    pub(crate) fn lan_announce(&self) -> Option<SocketAddr> {
+        self.lan_announce
+    }
+
+    pub(crate) fn client_timeout(&self) -> Duration {
+        self.client_timeout
+    }
+}
@@ -0,0 +1,79 @@
+use std::{io, net::SocketAddr, time::Duration};
+
+use async_std::{net::UdpSocket, task};
+use de_messages::LanAnnouncement;
+use tracing::{info, warn};
+
+use crate::{games::Games, shutdown::Shutdown};
+
+/// Open games are announced this often.
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+/// Maximum number of games in a single announcement. This keeps the
+/// announcement within a single datagram.
+const MAX_ANNOUNCED_GAMES: usize = 16;
+
+/// Periodically announces the connector together with its open games to the
+/// local network, see [`LanAnnouncement`].
+pub(crate) struct LanAnnouncer {
+    socket: UdpSocket,
+    target: SocketAddr,
+    port: u16,
+    games: Games,
+    shutdown: Shutdown,
+}
+
+impl LanAnnouncer {
+    /// # Arguments
+    ///
+    /// * `target` - address the announcements are sent to. It is usually a
+    ///   broadcast address.
+    ///
+    /// * `port` - port of the main server.
+    ///
+    /// * `games` - registry of the running games.
+    ///
+    /// * `shutdown` - the announcements stop once this signal is triggered.
+    pub(crate) async fn bind(
+        target: SocketAddr,
+        port: u16,
+        games: Games,
+        shutdown: Shutdown,
+    ) -> io::Result<Self> {
+        let bind_addr: SocketAddr = if target.is_ipv4() {
+            "0.0.0.0:0".parse().unwrap()
+        } else {
+            "[::]:0".parse().unwrap()
+        };
+        let socket = UdpSocket::bind(bind_addr).await?;
+        socket.set_broadcast(true)?;
+
+        Ok(Self {
+            socket,
+            target,
+            port,
+            games,
+            shutdown,
+        })
+    }
+
+    pub(crate) async fn run(self) {
+        info!("Announcing open games to {}", self.target);
+
+        while !self.shutdown.is_triggered() {
+            let announcement =
+                LanAnnouncement::new(self.port, self.games.lan_games(MAX_ANNOUNCED_GAMES).await);
+            match announcement.encode() {
+                Ok(data) => {
+                    if let Err(err) = self.socket.send_to(&data, self.target).await {
+                        warn!("Failed to send LAN announcement: {err:?}");
+                    }
+                }
+                Err(err) => warn!("Failed to encode LAN announcement: {err:?}"),
+            }
+
+            task::sleep(ANNOUNCE_INTERVAL).await;
+        }
+
+        info!("Stopped announcing open games to {}", self.target);
+    }
+}
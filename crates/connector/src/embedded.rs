@@ -0,0 +1,42 @@
+use async_std::{channel::Sender, task};
+use tracing::{error, info};
+
+use crate::{bind, conf::ConnectorConf, shutdown::Shutdown};
+
+/// DE Connector running within the current process, for example within the
+/// game client.
+///
+/// The connector stops accepting new games once this is dropped. Already
+/// opened games keep running until all their players leave.
+pub struct EmbeddedConnector {
+    port: u16,
+    _shutdown: Sender<()>,
+}
+
+impl EmbeddedConnector {
+    /// Starts the connector in the background. This blocks only until all
+    /// servers of the connector are bound.
+    pub fn start(conf: ConnectorConf) -> anyhow::Result<Self> {
+        let port = conf.port();
+        info!("Starting embedded connector on port {port}...");
+
+        let (sender, shutdown) = Shutdown::new();
+        let server = task::block_on(bind(conf, shutdown))?;
+        task::spawn(async move {
+            match server.run().await {
+                Ok(()) => info!("Embedded connector on port {port} stopped."),
+                Err(error) => error!("Embedded connector on port {port} failed: {error:?}"),
+            }
+        });
+
+        Ok(Self {
+            port,
+            _shutdown: sender,
+        })
+    }
+
+    /// Port of the main server of the connector.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
@@ -1,13 +1,17 @@
 use std::time::Duration;
 
 use async_std::{channel::Sender, future::timeout};
-use de_messages::ToGame;
-use de_net::{ConnErrorReceiver, Reliability};
+use de_net::ConnErrorReceiver;
 use tracing::{error, info, warn};
 
-use super::message::InMessage;
+use super::{message::ServerInput, monitor::Traffic};
 
-pub(super) async fn run(port: u16, errors: ConnErrorReceiver, server: Sender<InMessage<ToGame>>) {
+pub(super) async fn run(
+    port: u16,
+    errors: ConnErrorReceiver,
+    server: Sender<ServerInput>,
+    traffic: Traffic,
+) {
     info!("Starting game connection error handler on port {port}...");
 
     loop {
@@ -25,12 +29,9 @@ pub(super) async fn run(port: u16, errors: ConnErrorReceiver, server: Sender<InM
         };
 
         warn!("In game connection lost with {:?}", error.target());
+        traffic.connection_error();
         let _ = server
-            .send(InMessage::new(
-                error.target(),
-                Reliability::Unordered,
-                ToGame::Leave,
-            ))
+            .send(ServerInput::ConnectionLost(error.target()))
             .await;
     }
 
@@ -1,36 +1,56 @@
-use std::{net::SocketAddr, time::Duration};
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
-use async_std::{
-    channel::{Receiver, Sender},
-    task,
+use async_std::{channel::Receiver, future::timeout, task};
+use de_messages::{
+    BorrowedReplayEvent, FromGame, HostError, JoinError, PauseError, Readiness, RejoinToken,
+    ToGame, PROTOCOL_VERSION,
 };
-use de_messages::{FromGame, JoinError, Readiness, ToGame};
-use de_net::{OutPackage, Peers, Reliability};
+use de_net::{OutPackage, PackageSender, Peers, Reliability};
+use de_types::player::Player;
 use tracing::{error, info, warn};
 
 use super::{
-    message::{InMessage, MessageMeta},
-    state::{GameState, JoinError as JoinErrorInner},
+    message::{InMessage, MessageMeta, ServerInput},
+    replay::ReplayRecorder,
+    state::{
+        GameState, HostCommandError, JoinError as JoinErrorInner, PauseError as PauseErrorInner,
+    },
 };
 use crate::clients::Clients;
 
+/// Slot of a player whose connection to a running game was lost is reserved
+/// for this long. See [`ToGame::Rejoin`].
+const REJOIN_GRACE_PERIOD: Duration = Duration::from_secs(60);
+/// Heartbeats are sent to clients which were silent for this fraction of the
+/// client timeout.
+const HEARTBEAT_FRACTION: u32 = 4;
+
 pub(super) struct GameProcessor {
     port: u16,
     owner: SocketAddr,
-    messages: Receiver<InMessage<ToGame>>,
-    outputs: Sender<OutPackage>,
+    messages: Receiver<ServerInput>,
+    outputs: PackageSender,
     state: GameState,
     clients: Clients,
+    replay: ReplayRecorder,
+    client_timeout: Duration,
+    last_heartbeat: Instant,
 }
 
 impl GameProcessor {
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn new(
         port: u16,
         owner: SocketAddr,
-        messages: Receiver<InMessage<ToGame>>,
-        outputs: Sender<OutPackage>,
+        messages: Receiver<ServerInput>,
+        outputs: PackageSender,
         state: GameState,
         clients: Clients,
+        replay: ReplayRecorder,
+        client_timeout: Duration,
     ) -> Self {
         Self {
             port,
@@ -39,6 +59,9 @@ impl GameProcessor {
             outputs,
             state,
             clients,
+            replay,
+            client_timeout,
+            last_heartbeat: Instant::now(),
         }
     }
 
@@ -62,35 +85,31 @@ impl GameProcessor {
                 break;
             }
 
-            let Ok(message) = self.messages.recv().await else {
-                error!(
-                    "Game message channel on port {} is unexpectedly closed.",
-                    self.port
-                );
-                break;
+            let input = match timeout(Duration::from_secs(1), self.messages.recv()).await {
+                Ok(Ok(input)) => Some(input),
+                Ok(Err(_)) => {
+                    error!(
+                        "Game message channel on port {} is unexpectedly closed.",
+                        self.port
+                    );
+                    break;
+                }
+                Err(_) => None,
             };
 
-            if self.handle_ignore(&message).await {
-                continue;
-            }
-
-            match message.message() {
-                ToGame::Ping(id) => {
-                    self.process_ping(message.meta(), *id).await;
-                }
-                ToGame::Join => {
-                    self.process_join(message.meta()).await;
-                }
-                ToGame::Leave => {
-                    self.process_leave(message.meta()).await;
-                }
-                ToGame::Readiness(readiness) => {
-                    self.process_readiness(message.meta(), *readiness).await;
-                }
+            match input {
+                Some(ServerInput::Message(message)) => self.process_message(message).await,
+                Some(ServerInput::ConnectionLost(addr)) => self.process_connection_lost(addr).await,
+                Some(ServerInput::Kick(addr)) => self.process_kick(addr).await,
+                None => (),
             }
+            self.check_activity().await;
+            self.expire_disconnected().await;
+            self.try_resume().await;
 
             if self.state.is_empty().await {
                 info!("Everybody disconnected, quitting...");
+                self.remove_spectators().await;
                 break;
             }
         }
@@ -101,12 +120,70 @@ impl GameProcessor {
         );
     }
 
+    async fn process_message(&mut self, message: InMessage<ToGame>) {
+        if self.handle_ignore(&message).await {
+            return;
+        }
+
+        match message.message() {
+            ToGame::Ping(id) => {
+                self.process_ping(message.meta(), *id).await;
+            }
+            ToGame::Heartbeat => {
+                // Client activity is tracked upon reception of any message.
+            }
+            ToGame::Join { version } => {
+                self.process_join(message.meta(), *version, JoinMode::Player)
+                    .await;
+            }
+            ToGame::JoinSpectator { version } => {
+                self.process_join(message.meta(), *version, JoinMode::Spectator)
+                    .await;
+            }
+            ToGame::Leave => {
+                self.process_leave(message.meta()).await;
+            }
+            ToGame::Readiness(readiness) => {
+                self.process_readiness(message.meta(), *readiness).await;
+            }
+            ToGame::Rejoin { version, token } => {
+                self.process_join(message.meta(), *version, JoinMode::Rejoin(*token))
+                    .await;
+            }
+            ToGame::Restore => {
+                self.process_restore(message.meta()).await;
+            }
+            ToGame::Kick(player) => {
+                self.process_host_kick(message.meta(), *player).await;
+            }
+            ToGame::SetLocked(locked) => {
+                self.process_set_locked(message.meta(), *locked).await;
+            }
+            ToGame::SetMaxPlayers(max_players) => {
+                self.process_set_max_players(message.meta(), *max_players)
+                    .await;
+            }
+            ToGame::Pause => {
+                self.process_pause(message.meta()).await;
+            }
+            ToGame::Resume => {
+                self.process_resume(message.meta()).await;
+            }
+        }
+    }
+
     /// Returns true if the massage should be ignored and further handles such
     /// messages.
     async fn handle_ignore(&self, message: &InMessage<ToGame>) -> bool {
-        if matches!(message.message(), ToGame::Join | ToGame::Leave) {
-            // Join must be excluded from the condition because of the
-            // chicken and egg problem.
+        if matches!(
+            message.message(),
+            ToGame::Join { .. }
+                | ToGame::JoinSpectator { .. }
+                | ToGame::Rejoin { .. }
+                | ToGame::Leave
+        ) {
+            // Join and rejoin must be excluded from the condition because of
+            // the chicken and egg problem.
             //
             // Leave must be excluded due to possibility that the message
             // was redelivered.
@@ -153,7 +230,32 @@ impl GameProcessor {
     }
 
     /// Process connect message.
-    async fn process_join(&mut self, meta: MessageMeta) {
+    ///
+    /// # Arguments
+    ///
+    /// * `meta` - metadata of the connect message.
+    ///
+    /// * `version` - protocol version of the client.
+    ///
+    /// * `mode` - the way the client is connected to the game.
+    async fn process_join(&mut self, meta: MessageMeta, version: u32, mode: JoinMode) {
+        if version != PROTOCOL_VERSION {
+            warn!(
+                "Client {:?} could not join game on port {} due to incompatible protocol \
+                 version {version}.",
+                meta.source, self.port
+            );
+            self.send(
+                &FromGame::JoinError(JoinError::IncompatibleVersion {
+                    server: PROTOCOL_VERSION,
+                }),
+                Reliability::Unordered,
+                meta.source,
+            )
+            .await;
+            return;
+        }
+
         if let Err(err) = self.clients.reserve(meta.source).await {
             warn!("Join request error: {err}");
             self.send(
@@ -165,7 +267,13 @@ impl GameProcessor {
             return;
         }
 
-        match self.join(meta.source).await {
+        let result = match mode {
+            JoinMode::Player => self.join(meta.source).await,
+            JoinMode::Spectator => self.join_spectator(meta.source).await,
+            JoinMode::Rejoin(token) => self.rejoin(meta.source, token).await,
+        };
+
+        match result {
             Ok(_) => {
                 self.clients.set(meta.source, self.port).await;
             }
@@ -213,19 +321,66 @@ impl GameProcessor {
                         )
                         .await;
                     }
+                    JoinErrorInner::GameLocked => {
+                        warn!(
+                            "Player {:?} could not join game on port {} because the game is \
+                             locked.",
+                            meta.source, self.port
+                        );
+
+                        self.send(
+                            &FromGame::JoinError(JoinError::GameLocked),
+                            Reliability::Unordered,
+                            meta.source,
+                        )
+                        .await;
+                    }
+                    JoinErrorInner::Kicked => {
+                        warn!(
+                            "Client {:?} could not join game on port {} because it was kicked \
+                             from the game.",
+                            meta.source, self.port
+                        );
+
+                        self.send(
+                            &FromGame::JoinError(JoinError::Kicked),
+                            Reliability::Unordered,
+                            meta.source,
+                        )
+                        .await;
+                    }
+                    JoinErrorInner::InvalidToken => {
+                        warn!(
+                            "Player {:?} could not rejoin game on port {} due to an invalid \
+                             token.",
+                            meta.source, self.port
+                        );
+
+                        self.send(
+                            &FromGame::JoinError(JoinError::InvalidToken),
+                            Reliability::Unordered,
+                            meta.source,
+                        )
+                        .await;
+                    }
                 }
             }
         }
     }
 
     async fn join(&mut self, addr: SocketAddr) -> Result<(), JoinErrorInner> {
-        let id = self.state.add(addr).await?;
+        let (id, token) = self.state.add(addr).await?;
         info!(
             "Player {id} on {addr:?} just joined game on port {}.",
             self.port
         );
-        self.send(&FromGame::Joined(id), Reliability::SemiOrdered, addr)
-            .await;
+        self.replay.record(BorrowedReplayEvent::PeerJoined(id));
+        self.send(
+            &FromGame::Joined { player: id, token },
+            Reliability::SemiOrdered,
+            addr,
+        )
+        .await;
         self.send_all(
             &FromGame::PeerJoined(id),
             Reliability::SemiOrdered,
@@ -235,28 +390,379 @@ impl GameProcessor {
         Ok(())
     }
 
+    async fn join_spectator(&mut self, addr: SocketAddr) -> Result<(), JoinErrorInner> {
+        let (players, readiness) = self.state.add_spectator(addr).await?;
+        info!(
+            "Spectator on {addr:?} just joined game on port {}.",
+            self.port
+        );
+        self.send(
+            &FromGame::JoinedSpectator { players, readiness },
+            Reliability::SemiOrdered,
+            addr,
+        )
+        .await;
+
+        if let Some((player, budget)) = self.state.paused(Instant::now()).await {
+            self.send(
+                &FromGame::GamePaused { player, budget },
+                Reliability::SemiOrdered,
+                addr,
+            )
+            .await;
+        }
+
+        Ok(())
+    }
+
+    async fn rejoin(&mut self, addr: SocketAddr, token: RejoinToken) -> Result<(), JoinErrorInner> {
+        let (id, session) = self.state.rejoin(addr, token).await?;
+        info!(
+            "Player {id} on {addr:?} just rejoined game on port {}.",
+            self.port
+        );
+        self.send(
+            &FromGame::Rejoined {
+                player: id,
+                session,
+            },
+            Reliability::SemiOrdered,
+            addr,
+        )
+        .await;
+
+        if let Some((player, budget)) = self.state.paused(Instant::now()).await {
+            self.send(
+                &FromGame::GamePaused { player, budget },
+                Reliability::SemiOrdered,
+                addr,
+            )
+            .await;
+        }
+
+        Ok(())
+    }
+
+    /// Sends a snapshot of all game entities to a rejoined player.
+    async fn process_restore(&mut self, meta: MessageMeta) {
+        {
+            let mut guard = self.state.lock().await;
+            let buffer = match guard.restore(meta.source) {
+                Ok(buffer) => buffer,
+                Err(err) => {
+                    warn!(
+                        "Invalid restore request from {source:?}: {err}",
+                        source = meta.source
+                    );
+                    return;
+                }
+            };
+
+            // The lock must be held until the snapshot is passed to the
+            // outputs so that no newer player message overtakes it.
+            for output in buffer.build_all() {
+                let _ = self.outputs.send(output).await;
+            }
+        }
+
+        info!(
+            "Game on port {} restored for player {:?}.",
+            self.port, meta.source
+        );
+        self.send(&FromGame::Restored, Reliability::SemiOrdered, meta.source)
+            .await;
+    }
+
     /// Process disconnect message.
     async fn process_leave(&mut self, meta: MessageMeta) {
-        let Some(mut player_state) = self.state.remove(meta.source).await else {
-            warn!("Tried to remove non-existent player {:?}.", meta.source);
+        self.leave(meta.source, &FromGame::Left).await;
+    }
+
+    /// Removes a misbehaving player from the game. Unlike players with a lost
+    /// connection, kicked players cannot rejoin the game.
+    async fn process_kick(&mut self, addr: SocketAddr) {
+        warn!(
+            "Kicking client {addr:?} from game on port {} due to repeatedly sent invalid \
+             messages.",
+            self.port
+        );
+        self.state.ban(addr).await;
+        self.leave(addr, &FromGame::Kicked).await;
+    }
+
+    /// Returns true if the message was sent by the host of the game, i.e. by
+    /// the client who opened it. Otherwise, a host error is sent back to the
+    /// client.
+    async fn check_host(&mut self, meta: MessageMeta) -> bool {
+        if self.state.is_host(meta.source).await {
+            return true;
+        }
+
+        warn!(
+            "Client {:?} sent a host command to game on port {} but it is not the host.",
+            meta.source, self.port
+        );
+        self.send(
+            &FromGame::HostError(HostError::NotHost),
+            Reliability::Unordered,
+            meta.source,
+        )
+        .await;
+        false
+    }
+
+    /// Removes a player from the game on request of the host.
+    async fn process_host_kick(&mut self, meta: MessageMeta, player: Player) {
+        if !self.check_host(meta).await {
+            return;
+        }
+
+        let addr = match self.state.addr(player).await {
+            Some(addr) if addr != meta.source => addr,
+            _ => {
+                warn!(
+                    "Host of game on port {} cannot kick player {player}.",
+                    self.port
+                );
+                self.send(
+                    &FromGame::HostError(HostError::InvalidPlayer),
+                    Reliability::Unordered,
+                    meta.source,
+                )
+                .await;
+                return;
+            }
+        };
+
+        info!(
+            "Player {player} on {addr:?} is being kicked by the host from game on port {}.",
+            self.port
+        );
+        self.state.ban(addr).await;
+        self.leave(addr, &FromGame::Kicked).await;
+    }
+
+    async fn process_set_locked(&mut self, meta: MessageMeta, locked: bool) {
+        if !self.check_host(meta).await {
+            return;
+        }
+
+        match self.state.set_locked(locked).await {
+            Ok(changed) => {
+                if changed {
+                    info!("Game on port {} locked: {locked}.", self.port);
+                    self.send_all(
+                        &FromGame::GameLocked(locked),
+                        Reliability::SemiOrdered,
+                        None,
+                    )
+                    .await;
+                }
+            }
+            Err(err) => self.host_error(meta, err).await,
+        }
+    }
+
+    async fn process_set_max_players(&mut self, meta: MessageMeta, max_players: Player) {
+        if !self.check_host(meta).await {
+            return;
+        }
+
+        match self.state.set_max_players(max_players).await {
+            Ok(changed) => {
+                if changed {
+                    info!(
+                        "Maximum number of players of game on port {} changed to {max_players}.",
+                        self.port
+                    );
+                    self.send_all(
+                        &FromGame::GameMaxPlayers(max_players),
+                        Reliability::SemiOrdered,
+                        None,
+                    )
+                    .await;
+                }
+            }
+            Err(err) => self.host_error(meta, err).await,
+        }
+    }
+
+    async fn host_error(&mut self, meta: MessageMeta, err: HostCommandError) {
+        warn!(
+            "Host command on game on port {} could not be executed: {err}",
+            self.port
+        );
+        let error = match err {
+            HostCommandError::GameStarted => HostError::GameStarted,
+            HostCommandError::SlotOccupied(_) => HostError::SlotOccupied,
+            HostCommandError::MapCapacity(_) => HostError::MapCapacity,
+        };
+        self.send(
+            &FromGame::HostError(error),
+            Reliability::Unordered,
+            meta.source,
+        )
+        .await;
+    }
+
+    async fn process_pause(&mut self, meta: MessageMeta) {
+        match self.state.pause(meta.source, Instant::now()).await {
+            Ok((player, budget)) => {
+                info!(
+                    "Game on port {} paused by player {player} for at most {budget:?}.",
+                    self.port
+                );
+                self.send_all(
+                    &FromGame::GamePaused { player, budget },
+                    Reliability::SemiOrdered,
+                    None,
+                )
+                .await;
+            }
+            Err(err) => self.pause_error(meta, err).await,
+        }
+    }
+
+    async fn process_resume(&mut self, meta: MessageMeta) {
+        match self.state.vote_resume(meta.source).await {
+            Ok(player) => {
+                self.send_all(
+                    &FromGame::ResumeVoted(player),
+                    Reliability::SemiOrdered,
+                    None,
+                )
+                .await;
+                self.try_resume().await;
+            }
+            Err(err) => self.pause_error(meta, err).await,
+        }
+    }
+
+    async fn pause_error(&mut self, meta: MessageMeta, err: PauseErrorInner) {
+        warn!(
+            "Pause request from {:?} to game on port {} failed: {err}",
+            meta.source, self.port
+        );
+        let error = match err {
+            // Spectators cannot pause the game.
+            PauseErrorInner::UnknownClient(_) => return,
+            PauseErrorInner::GameNotRunning => PauseError::GameNotRunning,
+            PauseErrorInner::AlreadyPaused => PauseError::AlreadyPaused,
+            PauseErrorInner::NotPaused => PauseError::NotPaused,
+            PauseErrorInner::BudgetExhausted => PauseError::BudgetExhausted,
+        };
+        self.send(
+            &FromGame::PauseError(error),
+            Reliability::Unordered,
+            meta.source,
+        )
+        .await;
+    }
+
+    /// Resumes a paused game once all players voted for it or once the pause
+    /// budget of the pausing player is exhausted.
+    async fn try_resume(&mut self) {
+        if self.state.try_resume(Instant::now()).await {
+            info!("Game on port {} resumed.", self.port);
+            self.send_all(&FromGame::GameResumed, Reliability::SemiOrdered, None)
+                .await;
+        }
+    }
+
+    /// Handles a client whose connection was lost. Players of a running game
+    /// are given a chance to rejoin the game, others are removed from the game
+    /// right away.
+    async fn process_connection_lost(&mut self, addr: SocketAddr) {
+        let deadline = Instant::now() + REJOIN_GRACE_PERIOD;
+        let Some(id) = self.state.disconnect(addr, deadline).await else {
+            self.leave(addr, &FromGame::Left).await;
+            return;
+        };
+
+        self.clients.free(addr).await;
+        info!(
+            "Player {id} on {addr:?} lost connection to game on port {}, waiting for a rejoin.",
+            self.port
+        );
+    }
+
+    /// Sends heartbeats to clients which have been silent for a while and
+    /// handles clients silent for longer than the client timeout as if their
+    /// connection was lost.
+    async fn check_activity(&mut self) {
+        let time = Instant::now();
+        let Some(deadline) = time.checked_sub(self.client_timeout) else {
+            return;
+        };
+
+        for addr in self.state.silent(deadline).await {
+            warn!(
+                "Client {addr:?} of game on port {} has not sent any message for {:?}.",
+                self.port, self.client_timeout
+            );
+            self.process_connection_lost(addr).await;
+        }
+
+        let heartbeat_interval = self.client_timeout / HEARTBEAT_FRACTION;
+        if time - self.last_heartbeat < heartbeat_interval {
+            return;
+        }
+        self.last_heartbeat = time;
+
+        for addr in self.state.silent(time - heartbeat_interval).await {
+            self.send(&FromGame::Heartbeat, Reliability::Unordered, addr)
+                .await;
+        }
+    }
+
+    /// Removes disconnected players whose grace period has expired.
+    async fn expire_disconnected(&mut self) {
+        for id in self.state.expire(Instant::now()).await {
+            info!(
+                "Player {id} did not rejoin game on port {} in time.",
+                self.port
+            );
+            self.replay.record(BorrowedReplayEvent::PeerLeft(id));
+            self.send_all(&FromGame::PeerLeft(id), Reliability::SemiOrdered, None)
+                .await;
+        }
+    }
+
+    /// Removes a player or a spectator from the game.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the removed client.
+    ///
+    /// * `farewell` - message sent to the removed client, i.e.
+    ///   [`FromGame::Left`] or [`FromGame::Kicked`].
+    async fn leave(&mut self, addr: SocketAddr, farewell: &FromGame) {
+        if self.state.is_spectator(addr).await {
+            self.leave_spectator(addr, farewell).await;
+            return;
+        }
+
+        let Some(mut player_state) = self.state.remove(addr).await else {
+            warn!("Tried to remove non-existent player {addr:?}.");
             return;
         };
 
-        self.clients.free(meta.source).await;
+        self.clients.free(addr).await;
 
         info!(
-            "Player {} on {:?} just left game on port {}.",
+            "Player {} on {addr:?} just left game on port {}.",
             player_state.id(),
-            meta.source,
             self.port
         );
 
+        self.replay
+            .record(BorrowedReplayEvent::PeerLeft(player_state.id()));
+
         for output in player_state.buffer_mut().build_all() {
             let _ = self.outputs.send(output).await;
         }
 
-        self.send(&FromGame::Left, Reliability::SemiOrdered, meta.source)
-            .await;
+        self.send(farewell, Reliability::SemiOrdered, addr).await;
         self.send_all(
             &FromGame::PeerLeft(player_state.id()),
             Reliability::SemiOrdered,
@@ -265,10 +771,48 @@ impl GameProcessor {
         .await;
     }
 
+    async fn leave_spectator(&mut self, addr: SocketAddr, farewell: &FromGame) {
+        let Some(mut spectator_state) = self.state.remove_spectator(addr).await else {
+            warn!("Tried to remove non-existent spectator {addr:?}.");
+            return;
+        };
+
+        self.clients.free(addr).await;
+        info!(
+            "Spectator on {addr:?} just left game on port {}.",
+            self.port
+        );
+
+        for output in spectator_state.buffer_mut().build_all() {
+            let _ = self.outputs.send(output).await;
+        }
+        self.send(farewell, Reliability::SemiOrdered, addr).await;
+    }
+
+    /// Disconnects all spectators from the game. This is done once the last
+    /// player leaves the game.
+    async fn remove_spectators(&mut self) {
+        for (addr, mut spectator_state) in self.state.remove_spectators().await {
+            self.clients.free(addr).await;
+            info!(
+                "Spectator on {addr:?} was disconnected from game on port {}.",
+                self.port
+            );
+
+            for output in spectator_state.buffer_mut().build_all() {
+                let _ = self.outputs.send(output).await;
+            }
+            self.send(&FromGame::Left, Reliability::SemiOrdered, addr)
+                .await;
+        }
+    }
+
     async fn process_readiness(&mut self, meta: MessageMeta, readiness: Readiness) {
         match self.state.update_readiness(meta.source, readiness).await {
             Ok(progressed) => {
                 if progressed {
+                    self.replay
+                        .record(BorrowedReplayEvent::GameReadiness(readiness));
                     self.send_all(
                         &FromGame::GameReadiness(readiness),
                         Reliability::SemiOrdered,
@@ -312,3 +856,11 @@ impl GameProcessor {
         let _ = self.outputs.send(message).await;
     }
 }
+
+/// The way a client is connected to a game.
+#[derive(Clone, Copy)]
+enum JoinMode {
+    Player,
+    Spectator,
+    Rejoin(RejoinToken),
+}
@@ -0,0 +1,262 @@
+use ahash::AHashMap;
+use de_messages::{EntityNet, HealthDelta, PathNet, ToPlayers, TransformNet};
+use de_types::{objects::ActiveObjectType, player::Player};
+use tracing::warn;
+
+/// Last known state of all entities in the game. It is updated from player
+/// messages relayed among the players and it is used to restore the game on
+/// the side of rejoined players.
+pub(super) struct EntityLedger {
+    entities: AHashMap<EntityNet, EntityRecord>,
+    /// Sequence number of the latest recorded snapshot of each player.
+    /// Snapshots are relayed unreliably, thus possibly out of order.
+    snapshots: AHashMap<Player, u32>,
+}
+
+impl EntityLedger {
+    pub(super) fn new() -> Self {
+        Self {
+            entities: AHashMap::new(),
+            snapshots: AHashMap::new(),
+        }
+    }
+
+    /// Updates the ledger with a message relayed among the players.
+    pub(super) fn record(&mut self, message: &ToPlayers) {
+        match message {
+            ToPlayers::Spawn {
+                entity,
+                player,
+                object_type,
+                transform,
+            } => {
+                self.entities.insert(
+                    *entity,
+                    EntityRecord::new(*player, *object_type, transform.clone()),
+                );
+            }
+            ToPlayers::Despawn { entity } => {
+                self.entities.remove(entity);
+            }
+            ToPlayers::SetPath { entity, waypoints } => {
+                if let Some(record) = self.entities.get_mut(entity) {
+                    record.path = waypoints.clone();
+                }
+            }
+            ToPlayers::Transform {
+                entity, transform, ..
+            } => {
+                if let Some(record) = self.entities.get_mut(entity) {
+                    record.transform = transform.clone();
+                }
+            }
+            ToPlayers::ChangeHealth { entity, delta } => {
+                if let Some(record) = self.entities.get_mut(entity) {
+                    record.health += f32::from(delta);
+                }
+            }
+            ToPlayers::Snapshot(part) => {
+                let Some(player) = part.changed().first().map(|delta| delta.entity().player())
+                else {
+                    return;
+                };
+
+                let latest = self.snapshots.entry(player).or_insert(part.sequence());
+                if *latest > part.sequence() {
+                    return;
+                }
+                *latest = part.sequence();
+
+                for delta in part.changed() {
+                    if let (Some(record), Some(transform)) =
+                        (self.entities.get_mut(&delta.entity()), delta.transform())
+                    {
+                        record.transform = transform.clone();
+                    }
+                }
+            }
+            ToPlayers::Chat(_)
+            | ToPlayers::Projectile(_)
+            | ToPlayers::Checksum { .. }
+            | ToPlayers::Resync { .. }
+            | ToPlayers::SnapshotAck { .. } => (),
+        }
+    }
+
+    /// Returns true if the entity exists, i.e. it was spawned and not yet
+    /// despawned.
+    pub(super) fn contains(&self, entity: EntityNet) -> bool {
+        self.entities.contains_key(&entity)
+    }
+
+    /// Removes all entities simulated by a player. This should be called once
+    /// the player leaves the game because other players despawn entities of
+    /// the leaving player.
+    pub(super) fn remove_player(&mut self, player: Player) {
+        self.entities.retain(|entity, _| entity.player() != player);
+        self.snapshots.remove(&player);
+    }
+
+    /// Returns player messages which, when delivered in the returned order,
+    /// spawn all entities from the ledger and bring them to their last known
+    /// state. Each message is paired with the player simulating the entity.
+    pub(super) fn snapshot(&self) -> Vec<(Player, ToPlayers)> {
+        let mut messages = Vec::with_capacity(self.entities.len());
+
+        for (&entity, record) in self.entities.iter() {
+            messages.push((
+                entity.player(),
+                ToPlayers::Spawn {
+                    entity,
+                    player: record.player,
+                    object_type: record.object_type,
+                    transform: record.transform.clone(),
+                },
+            ));
+        }
+
+        for (&entity, record) in self.entities.iter() {
+            if let Some(path) = record.path.as_ref() {
+                // The path is sent whole, the entity catches up with it from
+                // its current position.
+                messages.push((
+                    entity.player(),
+                    ToPlayers::SetPath {
+                        entity,
+                        waypoints: Some(path.clone()),
+                    },
+                ));
+            }
+
+            if record.health != 0. {
+                match HealthDelta::try_from(record.health) {
+                    Ok(delta) => {
+                        messages.push((entity.player(), ToPlayers::ChangeHealth { entity, delta }))
+                    }
+                    Err(error) => warn!("Skipping health of entity {entity:?}: {error}"),
+                }
+            }
+        }
+
+        messages
+    }
+}
+
+struct EntityRecord {
+    player: Player,
+    object_type: ActiveObjectType,
+    transform: TransformNet,
+    path: Option<PathNet>,
+    /// Sum of all health changes since the entity was spawned.
+    health: f32,
+}
+
+impl EntityRecord {
+    fn new(player: Player, object_type: ActiveObjectType, transform: TransformNet) -> Self {
+        Self {
+            player,
+            object_type,
+            transform,
+            path: None,
+            health: 0.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use de_messages::{EntityDeltaNet, EntityStateNet, NetEntityIndex, SnapshotPartNet};
+    use de_types::objects::UnitType;
+    use glam::{Vec3, Vec4};
+
+    use super::*;
+
+    fn spawn(entity: EntityNet) -> ToPlayers {
+        ToPlayers::Spawn {
+            entity,
+            player: entity.player(),
+            object_type: ActiveObjectType::Unit(UnitType::Attacker),
+            transform: TransformNet::new(Vec3::new(1., 2., 3.).into(), Vec4::W.into()),
+        }
+    }
+
+    #[test]
+    fn test_ledger() {
+        let entity_a = EntityNet::new(Player::Player1, NetEntityIndex::new(1, 0).unwrap());
+        let entity_b = EntityNet::new(Player::Player2, NetEntityIndex::new(1, 0).unwrap());
+        let entity_c = EntityNet::new(Player::Player2, NetEntityIndex::new(2, 0).unwrap());
+
+        let mut ledger = EntityLedger::new();
+        assert!(ledger.snapshot().is_empty());
+
+        ledger.record(&spawn(entity_a));
+        ledger.record(&spawn(entity_b));
+        ledger.record(&spawn(entity_c));
+        ledger.record(&ToPlayers::ChangeHealth {
+            entity: entity_b,
+            delta: HealthDelta::try_from(-10.).unwrap(),
+        });
+        ledger.record(&ToPlayers::ChangeHealth {
+            entity: entity_b,
+            delta: HealthDelta::try_from(-5.).unwrap(),
+        });
+        ledger.record(&ToPlayers::Despawn { entity: entity_c });
+
+        let snapshot = ledger.snapshot();
+        assert_eq!(snapshot.len(), 3);
+        assert!(snapshot[..2]
+            .iter()
+            .all(|(_, m)| matches!(m, ToPlayers::Spawn { .. })));
+        let (source, ToPlayers::ChangeHealth { entity, delta }) = &snapshot[2] else {
+            panic!("Unexpected snapshot message: {:?}", snapshot[2]);
+        };
+        assert_eq!(*source, Player::Player2);
+        assert_eq!(*entity, entity_b);
+        assert_eq!(f32::from(delta), -15.);
+
+        ledger.remove_player(Player::Player2);
+        let snapshot = ledger.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert!(matches!(
+            snapshot[0],
+            (Player::Player1, ToPlayers::Spawn { entity, .. }) if entity == entity_a
+        ));
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let entity = EntityNet::new(Player::Player1, NetEntityIndex::new(1, 0).unwrap());
+
+        fn transform(x: f32) -> TransformNet {
+            TransformNet::new(Vec3::new(x, 0., 0.).into(), Vec4::W.into())
+        }
+
+        fn part(entity: EntityNet, sequence: u32, x: f32) -> ToPlayers {
+            let state = EntityStateNet::new(transform(x), 10., None);
+            let delta = EntityDeltaNet::new(entity, None, &state).unwrap();
+            let mut parts =
+                SnapshotPartNet::split(sequence, None, Duration::ZERO, vec![delta], vec![])
+                    .unwrap();
+            ToPlayers::Snapshot(parts.pop().unwrap())
+        }
+
+        fn recorded(ledger: &EntityLedger) -> TransformNet {
+            let ToPlayers::Spawn { transform, .. } = &ledger.snapshot()[0].1 else {
+                panic!("Spawn message expected.");
+            };
+            transform.clone()
+        }
+
+        let mut ledger = EntityLedger::new();
+        ledger.record(&spawn(entity));
+        ledger.record(&part(entity, 2, 5.));
+        assert_eq!(recorded(&ledger), transform(5.));
+        // Out of order snapshots are ignored.
+        ledger.record(&part(entity, 1, 6.));
+        assert_eq!(recorded(&ledger), transform(5.));
+        ledger.record(&part(entity, 3, 7.));
+        assert_eq!(recorded(&ledger), transform(7.));
+    }
+}
@@ -1,5 +1,6 @@
 use std::net::SocketAddr;
 
+use de_messages::ToGame;
 use de_net::Reliability;
 
 pub(super) struct InMessage<M> {
@@ -32,3 +33,21 @@ pub(super) struct MessageMeta {
     pub(super) source: SocketAddr,
     pub(super) reliability: Reliability,
 }
+
+/// Input of the game server message handler.
+pub(super) enum ServerInput {
+    /// A message sent by a client to the server.
+    Message(InMessage<ToGame>),
+    /// Connection to a client was lost, i.e. some reliably sent packages could
+    /// not be delivered to the client.
+    ConnectionLost(SocketAddr),
+    /// The client is to be removed from the game due to repeated sending of
+    /// invalid player messages.
+    Kick(SocketAddr),
+}
+
+impl From<InMessage<ToGame>> for ServerInput {
+    fn from(message: InMessage<ToGame>) -> Self {
+        Self::Message(message)
+    }
+}
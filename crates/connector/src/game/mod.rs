@@ -1,19 +1,25 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, path::Path, time::Duration};
 
 use async_std::{channel::bounded, task};
-use de_net::{self, Socket};
+use de_messages::ReplayHeader;
+use de_net::{self, Authentication, Socket};
 use de_types::player::Player;
 
-use self::{greceiver::GameProcessor, state::GameState};
-use crate::clients::Clients;
+pub(crate) use self::monitor::{GameMetrics, GameMonitor, PlayerMetrics};
+use self::{greceiver::GameProcessor, monitor::Traffic, replay::ReplayRecorder, state::GameState};
+use crate::{clients::Clients, games::Games};
 
 mod buffer;
 mod ereceiver;
 mod greceiver;
+mod ledger;
 mod message;
+mod monitor;
 mod mreceiver;
 mod preceiver;
+mod replay;
 mod state;
+mod validation;
 
 /// Startup game network server communicating via `net`.
 ///
@@ -21,35 +27,90 @@ mod state;
 ///
 /// * `clients` - global clients tracker.
 ///
+/// * `games` - global registry of running games. The game is registered for
+///   as long as it runs.
+///
 /// * `socket` - socket to use for the game server.
 ///
+/// * `authentication` - authentication of datagrams exchanged with the
+///   players.
+///
 /// * `owner` - address of the creator of the game. This client will be
 ///   automatically added to the game as if they sent
 ///   [`de_messages::ToGame::Join`].
 ///
 /// * `max_players` - maximum number of clients which may connect to the game
 ///   at the same time
+///
+/// * `map_max_players` - maximum number of players supported by the map. The
+///   host cannot raise `max_players` above it.
+///
+/// * `map_hash` - hash of the map the game is played on.
+///
+/// * `replay_dir` - if not None, the game is recorded to a replay file in this
+///   directory.
+///
+/// * `client_timeout` - players and spectators which do not send any message
+///   for this long are handled as if their connection was lost.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn startup(
     clients: Clients,
+    mut games: Games,
     socket: Socket,
+    authentication: Authentication,
     owner: SocketAddr,
     max_players: Player,
+    map_max_players: Player,
+    map_hash: [u8; 32],
+    replay_dir: Option<&Path>,
+    client_timeout: Duration,
 ) {
     let port = socket.port();
+    let replay = match replay_dir {
+        Some(dir) => {
+            ReplayRecorder::start(port, dir, ReplayHeader::new(map_hash, max_players)).await
+        }
+        None => ReplayRecorder::disabled(),
+    };
+
     let (outputs, inputs, errors) = de_net::startup(
         |t| {
             task::spawn(t);
         },
         socket,
+        authentication,
     );
 
+    let traffic = Traffic::new();
+    let state = GameState::new(max_players, map_max_players, owner);
+    games
+        .insert(GameMonitor::new(
+            port,
+            map_hash,
+            state.clone(),
+            outputs.clone(),
+            traffic.clone(),
+        ))
+        .await;
+
     let (server_sender, server_receiver) = bounded(16);
-    task::spawn(ereceiver::run(port, errors, server_sender.clone()));
+    task::spawn(ereceiver::run(
+        port,
+        errors,
+        server_sender.clone(),
+        traffic.clone(),
+    ));
 
     let (players_sender, players_receiver) = bounded(16);
-    task::spawn(mreceiver::run(port, inputs, server_sender, players_sender));
+    task::spawn(mreceiver::run(
+        port,
+        inputs,
+        server_sender.clone(),
+        players_sender,
+        state.clone(),
+        traffic.clone(),
+    ));
 
-    let state = GameState::new(max_players);
     let server = GameProcessor::new(
         port,
         owner,
@@ -57,8 +118,21 @@ pub(crate) async fn startup(
         outputs.clone(),
         state.clone(),
         clients,
+        replay.clone(),
+        client_timeout,
     );
-    task::spawn(server.run());
+    task::spawn(async move {
+        server.run().await;
+        games.remove(port).await;
+    });
 
-    task::spawn(preceiver::run(port, players_receiver, outputs, state));
+    task::spawn(preceiver::run(
+        port,
+        players_receiver,
+        server_sender,
+        outputs,
+        state,
+        replay,
+        traffic,
+    ));
 }
@@ -0,0 +1,191 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use de_messages::{LanGame, Readiness};
+use de_net::PackageSender;
+use serde::Serialize;
+
+use super::state::GameState;
+
+/// Traffic counters of a single game shared among the game server tasks.
+#[derive(Clone)]
+pub(super) struct Traffic(Arc<TrafficInner>);
+
+struct TrafficInner {
+    received: AtomicU64,
+    relayed: AtomicU64,
+    rejected: AtomicU64,
+    connection_errors: AtomicU64,
+}
+
+impl Traffic {
+    pub(super) fn new() -> Self {
+        Self(Arc::new(TrafficInner {
+            received: AtomicU64::new(0),
+            relayed: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            connection_errors: AtomicU64::new(0),
+        }))
+    }
+
+    /// Records a package received from a client.
+    pub(super) fn received(&self) {
+        self.0.received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a package with player messages sent to a client.
+    pub(super) fn relayed(&self) {
+        self.0.relayed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a dropped invalid player message.
+    pub(super) fn rejected(&self) {
+        self.0.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a lost connection to a client.
+    pub(super) fn connection_error(&self) {
+        self.0.connection_errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Handle for monitoring of a running game.
+#[derive(Clone)]
+pub(crate) struct GameMonitor {
+    port: u16,
+    map_hash: [u8; 32],
+    state: GameState,
+    outputs: PackageSender,
+    traffic: Traffic,
+}
+
+impl GameMonitor {
+    pub(super) fn new(
+        port: u16,
+        map_hash: [u8; 32],
+        state: GameState,
+        outputs: PackageSender,
+        traffic: Traffic,
+    ) -> Self {
+        Self {
+            port,
+            map_hash,
+            state,
+            outputs,
+            traffic,
+        }
+    }
+
+    pub(crate) fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns a description of the game for LAN announcements or None if
+    /// the game cannot be joined, i.e. if it is running, locked or full.
+    pub(crate) async fn lan_game(&self) -> Option<LanGame> {
+        let overview = self.state.overview().await;
+        let players = overview.players.len() + overview.disconnected.len();
+        if overview.readiness != Readiness::NotReady
+            || overview.locked
+            || players >= usize::from(overview.max_players.to_num())
+        {
+            return None;
+        }
+
+        Some(LanGame::new(
+            self.port,
+            overview.max_players,
+            players as u8,
+            self.map_hash,
+        ))
+    }
+
+    /// Collects current metrics of the game.
+    pub(crate) async fn metrics(&self) -> GameMetrics {
+        let overview = self.state.overview().await;
+
+        let players: Vec<PlayerMetrics> = overview
+            .players
+            .iter()
+            .map(|player| {
+                let stats = self.outputs.stats(player.addr);
+                PlayerMetrics {
+                    player: player.id.to_num(),
+                    addr: player.addr,
+                    readiness: format!("{:?}", player.readiness),
+                    rtt_ms: stats
+                        .and_then(|stats| stats.rtt())
+                        .map(|rtt| rtt.as_secs_f64() * 1000.),
+                    loss: stats.map_or(0., |stats| stats.loss()),
+                    resends: stats.map_or(0, |stats| stats.resends()),
+                }
+            })
+            .collect();
+
+        let compression = self.outputs.compression_stats();
+        GameMetrics {
+            port: self.port,
+            max_players: overview.max_players.to_num(),
+            readiness: format!("{:?}", overview.readiness),
+            locked: overview.locked,
+            resends: players.iter().map(|player| player.resends).sum(),
+            players,
+            disconnected: overview.disconnected.iter().map(|id| id.to_num()).collect(),
+            spectators: overview.spectators,
+            packages_received: self.traffic.0.received.load(Ordering::Relaxed),
+            packages_relayed: self.traffic.0.relayed.load(Ordering::Relaxed),
+            messages_rejected: self.traffic.0.rejected.load(Ordering::Relaxed),
+            bytes_received: compression.received(),
+            bytes_sent: compression.sent(),
+            connection_errors: self.traffic.0.connection_errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Point in time metrics of a single game.
+#[derive(Serialize)]
+pub(crate) struct GameMetrics {
+    pub(crate) port: u16,
+    pub(crate) max_players: u8,
+    pub(crate) readiness: String,
+    /// True if the game was locked by its host.
+    pub(crate) locked: bool,
+    /// Connected players sorted by their number.
+    pub(crate) players: Vec<PlayerMetrics>,
+    /// Numbers of players whose connection was lost and who might rejoin the
+    /// game.
+    pub(crate) disconnected: Vec<u8>,
+    pub(crate) spectators: usize,
+    /// Number of packages received from all clients.
+    pub(crate) packages_received: u64,
+    /// Number of packages with player messages sent to all clients.
+    pub(crate) packages_relayed: u64,
+    /// Number of dropped invalid player messages.
+    pub(crate) messages_rejected: u64,
+    /// Payload bytes received from all clients.
+    pub(crate) bytes_received: u64,
+    /// Payload bytes sent to all clients.
+    pub(crate) bytes_sent: u64,
+    /// Re-sent reliable packages to the currently connected players.
+    pub(crate) resends: u64,
+    /// Number of lost client connections.
+    pub(crate) connection_errors: u64,
+}
+
+/// Point in time metrics of a player connected to a game.
+#[derive(Serialize)]
+pub(crate) struct PlayerMetrics {
+    pub(crate) player: u8,
+    pub(crate) addr: SocketAddr,
+    pub(crate) readiness: String,
+    /// Smoothed round-trip time in milliseconds.
+    pub(crate) rtt_ms: Option<f64>,
+    /// Estimated fraction of lost reliably sent packages.
+    pub(crate) loss: f32,
+    pub(crate) resends: u64,
+}
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_std::{channel::Sender, future::timeout};
 use bincode::error::DecodeError;
@@ -7,13 +7,19 @@ use de_net::{InPackage, PackageReceiver, Peers};
 use thiserror::Error;
 use tracing::{error, info, warn};
 
-use super::message::InMessage;
+use super::{
+    message::{InMessage, ServerInput},
+    monitor::Traffic,
+    state::GameState,
+};
 
 pub(super) async fn run(
     port: u16,
     packages: PackageReceiver,
-    server: Sender<InMessage<ToGame>>,
+    server: Sender<ServerInput>,
     players: Sender<InMessage<ToPlayers>>,
+    mut state: GameState,
+    traffic: Traffic,
 ) {
     info!("Starting game server input processor on port {port}...");
 
@@ -35,11 +41,13 @@ pub(super) async fn run(
             error!("Inputs channel on port {port} was unexpectedly closed.");
             break;
         };
+        traffic.received();
+        state.touch(package.source(), Instant::now()).await;
 
         let peers = package.peers();
         let result = match peers {
-            Peers::Server => handle_package(package, &server).await,
-            Peers::Players => handle_package(package, &players).await,
+            Peers::Server => handle_package::<ToGame, _>(package, &server).await,
+            Peers::Players => handle_package::<ToPlayers, _>(package, &players).await,
         };
 
         if let Err(err) = result {
@@ -59,21 +67,18 @@ pub(super) async fn run(
     info!("Game server input processor on port {port} finished.");
 }
 
-async fn handle_package<M>(
+async fn handle_package<M, O>(
     package: InPackage,
-    output: &Sender<InMessage<M>>,
+    output: &Sender<O>,
 ) -> Result<(), PackageHandleError>
 where
     M: bincode::Decode,
+    O: From<InMessage<M>>,
 {
     for message_result in package.decode() {
         let message = message_result.map_err(PackageHandleError::from)?;
         output
-            .send(InMessage::new(
-                package.source(),
-                package.reliability(),
-                message,
-            ))
+            .send(InMessage::new(package.source(), package.reliability(), message).into())
             .await
             .map_err(|_| PackageHandleError::SendError)?;
     }
@@ -1,19 +1,40 @@
-use std::time::{Duration, Instant};
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
-use async_std::{channel::Receiver, future::timeout};
-use de_messages::{BorrowedFromPlayers, FromGame, ToPlayers};
+use ahash::AHashMap;
+use async_std::{
+    channel::{Receiver, Sender},
+    future::timeout,
+};
+use de_messages::{BorrowedFromPlayers, BorrowedReplayEvent, FromGame, ToPlayers};
 use de_net::{OutPackage, PackageSender, Peers};
 use tracing::{error, info, warn};
 
-use super::{message::InMessage, state::GameState};
+use super::{
+    message::{InMessage, ServerInput},
+    monitor::Traffic,
+    replay::ReplayRecorder,
+    state::GameState,
+};
+
+/// A client is kicked from the game once it sends more than this number of
+/// invalid player messages within [`OFFENSE_WINDOW`].
+const MAX_OFFENSES: u32 = 32;
+const OFFENSE_WINDOW: Duration = Duration::from_secs(10);
 
 pub(super) async fn run(
     port: u16,
     messages: Receiver<InMessage<ToPlayers>>,
+    server: Sender<ServerInput>,
     outputs: PackageSender,
     mut state: GameState,
+    replay: ReplayRecorder,
+    traffic: Traffic,
 ) {
     info!("Starting game player package handler on port {port}...");
+    let mut offenses = Offenses::new();
 
     'main: loop {
         if messages.is_closed() {
@@ -35,6 +56,14 @@ pub(super) async fn run(
             let time = Instant::now();
             let meta = message.meta();
             let Some(player_id) = state.id(meta.source).await else {
+                if state.is_spectator(meta.source).await {
+                    warn!(
+                        "Received a player message from a spectator, ignoring: {:?}.",
+                        meta.source
+                    );
+                    continue;
+                }
+
                 warn!(
                     "Received a player message from a non-participating client: {:?}.",
                     meta.source
@@ -54,8 +83,30 @@ pub(super) async fn run(
                 continue;
             };
 
+            let validation = state.lock().await.validate(player_id, message.message());
+            if let Err(err) = validation {
+                traffic.rejected();
+
+                if err.is_offense() {
+                    warn!(
+                        "Dropping invalid message from player {player_id} on {:?}: {err}",
+                        meta.source
+                    );
+                    if offenses.offend(meta.source, time)
+                        && server.send(ServerInput::Kick(meta.source)).await.is_err()
+                    {
+                        break 'main;
+                    }
+                }
+
+                continue;
+            }
+
             let out_message = BorrowedFromPlayers::new(player_id, message.message());
-            for buffer in state.lock().await.buffers_mut(Some(meta.source)) {
+            replay.record(BorrowedReplayEvent::Message(out_message));
+            let mut guard = state.lock().await;
+            guard.record(message.message());
+            for buffer in guard.buffers_mut(Some(meta.source)) {
                 if let Err(err) = buffer.push(meta.reliability, &out_message, time) {
                     warn!("Could not encode player message, skipping: {err:?}");
                 }
@@ -70,9 +121,78 @@ pub(super) async fn run(
                 if result.is_err() {
                     break 'main;
                 }
+                traffic.relayed();
             }
         }
     }
 
+    let compression = outputs.compression_stats();
+    info!(
+        "Game on port {port} payload compression: {{ sent: {}B of {}B, received: {}B of {}B }}",
+        compression.sent(),
+        compression.sent_raw(),
+        compression.received(),
+        compression.received_raw(),
+    );
+
     info!("Game player package handler on port {port} finished.");
 }
+
+/// Counts invalid messages sent by individual clients.
+struct Offenses(AHashMap<SocketAddr, OffenseWindow>);
+
+impl Offenses {
+    fn new() -> Self {
+        Self(AHashMap::new())
+    }
+
+    /// Records an invalid message and returns true if the client exceeded
+    /// the offense limit and should be kicked. The limit is exceeded only
+    /// once per client.
+    fn offend(&mut self, addr: SocketAddr, time: Instant) -> bool {
+        let window = self.0.entry(addr).or_insert(OffenseWindow {
+            start: time,
+            count: 0,
+        });
+        if time - window.start > OFFENSE_WINDOW {
+            window.start = time;
+            window.count = 0;
+        }
+
+        window.count += 1;
+        window.count == MAX_OFFENSES + 1
+    }
+}
+
+struct OffenseWindow {
+    start: Instant,
+    count: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offenses() {
+        let addr_a: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+        let addr_b: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+        let start = Instant::now();
+
+        let mut offenses = Offenses::new();
+        for i in 0..MAX_OFFENSES {
+            assert!(!offenses.offend(addr_a, start + Duration::from_millis(i.into())));
+        }
+        assert!(!offenses.offend(addr_b, start));
+
+        // The window expired.
+        let later = start + OFFENSE_WINDOW + Duration::from_secs(1);
+        assert!(!offenses.offend(addr_a, later));
+
+        for _ in 1..MAX_OFFENSES {
+            assert!(!offenses.offend(addr_a, later));
+        }
+        assert!(offenses.offend(addr_a, later));
+        assert!(!offenses.offend(addr_a, later));
+    }
+}
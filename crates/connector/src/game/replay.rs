@@ -0,0 +1,152 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use async_std::{
+    channel::{bounded, Receiver, Sender, TrySendError},
+    fs::File,
+    future::timeout,
+    io::{BufWriter, WriteExt},
+    task,
+};
+use de_messages::{
+    encode_replay_item, BorrowedReplayEvent, BorrowedReplayRecord, ReplayHeader, REPLAY_FILE_SUFFIX,
+};
+use tracing::{error, info, warn};
+
+/// Maximum number of encoded records waiting to be written.
+const QUEUE_SIZE: usize = 256;
+/// Written records are flushed to the file at least this often.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Records game events to a replay file.
+///
+/// The recorder is cheap to clone, all clones record into the same file. The
+/// file is finalized once all the clones are dropped.
+///
+/// Recording never blocks the game. Records which cannot be queued for
+/// writing, e.g. due to a slow disk, are dropped.
+#[derive(Clone)]
+pub(super) struct ReplayRecorder {
+    start: Instant,
+    sender: Option<Sender<Vec<u8>>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl ReplayRecorder {
+    /// Returns a recorder which does not record anything.
+    pub(super) fn disabled() -> Self {
+        Self {
+            start: Instant::now(),
+            sender: None,
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Creates a new replay file in `dir` and starts a task writing the
+    /// recorded events into it. A disabled recorder is returned if the file
+    /// cannot be created.
+    pub(super) async fn start(port: u16, dir: &Path, header: ReplayHeader) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_secs());
+        let path = dir.join(format!("{timestamp}-{port}{REPLAY_FILE_SUFFIX}"));
+
+        let file = match File::create(&path).await {
+            Ok(file) => file,
+            Err(err) => {
+                error!("Failed to create replay file {path:?}: {err:?}");
+                return Self::disabled();
+            }
+        };
+
+        let mut data = Vec::new();
+        encode_replay_item(&header, &mut data).unwrap();
+
+        info!("Recording game on port {port} to {path:?}.");
+        let (sender, receiver) = bounded(QUEUE_SIZE);
+        let dropped = Arc::new(AtomicU64::new(0));
+        task::spawn(run(port, file, data, receiver, Arc::clone(&dropped)));
+
+        Self {
+            start: Instant::now(),
+            sender: Some(sender),
+            dropped,
+        }
+    }
+
+    /// Records a single event. The event is timestamped with the time
+    /// elapsed since the start of the recording.
+    pub(super) fn record(&self, event: BorrowedReplayEvent<'_>) {
+        let Some(sender) = self.sender.as_ref() else {
+            return;
+        };
+
+        let record = BorrowedReplayRecord::new(self.start.elapsed(), event);
+        let mut data = Vec::new();
+        if let Err(err) = encode_replay_item(&record, &mut data) {
+            warn!("Could not encode replay record, skipping: {err:?}");
+            return;
+        }
+
+        if let Err(TrySendError::Full(_)) = sender.try_send(data) {
+            if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                warn!("Replay writer is falling behind, dropping replay records.");
+            }
+        }
+    }
+}
+
+async fn run(
+    port: u16,
+    file: File,
+    header: Vec<u8>,
+    records: Receiver<Vec<u8>>,
+    dropped: Arc<AtomicU64>,
+) {
+    let mut writer = BufWriter::new(file);
+    let mut last_flush = Instant::now();
+
+    if let Err(err) = writer.write_all(&header).await {
+        error!("Failed to write replay header of game on port {port}: {err:?}");
+        return;
+    }
+
+    loop {
+        match timeout(FLUSH_INTERVAL, records.recv()).await {
+            Ok(Ok(data)) => {
+                if let Err(err) = writer.write_all(&data).await {
+                    error!("Failed to write replay of game on port {port}: {err:?}");
+                    return;
+                }
+            }
+            Ok(Err(_)) => break,
+            Err(_) => (),
+        }
+
+        // Make sure that the replay is not lost in case of a crash.
+        if last_flush.elapsed() >= FLUSH_INTERVAL {
+            if let Err(err) = writer.flush().await {
+                error!("Failed to flush replay of game on port {port}: {err:?}");
+                return;
+            }
+            last_flush = Instant::now();
+        }
+    }
+
+    if let Err(err) = writer.flush().await {
+        error!("Failed to flush replay of game on port {port}: {err:?}");
+        return;
+    }
+
+    let dropped = dropped.load(Ordering::Relaxed);
+    if dropped > 0 {
+        warn!("Dropped {dropped} records of replay of game on port {port}.");
+    }
+    info!("Replay of game on port {port} finished.");
+}
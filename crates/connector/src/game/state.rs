@@ -1,12 +1,28 @@
-use std::{collections::hash_map::Entry, net::SocketAddr};
+use std::{
+    collections::hash_map::Entry,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use async_std::sync::{Arc, RwLock, RwLockWriteGuard};
-use de_messages::Readiness;
+use bincode::error::EncodeError;
+use de_messages::{BorrowedFromPlayers, Readiness, RejoinToken, ToPlayers};
+use de_net::Reliability;
 use de_types::player::{Player, PlayerRange};
 use thiserror::Error;
 
-use super::buffer::PlayerBuffer;
+use super::{
+    buffer::PlayerBuffer,
+    ledger::EntityLedger,
+    validation::{self, InvalidMessage},
+};
+
+/// Maximum number of spectators which might be connected to a single game at
+/// the same time.
+const MAX_SPECTATORS: usize = 8;
+/// Total time for which each player might pause the game.
+const PAUSE_BUDGET: Duration = Duration::from_secs(120);
 
 #[derive(Clone)]
 pub(super) struct GameState {
@@ -14,9 +30,20 @@ pub(super) struct GameState {
 }
 
 impl GameState {
-    pub(super) fn new(max_players: Player) -> Self {
+    /// # Arguments
+    ///
+    /// * `max_players` - maximum number of players of the game.
+    ///
+    /// * `map_max_players` - maximum number of players supported by the map.
+    ///
+    /// * `host` - address of the client who opened the game.
+    pub(super) fn new(max_players: Player, map_max_players: Player, host: SocketAddr) -> Self {
         Self {
-            inner: Arc::new(RwLock::new(GameStateInner::new(max_players))),
+            inner: Arc::new(RwLock::new(GameStateInner::new(
+                max_players,
+                map_max_players,
+                host,
+            ))),
         }
     }
 
@@ -26,36 +53,128 @@ impl GameState {
         }
     }
 
-    /// Returns true if there is no players currently connected to the game.
+    /// Returns true if there is no players currently connected to the game
+    /// and there is no reserved slot of a disconnected player. Spectators are
+    /// not taken into account.
     pub(super) async fn is_empty(&self) -> bool {
         self.inner.read().await.is_empty()
     }
 
-    /// Returns true if a player with `addr` is connected to the game.
+    /// Returns true if a player or a spectator with `addr` is connected to the
+    /// game.
     pub(super) async fn contains(&self, addr: SocketAddr) -> bool {
         self.inner.read().await.contains(addr)
     }
 
+    /// Returns true if a spectator with `addr` is connected to the game.
+    pub(super) async fn is_spectator(&self, addr: SocketAddr) -> bool {
+        self.inner.read().await.is_spectator(addr)
+    }
+
+    /// Returns true if `addr` belongs to the host of the game, i.e. to the
+    /// client who opened the game, and the host is still part of the game.
+    pub(super) async fn is_host(&self, addr: SocketAddr) -> bool {
+        self.inner.read().await.is_host(addr)
+    }
+
     /// Returns ID of the player or None if such player is not part of the
     /// game.
     pub(super) async fn id(&self, addr: SocketAddr) -> Option<Player> {
         self.inner.read().await.id(addr)
     }
 
-    /// Adds a player to the game and returns ID of the added player.
-    pub(super) async fn add(&mut self, addr: SocketAddr) -> Result<Player, JoinError> {
+    /// Adds a player to the game and returns ID and rejoin token of the added
+    /// player.
+    pub(super) async fn add(
+        &mut self,
+        addr: SocketAddr,
+    ) -> Result<(Player, RejoinToken), JoinError> {
         self.inner.write().await.add(addr)
     }
 
+    /// Adds a previously disconnected player back to the game (see
+    /// [`Self::disconnect`]) and returns ID and session number of the player.
+    ///
+    /// Player messages are not delivered to the player until the game is
+    /// restored on their side, see [`GameStateGuard::restore`].
+    pub(super) async fn rejoin(
+        &mut self,
+        addr: SocketAddr,
+        token: RejoinToken,
+    ) -> Result<(Player, u8), JoinError> {
+        self.inner.write().await.rejoin(addr, token)
+    }
+
+    /// Returns address of a connected player or None if such player is not
+    /// connected to the game.
+    pub(super) async fn addr(&self, id: Player) -> Option<SocketAddr> {
+        self.inner.read().await.addr(id)
+    }
+
+    /// Adds a spectator to the game and returns IDs of all currently
+    /// connected players together with the readiness of the game.
+    ///
+    /// A spectator might join a running game as well. Player messages are not
+    /// delivered to such a spectator until the game is restored on their side,
+    /// see [`GameStateGuard::restore`].
+    pub(super) async fn add_spectator(
+        &mut self,
+        addr: SocketAddr,
+    ) -> Result<(Vec<Player>, Readiness), JoinError> {
+        self.inner.write().await.add_spectator(addr)
+    }
+
+    /// Prevents a client from joining, rejoining or spectating the game ever
+    /// again. The client is not removed from the game, see [`Self::remove`]
+    /// and [`Self::remove_spectator`].
+    pub(super) async fn ban(&mut self, addr: SocketAddr) {
+        self.inner.write().await.ban(addr)
+    }
+
     /// Removes a single player from the game. It returns state object of the
     /// player if the player was part of the game or None otherwise.
     pub(super) async fn remove(&mut self, addr: SocketAddr) -> Option<PlayerSlot> {
         self.inner.write().await.remove(addr)
     }
 
+    /// Removes a player whose connection was lost from the game but keeps
+    /// their slot reserved till `deadline`, so that the player might rejoin
+    /// the game.
+    ///
+    /// Slots are reserved only in a running game (i.e. the game is
+    /// initialized). None is returned and the player is kept in the game if
+    /// the game is not running or if the player is not part of the game.
+    pub(super) async fn disconnect(
+        &mut self,
+        addr: SocketAddr,
+        deadline: Instant,
+    ) -> Option<Player> {
+        self.inner.write().await.disconnect(addr, deadline)
+    }
+
+    /// Releases all reserved slots of disconnected players whose deadline is
+    /// before `time`. Returns IDs of the players.
+    pub(super) async fn expire(&mut self, time: Instant) -> Vec<Player> {
+        self.inner.write().await.expire(time)
+    }
+
+    /// Removes a single spectator from the game. It returns state object of
+    /// the spectator if the spectator was part of the game or None otherwise.
+    pub(super) async fn remove_spectator(&mut self, addr: SocketAddr) -> Option<SpectatorSlot> {
+        self.inner.write().await.remove_spectator(addr)
+    }
+
+    /// Removes all spectators from the game and returns their addresses and
+    /// state objects.
+    pub(super) async fn remove_spectators(&mut self) -> Vec<(SocketAddr, SpectatorSlot)> {
+        self.inner.write().await.remove_spectators()
+    }
+
     /// Updates readiness of a single player. Whole game readiness is updated
     /// once all players reach another readiness stage.
     ///
+    /// Readiness updates of spectators are ignored.
+    ///
     /// Returns true if game readiness progressed as a result (to the readiness
     /// of the player).
     pub(super) async fn update_readiness(
@@ -66,8 +185,75 @@ impl GameState {
         self.inner.write().await.update_readiness(addr, readiness)
     }
 
+    /// Locks (no further players or spectators might join the game) or
+    /// unlocks the game. This is possible only before the game starts.
+    ///
+    /// Returns true if the lock state has changed.
+    pub(super) async fn set_locked(&mut self, locked: bool) -> Result<bool, HostCommandError> {
+        self.inner.write().await.set_locked(locked)
+    }
+
+    /// Changes maximum number of players of the game. This is possible only
+    /// before the game starts, all connected players must fit under the new
+    /// maximum and the new maximum must not exceed the capacity of the map.
+    ///
+    /// Returns true if the maximum number of players has changed.
+    pub(super) async fn set_max_players(
+        &mut self,
+        max_players: Player,
+    ) -> Result<bool, HostCommandError> {
+        self.inner.write().await.set_max_players(max_players)
+    }
+
+    /// Records activity of a player or a spectator at `time`. Nothing happens
+    /// if the client is not part of the game.
+    pub(super) async fn touch(&mut self, addr: SocketAddr, time: Instant) {
+        self.inner.write().await.touch(addr, time);
+    }
+
+    /// Returns addresses of all players and spectators whose last activity
+    /// (see [`Self::touch`]) happened before `time`.
+    pub(super) async fn silent(&self, time: Instant) -> Vec<SocketAddr> {
+        self.inner.read().await.silent(time)
+    }
+
+    /// Pauses the game. Returns ID and the remaining pause budget of the
+    /// pausing player.
+    pub(super) async fn pause(
+        &mut self,
+        addr: SocketAddr,
+        time: Instant,
+    ) -> Result<(Player, Duration), PauseError> {
+        self.inner.write().await.pause(addr, time)
+    }
+
+    /// Records a vote of a player for resumption of the paused game. Returns
+    /// ID of the voting player.
+    pub(super) async fn vote_resume(&mut self, addr: SocketAddr) -> Result<Player, PauseError> {
+        self.inner.write().await.vote_resume(addr)
+    }
+
+    /// Resumes the paused game if all connected players voted for it or if
+    /// the pause budget of the pausing player is exhausted at `time`.
+    ///
+    /// Returns true if the game was resumed.
+    pub(super) async fn try_resume(&mut self, time: Instant) -> bool {
+        self.inner.write().await.try_resume(time)
+    }
+
+    /// Returns ID and the remaining pause budget of the player who paused the
+    /// game or None if the game is not paused.
+    pub(super) async fn paused(&self, time: Instant) -> Option<(Player, Duration)> {
+        self.inner.read().await.paused(time)
+    }
+
+    /// Returns an overview of the game and its participants.
+    pub(super) async fn overview(&self) -> GameOverview {
+        self.inner.read().await.overview()
+    }
+
     /// Constructs and returns package targets which includes all or all but
-    /// one players connected to the game.
+    /// one players and spectators connected to the game.
     ///
     /// # Arguments
     ///
@@ -83,7 +269,35 @@ pub(super) struct GameStateGuard<'a> {
 }
 
 impl<'a> GameStateGuard<'a> {
-    /// Returns an iterator over message buffers of all or all but one player.
+    /// Checks that a player message might be relayed to other players. See
+    /// [`validation::validate`].
+    pub(super) fn validate(
+        &self,
+        sender: Player,
+        message: &ToPlayers,
+    ) -> Result<(), InvalidMessage> {
+        validation::validate(sender, message, &self.guard.ledger)
+    }
+
+    /// Updates last known state of game entities with a message relayed among
+    /// the players.
+    pub(super) fn record(&mut self, message: &ToPlayers) {
+        self.guard.ledger.record(message);
+    }
+
+    /// Pushes a snapshot of all entities in the game to the message buffer of
+    /// a rejoined player (or of a spectator who joined a running game) and
+    /// returns the buffer. The client receives all player messages from now
+    /// on.
+    pub(super) fn restore(&mut self, addr: SocketAddr) -> Result<&mut PlayerBuffer, RestoreError> {
+        self.guard.restore(addr)
+    }
+
+    /// Returns an iterator over message buffers of all or all but one player
+    /// and of all spectators.
+    ///
+    /// Buffers of rejoined players and spectators whose game has not yet been
+    /// restored are excluded.
     ///
     /// # Arguments
     ///
@@ -97,64 +311,234 @@ impl<'a> GameStateGuard<'a> {
 }
 
 struct GameStateInner {
+    max_players: Player,
+    map_max_players: Player,
+    /// Address of the host of the game. It is None once the host leaves the
+    /// game or while their connection is lost.
+    host: Option<SocketAddr>,
     available_ids: AvailableIds,
     readiness: Readiness,
+    locked: bool,
+    pause: Option<Pause>,
     players: AHashMap<SocketAddr, PlayerSlot>,
+    disconnected: Vec<DisconnectedSlot>,
+    spectators: AHashMap<SocketAddr, SpectatorSlot>,
+    /// Addresses of kicked clients, see [`GameState::ban`].
+    banned: AHashSet<SocketAddr>,
+    ledger: EntityLedger,
 }
 
 impl GameStateInner {
-    fn new(max_players: Player) -> Self {
+    fn new(max_players: Player, map_max_players: Player, host: SocketAddr) -> Self {
         Self {
+            max_players,
+            map_max_players,
+            host: Some(host),
             available_ids: AvailableIds::new(max_players),
             readiness: Readiness::default(),
+            locked: false,
+            pause: None,
             players: AHashMap::new(),
+            disconnected: Vec::new(),
+            spectators: AHashMap::new(),
+            banned: AHashSet::new(),
+            ledger: EntityLedger::new(),
         }
     }
 
     fn is_empty(&self) -> bool {
-        self.players.is_empty()
+        self.players.is_empty() && self.disconnected.is_empty()
     }
 
     fn contains(&self, addr: SocketAddr) -> bool {
-        self.players.contains_key(&addr)
+        self.players.contains_key(&addr) || self.is_spectator(addr)
+    }
+
+    fn is_spectator(&self, addr: SocketAddr) -> bool {
+        self.spectators.contains_key(&addr)
+    }
+
+    fn is_host(&self, addr: SocketAddr) -> bool {
+        self.host == Some(addr) && self.players.contains_key(&addr)
     }
 
     fn id(&self, addr: SocketAddr) -> Option<Player> {
         self.players.get(&addr).map(|p| p.id)
     }
 
-    fn add(&mut self, addr: SocketAddr) -> Result<Player, JoinError> {
+    fn addr(&self, id: Player) -> Option<SocketAddr> {
+        self.players
+            .iter()
+            .find(|(_, player)| player.id == id)
+            .map(|(&addr, _)| addr)
+    }
+
+    fn add(&mut self, addr: SocketAddr) -> Result<(Player, RejoinToken), JoinError> {
+        if self.banned.contains(&addr) {
+            return Err(JoinError::Kicked);
+        }
         if self.readiness != Readiness::NotReady {
             return Err(JoinError::GameNotOpened);
         }
+        if self.locked {
+            return Err(JoinError::GameLocked);
+        }
+        if self.is_spectator(addr) {
+            return Err(JoinError::AlreadyJoined);
+        }
 
         match self.players.entry(addr) {
             Entry::Occupied(_) => Err(JoinError::AlreadyJoined),
             Entry::Vacant(vacant) => match self.available_ids.lease() {
                 Some(id) => {
-                    vacant.insert(PlayerSlot::new(id, addr));
-                    Ok(id)
+                    let slot = vacant.insert(PlayerSlot::new(id, addr));
+                    Ok((id, slot.token))
                 }
                 None => Err(JoinError::GameFull),
             },
         }
     }
 
+    fn rejoin(&mut self, addr: SocketAddr, token: RejoinToken) -> Result<(Player, u8), JoinError> {
+        if self.banned.contains(&addr) {
+            return Err(JoinError::Kicked);
+        }
+        if self.contains(addr) {
+            return Err(JoinError::AlreadyJoined);
+        }
+
+        let Some(index) = self.disconnected.iter().position(|d| d.token == token) else {
+            return Err(JoinError::InvalidToken);
+        };
+        // Entity indices of further sessions could not be told apart from
+        // older ones, see `NetEntityIndex`.
+        let Some(session) = self.disconnected[index].session.checked_add(1) else {
+            return Err(JoinError::InvalidToken);
+        };
+
+        let disconnected = self.disconnected.swap_remove(index);
+        if disconnected.host {
+            self.host = Some(addr);
+        }
+        let slot = PlayerSlot {
+            id: disconnected.id,
+            readiness: self.readiness,
+            last_active: Instant::now(),
+            pause_budget: disconnected.pause_budget,
+            token,
+            session,
+            restored: false,
+            buffer: PlayerBuffer::new(addr),
+        };
+        self.players.insert(addr, slot);
+        Ok((disconnected.id, session))
+    }
+
+    fn add_spectator(&mut self, addr: SocketAddr) -> Result<(Vec<Player>, Readiness), JoinError> {
+        if self.banned.contains(&addr) {
+            return Err(JoinError::Kicked);
+        }
+        if self.locked {
+            return Err(JoinError::GameLocked);
+        }
+        if self.players.contains_key(&addr) {
+            return Err(JoinError::AlreadyJoined);
+        }
+
+        let num_spectators = self.spectators.len();
+        match self.spectators.entry(addr) {
+            Entry::Occupied(_) => Err(JoinError::AlreadyJoined),
+            Entry::Vacant(vacant) => {
+                if num_spectators >= MAX_SPECTATORS {
+                    Err(JoinError::GameFull)
+                } else {
+                    // Entities of a running game are restored on request.
+                    let restored = self.readiness != Readiness::Initialized;
+                    vacant.insert(SpectatorSlot::new(addr, restored));
+
+                    let mut players: Vec<Player> =
+                        self.players.values().map(|player| player.id).collect();
+                    players.sort();
+                    Ok((players, self.readiness))
+                }
+            }
+        }
+    }
+
+    fn ban(&mut self, addr: SocketAddr) {
+        self.banned.insert(addr);
+    }
+
     fn remove(&mut self, addr: SocketAddr) -> Option<PlayerSlot> {
         match self.players.remove_entry(&addr) {
             Some((_, player)) => {
+                if self.host == Some(addr) {
+                    self.host = None;
+                }
                 self.available_ids.release(player.id);
+                self.ledger.remove_player(player.id);
                 Some(player)
             }
             None => None,
         }
     }
 
+    fn disconnect(&mut self, addr: SocketAddr, deadline: Instant) -> Option<Player> {
+        if self.readiness != Readiness::Initialized {
+            return None;
+        }
+
+        let player = self.players.remove(&addr)?;
+        let host = self.host == Some(addr);
+        if host {
+            self.host = None;
+        }
+        self.disconnected.push(DisconnectedSlot {
+            id: player.id,
+            host,
+            pause_budget: player.pause_budget,
+            token: player.token,
+            session: player.session,
+            deadline,
+        });
+        Some(player.id)
+    }
+
+    fn expire(&mut self, time: Instant) -> Vec<Player> {
+        let mut expired = Vec::new();
+        self.disconnected.retain(|slot| {
+            if slot.deadline < time {
+                expired.push(slot.id);
+                false
+            } else {
+                true
+            }
+        });
+
+        for &id in &expired {
+            self.available_ids.release(id);
+            self.ledger.remove_player(id);
+        }
+        expired
+    }
+
+    fn remove_spectator(&mut self, addr: SocketAddr) -> Option<SpectatorSlot> {
+        self.spectators.remove(&addr)
+    }
+
+    fn remove_spectators(&mut self) -> Vec<(SocketAddr, SpectatorSlot)> {
+        self.spectators.drain().collect()
+    }
+
     fn update_readiness(
         &mut self,
         addr: SocketAddr,
         readiness: Readiness,
     ) -> Result<bool, ReadinessUpdateError> {
+        if self.is_spectator(addr) {
+            return Ok(false);
+        }
+
         let Some(player) = self.players.get_mut(&addr) else {
             return Err(ReadinessUpdateError::UnknownClient(addr));
         };
@@ -194,9 +578,166 @@ impl GameStateInner {
         Ok(progressed)
     }
 
+    fn set_locked(&mut self, locked: bool) -> Result<bool, HostCommandError> {
+        if self.readiness != Readiness::NotReady {
+            return Err(HostCommandError::GameStarted);
+        }
+
+        let changed = self.locked != locked;
+        self.locked = locked;
+        Ok(changed)
+    }
+
+    fn set_max_players(&mut self, max_players: Player) -> Result<bool, HostCommandError> {
+        if self.readiness != Readiness::NotReady {
+            return Err(HostCommandError::GameStarted);
+        }
+        if max_players > self.map_max_players {
+            return Err(HostCommandError::MapCapacity(self.map_max_players));
+        }
+        if let Some(player) = self.players.values().find(|p| p.id > max_players) {
+            return Err(HostCommandError::SlotOccupied(player.id));
+        }
+
+        if self.max_players == max_players {
+            return Ok(false);
+        }
+
+        self.max_players = max_players;
+        self.available_ids = AvailableIds::new(max_players);
+        for player in self.players.values() {
+            self.available_ids.take(player.id);
+        }
+        Ok(true)
+    }
+
+    fn pause(&mut self, addr: SocketAddr, time: Instant) -> Result<(Player, Duration), PauseError> {
+        if self.readiness != Readiness::Initialized {
+            return Err(PauseError::GameNotRunning);
+        }
+        if self.pause.is_some() {
+            return Err(PauseError::AlreadyPaused);
+        }
+        let Some(player) = self.players.get(&addr) else {
+            return Err(PauseError::UnknownClient(addr));
+        };
+        if player.pause_budget.is_zero() {
+            return Err(PauseError::BudgetExhausted);
+        }
+
+        self.pause = Some(Pause {
+            player: player.id,
+            since: time,
+            deadline: time + player.pause_budget,
+            votes: Vec::new(),
+        });
+        Ok((player.id, player.pause_budget))
+    }
+
+    fn vote_resume(&mut self, addr: SocketAddr) -> Result<Player, PauseError> {
+        let Some(pause) = self.pause.as_mut() else {
+            return Err(PauseError::NotPaused);
+        };
+        let Some(player) = self.players.get(&addr) else {
+            return Err(PauseError::UnknownClient(addr));
+        };
+
+        if !pause.votes.contains(&player.id) {
+            pause.votes.push(player.id);
+        }
+        Ok(player.id)
+    }
+
+    fn try_resume(&mut self, time: Instant) -> bool {
+        let Some(pause) = self.pause.as_ref() else {
+            return false;
+        };
+
+        let voted = self.players.values().all(|p| pause.votes.contains(&p.id));
+        if time < pause.deadline && !voted {
+            return false;
+        }
+
+        let elapsed = time.saturating_duration_since(pause.since);
+        let id = pause.player;
+        self.pause = None;
+
+        let budget = self
+            .players
+            .values_mut()
+            .filter(|p| p.id == id)
+            .map(|p| &mut p.pause_budget)
+            .chain(
+                self.disconnected
+                    .iter_mut()
+                    .filter(|d| d.id == id)
+                    .map(|d| &mut d.pause_budget),
+            )
+            .next();
+        if let Some(budget) = budget {
+            *budget = budget.saturating_sub(elapsed);
+        }
+
+        true
+    }
+
+    fn paused(&self, time: Instant) -> Option<(Player, Duration)> {
+        self.pause
+            .as_ref()
+            .map(|pause| (pause.player, pause.deadline.saturating_duration_since(time)))
+    }
+
+    fn touch(&mut self, addr: SocketAddr, time: Instant) {
+        if let Some(player) = self.players.get_mut(&addr) {
+            player.last_active = player.last_active.max(time);
+        } else if let Some(spectator) = self.spectators.get_mut(&addr) {
+            spectator.last_active = spectator.last_active.max(time);
+        }
+    }
+
+    fn silent(&self, time: Instant) -> Vec<SocketAddr> {
+        let players = self
+            .players
+            .iter()
+            .map(|(&addr, player)| (addr, player.last_active));
+        let spectators = self
+            .spectators
+            .iter()
+            .map(|(&addr, spectator)| (addr, spectator.last_active));
+        players
+            .chain(spectators)
+            .filter_map(|(addr, last_active)| (last_active < time).then_some(addr))
+            .collect()
+    }
+
+    fn overview(&self) -> GameOverview {
+        let mut players: Vec<PlayerOverview> = self
+            .players
+            .iter()
+            .map(|(&addr, player)| PlayerOverview {
+                id: player.id,
+                addr,
+                readiness: player.readiness,
+            })
+            .collect();
+        players.sort_by_key(|player| player.id);
+
+        let mut disconnected: Vec<Player> = self.disconnected.iter().map(|d| d.id).collect();
+        disconnected.sort();
+
+        GameOverview {
+            max_players: self.max_players,
+            readiness: self.readiness,
+            locked: self.locked,
+            players,
+            disconnected,
+            spectators: self.spectators.len(),
+        }
+    }
+
     fn targets(&self, exclude: Option<SocketAddr>) -> Vec<SocketAddr> {
-        let mut addrs = Vec::with_capacity(self.players.len());
-        for &addr in self.players.keys() {
+        let mut addrs = Vec::with_capacity(self.players.len() + self.spectators.len());
+        for &addr in self.players.keys().chain(self.spectators.keys()) {
             if Some(addr) != exclude {
                 addrs.push(addr);
             }
@@ -204,20 +745,82 @@ impl GameStateInner {
         addrs
     }
 
+    fn restore(&mut self, addr: SocketAddr) -> Result<&mut PlayerBuffer, RestoreError> {
+        let (restored, buffer) = if let Some(player) = self.players.get_mut(&addr) {
+            (&mut player.restored, &mut player.buffer)
+        } else if let Some(spectator) = self.spectators.get_mut(&addr) {
+            (&mut spectator.restored, &mut spectator.buffer)
+        } else {
+            return Err(RestoreError::UnknownClient(addr));
+        };
+        if *restored {
+            return Err(RestoreError::AlreadyRestored);
+        }
+        *restored = true;
+
+        let time = Instant::now();
+        for (source, message) in self.ledger.snapshot() {
+            buffer.push(
+                Reliability::SemiOrdered,
+                &BorrowedFromPlayers::new(source, &message),
+                time,
+            )?;
+        }
+
+        Ok(buffer)
+    }
+
     fn buffers_mut(
         &mut self,
         exclude: Option<SocketAddr>,
     ) -> impl Iterator<Item = &mut PlayerBuffer> {
-        self.players.iter_mut().filter_map(move |(&addr, player)| {
-            if Some(addr) == exclude {
+        let players = self.players.iter_mut().filter_map(move |(&addr, player)| {
+            if Some(addr) == exclude || !player.restored {
                 None
             } else {
                 Some(&mut player.buffer)
             }
-        })
+        });
+        let spectators = self
+            .spectators
+            .values_mut()
+            .filter(|spectator| spectator.restored)
+            .map(|spectator| &mut spectator.buffer);
+        players.chain(spectators)
     }
 }
 
+/// State of a paused game.
+struct Pause {
+    /// The player who paused the game.
+    player: Player,
+    since: Instant,
+    /// The game is resumed at this time at the latest, i.e. once the pause
+    /// budget of the pausing player is exhausted.
+    deadline: Instant,
+    /// Players who voted for resumption of the game.
+    votes: Vec<Player>,
+}
+
+/// Point in time overview of a game.
+pub(super) struct GameOverview {
+    pub(super) max_players: Player,
+    pub(super) readiness: Readiness,
+    /// True if no further players or spectators might join the game.
+    pub(super) locked: bool,
+    /// Connected players sorted by their ID.
+    pub(super) players: Vec<PlayerOverview>,
+    /// Players with a reserved slot whose connection was lost.
+    pub(super) disconnected: Vec<Player>,
+    pub(super) spectators: usize,
+}
+
+pub(super) struct PlayerOverview {
+    pub(super) id: Player,
+    pub(super) addr: SocketAddr,
+    pub(super) readiness: Readiness,
+}
+
 struct AvailableIds(Vec<Player>);
 
 impl AvailableIds {
@@ -232,6 +835,16 @@ impl AvailableIds {
         self.0.pop()
     }
 
+    /// Borrows a particular ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ID is already borrowed.
+    fn take(&mut self, id: Player) {
+        let index = self.0.iter().position(|other| *other == id).unwrap();
+        self.0.remove(index);
+    }
+
     /// Makes a borrowed ID available for another borrow.
     ///
     /// # Panics
@@ -258,6 +871,46 @@ pub(super) enum JoinError {
     GameFull,
     #[error("The game is no longer opened.")]
     GameNotOpened,
+    #[error("There is no disconnected player with the rejoin token.")]
+    InvalidToken,
+    #[error("The game is locked.")]
+    GameLocked,
+    #[error("The client was kicked from the game.")]
+    Kicked,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub(super) enum PauseError {
+    #[error("Client {0:?} is not a player of the game.")]
+    UnknownClient(SocketAddr),
+    #[error("The game is not running.")]
+    GameNotRunning,
+    #[error("The game is already paused.")]
+    AlreadyPaused,
+    #[error("The game is not paused.")]
+    NotPaused,
+    #[error("The pause budget of the player is exhausted.")]
+    BudgetExhausted,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub(super) enum HostCommandError {
+    #[error("The game has already started.")]
+    GameStarted,
+    #[error("Player {0} is connected to the game.")]
+    SlotOccupied(Player),
+    #[error("The map supports at most {0} players.")]
+    MapCapacity(Player),
+}
+
+#[derive(Debug, Error)]
+pub(super) enum RestoreError {
+    #[error("Client {0:?} is not part of the game.")]
+    UnknownClient(SocketAddr),
+    #[error("The game has been already restored.")]
+    AlreadyRestored,
+    #[error("Could not encode snapshot message: {0}")]
+    Encode(#[from] EncodeError),
 }
 
 #[derive(Debug, Error, PartialEq)]
@@ -275,6 +928,16 @@ pub(super) enum ReadinessUpdateError {
 pub(super) struct PlayerSlot {
     id: Player,
     readiness: Readiness,
+    /// Time of the last message received from the player.
+    last_active: Instant,
+    /// Remaining time for which the player might pause the game.
+    pause_budget: Duration,
+    token: RejoinToken,
+    /// Number of times the player rejoined the game.
+    session: u8,
+    /// False if the player rejoined the game and the game has not yet been
+    /// restored on their side.
+    restored: bool,
     buffer: PlayerBuffer,
 }
 
@@ -283,6 +946,11 @@ impl PlayerSlot {
         Self {
             id,
             readiness: Readiness::default(),
+            last_active: Instant::now(),
+            pause_budget: PAUSE_BUDGET,
+            token: random_token(),
+            session: 0,
+            restored: true,
             buffer: PlayerBuffer::new(addr),
         }
     }
@@ -296,9 +964,52 @@ impl PlayerSlot {
     }
 }
 
+/// Generates an unguessable token with which the player may rejoin the game.
+fn random_token() -> RejoinToken {
+    let mut bytes = [0; 16];
+    getrandom::getrandom(&mut bytes).expect("Failed to generate a rejoin token.");
+    RejoinToken::new(u128::from_le_bytes(bytes))
+}
+
+/// Reserved slot of a player whose connection was lost.
+struct DisconnectedSlot {
+    id: Player,
+    /// True if the player was the host of the game. The host role is
+    /// restored once the player rejoins.
+    host: bool,
+    pause_budget: Duration,
+    token: RejoinToken,
+    session: u8,
+    /// The slot is released once this deadline passes.
+    deadline: Instant,
+}
+
+pub(super) struct SpectatorSlot {
+    /// Time of the last message received from the spectator.
+    last_active: Instant,
+    /// False if the spectator joined a running game and the game has not yet
+    /// been restored on their side.
+    restored: bool,
+    buffer: PlayerBuffer,
+}
+
+impl SpectatorSlot {
+    fn new(addr: SocketAddr, restored: bool) -> Self {
+        Self {
+            last_active: Instant::now(),
+            restored,
+            buffer: PlayerBuffer::new(addr),
+        }
+    }
+
+    pub(super) fn buffer_mut(&mut self) -> &mut PlayerBuffer {
+        &mut self.buffer
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
+    use std::{collections::HashSet, time::Duration};
 
     use async_std::task;
 
@@ -307,13 +1018,29 @@ mod tests {
     #[test]
     fn test_state() {
         task::block_on(task::spawn(async {
-            let mut state = GameState::new(Player::Player4);
+            let mut state = GameState::new(
+                Player::Player4,
+                Player::Player4,
+                "127.0.0.1:1001".parse().unwrap(),
+            );
             let mut ids: HashSet<Player> = HashSet::new();
 
-            assert!(ids.insert(state.add("127.0.0.1:1001".parse().unwrap()).await.unwrap()));
+            assert!(ids.insert(
+                state
+                    .add("127.0.0.1:1001".parse().unwrap())
+                    .await
+                    .unwrap()
+                    .0
+            ));
             assert!(state.contains("127.0.0.1:1001".parse().unwrap()).await);
 
-            assert!(ids.insert(state.add("127.0.0.1:1002".parse().unwrap()).await.unwrap()));
+            assert!(ids.insert(
+                state
+                    .add("127.0.0.1:1002".parse().unwrap())
+                    .await
+                    .unwrap()
+                    .0
+            ));
             assert!(state.contains("127.0.0.1:1001".parse().unwrap()).await);
             assert!(state.contains("127.0.0.1:1002".parse().unwrap()).await);
 
@@ -327,7 +1054,13 @@ mod tests {
             assert!(!state.contains("127.0.0.1:1001".parse().unwrap()).await);
             assert!(state.contains("127.0.0.1:1002".parse().unwrap()).await);
 
-            assert!(ids.insert(state.add("127.0.0.1:1001".parse().unwrap()).await.unwrap()));
+            assert!(ids.insert(
+                state
+                    .add("127.0.0.1:1001".parse().unwrap())
+                    .await
+                    .unwrap()
+                    .0
+            ));
             assert!(state.contains("127.0.0.1:1001".parse().unwrap()).await);
             assert!(state.contains("127.0.0.1:1002".parse().unwrap()).await);
 
@@ -342,6 +1075,7 @@ mod tests {
                         .add(format!("127.0.0.1:100{i}").parse().unwrap())
                         .await
                         .unwrap()
+                        .0
                 ));
             }
 
@@ -359,7 +1093,7 @@ mod tests {
         let client_b: SocketAddr = "127.0.0.1:8082".parse().unwrap();
         let client_c: SocketAddr = "127.0.0.1:8083".parse().unwrap();
 
-        let mut state = GameStateInner::new(Player::Player3);
+        let mut state = GameStateInner::new(Player::Player3, Player::Player4, client_a);
 
         state.add(client_a).unwrap();
         state.add(client_b).unwrap();
@@ -419,7 +1153,11 @@ mod tests {
 
     #[test]
     fn test_targets() {
-        let mut state = GameStateInner::new(Player::Player4);
+        let mut state = GameStateInner::new(
+            Player::Player4,
+            Player::Player4,
+            "127.0.0.1:2001".parse().unwrap(),
+        );
 
         assert!(state.targets(None).is_empty());
 
@@ -455,6 +1193,322 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_spectators() {
+        let player: SocketAddr = "127.0.0.1:3001".parse().unwrap();
+        let spectator: SocketAddr = "127.0.0.1:3002".parse().unwrap();
+
+        let mut state = GameStateInner::new(Player::Player1, Player::Player4, player);
+        state.add(player).unwrap();
+        assert_eq!(
+            state.add_spectator(spectator),
+            Ok((vec![Player::Player1], Readiness::NotReady))
+        );
+
+        assert!(state.contains(spectator));
+        assert!(state.is_spectator(spectator));
+        assert!(!state.is_spectator(player));
+        assert_eq!(state.id(spectator), None);
+        assert_eq!(state.add(spectator), Err(JoinError::AlreadyJoined));
+        assert_eq!(state.add_spectator(player), Err(JoinError::AlreadyJoined));
+        assert_eq!(
+            state.add_spectator(spectator),
+            Err(JoinError::AlreadyJoined)
+        );
+
+        for i in 1..MAX_SPECTATORS {
+            state
+                .add_spectator(format!("127.0.0.1:310{i}").parse().unwrap())
+                .unwrap();
+        }
+        assert_eq!(
+            state.add_spectator("127.0.0.1:3200".parse().unwrap()),
+            Err(JoinError::GameFull)
+        );
+
+        assert_eq!(state.targets(None).len(), 1 + MAX_SPECTATORS);
+        assert_eq!(state.buffers_mut(Some(player)).count(), MAX_SPECTATORS);
+
+        assert!(!state
+            .update_readiness(spectator, Readiness::Prepared)
+            .unwrap());
+        assert!(state.update_readiness(player, Readiness::Ready).unwrap());
+        assert_eq!(state.readiness, Readiness::Ready);
+
+        assert!(state.remove(player).is_some());
+        assert!(state.is_empty());
+        assert!(state.remove_spectator(spectator).is_some());
+        assert!(!state.contains(spectator));
+        assert_eq!(state.remove_spectators().len(), MAX_SPECTATORS - 1);
+        assert!(state.targets(None).is_empty());
+    }
+
+    #[test]
+    fn test_spectate_running() {
+        let client_a: SocketAddr = "127.0.0.1:3501".parse().unwrap();
+        let client_b: SocketAddr = "127.0.0.1:3502".parse().unwrap();
+        let spectator: SocketAddr = "127.0.0.1:3503".parse().unwrap();
+
+        let mut state = GameStateInner::new(Player::Player2, Player::Player4, client_a);
+        state.add(client_a).unwrap();
+        state.add(client_b).unwrap();
+        for readiness in [
+            Readiness::Ready,
+            Readiness::Prepared,
+            Readiness::Initialized,
+        ] {
+            state.update_readiness(client_a, readiness).unwrap();
+            state.update_readiness(client_b, readiness).unwrap();
+        }
+
+        assert_eq!(
+            state.add_spectator(spectator),
+            Ok((
+                vec![Player::Player1, Player::Player2],
+                Readiness::Initialized
+            ))
+        );
+        assert_eq!(state.targets(None).len(), 3);
+        // Player messages are not delivered before the game is restored.
+        assert_eq!(state.buffers_mut(None).count(), 2);
+
+        state.restore(spectator).unwrap();
+        assert_eq!(state.buffers_mut(None).count(), 3);
+        assert!(matches!(
+            state.restore(spectator),
+            Err(RestoreError::AlreadyRestored)
+        ));
+    }
+
+    #[test]
+    fn test_rejoin() {
+        let client_a: SocketAddr = "127.0.0.1:4001".parse().unwrap();
+        let client_b: SocketAddr = "127.0.0.1:4002".parse().unwrap();
+        let client_c: SocketAddr = "127.0.0.1:4003".parse().unwrap();
+
+        let mut state = GameStateInner::new(Player::Player2, Player::Player4, client_a);
+        let (id_a, token) = state.add(client_a).unwrap();
+        let (_, token_b) = state.add(client_b).unwrap();
+        assert_ne!(token, token_b);
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        // Slots are reserved only in a running game.
+        assert!(state.disconnect(client_a, deadline).is_none());
+        assert!(state.contains(client_a));
+
+        for readiness in [
+            Readiness::Ready,
+            Readiness::Prepared,
+            Readiness::Initialized,
+        ] {
+            state.update_readiness(client_a, readiness).unwrap();
+            state.update_readiness(client_b, readiness).unwrap();
+        }
+
+        assert_eq!(state.disconnect(client_a, deadline), Some(id_a));
+        assert!(!state.contains(client_a));
+        assert!(!state.is_empty());
+        assert_eq!(state.targets(None), vec![client_b]);
+
+        let overview = state.overview();
+        assert_eq!(overview.max_players, Player::Player2);
+        assert_eq!(overview.readiness, Readiness::Initialized);
+        assert_eq!(overview.players.len(), 1);
+        assert_eq!(overview.players[0].addr, client_b);
+        assert_eq!(overview.disconnected, vec![id_a]);
+
+        assert_eq!(
+            state.rejoin(client_c, RejoinToken::new(7)),
+            Err(JoinError::InvalidToken)
+        );
+        assert_eq!(state.rejoin(client_b, token), Err(JoinError::AlreadyJoined));
+        assert_eq!(state.rejoin(client_c, token), Ok((id_a, 1)));
+        assert_eq!(state.id(client_c), Some(id_a));
+        // The host keeps their role after a rejoin.
+        assert!(state.is_host(client_c));
+        assert_eq!(state.targets(None).len(), 2);
+        // Player messages are not delivered before the game is restored.
+        assert_eq!(state.buffers_mut(None).count(), 1);
+
+        state.restore(client_c).unwrap();
+        assert_eq!(state.buffers_mut(None).count(), 2);
+        assert!(matches!(
+            state.restore(client_c),
+            Err(RestoreError::AlreadyRestored)
+        ));
+
+        assert_eq!(state.disconnect(client_c, deadline), Some(id_a));
+        assert!(state.expire(Instant::now()).is_empty());
+        assert_eq!(state.expire(deadline + Duration::from_secs(1)), vec![id_a]);
+        assert_eq!(state.rejoin(client_c, token), Err(JoinError::InvalidToken));
+        assert_eq!(state.available_ids.lease(), Some(id_a));
+    }
+
+    #[test]
+    fn test_host_commands() {
+        let client_a: SocketAddr = "127.0.0.1:5001".parse().unwrap();
+        let client_b: SocketAddr = "127.0.0.1:5002".parse().unwrap();
+        let client_c: SocketAddr = "127.0.0.1:5003".parse().unwrap();
+        let client_d: SocketAddr = "127.0.0.1:5004".parse().unwrap();
+
+        let mut state = GameStateInner::new(Player::Player4, Player::Player4, client_a);
+        state.add(client_a).unwrap();
+        state.add(client_b).unwrap();
+        state.add(client_c).unwrap();
+        state.remove(client_b).unwrap();
+        assert_eq!(state.addr(Player::Player3), Some(client_c));
+        assert_eq!(state.addr(Player::Player2), None);
+
+        assert_eq!(state.set_locked(true), Ok(true));
+        assert_eq!(state.set_locked(true), Ok(false));
+        assert_eq!(state.add(client_d), Err(JoinError::GameLocked));
+        assert_eq!(state.add_spectator(client_d), Err(JoinError::GameLocked));
+        assert_eq!(state.set_locked(false), Ok(true));
+
+        assert_eq!(
+            state.set_max_players(Player::Player2),
+            Err(HostCommandError::SlotOccupied(Player::Player3))
+        );
+        assert_eq!(state.set_max_players(Player::Player3), Ok(true));
+        assert_eq!(state.set_max_players(Player::Player3), Ok(false));
+        assert_eq!(state.overview().max_players, Player::Player3);
+        assert_eq!(state.add(client_d).unwrap().0, Player::Player2);
+        assert_eq!(state.add(client_b), Err(JoinError::GameFull));
+
+        for client in [client_a, client_c, client_d] {
+            state.update_readiness(client, Readiness::Ready).unwrap();
+        }
+        assert_eq!(state.set_locked(true), Err(HostCommandError::GameStarted));
+        assert_eq!(
+            state.set_max_players(Player::Player4),
+            Err(HostCommandError::GameStarted)
+        );
+    }
+
+    #[test]
+    fn test_ban() {
+        let client_a: SocketAddr = "127.0.0.1:5201".parse().unwrap();
+        let client_b: SocketAddr = "127.0.0.1:5202".parse().unwrap();
+        let client_c: SocketAddr = "127.0.0.1:5203".parse().unwrap();
+
+        let mut state = GameStateInner::new(Player::Player4, Player::Player4, client_a);
+        state.add(client_a).unwrap();
+        let (_, token) = state.add(client_b).unwrap();
+
+        state.ban(client_b);
+        state.remove(client_b).unwrap();
+        assert_eq!(state.add(client_b), Err(JoinError::Kicked));
+        assert_eq!(state.add_spectator(client_b), Err(JoinError::Kicked));
+        assert_eq!(state.rejoin(client_b, token), Err(JoinError::Kicked));
+
+        assert_eq!(state.add(client_c).unwrap().0, Player::Player2);
+    }
+
+    #[test]
+    fn test_host() {
+        let client_a: SocketAddr = "127.0.0.1:5501".parse().unwrap();
+        let client_b: SocketAddr = "127.0.0.1:5502".parse().unwrap();
+        let client_c: SocketAddr = "127.0.0.1:5503".parse().unwrap();
+
+        let mut state = GameStateInner::new(Player::Player2, Player::Player3, client_a);
+        // The host is not part of the game until they join.
+        assert!(!state.is_host(client_a));
+        state.add(client_a).unwrap();
+        state.add(client_b).unwrap();
+        assert!(state.is_host(client_a));
+        assert!(!state.is_host(client_b));
+
+        assert_eq!(
+            state.set_max_players(Player::Player4),
+            Err(HostCommandError::MapCapacity(Player::Player3))
+        );
+        assert_eq!(state.set_max_players(Player::Player3), Ok(true));
+
+        // The host role is not passed to the player who takes the slot of the
+        // host.
+        state.remove(client_a).unwrap();
+        assert_eq!(state.add(client_c).unwrap().0, Player::Player1);
+        assert!(!state.is_host(client_c));
+        assert!(!state.is_host(client_a));
+    }
+
+    #[test]
+    fn test_activity() {
+        let player: SocketAddr = "127.0.0.1:6001".parse().unwrap();
+        let spectator: SocketAddr = "127.0.0.1:6002".parse().unwrap();
+        let other: SocketAddr = "127.0.0.1:6003".parse().unwrap();
+
+        let mut state = GameStateInner::new(Player::Player2, Player::Player4, player);
+        let start = Instant::now();
+        state.add(player).unwrap();
+        state.add_spectator(spectator).unwrap();
+        assert!(state.silent(start).is_empty());
+
+        let time = start + Duration::from_secs(10);
+        state.touch(player, time);
+        state.touch(other, time);
+        assert_eq!(state.silent(time), vec![spectator]);
+        // Activity never goes back in time.
+        state.touch(player, start);
+        assert_eq!(state.silent(time), vec![spectator]);
+
+        state.touch(spectator, time + Duration::from_secs(1));
+        assert_eq!(state.silent(time + Duration::from_secs(1)), vec![player]);
+    }
+
+    #[test]
+    fn test_pause() {
+        let client_a: SocketAddr = "127.0.0.1:7001".parse().unwrap();
+        let client_b: SocketAddr = "127.0.0.1:7002".parse().unwrap();
+
+        let mut state = GameStateInner::new(Player::Player2, Player::Player4, client_a);
+        let (id_a, _) = state.add(client_a).unwrap();
+        let (id_b, _) = state.add(client_b).unwrap();
+
+        let start = Instant::now();
+        assert_eq!(
+            state.pause(client_a, start),
+            Err(PauseError::GameNotRunning)
+        );
+
+        for readiness in [
+            Readiness::Ready,
+            Readiness::Prepared,
+            Readiness::Initialized,
+        ] {
+            state.update_readiness(client_a, readiness).unwrap();
+            state.update_readiness(client_b, readiness).unwrap();
+        }
+
+        assert_eq!(state.vote_resume(client_a), Err(PauseError::NotPaused));
+        assert_eq!(state.pause(client_a, start), Ok((id_a, PAUSE_BUDGET)));
+        assert_eq!(state.pause(client_b, start), Err(PauseError::AlreadyPaused));
+        assert_eq!(state.paused(start), Some((id_a, PAUSE_BUDGET)));
+
+        // All players have to vote.
+        let time = start + Duration::from_secs(20);
+        assert_eq!(state.vote_resume(client_b), Ok(id_b));
+        assert!(!state.try_resume(time));
+        assert_eq!(state.vote_resume(client_a), Ok(id_a));
+        assert!(state.try_resume(time));
+        assert!(state.paused(time).is_none());
+        assert!(!state.try_resume(time));
+
+        // Consumed pause time is deducted from the budget.
+        let remaining = PAUSE_BUDGET - Duration::from_secs(20);
+        assert_eq!(state.pause(client_a, time), Ok((id_a, remaining)));
+        assert!(!state.try_resume(time + remaining - Duration::from_secs(1)));
+        assert!(state.try_resume(time + remaining));
+        assert_eq!(
+            state.pause(client_a, time + remaining),
+            Err(PauseError::BudgetExhausted)
+        );
+        assert_eq!(
+            state.pause(client_b, time + remaining),
+            Ok((id_b, PAUSE_BUDGET))
+        );
+    }
+
     #[test]
     fn test_available_ids() {
         let mut ids = AvailableIds::new(Player::Player3);
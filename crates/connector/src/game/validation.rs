@@ -0,0 +1,224 @@
+use de_messages::{EntityNet, ToPlayers};
+use de_types::player::Player;
+use thiserror::Error;
+
+use super::ledger::EntityLedger;
+
+/// Checks that a player message might be sent by a player.
+///
+/// Players may control (spawn, despawn, move, etc.) only the entities they
+/// simulate. The only exception is health of other players' entities, which
+/// might be decreased because entities are damaged by the attacking player.
+///
+/// Chat messages and projectiles are always valid because the sender of a
+/// relayed message is always set by the server.
+///
+/// # Arguments
+///
+/// * `sender` - the player who sent the message.
+///
+/// * `message` - the validated message.
+///
+/// * `ledger` - last known state of the game entities, the message must not
+///   be yet recorded to it.
+pub(super) fn validate(
+    sender: Player,
+    message: &ToPlayers,
+    ledger: &EntityLedger,
+) -> Result<(), InvalidMessage> {
+    match message {
+        ToPlayers::Chat(_)
+        | ToPlayers::Projectile(_)
+        | ToPlayers::Resync { .. }
+        | ToPlayers::SnapshotAck { .. } => Ok(()),
+        ToPlayers::Spawn { entity, player, .. } => {
+            owned(sender, *entity)?;
+            if *player != sender {
+                return Err(InvalidMessage::ForeignSpawn { player: *player });
+            }
+            if ledger.contains(*entity) {
+                return Err(InvalidMessage::DuplicateEntity { entity: *entity });
+            }
+            Ok(())
+        }
+        ToPlayers::Despawn { entity }
+        | ToPlayers::SetPath { entity, .. }
+        | ToPlayers::Transform { entity, .. } => owned(sender, *entity),
+        ToPlayers::ChangeHealth { entity, delta } => {
+            if !ledger.contains(*entity) {
+                return Err(InvalidMessage::UnknownEntity { entity: *entity });
+            }
+            if entity.player() != sender && f32::from(delta) > 0. {
+                return Err(InvalidMessage::ForeignHealing { entity: *entity });
+            }
+            Ok(())
+        }
+        ToPlayers::Checksum { entities, .. } => {
+            for checksum in entities.entities() {
+                owned(sender, checksum.entity())?;
+            }
+            Ok(())
+        }
+        ToPlayers::Snapshot(part) => {
+            for delta in part.changed() {
+                owned(sender, delta.entity())?;
+            }
+            for &entity in part.removed() {
+                owned(sender, entity)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn owned(sender: Player, entity: EntityNet) -> Result<(), InvalidMessage> {
+    if entity.player() == sender {
+        Ok(())
+    } else {
+        Err(InvalidMessage::ForeignEntity { entity })
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub(super) enum InvalidMessage {
+    #[error("entity {entity:?} is simulated by a different player")]
+    ForeignEntity { entity: EntityNet },
+    #[error("cannot spawn an entity of a different player {player}")]
+    ForeignSpawn { player: Player },
+    #[error("entity {entity:?} already exists")]
+    DuplicateEntity { entity: EntityNet },
+    #[error("entity {entity:?} does not exist")]
+    UnknownEntity { entity: EntityNet },
+    #[error("cannot increase health of entity {entity:?} of a different player")]
+    ForeignHealing { entity: EntityNet },
+}
+
+impl InvalidMessage {
+    /// Returns true if the message could not have been sent by a well behaved
+    /// client.
+    ///
+    /// Messages related to unknown entities might be sent by well behaved
+    /// clients because messages from different players are not ordered, e.g.
+    /// an entity might be damaged by one player right after it was despawned
+    /// by another player.
+    pub(super) fn is_offense(&self) -> bool {
+        !matches!(self, Self::UnknownEntity { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use de_messages::{
+        EntityDeltaNet, EntityStateNet, HealthDelta, NetEntityIndex, SnapshotPartNet, TransformNet,
+    };
+    use de_types::objects::{ActiveObjectType, UnitType};
+    use glam::{Vec3, Vec4};
+
+    use super::*;
+
+    fn spawn(entity: EntityNet, player: Player) -> ToPlayers {
+        ToPlayers::Spawn {
+            entity,
+            player,
+            object_type: ActiveObjectType::Unit(UnitType::Attacker),
+            transform: TransformNet::new(Vec3::ZERO.into(), Vec4::W.into()),
+        }
+    }
+
+    fn change_health(entity: EntityNet, delta: f32) -> ToPlayers {
+        ToPlayers::ChangeHealth {
+            entity,
+            delta: HealthDelta::try_from(delta).unwrap(),
+        }
+    }
+
+    fn snapshot(changed: EntityNet, removed: EntityNet) -> ToPlayers {
+        let state = EntityStateNet::new(
+            TransformNet::new(Vec3::ZERO.into(), Vec4::W.into()),
+            10.,
+            None,
+        );
+        let delta = EntityDeltaNet::new(changed, None, &state).unwrap();
+        let mut parts =
+            SnapshotPartNet::split(1, None, Duration::ZERO, vec![delta], vec![removed]).unwrap();
+        ToPlayers::Snapshot(parts.pop().unwrap())
+    }
+
+    #[test]
+    fn test_validate() {
+        let entity_a = EntityNet::new(Player::Player1, NetEntityIndex::new(1, 0).unwrap());
+        let entity_b = EntityNet::new(Player::Player2, NetEntityIndex::new(1, 0).unwrap());
+        let entity_c = EntityNet::new(Player::Player2, NetEntityIndex::new(2, 0).unwrap());
+
+        let mut ledger = EntityLedger::new();
+        assert_eq!(
+            validate(Player::Player1, &spawn(entity_a, Player::Player1), &ledger),
+            Ok(())
+        );
+        ledger.record(&spawn(entity_a, Player::Player1));
+        ledger.record(&spawn(entity_b, Player::Player2));
+
+        assert_eq!(
+            validate(Player::Player1, &spawn(entity_a, Player::Player1), &ledger),
+            Err(InvalidMessage::DuplicateEntity { entity: entity_a })
+        );
+        assert_eq!(
+            validate(Player::Player1, &spawn(entity_c, Player::Player2), &ledger),
+            Err(InvalidMessage::ForeignEntity { entity: entity_c })
+        );
+        assert_eq!(
+            validate(Player::Player2, &spawn(entity_c, Player::Player1), &ledger),
+            Err(InvalidMessage::ForeignSpawn {
+                player: Player::Player1
+            })
+        );
+
+        assert_eq!(
+            validate(
+                Player::Player1,
+                &ToPlayers::Despawn { entity: entity_b },
+                &ledger
+            ),
+            Err(InvalidMessage::ForeignEntity { entity: entity_b })
+        );
+        assert_eq!(
+            validate(
+                Player::Player2,
+                &ToPlayers::Despawn { entity: entity_b },
+                &ledger
+            ),
+            Ok(())
+        );
+
+        assert_eq!(
+            validate(Player::Player1, &change_health(entity_b, -10.), &ledger),
+            Ok(())
+        );
+        assert_eq!(
+            validate(Player::Player1, &change_health(entity_b, 10.), &ledger),
+            Err(InvalidMessage::ForeignHealing { entity: entity_b })
+        );
+        assert_eq!(
+            validate(Player::Player2, &change_health(entity_b, 10.), &ledger),
+            Ok(())
+        );
+        let error = validate(Player::Player1, &change_health(entity_c, -10.), &ledger).unwrap_err();
+        assert_eq!(error, InvalidMessage::UnknownEntity { entity: entity_c });
+        assert!(!error.is_offense());
+
+        assert_eq!(
+            validate(Player::Player2, &snapshot(entity_b, entity_c), &ledger),
+            Ok(())
+        );
+        assert_eq!(
+            validate(Player::Player2, &snapshot(entity_b, entity_a), &ledger),
+            Err(InvalidMessage::ForeignEntity { entity: entity_a })
+        );
+        assert_eq!(
+            validate(Player::Player1, &snapshot(entity_b, entity_a), &ledger),
+            Err(InvalidMessage::ForeignEntity { entity: entity_b })
+        );
+    }
+}
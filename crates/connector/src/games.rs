@@ -0,0 +1,61 @@
+use ahash::AHashMap;
+use async_std::sync::{Arc, RwLock};
+use de_messages::LanGame;
+
+use crate::game::{GameMetrics, GameMonitor};
+
+/// Registry of all running games, used for their monitoring.
+#[derive(Clone)]
+pub(crate) struct Games {
+    inner: Arc<RwLock<AHashMap<u16, GameMonitor>>>,
+}
+
+impl Games {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(AHashMap::new())),
+        }
+    }
+
+    /// Registers a newly started game.
+    pub(crate) async fn insert(&mut self, monitor: GameMonitor) {
+        self.inner.write().await.insert(monitor.port(), monitor);
+    }
+
+    /// Unregisters a finished game.
+    pub(crate) async fn remove(&mut self, port: u16) {
+        self.inner.write().await.remove(&port);
+    }
+
+    /// Collects up to `limit` games which might be joined, sorted by their
+    /// port.
+    pub(crate) async fn lan_games(&self, limit: usize) -> Vec<LanGame> {
+        let mut monitors: Vec<GameMonitor> = self.inner.read().await.values().cloned().collect();
+        monitors.sort_by_key(|monitor| monitor.port());
+
+        let mut games = Vec::new();
+        for monitor in monitors {
+            if games.len() >= limit {
+                break;
+            }
+            if let Some(game) = monitor.lan_game().await {
+                games.push(game);
+            }
+        }
+        games
+    }
+
+    /// Collects metrics of all running games sorted by their port.
+    pub(crate) async fn metrics(&self) -> Vec<GameMetrics> {
+        // The registry must not be locked while the individual games are,
+        // otherwise game startup and shutdown would be blocked.
+        let mut monitors: Vec<GameMonitor> = self.inner.read().await.values().cloned().collect();
+        monitors.sort_by_key(|monitor| monitor.port());
+
+        let mut metrics = Vec::with_capacity(monitors.len());
+        for monitor in monitors {
+            metrics.push(monitor.metrics().await);
+        }
+        metrics
+    }
+}
@@ -3,14 +3,24 @@ use async_std::task;
 use de_net::Socket;
 use tracing::info;
 
-use crate::server::MainServer;
+use crate::{
+    admin::AdminServer, discovery::LanAnnouncer, games::Games, server::MainServer,
+    shutdown::Shutdown,
+};
+pub use crate::{conf::ConnectorConf, embedded::EmbeddedConnector};
 
+mod admin;
 mod clients;
+mod conf;
+mod discovery;
+mod embedded;
 mod game;
+mod games;
 mod server;
+mod shutdown;
 
-const PORT: u16 = 8082;
-
+/// Runs a stand-alone connector configured with environment variables. This
+/// blocks until the connector fails.
 pub fn start() -> Result<(), String> {
     info!("Starting...");
     task::block_on(task::spawn(async {
@@ -19,11 +29,81 @@ pub fn start() -> Result<(), String> {
 }
 
 async fn start_inner() -> anyhow::Result<()> {
-    let socket = Socket::bind(Some(PORT))
+    let conf = ConnectorConf::from_env()?;
+    let server = bind(conf, Shutdown::never()).await?;
+    server.run().await
+}
+
+/// Binds all servers of a connector and spawns all auxiliary tasks. The main
+/// server is returned and it is up to the caller to run it.
+async fn bind(conf: ConnectorConf, shutdown: Shutdown) -> anyhow::Result<MainServer> {
+    let port = conf.port();
+    let socket = Socket::bind(Some(port))
         .await
-        .with_context(|| format!("Failed to open network on port {PORT}"))?;
-    info!("Listening on port {PORT}");
+        .with_context(|| format!("Failed to open network on port {port}"))?;
+    let socket = match conf.impairment() {
+        Some(impairment) => socket.with_impairment(impairment.clone()),
+        None => socket,
+    };
+    info!("Listening on port {port}");
 
-    let server = MainServer::start(socket);
-    server.run().await
+    match conf.replay_dir() {
+        Some(dir) => info!("Recording game replays to {dir:?}"),
+        None => info!("Game replay recording is disabled"),
+    }
+
+    match conf.game_key() {
+        Some(_) => info!("Games require authentication"),
+        None => info!("Game authentication is disabled"),
+    }
+
+    info!(
+        "Silent clients are removed after {:?}",
+        conf.client_timeout()
+    );
+
+    let games = Games::new();
+
+    let admin = match conf.admin_addr() {
+        Some(addr) => {
+            let admin = AdminServer::bind(addr, games.clone(), shutdown.clone())
+                .await
+                .with_context(|| format!("Failed to open admin endpoint on {addr}"))?;
+            info!("Admin endpoint listening on {addr}");
+            Some(admin)
+        }
+        None => {
+            info!("Admin endpoint is disabled");
+            None
+        }
+    };
+
+    let announcer = match conf.lan_announce() {
+        Some(addr) => Some(
+            LanAnnouncer::bind(addr, port, games.clone(), shutdown.clone())
+                .await
+                .with_context(|| format!("Failed to open LAN announcements to {addr}"))?,
+        ),
+        None => {
+            info!("LAN announcements are disabled");
+            None
+        }
+    };
+
+    if let Some(admin) = admin {
+        task::spawn(admin.run());
+    }
+    if let Some(announcer) = announcer {
+        task::spawn(announcer.run());
+    }
+
+    Ok(MainServer::start(
+        socket,
+        games,
+        conf.replay_dir().cloned(),
+        conf.game_key().map(ToOwned::to_owned),
+        conf.impairment().cloned(),
+        conf.client_timeout(),
+        shutdown,
+    ))
 }
@@ -1,15 +1,22 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
 
 use anyhow::Context;
-use async_std::task;
-use de_messages::{FromServer, GameOpenError, ToServer};
+use async_std::{future::timeout, task};
+use de_lobby_model::GameSecret;
+use de_messages::{FromServer, GameOpenError, ToServer, PROTOCOL_VERSION};
 use de_net::{
-    self, MessageDecoder, OutPackage, PackageReceiver, PackageSender, Peers, Reliability, Socket,
+    self, Authentication, Impairment, MessageDecoder, OutPackage, PackageReceiver, PackageSender,
+    Peers, Reliability, SessionSecret, Socket,
 };
 use de_types::player::Player;
 use tracing::{error, info, warn};
 
-use crate::{clients::Clients, game};
+use crate::{
+    clients::Clients,
+    game,
+    games::Games,
+    shutdown::{Shutdown, SHUTDOWN_CHECK_INTERVAL},
+};
 
 /// Main game server responsible for initial communication with clients and
 /// establishment of game sub-servers.
@@ -17,31 +24,77 @@ pub(crate) struct MainServer {
     outputs: PackageSender,
     inputs: PackageReceiver,
     clients: Clients,
+    games: Games,
+    replay_dir: Option<PathBuf>,
+    game_key: Option<String>,
+    impairment: Option<Impairment>,
+    client_timeout: Duration,
+    shutdown: Shutdown,
 }
 
 impl MainServer {
     /// Setup the server & startup its network stack.
-    pub(crate) fn start(socket: Socket) -> Self {
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - socket of the main server.
+    ///
+    /// * `games` - registry of running games, all opened games are registered
+    ///   into it.
+    ///
+    /// * `replay_dir` - if not None, replays of all games are recorded into
+    ///   this directory.
+    ///
+    /// * `game_key` - if not None, all games require authentication with a
+    ///   game secret derived from this key (see [`GameSecret::derive`]).
+    ///
+    /// * `impairment` - if not None, network conditions of all game servers
+    ///   are degraded accordingly. This is intended for testing only.
+    ///
+    /// * `client_timeout` - clients of all games which do not send any message
+    ///   for this long are considered dead.
+    ///
+    /// * `shutdown` - the server stops once this signal is triggered.
+    pub(crate) fn start(
+        socket: Socket,
+        games: Games,
+        replay_dir: Option<PathBuf>,
+        game_key: Option<String>,
+        impairment: Option<Impairment>,
+        client_timeout: Duration,
+        shutdown: Shutdown,
+    ) -> Self {
         let (outputs, inputs, _) = de_net::startup(
             |t| {
                 task::spawn(t);
             },
             socket,
+            Authentication::disabled(),
         );
         Self {
             outputs,
             inputs,
             clients: Clients::new(),
+            games,
+            replay_dir,
+            game_key,
+            impairment,
+            client_timeout,
+            shutdown,
         }
     }
 
     pub(crate) async fn run(mut self) -> anyhow::Result<()> {
         loop {
-            let package = self
-                .inputs
-                .recv()
-                .await
-                .context("Inputs channel unexpectedly closed")?;
+            if self.shutdown.is_triggered() {
+                info!("Main server is shutting down.");
+                return Ok(());
+            }
+
+            let Ok(result) = timeout(SHUTDOWN_CHECK_INTERVAL, self.inputs.recv()).await else {
+                continue;
+            };
+            let package = result.context("Inputs channel unexpectedly closed")?;
 
             match package.peers() {
                 Peers::Players => {
@@ -67,14 +120,55 @@ impl MainServer {
 
             match message {
                 ToServer::Ping(id) => self.reply(&FromServer::Pong(id), source).await?,
-                ToServer::OpenGame { max_players } => self.open_game(source, max_players).await?,
+                ToServer::OpenGame {
+                    version,
+                    max_players,
+                    map_max_players,
+                    map_hash,
+                } => {
+                    if version != PROTOCOL_VERSION {
+                        warn!(
+                            "OpenGame request from {source:?} with incompatible protocol \
+                             version {version}."
+                        );
+                        self.reply(
+                            &FromServer::GameOpenError(GameOpenError::IncompatibleVersion {
+                                server: PROTOCOL_VERSION,
+                            }),
+                            source,
+                        )
+                        .await?;
+                        continue;
+                    }
+                    if max_players > map_max_players {
+                        warn!(
+                            "OpenGame request from {source:?} with {max_players} players on \
+                             a map for {map_max_players} players."
+                        );
+                        self.reply(
+                            &FromServer::GameOpenError(GameOpenError::TooManyPlayers),
+                            source,
+                        )
+                        .await?;
+                        continue;
+                    }
+
+                    self.open_game(source, max_players, map_max_players, map_hash)
+                        .await?
+                }
             }
         }
 
         Ok(())
     }
 
-    async fn open_game(&mut self, source: SocketAddr, max_players: Player) -> anyhow::Result<()> {
+    async fn open_game(
+        &mut self,
+        source: SocketAddr,
+        max_players: Player,
+        map_max_players: Player,
+        map_hash: [u8; 32],
+    ) -> anyhow::Result<()> {
         if let Err(err) = self.clients.reserve(source).await {
             warn!("OpenGame request error: {err}");
             self.reply(
@@ -87,12 +181,40 @@ impl MainServer {
 
         match Socket::bind(None).await {
             Ok(socket) => {
+                let socket = match self.impairment.as_ref() {
+                    Some(impairment) => socket.with_impairment(impairment.clone()),
+                    None => socket,
+                };
                 let port = socket.port();
                 self.clients.set(source, port).await;
 
+                let (nonce, authentication) = match self.game_key.as_ref() {
+                    Some(key) => {
+                        let nonce = fastrand::u64(..);
+                        let secret = GameSecret::derive(key.as_bytes(), port, nonce);
+                        let authentication =
+                            Authentication::server(SessionSecret::new(*secret.bytes()));
+                        (Some(nonce), authentication)
+                    }
+                    None => (None, Authentication::disabled()),
+                };
+
                 info!("Starting new game on port {port}.");
-                self.reply(&FromServer::GameOpened { port }, source).await?;
-                game::startup(self.clients.clone(), socket, source, max_players).await;
+                self.reply(&FromServer::GameOpened { port, nonce }, source)
+                    .await?;
+                game::startup(
+                    self.clients.clone(),
+                    self.games.clone(),
+                    socket,
+                    authentication,
+                    source,
+                    max_players,
+                    map_max_players,
+                    map_hash,
+                    self.replay_dir.as_deref(),
+                    self.client_timeout,
+                )
+                .await;
                 Ok(())
             }
             Err(error) => {
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use async_std::channel::{bounded, Receiver, Sender};
+
+/// Tasks waiting for an event check the signal at least this often.
+pub(crate) const SHUTDOWN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Signal telling long running tasks of the connector (main server, admin
+/// endpoint, LAN announcer) to finish. Already running games are not affected
+/// by the signal, they finish once all their players leave.
+#[derive(Clone)]
+pub(crate) struct Shutdown(Option<Receiver<()>>);
+
+impl Shutdown {
+    /// Returns a new signal which is triggered once the returned sender is
+    /// dropped.
+    pub(crate) fn new() -> (Sender<()>, Self) {
+        let (sender, receiver) = bounded(1);
+        (sender, Self(Some(receiver)))
+    }
+
+    /// Returns a signal which is never triggered.
+    pub(crate) fn never() -> Self {
+        Self(None)
+    }
+
+    pub(crate) fn is_triggered(&self) -> bool {
+        self.0.as_ref().is_some_and(|receiver| receiver.is_closed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shutdown() {
+        assert!(!Shutdown::never().is_triggered());
+
+        let (sender, shutdown) = Shutdown::new();
+        let cloned = shutdown.clone();
+        assert!(!shutdown.is_triggered());
+        drop(sender);
+        assert!(shutdown.is_triggered());
+        assert!(cloned.is_triggered());
+    }
+}
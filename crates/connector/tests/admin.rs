@@ -0,0 +1,142 @@
+use std::{
+    io::{Read, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream},
+};
+
+use async_std::task;
+use de_messages::{FromGame, FromServer, ToServer, PROTOCOL_VERSION};
+use de_net::{
+    self, Authentication, OutPackage, PackageReceiver, PackageSender, Peers, Reliability, Socket,
+};
+use de_types::player::Player;
+use ntest::timeout;
+use serde_json::Value;
+
+use crate::common::{spawn_with_env_and_wait, term_and_wait};
+
+mod common;
+
+const ADMIN_ADDR: &str = "127.0.0.1:8083";
+
+#[test]
+#[timeout(10_000)]
+fn test() {
+    let child = spawn_with_env_and_wait(&[("DE_ADMIN_ADDR", ADMIN_ADDR)]);
+
+    let (status, body) = get("/games");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    let games: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(games["games"].as_array().unwrap().len(), 0);
+
+    let port = task::block_on(task::spawn(async {
+        let main_server = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8082);
+        let comms = Comms::init().await;
+
+        comms
+            .send(
+                main_server,
+                ToServer::OpenGame {
+                    version: PROTOCOL_VERSION,
+                    max_players: 3.try_into().unwrap(),
+                    map_max_players: 4.try_into().unwrap(),
+                    map_hash: [0; 32],
+                },
+            )
+            .await;
+        let port = match comms.recv::<FromServer>().await.as_slice() {
+            [FromServer::GameOpened { port, .. }] => *port,
+            response => panic!("Unexpected response: {response:?}"),
+        };
+
+        match comms.recv::<FromGame>().await.as_slice() {
+            [FromGame::Joined {
+                player: Player::Player1,
+                ..
+            }] => (),
+            response => panic!("Unexpected response: {response:?}"),
+        }
+
+        port
+    }));
+
+    let (status, body) = get("/games");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    let games: Value = serde_json::from_str(&body).unwrap();
+    let games = games["games"].as_array().unwrap();
+    assert_eq!(games.len(), 1);
+    let game = &games[0];
+    assert_eq!(game["port"], port);
+    assert_eq!(game["max_players"], 3);
+    assert_eq!(game["readiness"], "NotReady");
+    assert_eq!(game["spectators"], 0);
+    let players = game["players"].as_array().unwrap();
+    assert_eq!(players.len(), 1);
+    assert_eq!(players[0]["player"], 1);
+    assert_eq!(players[0]["readiness"], "NotReady");
+
+    let (status, body) = get("/metrics");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(body.lines().any(|line| line == "de_connector_games 1"));
+    assert!(body
+        .lines()
+        .any(|line| line == format!("de_connector_game_players{{port=\"{port}\"}} 1")));
+
+    let (status, _) = get("/unknown");
+    assert_eq!(status, "HTTP/1.1 404 Not Found");
+
+    term_and_wait(child);
+}
+
+/// Sends a GET request to the admin endpoint and returns the response status
+/// line and body.
+fn get(path: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(ADMIN_ADDR).unwrap();
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    (head.lines().next().unwrap().to_owned(), body.to_owned())
+}
+
+struct Comms {
+    sender: PackageSender,
+    receiver: PackageReceiver,
+}
+
+impl Comms {
+    async fn init() -> Self {
+        let socket = Socket::bind(None).await.unwrap();
+        let (sender, receiver, _) = de_net::startup(
+            |t| {
+                task::spawn(t);
+            },
+            socket,
+            Authentication::disabled(),
+        );
+
+        Self { sender, receiver }
+    }
+
+    async fn send<E>(&self, addr: SocketAddr, message: E)
+    where
+        E: bincode::Encode,
+    {
+        let package =
+            OutPackage::encode_single(&message, Reliability::SemiOrdered, Peers::Server, addr)
+                .unwrap();
+        self.sender.send(package).await.unwrap();
+    }
+
+    async fn recv<P>(&self) -> Vec<P>
+    where
+        P: bincode::Decode,
+    {
+        let package = self.receiver.recv().await.unwrap();
+        let mut messages = Vec::new();
+        for message in package.decode::<P>() {
+            messages.push(message.unwrap());
+        }
+        messages
+    }
+}
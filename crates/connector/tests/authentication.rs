@@ -0,0 +1,162 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use async_std::{future::timeout, task};
+use de_lobby_model::GameSecret;
+use de_messages::{FromGame, FromServer, ToGame, ToServer, PROTOCOL_VERSION};
+use de_net::{
+    self, Authentication, OutPackage, PackageReceiver, PackageSender, Peers, Reliability,
+    SessionSecret, Socket,
+};
+use de_types::player::Player;
+use ntest::timeout;
+
+use crate::common::{spawn_and_wait, term_and_wait};
+
+mod common;
+
+const GAME_KEY: &str = "connector-test-key";
+
+#[test]
+#[timeout(10_000)]
+fn test() {
+    let child = spawn_and_wait(Some(GAME_KEY));
+
+    task::block_on(task::spawn(async {
+        let main_server = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8082);
+
+        let comms_a = Comms::init().await;
+        let comms_b = Comms::init().await;
+        let comms_c = Comms::init().await;
+
+        // The main server does not require authentication.
+        comms_a
+            .send(
+                main_server,
+                ToServer::OpenGame {
+                    version: PROTOCOL_VERSION,
+                    max_players: 2.try_into().unwrap(),
+                    map_max_players: 4.try_into().unwrap(),
+                    map_hash: [0; 32],
+                },
+            )
+            .await;
+        let (port, nonce) = match comms_a.recv::<FromServer>().await.as_slice() {
+            [FromServer::GameOpened {
+                port,
+                nonce: Some(nonce),
+            }] => (*port, *nonce),
+            response => panic!("Unexpected response: {response:?}"),
+        };
+
+        let game_server = SocketAddr::new(main_server.ip(), port);
+        let secret = GameSecret::derive(GAME_KEY.as_bytes(), port, nonce);
+        let secret = SessionSecret::new(*secret.bytes());
+
+        comms_a.authentication.register(game_server, secret);
+        comms_a.send(game_server, ToGame::Ping(7)).await;
+
+        let mut joined = false;
+        let mut pong = false;
+        while !joined || !pong {
+            for message in comms_a.recv::<FromGame>().await {
+                match message {
+                    FromGame::Joined {
+                        player: Player::Player1,
+                        ..
+                    } => joined = true,
+                    FromGame::Pong(7) => pong = true,
+                    _ => panic!("Unexpected message: {message:?}"),
+                }
+            }
+        }
+
+        // Datagrams without a valid session are dropped by the game server.
+        comms_c
+            .send(
+                game_server,
+                ToGame::Join {
+                    version: PROTOCOL_VERSION,
+                },
+            )
+            .await;
+        assert!(
+            timeout(Duration::from_millis(500), comms_c.recv::<FromGame>())
+                .await
+                .is_err()
+        );
+
+        comms_b.authentication.register(game_server, secret);
+        comms_b
+            .send(
+                game_server,
+                ToGame::Join {
+                    version: PROTOCOL_VERSION,
+                },
+            )
+            .await;
+        match comms_b.recv::<FromGame>().await.as_slice() {
+            [FromGame::Joined {
+                player: Player::Player2,
+                ..
+            }] => (),
+            response => panic!("Unexpected response: {response:?}"),
+        }
+        match comms_a.recv::<FromGame>().await.as_slice() {
+            [FromGame::PeerJoined(Player::Player2)] => (),
+            response => panic!("Unexpected response: {response:?}"),
+        }
+    }));
+
+    term_and_wait(child);
+}
+
+struct Comms {
+    authentication: Authentication,
+    sender: PackageSender,
+    receiver: PackageReceiver,
+}
+
+impl Comms {
+    async fn init() -> Self {
+        let socket = Socket::bind(None).await.unwrap();
+        let authentication = Authentication::client();
+        let (sender, receiver, _) = de_net::startup(
+            |t| {
+                task::spawn(t);
+            },
+            socket,
+            authentication.clone(),
+        );
+
+        Self {
+            authentication,
+            sender,
+            receiver,
+        }
+    }
+
+    async fn send<E>(&self, addr: SocketAddr, message: E)
+    where
+        E: bincode::Encode,
+    {
+        let package =
+            OutPackage::encode_single(&message, Reliability::SemiOrdered, Peers::Server, addr)
+                .unwrap();
+        self.sender.send(package).await.unwrap();
+    }
+
+    async fn recv<P>(&self) -> Vec<P>
+    where
+        P: bincode::Decode,
+    {
+        let package = self.receiver.recv().await.unwrap();
+        let mut messages = Vec::new();
+        for message in package.decode::<P>() {
+            messages.push(message.unwrap());
+        }
+        messages
+    }
+}
@@ -1,56 +1,57 @@
-use std::{
-    net::{IpAddr, Ipv4Addr, SocketAddr},
-    time::Duration,
-};
+use std::time::Duration;
 
 use async_std::{future::timeout, task};
-use de_messages::{FromGame, FromServer, JoinError, Readiness, ToGame, ToServer};
-use de_net::{
-    self, ConnErrorReceiver, OutPackage, PackageReceiver, PackageSender, Peers, Reliability, Socket,
+use de_messages::{
+    FromGame, FromServer, GameOpenError, JoinError, Readiness, RejoinToken, ToGame, ToServer,
+    PROTOCOL_VERSION,
 };
 use de_types::player::Player;
 use ntest::timeout;
 
-use crate::common::{spawn_and_wait, term_and_wait};
+use crate::common::{check_response, spawn_and_wait, term_and_wait, Comms};
 
 mod common;
 
-macro_rules! check_response {
-    ($comms:expr, $expect:pat) => {
-        let mut response = $comms.recv().await;
-        if response.len() != 1 {
-            panic!("Unexpected number of messages: {response:?}");
-        }
-
-        let response = response.pop().unwrap();
-        match response {
-            $expect => (),
-            _ => panic!("Unexpected response: {response:?}"),
-        }
-    };
-}
-
 #[test]
 #[timeout(10_000)]
 fn test() {
-    let child = spawn_and_wait();
+    let child = spawn_and_wait(None);
 
     task::block_on(task::spawn(async {
         let mut comms_a = Comms::init().await;
         let mut comms_b = Comms::init().await;
         let mut comms_c = Comms::init().await;
         let mut comms_d = Comms::init().await;
+        let mut comms_e = Comms::init().await;
+
+        comms_d
+            .send(ToServer::OpenGame {
+                version: PROTOCOL_VERSION + 1,
+                max_players: 3.try_into().unwrap(),
+                map_max_players: 4.try_into().unwrap(),
+                map_hash: [0; 32],
+            })
+            .await;
+        check_response!(
+            comms_d,
+            FromServer::GameOpenError(GameOpenError::IncompatibleVersion {
+                server: PROTOCOL_VERSION
+            })
+        );
 
         comms_a
             .send(ToServer::OpenGame {
+                version: PROTOCOL_VERSION,
                 max_players: 3.try_into().unwrap(),
+                map_max_players: 4.try_into().unwrap(),
+                map_hash: [0; 32],
             })
             .await;
         let mut response = comms_a.recv::<FromServer>().await;
         assert_eq!(response.len(), 1);
         let response = response.pop().unwrap();
         let game_port = match response {
-            FromServer::GameOpened { port } => port,
+            FromServer::GameOpened { port, nonce: None } => port,
             _ => panic!("Unexpected message: {response:?}"),
         };
 
@@ -58,13 +59,59 @@ fn test() {
         comms_b.port = game_port;
         comms_c.port = game_port;
         comms_d.port = game_port;
+        comms_e.port = game_port;
+
+        check_response!(
+            comms_a,
+            FromGame::Joined {
+                player: Player::Player1,
+                ..
+            }
+        );
 
-        check_response!(comms_a, FromGame::Joined(Player::Player1));
+        comms_c
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION + 1,
+            })
+            .await;
+        check_response!(
+            comms_c,
+            FromGame::JoinError(JoinError::IncompatibleVersion {
+                server: PROTOCOL_VERSION
+            })
+        );
 
-        comms_b.send(ToGame::Join).await;
-        check_response!(comms_b, FromGame::Joined(Player::Player2));
+        comms_b
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(
+            comms_b,
+            FromGame::Joined {
+                player: Player::Player2,
+                ..
+            }
+        );
         check_response!(comms_a, FromGame::PeerJoined(Player::Player2));
 
+        // Spectators are not announced to the players (this is checked by the
+        // no-message assertions below).
+        comms_e
+            .send(ToGame::JoinSpectator {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(
+            comms_e,
+            FromGame::JoinedSpectator {
+                readiness: Readiness::NotReady,
+                ..
+            }
+        );
+        // Readiness of spectators is ignored.
+        comms_e.send(ToGame::Readiness(Readiness::Ready)).await;
+
         comms_a.send(ToGame::Readiness(Readiness::Ready)).await;
         // The other player is not yet ready -> no message should be received.
         assert!(
@@ -81,8 +128,13 @@ fn test() {
 
         check_response!(comms_a, FromGame::GameReadiness(Readiness::Ready));
         check_response!(comms_b, FromGame::GameReadiness(Readiness::Ready));
+        check_response!(comms_e, FromGame::GameReadiness(Readiness::Ready));
 
-        comms_c.send(ToGame::Join).await;
+        comms_c
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
         check_response!(comms_c, FromGame::JoinError(JoinError::GameNotOpened));
 
         comms_a.send(ToGame::Readiness(Readiness::Prepared)).await;
@@ -102,8 +154,13 @@ fn test() {
 
         check_response!(comms_a, FromGame::GameReadiness(Readiness::Prepared));
         check_response!(comms_b, FromGame::GameReadiness(Readiness::Prepared));
+        check_response!(comms_e, FromGame::GameReadiness(Readiness::Prepared));
 
-        comms_d.send(ToGame::Join).await;
+        comms_d
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
         check_response!(comms_d, FromGame::JoinError(JoinError::GameNotOpened));
 
         comms_a
@@ -127,62 +184,42 @@ fn test() {
 
         check_response!(comms_a, FromGame::GameReadiness(Readiness::Initialized));
         check_response!(comms_b, FromGame::GameReadiness(Readiness::Initialized));
+        check_response!(comms_e, FromGame::GameReadiness(Readiness::Initialized));
+
+        // Spectators might join a running game.
+        comms_d
+            .send(ToGame::JoinSpectator {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        let mut response = comms_d.recv::<FromGame>().await;
+        match response.pop() {
+            Some(FromGame::JoinedSpectator {
+                players,
+                readiness: Readiness::Initialized,
+            }) if response.is_empty() => {
+                assert_eq!(players, vec![Player::Player1, Player::Player2]);
+            }
+            other => panic!("Unexpected response: {other:?}"),
+        }
+        comms_d.send(ToGame::Restore).await;
+        check_response!(comms_d, FromGame::Restored);
+
+        // Nobody has been disconnected.
+        comms_c
+            .send(ToGame::Rejoin {
+                version: PROTOCOL_VERSION,
+                token: RejoinToken::new(1),
+            })
+            .await;
+        check_response!(comms_c, FromGame::JoinError(JoinError::InvalidToken));
 
         assert!(comms_a.errors.is_empty());
         assert!(comms_b.errors.is_empty());
         assert!(comms_c.errors.is_empty());
+        assert!(comms_d.errors.is_empty());
+        assert!(comms_e.errors.is_empty());
     }));
 
     term_and_wait(child);
 }
-
-struct Comms {
-    host: IpAddr,
-    port: u16,
-    sender: PackageSender,
-    receiver: PackageReceiver,
-    errors: ConnErrorReceiver,
-}
-
-impl Comms {
-    async fn init() -> Self {
-        let socket = Socket::bind(None).await.unwrap();
-        let (sender, receiver, errors) = de_net::startup(
-            |t| {
-                task::spawn(t);
-            },
-            socket,
-        );
-
-        Self {
-            host: IpAddr::V4(Ipv4Addr::LOCALHOST),
-            port: 8082,
-            sender,
-            receiver,
-            errors,
-        }
-    }
-
-    async fn send<E>(&self, message: E)
-    where
-        E: bincode::Encode,
-    {
-        let addr = SocketAddr::new(self.host, self.port);
-        let package =
-            OutPackage::encode_single(&message, Reliability::SemiOrdered, Peers::Server, addr)
-                .unwrap();
-        self.sender.send(package).await.unwrap();
-    }
-
-    async fn recv<P>(&self) -> Vec<P>
-    where
-        P: bincode::Decode,
-    {
-        let package = self.receiver.recv().await.unwrap();
-        let mut messages = Vec::new();
-        for message in package.decode::<P>() {
-            messages.push(message.unwrap());
-        }
-        messages
-    }
-}
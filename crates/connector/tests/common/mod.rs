@@ -1,4 +1,8 @@
+//! Utilities shared by the integration tests. Not every test uses all of them.
+#![allow(dead_code)]
+
 use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
     os::unix::process::CommandExt,
     process::{Child, Command, Stdio},
     thread::sleep,
@@ -6,15 +10,57 @@ use std::{
 };
 
 use assert_cmd::cargo::CommandCargoExt;
+use async_std::task;
+use de_net::{
+    self, Authentication, ConnErrorReceiver, OutPackage, PackageReceiver, PackageSender, Peers,
+    Reliability, Socket,
+};
 use nix::{
     libc::{prctl, PR_SET_PDEATHSIG, SIGTERM},
     sys::signal::{kill, Signal},
     unistd::Pid,
 };
 
-pub fn spawn_and_wait() -> Child {
+/// Receives a single message and panics unless it is the expected one.
+macro_rules! check_response {
+    ($comms:expr, $expect:pat $(if $guard:expr)?) => {
+        let mut response = $comms.recv().await;
+        if response.len() != 1 {
+            panic!("Unexpected number of messages: {response:?}");
+        }
+
+        let response = response.pop().unwrap();
+        match response {
+            $expect $(if $guard)? => (),
+            _ => panic!("Unexpected response: {response:?}"),
+        }
+    };
+}
+
+#[allow(unused_imports)]
+pub(crate) use check_response;
+
+/// Spawns DE Connector and waits until it is (likely) ready.
+///
+/// * `game_key` - if not None, DE Connector is configured to authenticate all
+///   game traffic with secrets derived from this key.
+pub fn spawn_and_wait(game_key: Option<&str>) -> Child {
     let mut command = Command::cargo_bin("de-connector").unwrap();
+    if let Some(game_key) = game_key {
+        command.env("DE_GAME_KEY", game_key);
+    }
+    spawn_command_and_wait(command)
+}
+
+/// Spawns DE Connector with additional environment variables and waits until
+/// it is (likely) ready.
+pub fn spawn_with_env_and_wait(vars: &[(&str, &str)]) -> Child {
+    let mut command = Command::cargo_bin("de-connector").unwrap();
+    command.envs(vars.iter().copied());
+    spawn_command_and_wait(command)
+}
 
+fn spawn_command_and_wait(mut command: Command) -> Child {
     unsafe {
         command.pre_exec(|| {
             let parent_pid = Pid::this();
@@ -36,3 +82,63 @@ pub fn term_and_wait(mut child: Child) {
     kill(pid, Signal::SIGTERM).unwrap();
     child.wait().unwrap();
 }
+
+/// Unauthenticated client communicating with DE Connector on localhost.
+pub struct Comms {
+    pub host: IpAddr,
+    pub port: u16,
+    pub sender: PackageSender,
+    pub receiver: PackageReceiver,
+    pub errors: ConnErrorReceiver,
+}
+
+impl Comms {
+    pub async fn init() -> Self {
+        let socket = Socket::bind(None).await.unwrap();
+        let (sender, receiver, errors) = de_net::startup(
+            |t| {
+                task::spawn(t);
+            },
+            socket,
+            Authentication::disabled(),
+        );
+
+        Self {
+            host: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 8082,
+            sender,
+            receiver,
+            errors,
+        }
+    }
+
+    /// Sends a message to the server.
+    pub async fn send<E>(&self, message: E)
+    where
+        E: bincode::Encode,
+    {
+        self.send_to(message, Peers::Server).await;
+    }
+
+    pub async fn send_to<E>(&self, message: E, peers: Peers)
+    where
+        E: bincode::Encode,
+    {
+        let addr = SocketAddr::new(self.host, self.port);
+        let package =
+            OutPackage::encode_single(&message, Reliability::SemiOrdered, peers, addr).unwrap();
+        self.sender.send(package).await.unwrap();
+    }
+
+    pub async fn recv<P>(&self) -> Vec<P>
+    where
+        P: bincode::Decode,
+    {
+        let package = self.receiver.recv().await.unwrap();
+        let mut messages = Vec::new();
+        for message in package.decode::<P>() {
+            messages.push(message.unwrap());
+        }
+        messages
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use async_std::{future::timeout, net::UdpSocket, task};
+use de_messages::{
+    FromGame, FromServer, LanAnnouncement, ToGame, ToServer, LAN_DISCOVERY_PORT,
+    MAX_ANNOUNCEMENT_SIZE, PROTOCOL_VERSION,
+};
+use de_types::player::Player;
+use ntest::timeout;
+
+use crate::common::{spawn_with_env_and_wait, term_and_wait, Comms};
+
+mod common;
+
+#[test]
+#[timeout(10_000)]
+fn test() {
+    let announce_addr = format!("127.0.0.1:{LAN_DISCOVERY_PORT}");
+    let child = spawn_with_env_and_wait(&[("DE_LAN_ANNOUNCE", announce_addr.as_str())]);
+
+    task::block_on(task::spawn(async {
+        let listener = UdpSocket::bind(("127.0.0.1", LAN_DISCOVERY_PORT))
+            .await
+            .unwrap();
+
+        let announcement = recv_announcement(&listener).await;
+        assert_eq!(announcement.version(), PROTOCOL_VERSION);
+        assert_eq!(announcement.port(), 8082);
+        assert!(announcement.games().is_empty());
+
+        let mut comms_a = Comms::init().await;
+        let mut comms_b = Comms::init().await;
+
+        comms_a
+            .send(ToServer::OpenGame {
+                version: PROTOCOL_VERSION,
+                max_players: 2.try_into().unwrap(),
+                map_max_players: 4.try_into().unwrap(),
+                map_hash: [7; 32],
+            })
+            .await;
+        let mut response = comms_a.recv::<FromServer>().await;
+        assert_eq!(response.len(), 1);
+        let response = response.pop().unwrap();
+        let game_port = match response {
+            FromServer::GameOpened { port, nonce: None } => port,
+            _ => panic!("Unexpected message: {response:?}"),
+        };
+        comms_a.port = game_port;
+        comms_b.port = game_port;
+
+        let mut response = comms_a.recv::<FromGame>().await;
+        assert!(matches!(
+            response.pop().unwrap(),
+            FromGame::Joined {
+                player: Player::Player1,
+                ..
+            }
+        ));
+
+        let announcement = recv_announcement_until(&listener, |a| !a.games().is_empty()).await;
+        assert_eq!(announcement.games().len(), 1);
+        let game = &announcement.games()[0];
+        assert_eq!(game.port(), game_port);
+        assert_eq!(game.max_players(), Player::Player2);
+        assert_eq!(game.players(), 1);
+        assert_eq!(game.map_hash(), &[7; 32]);
+
+        comms_b
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        let mut response = comms_b.recv::<FromGame>().await;
+        assert!(matches!(
+            response.pop().unwrap(),
+            FromGame::Joined {
+                player: Player::Player2,
+                ..
+            }
+        ));
+
+        // Full games are not announced.
+        recv_announcement_until(&listener, |a| a.games().is_empty()).await;
+    }));
+
+    term_and_wait(child);
+}
+
+async fn recv_announcement(listener: &UdpSocket) -> LanAnnouncement {
+    let mut buf = [0; MAX_ANNOUNCEMENT_SIZE];
+    let (len, _) = timeout(Duration::from_secs(3), listener.recv_from(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    LanAnnouncement::decode(&buf[..len]).unwrap()
+}
+
+/// Receives announcements until one satisfying the predicate arrives.
+async fn recv_announcement_until<F>(listener: &UdpSocket, predicate: F) -> LanAnnouncement
+where
+    F: Fn(&LanAnnouncement) -> bool,
+{
+    loop {
+        let announcement = recv_announcement(listener).await;
+        if predicate(&announcement) {
+            return announcement;
+        }
+    }
+}
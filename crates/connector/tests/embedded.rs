@@ -0,0 +1,118 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use async_std::{future::timeout, net::UdpSocket, task};
+use de_connector_lib::{ConnectorConf, EmbeddedConnector};
+use de_messages::{FromServer, LanAnnouncement, ToServer, MAX_ANNOUNCEMENT_SIZE};
+use de_net::{
+    self, Authentication, OutPackage, PackageReceiver, PackageSender, Peers, Reliability, Socket,
+};
+use ntest::timeout;
+
+const PORT: u16 = 8092;
+const ANNOUNCE_PORT: u16 = 8094;
+
+#[test]
+#[timeout(20_000)]
+fn test() {
+    task::block_on(task::spawn(async {
+        let listener = UdpSocket::bind((Ipv4Addr::LOCALHOST, ANNOUNCE_PORT))
+            .await
+            .unwrap();
+        let conf = ConnectorConf::new(PORT)
+            .with_lan_announce(SocketAddr::from((Ipv4Addr::LOCALHOST, ANNOUNCE_PORT)));
+
+        let connector = EmbeddedConnector::start(conf.clone()).unwrap();
+        assert_eq!(connector.port(), PORT);
+        // The port is already taken.
+        assert!(EmbeddedConnector::start(conf.clone()).is_err());
+
+        let announcement = recv_announcement(&listener).await.unwrap();
+        assert_eq!(announcement.port(), PORT);
+
+        let comms = Comms::init().await;
+        comms.send(ToServer::Ping(3)).await;
+        assert!(matches!(
+            comms.recv().await.as_slice(),
+            [FromServer::Pong(3)]
+        ));
+
+        drop(connector);
+
+        // Skip an announcement which might have been sent before the
+        // connector noticed the shutdown.
+        let _ = recv_announcement(&listener).await;
+        assert!(recv_announcement(&listener).await.is_none());
+
+        // The port is released once the network stack finishes.
+        let mut attempts = 0;
+        let connector = loop {
+            match EmbeddedConnector::start(conf.clone()) {
+                Ok(connector) => break connector,
+                Err(error) => {
+                    attempts += 1;
+                    assert!(attempts < 10, "{error:?}");
+                    task::sleep(Duration::from_millis(500)).await;
+                }
+            }
+        };
+
+        // A fresh network stack is needed since the restarted connector
+        // numbers its packages from scratch.
+        let comms = Comms::init().await;
+        comms.send(ToServer::Ping(4)).await;
+        assert!(matches!(
+            comms.recv().await.as_slice(),
+            [FromServer::Pong(4)]
+        ));
+        drop(connector);
+    }));
+}
+
+async fn recv_announcement(listener: &UdpSocket) -> Option<LanAnnouncement> {
+    let mut buf = [0; MAX_ANNOUNCEMENT_SIZE];
+    let (len, _) = timeout(Duration::from_secs(3), listener.recv_from(&mut buf))
+        .await
+        .ok()?
+        .unwrap();
+    Some(LanAnnouncement::decode(&buf[..len]).unwrap())
+}
+
+struct Comms {
+    sender: PackageSender,
+    receiver: PackageReceiver,
+}
+
+impl Comms {
+    async fn init() -> Self {
+        let socket = Socket::bind(None).await.unwrap();
+        let (sender, receiver, _) = de_net::startup(
+            |t| {
+                task::spawn(t);
+            },
+            socket,
+            Authentication::disabled(),
+        );
+
+        Self { sender, receiver }
+    }
+
+    async fn send(&self, message: ToServer) {
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, PORT));
+        let package =
+            OutPackage::encode_single(&message, Reliability::Unordered, Peers::Server, addr)
+                .unwrap();
+        self.sender.send(package).await.unwrap();
+    }
+
+    async fn recv(&self) -> Vec<FromServer> {
+        let package = self.receiver.recv().await.unwrap();
+        let mut messages = Vec::new();
+        for message in package.decode::<FromServer>() {
+            messages.push(message.unwrap());
+        }
+        messages
+    }
+}
@@ -0,0 +1,100 @@
+use std::time::{Duration, Instant};
+
+use async_std::{future::timeout, task};
+use de_messages::{FromGame, FromServer, ToGame, ToServer, PROTOCOL_VERSION};
+use de_types::player::Player;
+use ntest::timeout;
+
+use crate::common::{check_response, spawn_with_env_and_wait, term_and_wait, Comms};
+
+mod common;
+
+#[test]
+#[timeout(20_000)]
+fn test() {
+    let child = spawn_with_env_and_wait(&[("DE_CLIENT_TIMEOUT", "2")]);
+
+    task::block_on(task::spawn(async {
+        let mut comms_a = Comms::init().await;
+        let mut comms_b = Comms::init().await;
+
+        comms_a
+            .send(ToServer::OpenGame {
+                version: PROTOCOL_VERSION,
+                max_players: 2.try_into().unwrap(),
+                map_max_players: 4.try_into().unwrap(),
+                map_hash: [0; 32],
+            })
+            .await;
+        let mut response = comms_a.recv::<FromServer>().await;
+        assert_eq!(response.len(), 1);
+        let response = response.pop().unwrap();
+        let game_port = match response {
+            FromServer::GameOpened { port, nonce: None } => port,
+            _ => panic!("Unexpected message: {response:?}"),
+        };
+
+        comms_a.port = game_port;
+        comms_b.port = game_port;
+
+        check_response!(
+            comms_a,
+            FromGame::Joined {
+                player: Player::Player1,
+                ..
+            }
+        );
+
+        comms_b
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(
+            comms_b,
+            FromGame::Joined {
+                player: Player::Player2,
+                ..
+            }
+        );
+        check_response!(comms_a, FromGame::PeerJoined(Player::Player2));
+
+        // Player 1 keeps sending messages while player 2 is silent.
+        let start = Instant::now();
+        loop {
+            assert!(start.elapsed() < Duration::from_secs(6));
+            comms_a.send(ToGame::Ping(1)).await;
+
+            let Ok(messages) = timeout(Duration::from_millis(200), comms_a.recv()).await else {
+                continue;
+            };
+            match messages.as_slice() {
+                [FromGame::PeerLeft(Player::Player2)] => break,
+                [FromGame::Pong(1)] | [FromGame::Heartbeat] => (),
+                _ => panic!("Unexpected messages: {messages:?}"),
+            }
+        }
+        assert!(start.elapsed() > Duration::from_secs(1));
+
+        assert!(wait_for_left(&comms_b).await > 0);
+        // The game is closed once the last player goes silent.
+        assert!(wait_for_left(&comms_a).await > 0);
+    }));
+
+    term_and_wait(child);
+}
+
+/// Receives messages till [`FromGame::Left`] is received and returns the
+/// number of heartbeats received in the meantime. Delayed pongs are ignored.
+async fn wait_for_left(comms: &Comms) -> usize {
+    let mut heartbeats = 0;
+    loop {
+        let messages = comms.recv().await;
+        match messages.as_slice() {
+            [FromGame::Heartbeat] => heartbeats += 1,
+            [FromGame::Pong(_)] => (),
+            [FromGame::Left] => return heartbeats,
+            _ => panic!("Unexpected messages: {messages:?}"),
+        }
+    }
+}
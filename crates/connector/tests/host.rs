@@ -0,0 +1,139 @@
+use async_std::task;
+use de_messages::{FromGame, FromServer, HostError, JoinError, ToGame, ToServer, PROTOCOL_VERSION};
+use de_types::player::Player;
+use ntest::timeout;
+
+use crate::common::{check_response, spawn_and_wait, term_and_wait, Comms};
+
+mod common;
+
+#[test]
+#[timeout(10_000)]
+fn test() {
+    let child = spawn_and_wait(None);
+
+    task::block_on(task::spawn(async {
+        let mut comms_a = Comms::init().await;
+        let mut comms_b = Comms::init().await;
+        let mut comms_c = Comms::init().await;
+
+        comms_a
+            .send(ToServer::OpenGame {
+                version: PROTOCOL_VERSION,
+                max_players: 3.try_into().unwrap(),
+                map_max_players: 3.try_into().unwrap(),
+                map_hash: [0; 32],
+            })
+            .await;
+        let mut response = comms_a.recv::<FromServer>().await;
+        assert_eq!(response.len(), 1);
+        let response = response.pop().unwrap();
+        let game_port = match response {
+            FromServer::GameOpened { port, nonce: None } => port,
+            _ => panic!("Unexpected message: {response:?}"),
+        };
+
+        comms_a.port = game_port;
+        comms_b.port = game_port;
+        comms_c.port = game_port;
+
+        check_response!(
+            comms_a,
+            FromGame::Joined {
+                player: Player::Player1,
+                ..
+            }
+        );
+
+        comms_b
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(
+            comms_b,
+            FromGame::Joined {
+                player: Player::Player2,
+                ..
+            }
+        );
+        check_response!(comms_a, FromGame::PeerJoined(Player::Player2));
+
+        // Only the host might control the game.
+        comms_b.send(ToGame::SetLocked(true)).await;
+        check_response!(comms_b, FromGame::HostError(HostError::NotHost));
+
+        comms_a.send(ToGame::SetLocked(true)).await;
+        check_response!(comms_a, FromGame::GameLocked(true));
+        check_response!(comms_b, FromGame::GameLocked(true));
+
+        comms_c
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(comms_c, FromGame::JoinError(JoinError::GameLocked));
+
+        comms_a.send(ToGame::SetLocked(false)).await;
+        check_response!(comms_a, FromGame::GameLocked(false));
+        check_response!(comms_b, FromGame::GameLocked(false));
+
+        comms_a.send(ToGame::SetMaxPlayers(Player::Player1)).await;
+        check_response!(comms_a, FromGame::HostError(HostError::SlotOccupied));
+
+        comms_a.send(ToGame::SetMaxPlayers(Player::Player4)).await;
+        check_response!(comms_a, FromGame::HostError(HostError::MapCapacity));
+
+        comms_a.send(ToGame::SetMaxPlayers(Player::Player2)).await;
+        check_response!(comms_a, FromGame::GameMaxPlayers(Player::Player2));
+        check_response!(comms_b, FromGame::GameMaxPlayers(Player::Player2));
+
+        comms_c
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(comms_c, FromGame::JoinError(JoinError::GameFull));
+
+        comms_a.send(ToGame::Kick(Player::Player1)).await;
+        check_response!(comms_a, FromGame::HostError(HostError::InvalidPlayer));
+
+        comms_a.send(ToGame::Kick(Player::Player2)).await;
+        check_response!(comms_b, FromGame::Kicked);
+        check_response!(comms_a, FromGame::PeerLeft(Player::Player2));
+
+        comms_b.send(ToGame::SetLocked(true)).await;
+        check_response!(comms_b, FromGame::NotJoined);
+
+        // Kicked players cannot join the game again, not even as spectators.
+        comms_b
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(comms_b, FromGame::JoinError(JoinError::Kicked));
+        comms_b
+            .send(ToGame::JoinSpectator {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(comms_b, FromGame::JoinError(JoinError::Kicked));
+
+        // The freed slot is available to others.
+        comms_c
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(
+            comms_c,
+            FromGame::Joined {
+                player: Player::Player2,
+                ..
+            }
+        );
+        check_response!(comms_a, FromGame::PeerJoined(Player::Player2));
+    }));
+
+    term_and_wait(child);
+}
@@ -0,0 +1,122 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use async_std::task;
+use de_messages::{FromGame, FromServer, ToGame, ToServer, PROTOCOL_VERSION};
+use de_net::{
+    self, Authentication, Impairment, OutPackage, PackageReceiver, PackageSender, Peers,
+    Reliability, Socket,
+};
+use de_types::player::Player;
+use ntest::timeout;
+
+use crate::common::{spawn_with_env_and_wait, term_and_wait};
+
+mod common;
+
+const SERVER_IMPAIRMENT: &str =
+    "seed=1,loss=0.1,latency=10ms,jitter=5ms,duplication=0.05,reordering=0.1,reorder_delay=30ms";
+const CLIENT_IMPAIRMENT: &str =
+    "seed=2,loss=0.1,latency=10ms,jitter=5ms,duplication=0.05,reordering=0.1,reorder_delay=30ms";
+const PINGS: u32 = 100;
+
+#[test]
+#[timeout(30_000)]
+fn test() {
+    let child = spawn_with_env_and_wait(&[("DE_NET_IMPAIRMENT", SERVER_IMPAIRMENT)]);
+
+    task::block_on(task::spawn(async {
+        let main_server = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8082);
+        let comms = Comms::init().await;
+
+        comms
+            .send(
+                main_server,
+                ToServer::OpenGame {
+                    version: PROTOCOL_VERSION,
+                    max_players: 2.try_into().unwrap(),
+                    map_max_players: 4.try_into().unwrap(),
+                    map_hash: [0; 32],
+                },
+            )
+            .await;
+        let port = match comms.recv::<FromServer>().await.as_slice() {
+            [FromServer::GameOpened { port, .. }] => *port,
+            response => panic!("Unexpected response: {response:?}"),
+        };
+        let game_server = SocketAddr::new(main_server.ip(), port);
+
+        match comms.recv::<FromGame>().await.as_slice() {
+            [FromGame::Joined {
+                player: Player::Player1,
+                ..
+            }] => (),
+            response => panic!("Unexpected response: {response:?}"),
+        }
+
+        for id in 0..PINGS {
+            comms.send(game_server, ToGame::Ping(id)).await;
+        }
+
+        // Semi-ordered pings are processed in order and thus all pongs must
+        // be received exactly once and in order despite the impairment.
+        let mut expected = 0;
+        while expected < PINGS {
+            for message in comms.recv::<FromGame>().await {
+                match message {
+                    FromGame::Pong(id) => {
+                        assert_eq!(id, expected);
+                        expected += 1;
+                    }
+                    _ => panic!("Unexpected message: {message:?}"),
+                }
+            }
+        }
+    }));
+
+    term_and_wait(child);
+}
+
+struct Comms {
+    sender: PackageSender,
+    receiver: PackageReceiver,
+}
+
+impl Comms {
+    async fn init() -> Self {
+        let socket = Socket::bind(None)
+            .await
+            .unwrap()
+            .with_impairment(CLIENT_IMPAIRMENT.parse::<Impairment>().unwrap());
+        let (sender, receiver, _) = de_net::startup(
+            |t| {
+                task::spawn(t);
+            },
+            socket,
+            Authentication::disabled(),
+        );
+
+        Self { sender, receiver }
+    }
+
+    async fn send<E>(&self, addr: SocketAddr, message: E)
+    where
+        E: bincode::Encode,
+    {
+        let package =
+            OutPackage::encode_single(&message, Reliability::SemiOrdered, Peers::Server, addr)
+                .unwrap();
+        self.sender.send(package).await.unwrap();
+    }
+
+    async fn recv<P>(&self) -> Vec<P>
+    where
+        P: bincode::Decode,
+    {
+        let package = self.receiver.recv().await.unwrap();
+        let mut messages = Vec::new();
+        for message in package.decode::<P>() {
+            messages.push(message.unwrap());
+        }
+        messages
+    }
+}
@@ -34,6 +34,8 @@ impl ReceivedBuffer {
         );
     }
 
+    /// Returns ID of the first data datagram with the given reliability whose
+    /// data start with `filter_data`.
     fn find_id(&self, filter_reliability: Reliability, filter_data: &[u8]) -> Option<u32> {
         self.0.iter().find_map(|incomming| match incomming {
             Incomming::Data {
@@ -41,7 +43,7 @@ impl ReceivedBuffer {
                 id,
                 data,
             } => {
-                if *reliability == filter_reliability && data == filter_data {
+                if *reliability == filter_reliability && data.starts_with(filter_data) {
                     Some(*id)
                 } else {
                     None
@@ -111,7 +113,7 @@ enum Incomming {
 #[test]
 #[timeout(5000)]
 fn test() {
-    let child = spawn_and_wait();
+    let child = spawn_and_wait(None);
 
     async fn first(mut client: Socket, game_port: u16) {
         let server = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, game_port));
@@ -122,9 +124,9 @@ fn test() {
         received.load(&mut client, &mut buffer).await;
         received.load(&mut client, &mut buffer).await;
 
-        // [5, 1] -> FromGame::PeerJoined(Player2)
+        // [6, 1] -> FromGame::PeerJoined(Player2)
         let id = received
-            .find_id(Reliability::SemiOrdered, &[5, 1])
+            .find_id(Reliability::SemiOrdered, &[6, 1])
             .unwrap()
             .to_be_bytes();
         // And send a confirmation
@@ -336,11 +338,12 @@ async fn create_game() -> (Socket, u16) {
 
     // [32 + 16] -> unordered + Peers::Server
     // [0, 0, 7] -> datagram ID = 7
-    // [1 2] -> ToGame::OpenGame { max_players: Player3 }
-    client
-        .send(SERVER_ADDR, &[32 + 16, 0, 0, 7, 1, 2])
-        .await
-        .unwrap();
+    // [1] -> ToServer::OpenGame
+    // [5 2 2] -> { version: 5, max_players: Player3, map_max_players: Player3, .. }
+    // [0; 32] -> { map_hash: [0; 32] }
+    let mut datagram = vec![32 + 16, 0, 0, 7, 1, 5, 2, 2];
+    datagram.extend([0; 32]);
+    client.send(SERVER_ADDR, &datagram).await.unwrap();
 
     let mut received = ReceivedBuffer::new();
     received.load(&mut client, &mut buffer).await;
@@ -368,12 +371,14 @@ async fn create_game() -> (Socket, u16) {
 
         // Decode bincode encoded port:
         // [1] -> FromServer::GameOpened
-        // [p] or [261 p p] -> { port: p }
+        // [p] or [261 p p] -> { port: p, .. }
+        // [0] -> { nonce: None, .. }
         assert_eq!(data[0], 1);
-        if data.len() == 2 {
+        assert_eq!(data[data.len() - 1], 0);
+        if data.len() == 3 {
             data[1] as u16
         } else {
-            assert_eq!(data.len(), 4);
+            assert_eq!(data.len(), 5);
             assert_eq!(data[1], 251);
             u16::from_be_bytes([data[2], data[3]])
         }
@@ -388,7 +393,7 @@ async fn create_game() -> (Socket, u16) {
     let mut received = ReceivedBuffer::new();
     received.load(&mut client, &mut buffer).await;
 
-    // [2, 0] -> FromGame::Joined(Player1)
+    // [2, 0, ..] -> FromGame::Joined { player: Player1, .. }
     let id = received
         .find_id(Reliability::SemiOrdered, &[2, 0])
         .unwrap()
@@ -409,15 +414,18 @@ async fn join_game(game_port: u16) -> Socket {
 
     // [32 + 16] -> unordered + Peers::Server
     // [0, 0, 3] -> datagram ID = 3
-    // [1] -> ToGame::Join
-    client.send(server, &[32 + 16, 0, 0, 3, 1]).await.unwrap();
+    // [1 5] -> ToGame::Join { version: 5 }
+    client
+        .send(server, &[32 + 16, 0, 0, 3, 1, 5])
+        .await
+        .unwrap();
 
     let mut received = ReceivedBuffer::new();
     received.load(&mut client, &mut buffer).await;
     received.load(&mut client, &mut buffer).await;
     received.assert_confirmed(3);
 
-    // [2, 1] -> FromGame::Joined(Player2)
+    // [2, 1, ..] -> FromGame::Joined { player: Player2, .. }
     let id = received
         .find_id(Reliability::SemiOrdered, &[2, 1])
         .unwrap()
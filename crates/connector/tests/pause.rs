@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use async_std::task;
+use de_messages::{
+    FromGame, FromServer, PauseError, Readiness, ToGame, ToServer, PROTOCOL_VERSION,
+};
+use de_types::player::Player;
+use ntest::timeout;
+
+use crate::common::{check_response, spawn_and_wait, term_and_wait, Comms};
+
+mod common;
+
+#[test]
+#[timeout(10_000)]
+fn test() {
+    let child = spawn_and_wait(None);
+
+    task::block_on(task::spawn(async {
+        let mut comms_a = Comms::init().await;
+        let mut comms_b = Comms::init().await;
+
+        comms_a
+            .send(ToServer::OpenGame {
+                version: PROTOCOL_VERSION,
+                max_players: 2.try_into().unwrap(),
+                map_max_players: 4.try_into().unwrap(),
+                map_hash: [0; 32],
+            })
+            .await;
+        let mut response = comms_a.recv::<FromServer>().await;
+        assert_eq!(response.len(), 1);
+        let response = response.pop().unwrap();
+        let game_port = match response {
+            FromServer::GameOpened { port, nonce: None } => port,
+            _ => panic!("Unexpected message: {response:?}"),
+        };
+
+        comms_a.port = game_port;
+        comms_b.port = game_port;
+
+        check_response!(
+            comms_a,
+            FromGame::Joined {
+                player: Player::Player1,
+                ..
+            }
+        );
+
+        comms_b
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(
+            comms_b,
+            FromGame::Joined {
+                player: Player::Player2,
+                ..
+            }
+        );
+        check_response!(comms_a, FromGame::PeerJoined(Player::Player2));
+
+        // Only a running game might be paused.
+        comms_a.send(ToGame::Pause).await;
+        check_response!(comms_a, FromGame::PauseError(PauseError::GameNotRunning));
+
+        for readiness in [
+            Readiness::Ready,
+            Readiness::Prepared,
+            Readiness::Initialized,
+        ] {
+            comms_a.send(ToGame::Readiness(readiness)).await;
+            comms_b.send(ToGame::Readiness(readiness)).await;
+            check_response!(comms_a, FromGame::GameReadiness(r) if r == readiness);
+            check_response!(comms_b, FromGame::GameReadiness(r) if r == readiness);
+        }
+
+        comms_b.send(ToGame::Resume).await;
+        check_response!(comms_b, FromGame::PauseError(PauseError::NotPaused));
+
+        comms_a.send(ToGame::Pause).await;
+        check_response!(
+            comms_a,
+            FromGame::GamePaused {
+                player: Player::Player1,
+                budget
+            } if budget == Duration::from_secs(120)
+        );
+        check_response!(
+            comms_b,
+            FromGame::GamePaused {
+                player: Player::Player1,
+                ..
+            }
+        );
+
+        comms_b.send(ToGame::Pause).await;
+        check_response!(comms_b, FromGame::PauseError(PauseError::AlreadyPaused));
+
+        // The game is resumed only once all players vote for it.
+        comms_b.send(ToGame::Resume).await;
+        check_response!(comms_a, FromGame::ResumeVoted(Player::Player2));
+        check_response!(comms_b, FromGame::ResumeVoted(Player::Player2));
+
+        comms_a.send(ToGame::Resume).await;
+        check_response!(comms_a, FromGame::ResumeVoted(Player::Player1));
+        check_response!(comms_a, FromGame::GameResumed);
+        check_response!(comms_b, FromGame::ResumeVoted(Player::Player1));
+        check_response!(comms_b, FromGame::GameResumed);
+
+        // The consumed pause time is deducted from the budget.
+        comms_a.send(ToGame::Pause).await;
+        check_response!(
+            comms_a,
+            FromGame::GamePaused {
+                player: Player::Player1,
+                budget
+            } if budget < Duration::from_secs(120)
+        );
+        check_response!(comms_b, FromGame::GamePaused { .. });
+    }));
+
+    term_and_wait(child);
+}
@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use async_std::{future::timeout, task};
+use de_messages::{
+    EntityNet, FromGame, FromServer, JoinError, NetEntityIndex, ToGame, ToPlayers, ToServer,
+    PROTOCOL_VERSION,
+};
+use de_net::Peers;
+use de_types::player::Player;
+use ntest::timeout;
+
+use crate::common::{check_response, spawn_and_wait, term_and_wait, Comms};
+
+mod common;
+
+#[test]
+#[timeout(10_000)]
+fn test() {
+    let child = spawn_and_wait(None);
+
+    task::block_on(task::spawn(async {
+        let mut comms_a = Comms::init().await;
+        let mut comms_b = Comms::init().await;
+
+        comms_a
+            .send(ToServer::OpenGame {
+                version: PROTOCOL_VERSION,
+                max_players: 2.try_into().unwrap(),
+                map_max_players: 4.try_into().unwrap(),
+                map_hash: [0; 32],
+            })
+            .await;
+        let mut response = comms_a.recv::<FromServer>().await;
+        assert_eq!(response.len(), 1);
+        let response = response.pop().unwrap();
+        let game_port = match response {
+            FromServer::GameOpened { port, nonce: None } => port,
+            _ => panic!("Unexpected message: {response:?}"),
+        };
+
+        comms_a.port = game_port;
+        comms_b.port = game_port;
+
+        check_response!(
+            comms_a,
+            FromGame::Joined {
+                player: Player::Player1,
+                ..
+            }
+        );
+
+        comms_b
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(
+            comms_b,
+            FromGame::Joined {
+                player: Player::Player2,
+                ..
+            }
+        );
+        check_response!(comms_a, FromGame::PeerJoined(Player::Player2));
+
+        // Player 2 repeatedly tries to despawn an entity of player 1. None of
+        // the messages is relayed and player 2 is eventually kicked.
+        let entity = EntityNet::new(Player::Player1, NetEntityIndex::new(1, 0).unwrap());
+        for _ in 0..33 {
+            comms_b
+                .send_to(ToPlayers::Despawn { entity }, Peers::Players)
+                .await;
+        }
+
+        check_response!(comms_b, FromGame::Kicked);
+        check_response!(comms_a, FromGame::PeerLeft(Player::Player2));
+
+        comms_b
+            .send(ToGame::Join {
+                version: PROTOCOL_VERSION,
+            })
+            .await;
+        check_response!(comms_b, FromGame::JoinError(JoinError::Kicked));
+
+        assert!(timeout(Duration::from_secs(1), comms_a.receiver.recv())
+            .await
+            .is_err());
+    }));
+
+    term_and_wait(child);
+}
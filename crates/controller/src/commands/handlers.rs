@@ -18,6 +18,7 @@ use de_core::{
     gamestate::GameState,
     gconfig::GameConfig,
     objects::{ObjectTypeComponent, Playable},
+    pause::{ChangeSpeedEvent, RequestPauseEvent, RequestResumeEvent},
     player::PlayerComponent,
     schedule::InputSchedule,
     screengeom::ScreenRect,
@@ -125,6 +126,22 @@ impl Plugin for HandlersPlugin {
                 .run_if(in_state(GameState::Playing)),
         );
 
+        app.add_systems(
+            InputSchedule,
+            (
+                toggle_pause.run_if(KeyCondition::single(KeyCode::Pause).build()),
+                increase_speed.run_if(KeyCondition::single(KeyCode::Equal).build()),
+                increase_speed.run_if(KeyCondition::single(KeyCode::NumpadAdd).build()),
+                decrease_speed.run_if(KeyCondition::single(KeyCode::Minus).build()),
+                decrease_speed.run_if(KeyCondition::single(KeyCode::NumpadSubtract).build()),
+                toggle_menu
+                    .run_if(in_state(GameState::Paused))
+                    .run_if(KeyCondition::single(KeyCode::Escape).build())
+                    .before(GameMenuSet::Toggle),
+            )
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+        );
+
         Self::add_place_draft_systems(app);
     }
 }
@@ -342,6 +359,30 @@ fn handle_escape(
     }
 }
 
+fn toggle_menu(mut toggle_menu_events: EventWriter<ToggleGameMenuEvent>) {
+    toggle_menu_events.send(ToggleGameMenuEvent);
+}
+
+fn toggle_pause(
+    state: Res<State<GameState>>,
+    mut pause_events: EventWriter<RequestPauseEvent>,
+    mut resume_events: EventWriter<RequestResumeEvent>,
+) {
+    if state.get() == &GameState::Paused {
+        resume_events.send(RequestResumeEvent);
+    } else {
+        pause_events.send(RequestPauseEvent);
+    }
+}
+
+fn increase_speed(mut events: EventWriter<ChangeSpeedEvent>) {
+    events.send(ChangeSpeedEvent::Increase);
+}
+
+fn decrease_speed(mut events: EventWriter<ChangeSpeedEvent>) {
+    events.send(ChangeSpeedEvent::Decrease);
+}
+
 fn place_draft(
     building_type: BuildingType,
 ) -> impl Fn(Res<GameConfig>, Res<ObjectCounter>, Res<Pointer>, EventWriter<NewDraftEvent>) {
@@ -349,11 +390,11 @@ fn place_draft(
           counter: Res<ObjectCounter>,
           pointer: Res<Pointer>,
           mut events: EventWriter<NewDraftEvent>| {
-        if counter
-            .player(conf.locals().playable())
-            .map_or(0, |c| c.building_count())
-            >= PLAYER_MAX_BUILDINGS
-        {
+        let Some(playable) = conf.locals().playable() else {
+            return;
+        };
+
+        if counter.player(playable).map_or(0, |c| c.building_count()) >= PLAYER_MAX_BUILDINGS {
             warn!("Maximum number of buildings reached.");
             return;
         }
@@ -78,6 +78,10 @@ fn spawn(
     drafts: Query<(Entity, &Transform, &ObjectTypeComponent, &DraftAllowed)>,
     mut spawn_active_events: EventWriter<SpawnLocalActiveEvent>,
 ) {
+    let Some(playable) = game_config.locals().playable() else {
+        return;
+    };
+
     for (entity, &transform, &object_type, draft) in drafts.iter() {
         if draft.allowed() {
             commands.entity(entity).despawn_recursive();
@@ -88,7 +92,7 @@ fn spawn(
             spawn_active_events.send(SpawnLocalActiveEvent::stationary(
                 object_type,
                 transform,
-                game_config.locals().playable(),
+                playable,
             ));
         }
     }
@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use de_construction::EnqueueAssemblyEvent;
 use de_core::{
     cleanup::DespawnOnGameExit, gamestate::GameState, objects::ObjectTypeComponent,
-    schedule::InputSchedule,
+    schedule::InputSchedule, state::AppState,
 };
 use de_gui::{ButtonCommands, GuiCommands, OuterStyle};
 use de_objects::SolidObjects;
@@ -15,22 +15,28 @@ pub(crate) struct ActionBarPlugin;
 
 impl Plugin for ActionBarPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Playing), setup)
-            .add_systems(OnExit(GameState::Playing), cleanup)
-            .add_systems(
-                PostUpdate,
-                (
-                    detect_update.in_set(ActionBarSet::DetectUpdate),
-                    update
-                        .run_if(resource_exists_and_changed::<ActiveEntity>)
-                        .after(ActionBarSet::DetectUpdate),
-                )
-                    .run_if(in_state(GameState::Playing)),
+        app.add_systems(
+            OnTransition {
+                from: GameState::Waiting,
+                to: GameState::Playing,
+            },
+            setup,
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup)
+        .add_systems(
+            PostUpdate,
+            (
+                detect_update.in_set(ActionBarSet::DetectUpdate),
+                update
+                    .run_if(resource_exists_and_changed::<ActiveEntity>)
+                    .after(ActionBarSet::DetectUpdate),
             )
-            .add_systems(
-                InputSchedule,
-                button_system.run_if(in_state(GameState::Playing)),
-            );
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            InputSchedule,
+            button_system.run_if(in_state(GameState::Playing)),
+        );
     }
 }
 
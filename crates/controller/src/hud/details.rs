@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use de_core::{cleanup::DespawnOnGameExit, gamestate::GameState};
+use de_core::{cleanup::DespawnOnGameExit, gamestate::GameState, state::AppState};
 use de_energy::Battery;
 use de_gui::{BodyTextCommands, BodyTextOps, GuiCommands, OuterStyle};
 
@@ -12,9 +12,15 @@ pub(crate) struct DetailsPlugin;
 
 impl Plugin for DetailsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Playing), setup)
-            .add_systems(PostUpdate, update.run_if(in_state(GameState::Playing)))
-            .add_systems(OnExit(GameState::Playing), clean_up);
+        app.add_systems(
+            OnTransition {
+                from: GameState::Waiting,
+                to: GameState::Playing,
+            },
+            setup,
+        )
+        .add_systems(PostUpdate, update.run_if(in_state(GameState::Playing)))
+        .add_systems(OnExit(AppState::InGame), clean_up);
     }
 }
 
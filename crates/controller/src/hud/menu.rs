@@ -11,12 +11,18 @@ pub(crate) struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ToggleGameMenuEvent>()
-            .add_systems(OnEnter(GameState::Playing), setup)
-            .add_systems(OnExit(GameState::Playing), cleanup)
+            .add_systems(
+                OnTransition {
+                    from: GameState::Waiting,
+                    to: GameState::Playing,
+                },
+                setup,
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup)
             .add_systems(
                 InputSchedule,
                 (toggle_system.in_set(GameMenuSet::Toggle), button_system)
-                    .run_if(in_state(GameState::Playing)),
+                    .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
             );
     }
 }
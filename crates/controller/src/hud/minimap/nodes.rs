@@ -17,11 +17,17 @@ pub(super) struct NodesPlugin;
 
 impl Plugin for NodesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Playing), setup)
-            .add_systems(
-                PreMovement,
-                update_resolution.run_if(in_state(GameState::Playing)),
-            );
+        app.add_systems(
+            OnTransition {
+                from: GameState::Waiting,
+                to: GameState::Playing,
+            },
+            setup,
+        )
+        .add_systems(
+            PreMovement,
+            update_resolution.run_if(in_state(GameState::Playing)),
+        );
     }
 }
 
@@ -5,6 +5,7 @@ mod details;
 mod interaction;
 mod menu;
 mod minimap;
+mod pause;
 mod selection;
 
 pub(crate) use interaction::HudNodes;
@@ -13,7 +14,7 @@ pub(crate) use selection::UpdateSelectionBoxEvent;
 
 use self::{
     actionbar::ActionBarPlugin, details::DetailsPlugin, menu::MenuPlugin, minimap::MinimapPlugin,
-    selection::SelectionPlugin,
+    pause::PausePlugin, selection::SelectionPlugin,
 };
 
 const HUD_COLOR: Color = Color::BLACK;
@@ -28,6 +29,7 @@ impl Plugin for HudPlugin {
             ActionBarPlugin,
             MenuPlugin,
             MinimapPlugin,
+            PausePlugin,
         ));
     }
 }
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use de_core::{cleanup::DespawnOnGameExit, gamestate::GameState};
+use de_gui::{BodyTextCommands, BodyTextOps, GuiCommands, OuterStyle};
+
+use super::HUD_COLOR;
+
+/// Indicator of a paused game or of a non-default game speed.
+pub(crate) struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnTransition {
+                from: GameState::Waiting,
+                to: GameState::Playing,
+            },
+            setup,
+        )
+        .add_systems(
+            Update,
+            update.run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+        );
+    }
+}
+
+#[derive(Component)]
+struct IndicatorNode;
+
+#[derive(Component)]
+struct IndicatorText;
+
+fn setup(mut commands: GuiCommands) {
+    let node = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(30.),
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(35.),
+                    top: Val::Percent(5.),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: HUD_COLOR.into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            IndicatorNode,
+            DespawnOnGameExit,
+        ))
+        .id();
+    let text = commands
+        .spawn_body_text(
+            OuterStyle {
+                margin: UiRect::all(Val::Percent(2.)),
+                ..default()
+            },
+            "",
+        )
+        .insert(IndicatorText)
+        .id();
+    commands.entity(node).add_child(text);
+}
+
+fn update(
+    state: Res<State<GameState>>,
+    time: Res<Time<Virtual>>,
+    mut shown: Local<Option<(bool, f32)>>,
+    mut nodes: Query<&mut Visibility, With<IndicatorNode>>,
+    texts: Query<(Entity, Ref<IndicatorText>)>,
+    mut text_ops: BodyTextOps,
+) {
+    let Ok(mut visibility) = nodes.get_single_mut() else {
+        return;
+    };
+    let Ok((text_entity, indicator)) = texts.get_single() else {
+        return;
+    };
+
+    let paused = state.get() == &GameState::Paused;
+    let speed = time.relative_speed();
+    // The text is re-laid out on each change, thus it is updated only when
+    // the indicated state changes or when the indicator is (re)spawned.
+    if !indicator.is_added() && *shown == Some((paused, speed)) {
+        return;
+    }
+    *shown = Some((paused, speed));
+
+    let text = if paused {
+        Some("Game paused, press Pause to resume.".to_owned())
+    } else if speed != 1. {
+        Some(format!("Game speed {speed}x, press + or - to change it."))
+    } else {
+        None
+    };
+
+    visibility.set_if_neq(if text.is_some() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+    if let Some(text) = text {
+        text_ops.set_text(text_entity, text).unwrap();
+    }
+}
@@ -303,7 +303,7 @@ fn check_double_click(
     mut double_clicks: EventWriter<MouseDoubleClickedEvent>,
     mut last_click_position: Local<Option<Vec2>>,
     mut last_click_time: Local<f64>,
-    time: Res<Time>,
+    time: Res<Time<Real>>,
 ) {
     for mouse_clicked in clicks.read() {
         let current_time = time.elapsed_seconds_f64();
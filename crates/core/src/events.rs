@@ -5,8 +5,8 @@ use bevy::{ecs::system::Resource, prelude::*};
 use crate::gamestate::GameState;
 
 /// This plugin accumulates events received during [`GameState::Prepared`],
-/// [`GameState::Loading`] and [`GameState::Waiting`] and re-sends them on
-/// enter of [`GameState::Playing`].
+/// [`GameState::Loading`] and [`GameState::Waiting`] and re-sends them once the
+/// game-play starts, i.e. on transition to [`GameState::Playing`].
 pub struct ResendEventPlugin<T: Event> {
     _marker: PhantomData<T>,
 }
@@ -31,7 +31,10 @@ impl<T: Event> Plugin for ResendEventPlugin<T> {
                 ),
             )
             .add_systems(
-                OnEnter(GameState::Playing),
+                OnTransition {
+                    from: GameState::Waiting,
+                    to: GameState::Playing,
+                },
                 (resend_events::<T>, cleanup::<T>),
             );
     }
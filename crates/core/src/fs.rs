@@ -13,6 +13,17 @@ pub fn logs_dir() -> Result<AsyncPathBuf, DirError> {
     dir(dirs::cache_dir).map(|d| d.join("logs"))
 }
 
+/// Returns DE directory with recorded game replays.
+pub fn replays_dir() -> Result<AsyncPathBuf, DirError> {
+    dir(dirs::data_dir).map(|d| d.join("replays"))
+}
+
+/// Returns DE directory with maps downloaded or otherwise installed by the
+/// user.
+pub fn maps_dir() -> Result<AsyncPathBuf, DirError> {
+    dir(dirs::data_dir).map(|d| d.join("maps"))
+}
+
 fn dir<F>(base_dir: F) -> Result<AsyncPathBuf, DirError>
 where
     F: Fn() -> Option<SyncPathBuf>,
@@ -30,6 +30,9 @@ nested_state!(
         // initialization as well.
         Waiting,
         Playing,
+        // The game-play is paused, see `crate::pause`. The game goes back to
+        // `Playing` once resumed.
+        Paused,
     }
 );
 
@@ -10,6 +10,7 @@ use tinyvec::{array_vec, ArrayVec};
 pub struct GameConfig {
     map_path: PathBuf,
     multiplayer: bool,
+    rejoined: bool,
     locals: LocalPlayers,
 }
 
@@ -18,10 +19,19 @@ impl GameConfig {
         Self {
             map_path: map_path.into(),
             multiplayer,
+            rejoined: false,
             locals,
         }
     }
 
+    /// Marks the game as rejoined after a connection loss. Active objects are
+    /// not spawned from the map in such a case, they are restored from the
+    /// other players instead.
+    pub fn rejoined(mut self) -> Self {
+        self.rejoined = true;
+        self
+    }
+
     pub fn map_path(&self) -> &Path {
         self.map_path.as_path()
     }
@@ -30,6 +40,11 @@ impl GameConfig {
         self.multiplayer
     }
 
+    /// Returns true if the game is being rejoined after a connection loss.
+    pub fn is_rejoined(&self) -> bool {
+        self.rejoined
+    }
+
     pub fn locals(&self) -> &LocalPlayers {
         &self.locals
     }
@@ -42,8 +57,11 @@ impl GameConfig {
 /// "Local" is either a "Playable" player or a player controlled by the AI on
 /// this computer. During a multiplayer game, each AI player is simulated by
 /// exactly one computer.
+///
+/// There are no local players when the user only observes the game, for
+/// example during a game replay.
 pub struct LocalPlayers {
-    playable: Player,
+    playable: Option<Player>,
     locals: ArrayVec<[Player; Player::MAX_PLAYERS]>,
 }
 
@@ -60,6 +78,15 @@ impl LocalPlayers {
         Self::new(playable, array_vec!(_ => playable))
     }
 
+    /// Creates local players of a game which is only observed by the user. No
+    /// player is controlled or simulated on this computer.
+    pub fn observer() -> Self {
+        Self {
+            playable: None,
+            locals: ArrayVec::new(),
+        }
+    }
+
     /// # Arguments
     ///
     /// * `playable` - the player controlled locally by the user.
@@ -68,14 +95,23 @@ impl LocalPlayers {
     ///   include `playable`.
     pub fn new(playable: Player, locals: ArrayVec<[Player; Player::MAX_PLAYERS]>) -> Self {
         assert!((*locals).contains(&playable));
-        Self { playable, locals }
+        Self {
+            playable: Some(playable),
+            locals,
+        }
     }
 
-    /// The player controlled directly by the user on this computer.
-    pub fn playable(&self) -> Player {
+    /// The player controlled directly by the user on this computer. None is
+    /// returned if the user only observes the game.
+    pub fn playable(&self) -> Option<Player> {
         self.playable
     }
 
+    /// Returns true if the user only observes the game.
+    pub fn is_observer(&self) -> bool {
+        self.playable.is_none()
+    }
+
     pub fn locals(&self) -> &[Player] {
         self.locals.as_slice()
     }
@@ -83,7 +119,7 @@ impl LocalPlayers {
     /// Returns true if the player is controlled directly by the user on this
     /// computer.
     pub fn is_playable(&self, player: Player) -> bool {
-        self.playable == player
+        self.playable == Some(player)
     }
 
     /// Returns true if the player is simulated by this computer.
@@ -113,4 +149,21 @@ mod tests {
         );
         assert_eq!(config.map_path().to_string_lossy(), "/some/path");
     }
+
+    #[test]
+    fn test_local_players() {
+        let locals = LocalPlayers::from_max_player(Player::Player2, Player::Player3);
+        assert_eq!(locals.playable(), Some(Player::Player2));
+        assert!(!locals.is_observer());
+        assert!(locals.is_playable(Player::Player2));
+        assert!(!locals.is_playable(Player::Player1));
+        assert!(locals.is_local(Player::Player1));
+        assert!(!locals.is_local(Player::Player4));
+
+        let observer = LocalPlayers::observer();
+        assert_eq!(observer.playable(), None);
+        assert!(observer.is_observer());
+        assert!(!observer.is_playable(Player::Player1));
+        assert!(!observer.is_local(Player::Player1));
+    }
 }
@@ -2,6 +2,7 @@ use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
 use cleanup::CleanupPlugin;
 use gamestate::GameStateSetupPlugin;
 use iyes_progress::prelude::*;
+use pause::PausePlugin;
 use schedule::GameSchedulesPlugin;
 use state::AppState;
 use visibility::VisibilityPlugin;
@@ -17,6 +18,7 @@ pub mod gamestate;
 pub mod gconfig;
 pub mod gresult;
 pub mod objects;
+pub mod pause;
 pub mod player;
 pub mod schedule;
 pub mod screengeom;
@@ -35,5 +37,6 @@ impl PluginGroup for CorePluginGroup {
             .add(GameStateSetupPlugin)
             .add(VisibilityPlugin)
             .add(CleanupPlugin)
+            .add(PausePlugin)
     }
 }
@@ -0,0 +1,134 @@
+//! Pausing and speed of the game-play.
+//!
+//! The game is paused in [`GameState::Paused`]. Virtual time (i.e. the
+//! default [`Time`]) does not advance in the state, thus all time dependent
+//! simulation (e.g. manufacturing, laser charging or battery discharge) is
+//! frozen. Systems which need to run exactly once per game, e.g. setup of the
+//! game-play UI, should be scheduled on the transition from
+//! [`GameState::Waiting`] to [`GameState::Playing`] instead of on enter of
+//! [`GameState::Playing`].
+//!
+//! Systems which must keep running during a pause, e.g. user input handling
+//! or network traffic, must be driven by [`Time<Real>`] instead.
+//!
+//! Similarly, speed of the game-play is the relative speed of the virtual
+//! time. All simulation must therefore be driven by the default [`Time`] so
+//! that it follows a single clock, while systems driven by [`Time<Real>`]
+//! (e.g. camera movement) run at the normal speed regardless of the game-play
+//! speed.
+
+use bevy::prelude::*;
+
+use crate::{gamestate::GameState, gconfig::GameConfig, state::AppState};
+
+/// Selectable speeds of the game-play relative to the wall-clock time.
+const SPEEDS: [f32; 4] = [0.5, 1., 2., 4.];
+
+pub(crate) struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RequestPauseEvent>()
+            .add_event::<RequestResumeEvent>()
+            .add_event::<ChangeSpeedEvent>()
+            .add_systems(OnEnter(GameState::Paused), pause)
+            .add_systems(OnExit(GameState::Paused), unpause)
+            .add_systems(OnExit(AppState::InGame), reset_speed)
+            .add_systems(
+                Update,
+                (
+                    pause_singleplayer
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(on_event::<RequestPauseEvent>()),
+                    resume_singleplayer
+                        .run_if(in_state(GameState::Paused))
+                        .run_if(on_event::<RequestResumeEvent>()),
+                    change_speed
+                        .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused)))
+                        .run_if(on_event::<ChangeSpeedEvent>()),
+                )
+                    .run_if(singleplayer),
+            );
+    }
+}
+
+/// Send this event to request a pause of the game-play.
+///
+/// In a multiplayer game, the request is passed to the game server and the
+/// game is paused once the server accepts it.
+#[derive(Event)]
+pub struct RequestPauseEvent;
+
+/// Send this event to request resumption of the paused game-play.
+///
+/// In a multiplayer game, this is a vote passed to the game server. The game
+/// is resumed once all players vote for it (or once the pause budget of the
+/// pausing player is exhausted).
+#[derive(Event)]
+pub struct RequestResumeEvent;
+
+/// Send this event to change speed of the game-play. The speed might be
+/// changed only in a singleplayer game and it is reset to the normal speed
+/// once the game ends.
+#[derive(Event, Clone, Copy)]
+pub enum ChangeSpeedEvent {
+    /// Switches to the next faster speed (if any).
+    Increase,
+    /// Switches to the next slower speed (if any).
+    Decrease,
+}
+
+fn singleplayer(config: Option<Res<GameConfig>>) -> bool {
+    config.is_some_and(|config| !config.multiplayer())
+}
+
+fn pause(mut time: ResMut<Time<Virtual>>) {
+    info!("Pausing the game.");
+    time.pause();
+}
+
+fn unpause(mut time: ResMut<Time<Virtual>>) {
+    info!("Resuming the game.");
+    time.unpause();
+}
+
+fn pause_singleplayer(
+    mut events: EventReader<RequestPauseEvent>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    events.clear();
+    next_state.set(GameState::Paused);
+}
+
+fn resume_singleplayer(
+    mut events: EventReader<RequestResumeEvent>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    events.clear();
+    next_state.set(GameState::Playing);
+}
+
+fn change_speed(mut events: EventReader<ChangeSpeedEvent>, mut time: ResMut<Time<Virtual>>) {
+    let current = time.relative_speed();
+    let mut index = SPEEDS
+        .iter()
+        .position(|&speed| speed == current)
+        .unwrap_or(1);
+
+    for event in events.read() {
+        index = match event {
+            ChangeSpeedEvent::Increase => (index + 1).min(SPEEDS.len() - 1),
+            ChangeSpeedEvent::Decrease => index.saturating_sub(1),
+        };
+    }
+
+    let speed = SPEEDS[index];
+    if speed != current {
+        info!("Changing game speed to {speed}x.");
+        time.set_relative_speed(speed);
+    }
+}
+
+fn reset_speed(mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed(1.);
+}
@@ -99,7 +99,7 @@ fn process_events(mut events: EventReader<ToastEvent>, mut queue: ResMut<ToastQu
 
 fn spawn_and_despawn(
     mut commands: Commands,
-    time: Res<Time>,
+    time: Res<Time<Real>>,
     text_props: Res<TextProps>,
     mut queue: ResMut<ToastQueue>,
 ) {
@@ -117,7 +117,9 @@ fn spawn_map(
         match object.inner() {
             InnerObject::Active(object) => {
                 let player = object.player();
-                if !locals.is_local(player) {
+                // Active objects of a rejoined game are restored from the
+                // game server.
+                if game_config.is_rejoined() || !locals.is_local(player) {
                     continue;
                 }
 
@@ -1,10 +1,16 @@
 use anyhow::{Context, Result};
-use de_lobby_model::{User, UserWithPassword, UsernameAndPassword, MAX_USERNAME_LEN};
+use de_lobby_model::{
+    PasswordChange, User, UserWithPassword, UsernameAndPassword, MAX_USERNAME_LEN,
+};
+use jsonwebtoken::get_current_timestamp;
 use log::info;
 use sqlx::{query, sqlite::SqliteRow, Pool, Row, Sqlite};
 use thiserror::Error;
 
-use super::passwd::{DbPassword, MAX_PASS_HASH_LEN, MAX_PASS_SALT_LEN};
+use super::{
+    passwd::{DbPassword, MAX_PASS_HASH_LEN, MAX_PASS_SALT_LEN},
+    token::{Claims, MAX_TOKEN_ID_LEN},
+};
 use crate::{
     db::{FromRow, SQLITE_CONSTRAINT_PRIMARYKEY},
     db_error_code,
@@ -24,6 +30,7 @@ impl Users {
             username_len = MAX_USERNAME_LEN,
             pass_hash_len = MAX_PASS_HASH_LEN,
             pass_salt_len = MAX_PASS_SALT_LEN,
+            token_id_len = MAX_TOKEN_ID_LEN,
         );
 
         info!("Initializing users...");
@@ -57,20 +64,129 @@ impl Users {
         Ok(())
     }
 
-    /// Validates username and password of the user. Returns true if the user
-    /// exists and the password is correct.
-    pub(super) async fn login(&self, user: &UsernameAndPassword) -> Result<bool> {
+    /// Validates username and password of the user. Returns current token
+    /// generation of the user if the user exists and the password is correct.
+    pub(super) async fn login(&self, user: &UsernameAndPassword) -> Result<Option<u32>> {
         info!("Logging in user {}...", user.username());
 
+        let row = query(
+            "SELECT users.pass_hash, users.pass_salt, \
+             COALESCE(token_generations.generation, 0) AS generation \
+             FROM users \
+             LEFT JOIN token_generations ON token_generations.username = users.username \
+             WHERE users.username = ?;",
+        )
+        .bind(user.username())
+        .fetch_optional(self.pool)
+        .await?;
+        let Some(row) = row else { return Ok(None) };
+
+        let generation: u32 = row
+            .try_get("generation")
+            .context("Failed to retrieve token generation from the DB")?;
+        if DbPassword::try_from(row)?.check(user.password()) {
+            Ok(Some(generation))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Changes password of a user. All tokens issued before the change are
+    /// revoked. The new token generation of the user is returned.
+    pub(super) async fn change_password(
+        &self,
+        username: &str,
+        change: &PasswordChange,
+    ) -> Result<u32, PasswordChangeError> {
+        info!("Changing password of user {username}...");
+
+        let mut transaction = self.pool.begin().await?;
+
         let row = query("SELECT pass_hash, pass_salt FROM users WHERE username = ?;")
-            .bind(user.username())
-            .fetch_optional(self.pool)
+            .bind(username)
+            .fetch_optional(&mut transaction)
+            .await?;
+        let Some(row) = row else {
+            return Err(PasswordChangeError::UserNotFound);
+        };
+        if !DbPassword::try_from(row)?.check(change.password()) {
+            return Err(PasswordChangeError::WrongPassword);
+        }
+
+        let password = DbPassword::generate(change.new_password())?;
+        query("UPDATE users SET pass_hash = ?, pass_salt = ? WHERE username = ?;")
+            .bind(password.b64_encode_pwd_hash()?)
+            .bind(password.salt_str())
+            .bind(username)
+            .execute(&mut transaction)
             .await?;
-        let Some(row) = row else { return Ok(false) };
-        Ok(DbPassword::try_from(row)?.check(user.password()))
+
+        let generation: u32 = query(
+            "INSERT INTO token_generations (username, generation) VALUES (?, 1) \
+             ON CONFLICT(username) DO UPDATE SET generation = generation + 1 \
+             RETURNING generation;",
+        )
+        .bind(username)
+        .fetch_one(&mut transaction)
+        .await?
+        .try_get("generation")
+        .context("Failed to retrieve token generation from the DB")?;
+
+        transaction.commit().await?;
+        Ok(generation)
+    }
+
+    /// Adds a token to the revocation list. The token is kept on the list
+    /// until it expires. It returns false if the token has no ID and thus
+    /// cannot be revoked.
+    ///
+    /// Expired tokens are removed from the list as part of this call.
+    pub(super) async fn revoke(&self, claims: &Claims) -> Result<bool> {
+        let Some(id) = claims.id() else {
+            return Ok(false);
+        };
+
+        query("DELETE FROM revoked_tokens WHERE expires < ?;")
+            .bind(timestamp(get_current_timestamp()))
+            .execute(self.pool)
+            .await
+            .context("Failed to remove expired tokens from the DB")?;
+
+        query("INSERT OR IGNORE INTO revoked_tokens (id, expires) VALUES (?, ?);")
+            .bind(id)
+            .bind(timestamp(claims.expires()))
+            .execute(self.pool)
+            .await
+            .context("Failed to store a revoked token to the DB")?;
+        Ok(true)
+    }
+
+    /// Returns true if the token was revoked either explicitly or by a change
+    /// of the password of its user.
+    pub(crate) async fn is_revoked(&self, claims: &Claims) -> Result<bool> {
+        let row = query(
+            "SELECT \
+             EXISTS(SELECT 1 FROM revoked_tokens WHERE id = ?) \
+             OR EXISTS(SELECT 1 FROM token_generations WHERE username = ? AND generation > ?) \
+             AS revoked;",
+        )
+        .bind(claims.id())
+        .bind(claims.username())
+        .bind(claims.generation())
+        .fetch_one(self.pool)
+        .await
+        .context("Failed to check token revocation in the DB")?;
+
+        row.try_get("revoked")
+            .context("Failed to retrieve token revocation from the DB")
     }
 }
 
+/// Converts a Unix timestamp to a value storable in the DB.
+fn timestamp(value: u64) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
 #[derive(Error, Debug)]
 pub(super) enum RegistrationError {
     #[error("Username is already taken")]
@@ -81,6 +197,18 @@ pub(super) enum RegistrationError {
     Other(#[from] anyhow::Error),
 }
 
+#[derive(Error, Debug)]
+pub(super) enum PasswordChangeError {
+    #[error("User does not exist")]
+    UserNotFound,
+    #[error("Wrong password")]
+    WrongPassword,
+    #[error("A database error encountered")]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
 impl TryFrom<SqliteRow> for DbPassword {
     type Error = anyhow::Error;
 
@@ -1,17 +1,27 @@
-use actix_web::{post, web, HttpResponse, Responder};
-use de_lobby_model::{Token, UserWithPassword, UsernameAndPassword};
+use actix_web::{post, put, web, HttpResponse, Responder};
+use de_lobby_model::{PasswordChange, Token, UserWithPassword, UsernameAndPassword};
 use log::{error, info, warn};
 
 use super::{
-    db::{RegistrationError, Users},
-    token::{Claims, Tokens},
+    db::{PasswordChangeError, RegistrationError, Users},
+    token::{Claims, Tokens, TOKEN_LIFETIME},
 };
 
-/// Registers all authentication endpoints.
-pub(super) fn configure(cfg: &mut web::ServiceConfig) {
+/// Registers all public authentication endpoints.
+pub(super) fn configure_public(cfg: &mut web::ServiceConfig) {
     cfg.service(web::scope("/auth").service(sign_up).service(sign_in));
 }
 
+/// Registers all authentication endpoints which require a valid token.
+pub(super) fn configure_authenticated(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/auth")
+            .service(refresh)
+            .service(sign_out)
+            .service(change_password),
+    );
+}
+
 #[post("/sign-up")]
 async fn sign_up(
     tokens: web::Data<Tokens>,
@@ -25,18 +35,14 @@ async fn sign_up(
 
     match users.register(&user.0).await {
         Ok(_) => {
-            let token = match tokens.encode(&Claims::standard(user.0.user().username())) {
-                Ok(token) => token,
-                Err(error) => {
-                    error!("Token encoding error: {:?}", error);
-                    return HttpResponse::InternalServerError().finish();
-                }
+            let Some(token) = issue(tokens.as_ref(), user.0.user().username(), 0) else {
+                return HttpResponse::InternalServerError().finish();
             };
             info!(
                 "Registration of user {} was successful.",
                 user.user().username()
             );
-            HttpResponse::Ok().json(Token::new(token))
+            HttpResponse::Ok().json(token)
         }
         Err(RegistrationError::UsernameTaken) => {
             warn!("Username {} is already taken.", user.user().username());
@@ -56,20 +62,16 @@ async fn sign_in(
     user: web::Json<UsernameAndPassword>,
 ) -> impl Responder {
     match users.login(&user.0).await {
-        Ok(false) => {
+        Ok(None) => {
             warn!("Signing in of user {} was unsuccessful.", user.username());
             HttpResponse::Unauthorized().finish()
         }
-        Ok(true) => {
-            let token = match tokens.encode(&Claims::standard(user.0.username())) {
-                Ok(token) => token,
-                Err(error) => {
-                    error!("Token encoding error: {:?}", error);
-                    return HttpResponse::InternalServerError().finish();
-                }
+        Ok(Some(generation)) => {
+            let Some(token) = issue(tokens.as_ref(), user.0.username(), generation) else {
+                return HttpResponse::InternalServerError().finish();
             };
             info!("Signing in of user {} was successful.", user.username());
-            HttpResponse::Ok().json(Token::new(token))
+            HttpResponse::Ok().json(token)
         }
         Err(error) => {
             error!("Sign-in error: {:?}", error);
@@ -77,3 +79,92 @@ async fn sign_in(
         }
     }
 }
+
+/// Issues a new token in place of the token used to authenticate the request.
+///
+/// The old token is not revoked and stays valid until it expires, so that
+/// requests the client has already sent with it do not fail.
+#[post("/refresh")]
+async fn refresh(claims: web::ReqData<Claims>, tokens: web::Data<Tokens>) -> impl Responder {
+    match issue(tokens.as_ref(), claims.username(), claims.generation()) {
+        Some(token) => HttpResponse::Ok().json(token),
+        None => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Revokes the token used to authenticate the request.
+#[post("/sign-out")]
+async fn sign_out(claims: web::ReqData<Claims>, users: web::Data<Users>) -> impl Responder {
+    match users.revoke(&claims).await {
+        Ok(true) => {
+            info!("Signing out of user {} was successful.", claims.username());
+            HttpResponse::Ok().json(())
+        }
+        Ok(false) => {
+            warn!(
+                "Token of user {} has no ID and stays valid until it expires.",
+                claims.username()
+            );
+            HttpResponse::Ok().json(())
+        }
+        Err(error) => {
+            error!("Sign-out error: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Changes password of the authenticated user. All previously issued tokens of
+/// the user are revoked and a new token is issued.
+#[put("/password")]
+async fn change_password(
+    claims: web::ReqData<Claims>,
+    tokens: web::Data<Tokens>,
+    users: web::Data<Users>,
+    change: web::Json<PasswordChange>,
+) -> impl Responder {
+    if let Err(error) = change.validate() {
+        warn!("Invalid password change request: {}", error);
+        return HttpResponse::BadRequest().json(error.to_string());
+    }
+
+    let generation = match users.change_password(claims.username(), &change).await {
+        Ok(generation) => generation,
+        Err(PasswordChangeError::WrongPassword) => {
+            warn!(
+                "Password change of user {} failed due to a wrong password.",
+                claims.username()
+            );
+            return HttpResponse::Forbidden().json("Wrong password.");
+        }
+        Err(PasswordChangeError::UserNotFound) => {
+            warn!(
+                "Password change of non-existent user {}.",
+                claims.username()
+            );
+            return HttpResponse::NotFound().json("The user does not exist.");
+        }
+        Err(error) => {
+            error!("Password change error: {:?}", error);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    info!("Password of user {} was changed.", claims.username());
+    match issue(tokens.as_ref(), claims.username(), generation) {
+        Some(token) => HttpResponse::Ok().json(token),
+        None => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Issues a new token of a given token generation for a user. None is
+/// returned (and the error logged) if the token could not be encoded.
+fn issue(tokens: &Tokens, username: &str, generation: u32) -> Option<Token> {
+    match tokens.encode(&Claims::standard(username, generation)) {
+        Ok(token) => Some(Token::new(token, TOKEN_LIFETIME)),
+        Err(error) => {
+            error!("Token encoding error: {:?}", error);
+            None
+        }
+    }
+}
@@ -3,3 +3,20 @@ CREATE TABLE IF NOT EXISTS users (
     pass_hash CHARACTER({pass_hash_len}) NOT NULL,
     pass_salt CHARACTER({pass_salt_len}) NOT NULL
 );
+
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    id CHARACTER({token_id_len}) NOT NULL PRIMARY KEY,
+    expires INTEGER NOT NULL
+);
+
+-- Token generation is incremented with each password change. Tokens issued
+-- with an older generation are revoked. Users without a record are at
+-- generation 0.
+CREATE TABLE IF NOT EXISTS token_generations (
+    username CHARACTER({username_len}) NOT NULL PRIMARY KEY,
+    generation INTEGER NOT NULL,
+
+    FOREIGN KEY(username) REFERENCES users(username)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
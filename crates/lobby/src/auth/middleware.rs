@@ -1,22 +1,25 @@
-use std::future::{ready, Ready};
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
 
 use actix_web::{
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    error::ErrorUnauthorized,
+    error::{ErrorInternalServerError, ErrorUnauthorized},
     http::header::Header,
     web, Error, HttpMessage,
 };
 use actix_web_httpauth::headers::authorization::{Authorization, Bearer};
 use futures_util::future::LocalBoxFuture;
-use log::warn;
+use log::{error, warn};
 
-use super::token::Tokens;
+use super::{db::Users, token::Tokens};
 
 pub struct AuthMiddlewareFactory;
 
 impl<S, B> Transform<S, ServiceRequest> for AuthMiddlewareFactory
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -27,17 +30,19 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(AuthMiddleware { service }))
+        ready(Ok(AuthMiddleware {
+            service: Rc::new(service),
+        }))
     }
 }
 
 pub struct AuthMiddleware<S> {
-    service: S,
+    service: Rc<S>,
 }
 
 impl<S, B> Service<ServiceRequest> for AuthMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -49,13 +54,11 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let tokens = req.app_data::<web::Data<Tokens>>().unwrap().as_ref();
+        let users = req.app_data::<web::Data<Users>>().unwrap().clone();
 
-        match Authorization::<Bearer>::parse(&req) {
+        let claims = match Authorization::<Bearer>::parse(&req) {
             Ok(auth) => match tokens.decode(auth.as_ref().token()) {
-                Ok(claims) => {
-                    let previous = req.extensions_mut().insert(claims);
-                    assert!(previous.is_none());
-                }
+                Ok(claims) => claims,
                 Err(error) => {
                     warn!("JWT decoding error: {:?}", error);
                     return Box::pin(async move {
@@ -73,8 +76,27 @@ where
                     ))
                 });
             }
-        }
+        };
+
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {
+            match users.is_revoked(&claims).await {
+                Ok(false) => (),
+                Ok(true) => {
+                    warn!("Revoked JWT of user {} used.", claims.username());
+                    return Err(ErrorUnauthorized("The Bearer token was revoked."));
+                }
+                Err(error) => {
+                    error!("JWT revocation check error: {:?}", error);
+                    return Err(ErrorInternalServerError(
+                        "Failed to validate the Bearer token.",
+                    ));
+                }
+            }
 
-        Box::pin(self.service.call(req))
+            let previous = req.extensions_mut().insert(claims);
+            assert!(previous.is_none());
+            service.call(req).await
+        })
     }
 }
@@ -10,7 +10,7 @@ use crate::conf;
 mod db;
 mod endpoints;
 mod middleware;
-mod passwd;
+pub(crate) mod passwd;
 mod token;
 
 const JWT_SECRET_VAR_NAME: &str = "DE_JWT_SECRET";
@@ -62,6 +62,11 @@ impl Auth {
 
     /// Configure public scope of the actix-web application.
     pub fn configure_public(&self, cfg: &mut web::ServiceConfig) {
-        endpoints::configure(cfg);
+        endpoints::configure_public(cfg);
+    }
+
+    /// Configure authenticated scope of the actix-web application.
+    pub fn configure_authenticated(&self, cfg: &mut web::ServiceConfig) {
+        endpoints::configure_authenticated(cfg);
     }
 }
@@ -6,18 +6,18 @@ use pbkdf2::{
 use rand_core::OsRng;
 use subtle::ConstantTimeEq;
 
-pub(super) const MAX_PASS_HASH_LEN: usize = Output::B64_MAX_LENGTH;
+pub(crate) const MAX_PASS_HASH_LEN: usize = Output::B64_MAX_LENGTH;
 // `RECOMMENDED_LENGTH` bytes are B64 encoded by `SaltString`
-pub(super) const MAX_PASS_SALT_LEN: usize = ((Salt::RECOMMENDED_LENGTH * 4) / 3) + 1;
+pub(crate) const MAX_PASS_SALT_LEN: usize = ((Salt::RECOMMENDED_LENGTH * 4) / 3) + 1;
 
-/// Representation of a user password which can be safely loaded from and
-/// stored to a database.
-pub(super) struct DbPassword(Output, SaltString);
+/// Representation of a user or game password which can be safely loaded from
+/// and stored to a database.
+pub(crate) struct DbPassword(Output, SaltString);
 
 impl DbPassword {
     /// Create new DB password from a non-hashed original password and a random
     /// salt.
-    pub(super) fn generate(password: &str) -> Result<Self> {
+    pub(crate) fn generate(password: &str) -> Result<Self> {
         let salt = SaltString::generate(&mut OsRng);
         let hashed = Self::hash(password, &salt)?;
         Ok(Self::new(hashed, salt))
@@ -36,7 +36,7 @@ impl DbPassword {
     }
 
     /// Returns Base64 encoded, hashed & salted password.
-    pub(super) fn b64_encode_pwd_hash(&self) -> Result<String> {
+    pub(crate) fn b64_encode_pwd_hash(&self) -> Result<String> {
         let mut output = [0; MAX_PASS_HASH_LEN];
         Ok(self
             .0
@@ -46,13 +46,13 @@ impl DbPassword {
     }
 
     /// Returns password salt.
-    pub(super) fn salt_str(&self) -> &str {
+    pub(crate) fn salt_str(&self) -> &str {
         self.1.as_str()
     }
 
     /// Securely check that a given password corresponds to the password
     /// represented by `self`.
-    pub(super) fn check(&self, password: &str) -> bool {
+    pub(crate) fn check(&self, password: &str) -> bool {
         let Ok(hashed) = Self::hash(password, &self.1) else {
             return false;
         };
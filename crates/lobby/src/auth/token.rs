@@ -2,30 +2,75 @@ use anyhow::{Context, Result};
 use jsonwebtoken::{
     decode, encode, get_current_timestamp, DecodingKey, EncodingKey, Header, Validation,
 };
+use rand_core::{OsRng, RngCore};
 use serde::{Deserialize, Serialize};
 
-const TOKEN_LIFETIME: u64 = 86400;
+pub(super) const TOKEN_LIFETIME: u64 = 86400;
+// Token IDs are made of this many random bytes, hex encoded.
+const TOKEN_ID_BYTES: usize = 16;
+pub(super) const MAX_TOKEN_ID_LEN: usize = 2 * TOKEN_ID_BYTES;
 
 /// Client authentication token claims.
+///
+/// Tokens issued before token IDs and generations were introduced lack some
+/// of the claims. Such tokens are treated as tokens of generation 0 and they
+/// cannot be revoked individually.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     sub: String,
+    #[serde(default)]
+    iat: u64,
     exp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    jti: Option<String>,
+    #[serde(default)]
+    gen: u32,
 }
 
 impl Claims {
     /// Creates and returns new claims for a particular user. The expiration is
-    /// set to now + a fixed offset.
-    pub(super) fn standard<U: Into<String>>(username: U) -> Self {
+    /// set to now + a fixed offset. Each token gets a unique random ID so that
+    /// it can be revoked.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - the user the token is issued to.
+    ///
+    /// * `generation` - current token generation of the user. All tokens of
+    ///   older generations are revoked.
+    pub(super) fn standard<U: Into<String>>(username: U, generation: u32) -> Self {
+        let mut id = [0u8; TOKEN_ID_BYTES];
+        OsRng.fill_bytes(&mut id);
+
+        let now = get_current_timestamp();
         Self {
             sub: username.into(),
-            exp: get_current_timestamp() + TOKEN_LIFETIME,
+            iat: now,
+            exp: now + TOKEN_LIFETIME,
+            jti: Some(id.iter().map(|byte| format!("{byte:02x}")).collect()),
+            gen: generation,
         }
     }
 
     pub fn username(&self) -> &str {
         self.sub.as_str()
     }
+
+    /// Unix timestamp (in seconds) of the token expiration.
+    pub(super) fn expires(&self) -> u64 {
+        self.exp
+    }
+
+    /// Unique ID of the token or None if the token was issued without an
+    /// ID. Such tokens cannot be revoked.
+    pub(super) fn id(&self) -> Option<&str> {
+        self.jti.as_deref()
+    }
+
+    /// Token generation of the user at the time of the token issuance.
+    pub(super) fn generation(&self) -> u32 {
+        self.gen
+    }
 }
 
 #[derive(Clone)]
@@ -66,10 +111,42 @@ mod tests {
     fn test_tokens() {
         let secret_base64 = "eHg=";
         let tokens = Tokens::new(secret_base64).unwrap();
-        let token_a = tokens.encode(&Claims::standard("Indy")).unwrap();
-        let token_b = tokens.encode(&Claims::standard("Indy2")).unwrap();
+        let token_a = tokens.encode(&Claims::standard("Indy", 0)).unwrap();
+        let token_b = tokens.encode(&Claims::standard("Indy2", 0)).unwrap();
         assert_ne!(token_a, token_b);
+
+        let claims_a = Claims::standard("Indy", 0);
+        let claims_b = Claims::standard("Indy", 1);
+        assert_eq!(claims_a.id().unwrap().len(), MAX_TOKEN_ID_LEN);
+        assert_ne!(claims_a.id(), claims_b.id());
+        assert_eq!(claims_a.expires(), claims_a.iat + TOKEN_LIFETIME);
+        assert_eq!(claims_b.generation(), 1);
         assert_eq!(tokens.decode(&token_a).unwrap().username(), "Indy");
         assert_eq!(tokens.decode(&token_b).unwrap().username(), "Indy2");
     }
+
+    #[test]
+    fn test_legacy_claims() {
+        #[derive(Serialize)]
+        struct LegacyClaims {
+            sub: String,
+            exp: u64,
+        }
+
+        let tokens = Tokens::new("eHg=").unwrap();
+        let token = encode(
+            &Header::default(),
+            &LegacyClaims {
+                sub: "Indy".to_owned(),
+                exp: get_current_timestamp() + TOKEN_LIFETIME,
+            },
+            &tokens.encoding_key,
+        )
+        .unwrap();
+
+        let claims = tokens.decode(&token).unwrap();
+        assert_eq!(claims.username(), "Indy");
+        assert_eq!(claims.id(), None);
+        assert_eq!(claims.generation(), 0);
+    }
 }
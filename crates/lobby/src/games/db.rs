@@ -1,23 +1,30 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use de_lobby_model::{
-    Game, GameConfig, GameListing, GameMap, GamePartial, GamePlayer, GamePlayerInfo, GameSetup,
-    MAP_HASH_LEN, MAX_GAME_NAME_LEN, MAX_MAP_NAME_LEN, MAX_USERNAME_LEN,
+    Game, GameConfig, GameConnection, GameListing, GameMap, GameOutcome, GamePartial, GamePlayer,
+    GamePlayerInfo, GameSecret, GameSetup, GameStatus, MAP_HASH_LEN, MAX_GAME_NAME_LEN,
+    MAX_MAP_NAME_LEN, MAX_USERNAME_LEN,
 };
 use futures_util::TryStreamExt;
 use log::info;
-use sqlx::{query, sqlite::SqliteRow, Pool, Row, Sqlite, SqliteExecutor};
+use sqlx::{query, sqlite::SqliteRow, Pool, Row, Sqlite, SqliteExecutor, Transaction};
 use thiserror::Error;
 
 use crate::{
+    auth::passwd::{DbPassword, MAX_PASS_HASH_LEN, MAX_PASS_SALT_LEN},
     db::{FromRow, SQLITE_CONSTRAINT_FOREIGNKEY, SQLITE_CONSTRAINT_PRIMARYKEY},
     db_error_code, db_error_message,
+    results::{self, outcome_from_str, outcome_to_str, OUTCOME_LEN},
 };
 
 // This should correspond to the longest valid socket address. IPv6 hast up to
 // 39 characters + colon + 5 characters for port number.
 const SERVER_LEN: usize = 45;
+// Game secrets are hex encoded 32 bytes.
+const SECRET_LEN: usize = 64;
+// Length of the longest game status name.
+const STATUS_LEN: usize = 8;
 
 #[derive(Clone)]
 pub(super) struct Games {
@@ -37,9 +44,15 @@ impl Games {
             map_name_len = MAX_MAP_NAME_LEN,
             map_hash_len = MAP_HASH_LEN,
             server_len = SERVER_LEN,
+            secret_len = SECRET_LEN,
+            pass_hash_len = MAX_PASS_HASH_LEN,
+            pass_salt_len = MAX_PASS_SALT_LEN,
+            status_len = STATUS_LEN,
+            outcome_len = OUTCOME_LEN,
         );
 
         info!("Initializing games...");
+        migrate(pool).await?;
         query(&init_query)
             .execute(pool)
             .await
@@ -47,14 +60,19 @@ impl Games {
         Ok(Self { pool })
     }
 
-    /// This method creates a new game in the DB and places all users to it.
-    pub(super) async fn list(&self) -> Result<GameListing> {
+    /// This method lists open games and optionally also already started
+    /// games. Finished games are never listed.
+    pub(super) async fn list(&self, started: bool) -> Result<GameListing> {
         let mut rows = query(
             "SELECT games.*, count(players.ordinal) as num_players \
              FROM games \
              LEFT JOIN players ON (games.name = players.game) \
+             WHERE games.status = ? OR (? AND games.status = ?) \
              GROUP BY games.name;",
         )
+        .bind(status_to_str(GameStatus::Open))
+        .bind(started)
+        .bind(status_to_str(GameStatus::Started))
         .fetch(self.pool);
         let mut games = GameListing::empty();
         while let Some(row) = rows
@@ -79,7 +97,10 @@ impl Games {
             return Ok(None);
         };
 
-        let setup = GameSetup::try_from_row(game_row)?;
+        let status: String = game_row.try_get("status")?;
+        let status = status_from_str(&status)?;
+        let pass_hash: Option<String> = game_row.try_get("pass_hash")?;
+        let config = GameConfig::try_from_row(game_row)?;
 
         let mut players = Vec::new();
         let mut player_rows = query("SELECT ordinal, username FROM players WHERE game = ?;")
@@ -94,23 +115,100 @@ impl Games {
             players.push(GamePlayer::try_from_row(player_row)?);
         }
 
-        Ok(Some(Game::new(setup, players)))
+        Ok(Some(
+            Game::new(config, players)
+                .with_status(status)
+                .with_private(pass_hash.is_some()),
+        ))
     }
 
-    /// This method creates a new game in the DB and places all users to it.
-    pub(super) async fn create(&self, game: Game) -> Result<(), CreationError> {
-        let game_setup = game.setup();
+    /// This method retrieves connection details of a game. The password of
+    /// private games must match unless the user is already a player of the
+    /// game.
+    pub(super) async fn connection(
+        &self,
+        username: &str,
+        game: &str,
+        password: Option<&str>,
+    ) -> Result<GameConnection, ConnectionError> {
+        let Some(row) =
+            query("SELECT server, secret, pass_hash, pass_salt FROM games WHERE name = ?;")
+                .bind(game)
+                .fetch_optional(self.pool)
+                .await
+                .map_err(ConnectionError::Database)?
+        else {
+            return Err(ConnectionError::GameNotFound);
+        };
+
+        if !check_password(&row, password)? {
+            let member = query("SELECT username FROM players WHERE username = ? AND game = ?;")
+                .bind(username)
+                .bind(game)
+                .fetch_optional(self.pool)
+                .await
+                .map_err(ConnectionError::Database)?
+                .is_some();
+            if !member {
+                return Err(ConnectionError::WrongPassword);
+            }
+        }
+
+        let server: String = row.try_get("server").map_err(ConnectionError::Database)?;
+        let server: SocketAddr = server
+            .parse()
+            .context("Invalid game server address in the DB")?;
+        let secret: Option<String> = row.try_get("secret").map_err(ConnectionError::Database)?;
+        let secret: Option<GameSecret> = secret
+            .map(|secret| secret.parse())
+            .transpose()
+            .map_err(|error: String| anyhow!(error))?;
+        Ok(GameConnection::new(server, secret))
+    }
+
+    /// This method creates a new game in the DB and places its author to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_setup` - setup of the game.
+    ///
+    /// * `secret` - secret of a secured game.
+    ///
+    /// * `author` - the player creating the game.
+    pub(super) async fn create(
+        &self,
+        game_setup: &GameSetup,
+        secret: Option<GameSecret>,
+        author: &GamePlayer,
+    ) -> Result<(), CreationError> {
         let game_config = game_setup.config();
 
+        let password = game_setup
+            .password()
+            .map(DbPassword::generate)
+            .transpose()
+            .map_err(CreationError::Other)?;
+        let (pass_hash, pass_salt) = match password {
+            Some(ref password) => (
+                Some(password.b64_encode_pwd_hash()?),
+                Some(password.salt_str()),
+            ),
+            None => (None, None),
+        };
+
         let mut transaction = self.pool.begin().await.map_err(CreationError::Database)?;
 
         let result =
-            query("INSERT INTO games (name, max_players, map_hash, map_name, server) VALUES(?, ?, ?, ?, ?);")
+            query("INSERT INTO games (name, max_players, map_hash, map_name, server, secret, pass_hash, pass_salt, protocol_version, updated) VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, CAST(strftime('%s', 'now') AS INTEGER));")
                 .bind(game_config.name())
                 .bind(game_config.max_players())
                 .bind(game_config.map().hash())
                 .bind(game_config.map().name())
                 .bind(game_setup.server().to_string())
+                .bind(secret.map(|secret| secret.to_string()))
+                .bind(pass_hash)
+                .bind(pass_salt)
+                .bind(game_config.protocol_version())
                 .execute(&mut transaction)
                 .await;
         db_error_code!(
@@ -120,13 +218,9 @@ impl Games {
         );
         result.map_err(CreationError::Database)?;
 
-        let mut author = true;
-        for username in game.players() {
-            Self::add_player_inner(&mut transaction, author, username, game_config.name())
-                .await
-                .map_err(CreationError::AdditionError)?;
-            author = false;
-        }
+        Self::add_player_inner(&mut transaction, true, author, game_config.name())
+            .await
+            .map_err(CreationError::AdditionError)?;
 
         transaction
             .commit()
@@ -136,11 +230,26 @@ impl Games {
         Ok(())
     }
 
+    /// Adds a player to a game. The password of private games must match.
     pub(super) async fn add_player(
         &self,
         player: &GamePlayer,
         game: &str,
+        password: Option<&str>,
     ) -> Result<(), AdditionError> {
+        let row = query("SELECT pass_hash, pass_salt FROM games WHERE name = ?;")
+            .bind(game)
+            .fetch_optional(self.pool)
+            .await
+            .map_err(AdditionError::Database)?;
+
+        // Non-existent games are reported during the insertion.
+        if let Some(row) = row {
+            if !check_password(&row, password)? {
+                return Err(AdditionError::WrongPassword);
+            }
+        }
+
         Self::add_player_inner(self.pool, false, player, game).await
     }
 
@@ -179,14 +288,18 @@ impl Games {
             "UNIQUE constraint failed: players.game, players.ordinal"
         );
         db_error_message!(result, AdditionError::OrdinalTooLarge, "TOO-LARGE-ORDINAL");
+        db_error_message!(result, AdditionError::NotOpen, "GAME-NOT-OPEN");
 
         result.map_err(AdditionError::Database)?;
 
         Ok(())
     }
 
-    /// Removes a player from a game. Deletes the game if the player was the
-    /// game author.
+    /// Removes a player from a game.
+    ///
+    /// The game is abandoned, i.e. deleted, when its author leaves it before
+    /// it starts. Leaving an already started game is reported as a loss of
+    /// the leaving player and the game continues without the player.
     pub(super) async fn remove_player(
         &self,
         username: &str,
@@ -194,21 +307,29 @@ impl Games {
     ) -> Result<(), RemovalError> {
         let mut transaction = self.pool.begin().await.map_err(RemovalError::Database)?;
 
-        let mut rows = query("SELECT author FROM players WHERE username = ? AND game = ?;")
-            .bind(username)
-            .bind(game)
-            .fetch(self.pool);
+        let Some(row) = query(
+            "SELECT players.author, games.status \
+             FROM players JOIN games ON (games.name = players.game) \
+             WHERE players.username = ? AND players.game = ?;",
+        )
+        .bind(username)
+        .bind(game)
+        .fetch_optional(&mut transaction)
+        .await
+        .map_err(RemovalError::Database)?
+        else {
+            return Err(RemovalError::NotInTheGame);
+        };
+        let author: bool = row.try_get("author").map_err(RemovalError::Database)?;
+        let status: String = row.try_get("status").map_err(RemovalError::Database)?;
+        let started = status_from_str(&status)? != GameStatus::Open;
 
-        let action = match rows.try_next().await.map_err(RemovalError::Database)? {
-            Some(row) => {
-                let author: bool = row.try_get("author").map_err(RemovalError::Database)?;
-                if author {
-                    RemovalAction::Abandoned
-                } else {
-                    RemovalAction::Removed
-                }
-            }
-            None => return Err(RemovalError::NotInTheGame),
+        let action = if started {
+            RemovalAction::Forfeited
+        } else if author {
+            RemovalAction::Abandoned
+        } else {
+            RemovalAction::Removed
         };
 
         match action {
@@ -219,6 +340,18 @@ impl Games {
                     .await
                     .map_err(RemovalError::Database)?;
             }
+            RemovalAction::Forfeited => {
+                // A player who already reported the outcome keeps the report.
+                query("INSERT OR IGNORE INTO reports (username, game, outcome) VALUES (?, ?, ?);")
+                    .bind(username)
+                    .bind(game)
+                    .bind(outcome_to_str(GameOutcome::Lost))
+                    .execute(&mut transaction)
+                    .await
+                    .map_err(RemovalError::Database)?;
+                Self::remove_player_inner(&mut transaction, username, game).await?;
+                finalize(&mut transaction, game, false).await?;
+            }
             RemovalAction::Removed => {
                 Self::remove_player_inner(&mut transaction, username, game).await?;
             }
@@ -228,6 +361,294 @@ impl Games {
         Ok(())
     }
 
+    /// Moves a game to another lifecycle stage. Only the game author might
+    /// start the game, any of its players might finish it.
+    pub(super) async fn set_status(
+        &self,
+        username: &str,
+        game: &str,
+        status: GameStatus,
+    ) -> Result<(), StatusError> {
+        let mut transaction = self.pool.begin().await.map_err(StatusError::Database)?;
+
+        let Some(row) = query("SELECT status FROM games WHERE name = ?;")
+            .bind(game)
+            .fetch_optional(&mut transaction)
+            .await
+            .map_err(StatusError::Database)?
+        else {
+            return Err(StatusError::GameNotFound);
+        };
+        let current: String = row.try_get("status").map_err(StatusError::Database)?;
+        let current = status_from_str(&current)?;
+
+        let Some(row) = query("SELECT author FROM players WHERE username = ? AND game = ?;")
+            .bind(username)
+            .bind(game)
+            .fetch_optional(&mut transaction)
+            .await
+            .map_err(StatusError::Database)?
+        else {
+            return Err(StatusError::NotInTheGame);
+        };
+        let author: bool = row.try_get("author").map_err(StatusError::Database)?;
+
+        if status == GameStatus::Started && !author {
+            return Err(StatusError::NotAuthor);
+        }
+        if !current.can_become(status) {
+            return Err(StatusError::InvalidTransition {
+                from: current,
+                to: status,
+            });
+        }
+
+        if current != status {
+            query(
+                "UPDATE games \
+                 SET status = ?, updated = CAST(strftime('%s', 'now') AS INTEGER) \
+                 WHERE name = ?;",
+            )
+            .bind(status_to_str(status))
+            .bind(game)
+            .execute(&mut transaction)
+            .await
+            .map_err(StatusError::Database)?;
+        }
+
+        transaction.commit().await.map_err(StatusError::Database)?;
+        Ok(())
+    }
+
+    /// Changes maximum number of players of an open game. Only the game
+    /// author might do this.
+    ///
+    /// The new value must not exceed capacity of the game map (if the map is
+    /// known to the lobby) and must leave room for all joined players.
+    pub(super) async fn set_max_players(
+        &self,
+        username: &str,
+        game: &str,
+        max_players: u8,
+    ) -> Result<(), MaxPlayersError> {
+        let mut transaction = self.pool.begin().await.map_err(MaxPlayersError::Database)?;
+
+        let Some(row) = query(
+            "SELECT games.status, maps.max_players AS map_max_players \
+             FROM games LEFT JOIN maps ON maps.hash = games.map_hash \
+             WHERE games.name = ?;",
+        )
+        .bind(game)
+        .fetch_optional(&mut transaction)
+        .await
+        .map_err(MaxPlayersError::Database)?
+        else {
+            return Err(MaxPlayersError::GameNotFound);
+        };
+        let status: String = row.try_get("status").map_err(MaxPlayersError::Database)?;
+        let map_max_players: Option<u8> = row
+            .try_get("map_max_players")
+            .map_err(MaxPlayersError::Database)?;
+
+        let Some(row) = query("SELECT author FROM players WHERE username = ? AND game = ?;")
+            .bind(username)
+            .bind(game)
+            .fetch_optional(&mut transaction)
+            .await
+            .map_err(MaxPlayersError::Database)?
+        else {
+            return Err(MaxPlayersError::NotInTheGame);
+        };
+        let author: bool = row.try_get("author").map_err(MaxPlayersError::Database)?;
+
+        if !author {
+            return Err(MaxPlayersError::NotAuthor);
+        }
+        if status_from_str(&status)? != GameStatus::Open {
+            return Err(MaxPlayersError::NotOpen);
+        }
+        if let Some(map_max_players) = map_max_players {
+            if max_players > map_max_players {
+                return Err(MaxPlayersError::MapCapacity(map_max_players));
+            }
+        }
+
+        let row = query("SELECT MAX(ordinal) AS ordinal FROM players WHERE game = ?;")
+            .bind(game)
+            .fetch_one(&mut transaction)
+            .await
+            .map_err(MaxPlayersError::Database)?;
+        let ordinal: Option<u8> = row.try_get("ordinal").map_err(MaxPlayersError::Database)?;
+        if ordinal.is_some_and(|ordinal| ordinal > max_players) {
+            return Err(MaxPlayersError::SlotOccupied);
+        }
+
+        query(
+            "UPDATE games \
+             SET max_players = ?, updated = CAST(strftime('%s', 'now') AS INTEGER) \
+             WHERE name = ?;",
+        )
+        .bind(max_players)
+        .bind(game)
+        .execute(&mut transaction)
+        .await
+        .map_err(MaxPlayersError::Database)?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(MaxPlayersError::Database)?;
+        Ok(())
+    }
+
+    /// Stores the outcome of a game reported by one of its players and marks
+    /// the game as finished.
+    ///
+    /// Once all players remaining in the game report its outcome, the match
+    /// is recorded (see [`finalize`]) and the game is deleted.
+    pub(super) async fn report(
+        &self,
+        username: &str,
+        game: &str,
+        outcome: GameOutcome,
+    ) -> Result<(), ReportError> {
+        let mut transaction = self.pool.begin().await.map_err(ReportError::Database)?;
+
+        let Some(row) = query("SELECT status FROM games WHERE name = ?;")
+            .bind(game)
+            .fetch_optional(&mut transaction)
+            .await
+            .map_err(ReportError::Database)?
+        else {
+            return Err(ReportError::GameNotFound);
+        };
+        let status: String = row.try_get("status").map_err(ReportError::Database)?;
+
+        if query("SELECT username FROM players WHERE username = ? AND game = ?;")
+            .bind(username)
+            .bind(game)
+            .fetch_optional(&mut transaction)
+            .await
+            .map_err(ReportError::Database)?
+            .is_none()
+        {
+            // Players who left a started game were already reported as
+            // losers.
+            let reported = query("SELECT outcome FROM reports WHERE username = ? AND game = ?;")
+                .bind(username)
+                .bind(game)
+                .fetch_optional(&mut transaction)
+                .await
+                .map_err(ReportError::Database)?
+                .is_some();
+            return Err(if reported {
+                ReportError::AlreadyReported
+            } else {
+                ReportError::NotInTheGame
+            });
+        }
+        if status_from_str(&status)? == GameStatus::Open {
+            return Err(ReportError::NotStarted);
+        }
+
+        let result = query("INSERT INTO reports (username, game, outcome) VALUES (?, ?, ?);")
+            .bind(username)
+            .bind(game)
+            .bind(outcome_to_str(outcome))
+            .execute(&mut transaction)
+            .await;
+        db_error_code!(
+            result,
+            ReportError::AlreadyReported,
+            SQLITE_CONSTRAINT_PRIMARYKEY
+        );
+        result.map_err(ReportError::Database)?;
+
+        query(
+            "UPDATE games \
+             SET status = ?, updated = CAST(strftime('%s', 'now') AS INTEGER) \
+             WHERE name = ?;",
+        )
+        .bind(status_to_str(GameStatus::Finished))
+        .bind(game)
+        .execute(&mut transaction)
+        .await
+        .map_err(ReportError::Database)?;
+
+        finalize(&mut transaction, game, false).await?;
+
+        transaction.commit().await.map_err(ReportError::Database)?;
+        Ok(())
+    }
+
+    /// Records matches of finished games whose players did not report the
+    /// outcome within `deadline` since the last change and of started games
+    /// not changed for at least `started_idle` time. Players who have not
+    /// reported the outcome are counted as losers if exactly one player
+    /// reported a win, otherwise they are left out of the matches.
+    ///
+    /// Returns the number of recorded matches.
+    pub(super) async fn finalize_overdue(
+        &self,
+        deadline: Duration,
+        started_idle: Duration,
+    ) -> Result<u64> {
+        let deadline = i64::try_from(deadline.as_secs()).context("Deadline is too large")?;
+        let started_idle =
+            i64::try_from(started_idle.as_secs()).context("Started idle time is too large")?;
+
+        let games: Vec<String> = query(
+            "SELECT name FROM games \
+             WHERE (status = ? AND updated < CAST(strftime('%s', 'now') AS INTEGER) - ?) \
+             OR (status = ? AND updated < CAST(strftime('%s', 'now') AS INTEGER) - ?);",
+        )
+        .bind(status_to_str(GameStatus::Finished))
+        .bind(deadline)
+        .bind(status_to_str(GameStatus::Started))
+        .bind(started_idle)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to retrieve overdue games from the DB")?
+        .iter()
+        .map(|row| row.try_get("name"))
+        .collect::<Result<_, _>>()
+        .context("Failed to retrieve overdue games from the DB")?;
+
+        let mut recorded = 0;
+        for game in games {
+            let mut transaction = self.pool.begin().await?;
+            if finalize(&mut transaction, &game, true).await? {
+                recorded += 1;
+            }
+            transaction.commit().await?;
+        }
+        Ok(recorded)
+    }
+
+    /// Deletes all open and finished games which were not updated for at
+    /// least `idle` time and all started games which were not updated for at
+    /// least `started_idle` time.
+    ///
+    /// Returns the number of deleted games.
+    pub(super) async fn remove_stale(&self, idle: Duration, started_idle: Duration) -> Result<u64> {
+        let idle = i64::try_from(idle.as_secs()).context("Idle time is too large")?;
+        let started_idle =
+            i64::try_from(started_idle.as_secs()).context("Started idle time is too large")?;
+        let result = query(
+            "DELETE FROM games \
+             WHERE (status != ? AND updated < CAST(strftime('%s', 'now') AS INTEGER) - ?) \
+             OR (status = ? AND updated < CAST(strftime('%s', 'now') AS INTEGER) - ?);",
+        )
+        .bind(status_to_str(GameStatus::Started))
+        .bind(idle)
+        .bind(status_to_str(GameStatus::Started))
+        .bind(started_idle)
+        .execute(self.pool)
+        .await
+        .context("Failed to delete stale games from the DB")?;
+        Ok(result.rows_affected())
+    }
+
     async fn remove_player_inner<'c, E>(
         executor: E,
         username: &str,
@@ -253,10 +674,197 @@ impl Games {
     }
 }
 
+/// Records the match of a game (see [`results::record`]) and deletes the game
+/// once all players remaining in the game reported its outcome. Players who
+/// left the started game are counted as losers.
+///
+/// Returns true if the match was recorded.
+///
+/// # Arguments
+///
+/// * `transaction` - the match is recorded as part of this transaction.
+///
+/// * `game` - name of the game.
+///
+/// * `force` - if true, the match is recorded even if some of the players
+///   have not reported the outcome yet. If exactly one player reported a win,
+///   these players are counted as losers, otherwise they are left out of the
+///   match. Nothing is recorded if nobody reported the outcome.
+async fn finalize(
+    transaction: &mut Transaction<'_, Sqlite>,
+    game: &str,
+    force: bool,
+) -> Result<bool> {
+    let Some(row) = query("SELECT map_name FROM games WHERE name = ?;")
+        .bind(game)
+        .fetch_optional(&mut *transaction)
+        .await
+        .context("Failed to retrieve a game from the DB")?
+    else {
+        return Ok(false);
+    };
+    let map_name: String = row.try_get("map_name")?;
+
+    if !force {
+        let row = query(
+            "SELECT COUNT(*) AS missing FROM players \
+             LEFT JOIN reports \
+             ON (reports.username = players.username AND reports.game = players.game) \
+             WHERE players.game = ? AND reports.outcome IS NULL;",
+        )
+        .bind(game)
+        .fetch_one(&mut *transaction)
+        .await
+        .context("Failed to retrieve missing reports from the DB")?;
+        let missing: i64 = row.try_get("missing")?;
+        if missing > 0 {
+            return Ok(false);
+        }
+    }
+
+    if force {
+        let row = query("SELECT COUNT(*) AS winners FROM reports WHERE game = ? AND outcome = ?;")
+            .bind(game)
+            .bind(outcome_to_str(GameOutcome::Won))
+            .fetch_one(&mut *transaction)
+            .await
+            .context("Failed to retrieve winners from the DB")?;
+        let winners: i64 = row.try_get("winners")?;
+
+        // The winner would otherwise lose the rating gain whenever the losers
+        // simply do not report the outcome.
+        if winners == 1 {
+            query(
+                "INSERT OR IGNORE INTO reports (username, game, outcome) \
+                 SELECT username, game, ? FROM players WHERE game = ?;",
+            )
+            .bind(outcome_to_str(GameOutcome::Lost))
+            .bind(game)
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to store missing reports to the DB")?;
+        }
+    }
+
+    let mut outcomes = Vec::new();
+    {
+        let mut rows = query("SELECT username, outcome FROM reports WHERE game = ?;")
+            .bind(game)
+            .fetch(&mut *transaction);
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .context("Failed to retrieve a report from the DB")?
+        {
+            let username: String = row.try_get("username")?;
+            let outcome: String = row.try_get("outcome")?;
+            outcomes.push((username, outcome_from_str(&outcome)?));
+        }
+    }
+    if outcomes.is_empty() {
+        return Ok(false);
+    }
+
+    results::record(transaction, game, &map_name, outcomes.as_slice()).await?;
+    query("DELETE FROM games WHERE name = ?;")
+        .bind(game)
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to delete a game from the DB")?;
+    Ok(true)
+}
+
+/// Adds columns missing in a `games` table created by an older version of the
+/// lobby. Nothing is done if the table does not exist yet.
+async fn migrate(pool: &'static Pool<Sqlite>) -> Result<()> {
+    let columns: Vec<String> = query("SELECT name FROM pragma_table_info('games');")
+        .fetch_all(pool)
+        .await
+        .context("Failed to retrieve columns of the games table")?
+        .iter()
+        .map(|row| row.try_get("name"))
+        .collect::<Result<_, _>>()
+        .context("Failed to retrieve columns of the games table")?;
+    if columns.is_empty() {
+        return Ok(());
+    }
+
+    let optional_columns = [
+        ("secret", format!("CHARACTER({SECRET_LEN})")),
+        ("pass_hash", format!("CHARACTER({MAX_PASS_HASH_LEN})")),
+        ("pass_salt", format!("CHARACTER({MAX_PASS_SALT_LEN})")),
+        ("protocol_version", "INTEGER NOT NULL DEFAULT 0".to_owned()),
+        (
+            "status",
+            format!("CHARACTER({STATUS_LEN}) NOT NULL DEFAULT 'open'"),
+        ),
+    ];
+    for (name, definition) in optional_columns {
+        if columns.iter().any(|column| column == name) {
+            continue;
+        }
+
+        info!("Adding {name} column to the games table...");
+        query(&format!("ALTER TABLE games ADD COLUMN {name} {definition};"))
+            .execute(pool)
+            .await
+            .with_context(|| format!("Failed to add {name} column to the games table"))?;
+    }
+
+    if !columns.iter().any(|column| column == "updated") {
+        info!("Adding updated column to the games table...");
+        // SQLite does not allow non-constant defaults of added columns. The
+        // column is set explicitly on insertion instead.
+        query(
+            "ALTER TABLE games ADD COLUMN updated INTEGER NOT NULL DEFAULT 0; \
+             UPDATE games SET updated = CAST(strftime('%s', 'now') AS INTEGER);",
+        )
+        .execute(pool)
+        .await
+        .context("Failed to add updated column to the games table")?;
+    }
+
+    Ok(())
+}
+
+/// Returns true if the game from the row is public or if the password matches
+/// the password of the private game.
+fn check_password(row: &SqliteRow, password: Option<&str>) -> Result<bool> {
+    let pass_hash: Option<String> = row.try_get("pass_hash")?;
+    let pass_salt: Option<String> = row.try_get("pass_salt")?;
+
+    match (pass_hash, pass_salt) {
+        (Some(pass_hash), Some(pass_salt)) => {
+            let db_password = DbPassword::try_from((pass_hash.as_str(), pass_salt.as_str()))?;
+            Ok(password.is_some_and(|password| db_password.check(password)))
+        }
+        _ => Ok(true),
+    }
+}
+
+fn status_to_str(status: GameStatus) -> &'static str {
+    match status {
+        GameStatus::Open => "open",
+        GameStatus::Started => "started",
+        GameStatus::Finished => "finished",
+    }
+}
+
+fn status_from_str(status: &str) -> Result<GameStatus> {
+    match status {
+        "open" => Ok(GameStatus::Open),
+        "started" => Ok(GameStatus::Started),
+        "finished" => Ok(GameStatus::Finished),
+        _ => Err(anyhow!("Unknown game status: {status}")),
+    }
+}
+
 /// Action taken during removal of a player from a game.
 enum RemovalAction {
     /// The game was abandoned and all players removed from the game.
     Abandoned,
+    /// The player left an already started game and lost it.
+    Forfeited,
     /// The player left the game without any further action taken.
     Removed,
 }
@@ -283,6 +891,22 @@ pub(super) enum AdditionError {
     OrdinalTooLarge,
     #[error("The user or the game does not exist")]
     UserOrGameDoesNotExist,
+    #[error("The game is no longer open for joining")]
+    NotOpen,
+    #[error("Wrong or missing game password")]
+    WrongPassword,
+    #[error("A database error encountered")]
+    Database(#[source] sqlx::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+#[derive(Error, Debug)]
+pub(super) enum ConnectionError {
+    #[error("The game does not exist")]
+    GameNotFound,
+    #[error("Wrong or missing game password")]
+    WrongPassword,
     #[error("A database error encountered")]
     Database(#[source] sqlx::Error),
     #[error(transparent)]
@@ -295,6 +919,60 @@ pub(super) enum RemovalError {
     NotInTheGame,
     #[error("A database error encountered")]
     Database(#[source] sqlx::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+#[derive(Error, Debug)]
+pub(super) enum StatusError {
+    #[error("The game does not exist")]
+    GameNotFound,
+    #[error("User is not in the game")]
+    NotInTheGame,
+    #[error("Only the game author might do this")]
+    NotAuthor,
+    #[error("The game cannot move from {from:?} to {to:?}")]
+    InvalidTransition { from: GameStatus, to: GameStatus },
+    #[error("A database error encountered")]
+    Database(#[source] sqlx::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+#[derive(Error, Debug)]
+pub(super) enum MaxPlayersError {
+    #[error("The game does not exist")]
+    GameNotFound,
+    #[error("User is not in the game")]
+    NotInTheGame,
+    #[error("Only the game author might do this")]
+    NotAuthor,
+    #[error("The game is not open")]
+    NotOpen,
+    #[error("The map supports at most {0} players")]
+    MapCapacity(u8),
+    #[error("A player occupies a slot above the new maximum")]
+    SlotOccupied,
+    #[error("A database error encountered")]
+    Database(#[source] sqlx::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+#[derive(Error, Debug)]
+pub(super) enum ReportError {
+    #[error("The game does not exist")]
+    GameNotFound,
+    #[error("User is not in the game")]
+    NotInTheGame,
+    #[error("The game has not started yet")]
+    NotStarted,
+    #[error("User has already reported the game outcome")]
+    AlreadyReported,
+    #[error("A database error encountered")]
+    Database(#[source] sqlx::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
 }
 
 impl FromRow for GamePlayer {
@@ -307,24 +985,16 @@ impl FromRow for GamePlayer {
     }
 }
 
-impl FromRow for GameSetup {
-    type Error = anyhow::Error;
-
-    fn try_from_row(row: SqliteRow) -> Result<Self, Self::Error> {
-        let server: String = row.try_get("server")?;
-        let server: SocketAddr = server.parse()?;
-        let config = GameConfig::try_from_row(row)?;
-        Ok(Self::new(server, config))
-    }
-}
-
 impl FromRow for GamePartial {
     type Error = anyhow::Error;
 
     fn try_from_row(row: SqliteRow) -> Result<Self, Self::Error> {
         let num_players: u8 = row.try_get("num_players")?;
+        let status: String = row.try_get("status")?;
+        let status = status_from_str(&status)?;
+        let pass_hash: Option<String> = row.try_get("pass_hash")?;
         let config = GameConfig::try_from_row(row)?;
-        Ok(Self::new(config, num_players))
+        Ok(Self::new(config, num_players, status, pass_hash.is_some()))
     }
 }
 
@@ -334,8 +1004,9 @@ impl FromRow for GameConfig {
     fn try_from_row(row: SqliteRow) -> Result<Self, Self::Error> {
         let name: String = row.try_get("name")?;
         let max_players: u8 = row.try_get("max_players")?;
+        let protocol_version: u32 = row.try_get("protocol_version")?;
         let map = GameMap::try_from_row(row)?;
-        Ok(Self::new(name, max_players, map))
+        Ok(Self::new(name, max_players, map, protocol_version))
     }
 }
 
@@ -348,3 +1019,119 @@ impl FromRow for GameMap {
         Ok(Self::new(hash, name))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::results::ResultsService;
+
+    async fn memory_pool() -> &'static Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        Box::leak(Box::new(pool))
+    }
+
+    fn game_setup(name: &str) -> GameSetup {
+        let map = GameMap::new("a".repeat(MAP_HASH_LEN), "Map".to_owned());
+        let config = GameConfig::new(name.to_owned(), 4, map, 1);
+        GameSetup::new("127.0.0.1:8082".parse().unwrap(), config)
+    }
+
+    #[actix_web::test]
+    async fn test_finalize_forced() {
+        let pool = memory_pool().await;
+        query(
+            "CREATE TABLE users (username CHARACTER(32) NOT NULL PRIMARY KEY); \
+             INSERT INTO users (username) VALUES ('Indy'), ('Bob');",
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        let games = Games::init(pool).await.unwrap();
+        ResultsService::setup(pool).await.unwrap();
+
+        let author = GamePlayer::new("Indy".to_owned(), GamePlayerInfo::new(1));
+        let other = GamePlayer::new("Bob".to_owned(), GamePlayerInfo::new(2));
+        games
+            .create(&game_setup("Game"), None, &author)
+            .await
+            .unwrap();
+        games.add_player(&other, "Game", None).await.unwrap();
+        games
+            .set_status("Indy", "Game", GameStatus::Started)
+            .await
+            .unwrap();
+        games
+            .report("Indy", "Game", GameOutcome::Won)
+            .await
+            .unwrap();
+
+        let mut transaction = pool.begin().await.unwrap();
+        assert!(finalize(&mut transaction, "Game", true).await.unwrap());
+        transaction.commit().await.unwrap();
+        assert!(games.get("Game").await.unwrap().is_none());
+
+        let disputed: bool = query("SELECT disputed FROM matches;")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            .get("disputed");
+        assert!(!disputed);
+
+        let outcome: String = query("SELECT outcome FROM match_players WHERE username = 'Bob';")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            .get("outcome");
+        assert_eq!(outcome_from_str(&outcome).unwrap(), GameOutcome::Lost);
+
+        let rating = |username: &'static str| async move {
+            let rating: f64 = query("SELECT rating FROM ratings WHERE username = ?;")
+                .bind(username)
+                .fetch_one(pool)
+                .await
+                .unwrap()
+                .get("rating");
+            rating
+        };
+        assert!(rating("Indy").await > rating("Bob").await);
+    }
+
+    #[actix_web::test]
+    async fn test_migrate() {
+        let pool = memory_pool().await;
+        // Tables as created by the first version of the lobby.
+        query(
+            "CREATE TABLE users (username CHARACTER(32) NOT NULL PRIMARY KEY); \
+             INSERT INTO users (username) VALUES ('Indy'); \
+             CREATE TABLE games ( \
+                 name CHARACTER(32) NOT NULL PRIMARY KEY, \
+                 max_players TINYINT NOT NULL, \
+                 map_hash CHARACTER(64) NOT NULL, \
+                 map_name CHARACTER(32) NOT NULL, \
+                 server CHARACTER(45) NOT NULL \
+             );",
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let games = Games::init(pool).await.unwrap();
+        let author = GamePlayer::new("Indy".to_owned(), GamePlayerInfo::new(1));
+        games
+            .create(&game_setup("Game"), None, &author)
+            .await
+            .unwrap();
+
+        let game = games.get("Game").await.unwrap().unwrap();
+        assert_eq!(game.status(), GameStatus::Open);
+        assert!(!game.private());
+        assert_eq!(game.config().protocol_version(), 1);
+        assert_eq!(game.players().len(), 1);
+    }
+}
@@ -1,8 +1,18 @@
 use actix_web::{get, post, put, web, HttpResponse, Responder};
-use de_lobby_model::{Game, GamePlayer, GamePlayerInfo, GameSetup, Validatable};
+use de_lobby_model::{
+    GameAccess, GameJoinInfo, GameMaxPlayers, GamePlayer, GamePlayerInfo, GameReport, GameSetup,
+    GameStatus, Validatable,
+};
 use log::{error, warn};
+use serde::Deserialize;
 
-use super::db::{AdditionError, CreationError, Games, RemovalError};
+use super::{
+    db::{
+        AdditionError, ConnectionError, CreationError, Games, MaxPlayersError, RemovalError,
+        ReportError, StatusError,
+    },
+    GameKey,
+};
 use crate::auth::Claims;
 
 /// Registers all authentication endpoints.
@@ -11,9 +21,14 @@ pub(super) fn configure(cfg: &mut web::ServiceConfig) {
         web::scope("/games")
             .service(create)
             .service(get)
+            .service(connection)
             .service(list)
             .service(join)
-            .service(leave),
+            .service(leave)
+            .service(max_players)
+            .service(start)
+            .service(finish)
+            .service(report),
     );
 }
 
@@ -21,6 +36,7 @@ pub(super) fn configure(cfg: &mut web::ServiceConfig) {
 async fn create(
     claims: web::ReqData<Claims>,
     games: web::Data<Games>,
+    key: web::Data<GameKey>,
     game_setup: web::Json<GameSetup>,
 ) -> impl Responder {
     let game_setup = game_setup.into_inner();
@@ -29,9 +45,21 @@ async fn create(
         return HttpResponse::BadRequest().json(format!("{error}"));
     }
 
-    let game = Game::from_author(game_setup, claims.username().to_owned());
-    match games.create(game).await {
-        Ok(_) => HttpResponse::Ok().json(()),
+    let secret = match game_setup.nonce() {
+        Some(nonce) => match key.issue(game_setup.server().port(), nonce) {
+            Some(secret) => Some(secret),
+            None => {
+                warn!("Game creation error: game secrets are not issued.");
+                return HttpResponse::BadRequest()
+                    .json("Secured games are not supported by the lobby.");
+            }
+        },
+        None => None,
+    };
+
+    let author = GamePlayer::new(claims.username().to_owned(), GamePlayerInfo::new(1));
+    match games.create(&game_setup, secret, &author).await {
+        Ok(_) => HttpResponse::Ok().json(secret),
         Err(CreationError::NameTaken) => {
             warn!("Game creation error: game name is already taken.");
             HttpResponse::Conflict().json("Game name is already taken.")
@@ -60,9 +88,45 @@ async fn get(path: web::Path<String>, games: web::Data<Games>) -> impl Responder
     }
 }
 
+#[post("/{name}/connection")]
+async fn connection(
+    claims: web::ReqData<Claims>,
+    games: web::Data<Games>,
+    path: web::Path<String>,
+    access: web::Json<GameAccess>,
+) -> impl Responder {
+    let name = path.into_inner();
+
+    match games
+        .connection(claims.username(), name.as_str(), access.password())
+        .await
+    {
+        Ok(connection) => HttpResponse::Ok().json(connection),
+        Err(ConnectionError::GameNotFound) => {
+            warn!("Game connection error: the game does not exist.");
+            HttpResponse::NotFound().json("Game not found.")
+        }
+        Err(ConnectionError::WrongPassword) => {
+            warn!("Game connection error: wrong game password.");
+            HttpResponse::Forbidden().json("Wrong or missing game password.")
+        }
+        Err(error) => {
+            error!("Game connection error: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    /// Whether already started games should be listed too.
+    #[serde(default)]
+    started: bool,
+}
+
 #[get("")]
-async fn list(games: web::Data<Games>) -> impl Responder {
-    match games.list().await {
+async fn list(games: web::Data<Games>, listing_query: web::Query<ListQuery>) -> impl Responder {
+    match games.list(listing_query.started).await {
         Ok(games) => HttpResponse::Ok().json(games),
         Err(error) => {
             error!("Game listing error: {:?}", error);
@@ -76,18 +140,21 @@ async fn join(
     claims: web::ReqData<Claims>,
     games: web::Data<Games>,
     path: web::Path<String>,
-    player_info: web::Json<GamePlayerInfo>,
+    join_info: web::Json<GameJoinInfo>,
 ) -> impl Responder {
     let name = path.into_inner();
 
-    let ordinal = player_info.ordinal();
+    let ordinal = join_info.info().ordinal();
     if ordinal == 0 {
         warn!("Game joining error: got ordinal equal to 0.");
         return HttpResponse::BadRequest().json("Ordinals start with 0, got 1.");
     }
 
-    let player = GamePlayer::new(claims.username().to_owned(), player_info.0);
-    match games.add_player(&player, name.as_str()).await {
+    let player = GamePlayer::new(claims.username().to_owned(), join_info.info().clone());
+    match games
+        .add_player(&player, name.as_str(), join_info.password())
+        .await
+    {
         Ok(_) => HttpResponse::Ok().json(()),
         Err(AdditionError::AlreadyInAGame) => {
             warn!("Game joining error: a user is already in a different game.");
@@ -103,6 +170,14 @@ async fn join(
             HttpResponse::Conflict()
                 .json("The given ordinal is larger than maximum number of players.")
         }
+        Err(AdditionError::WrongPassword) => {
+            warn!("Game joining error: wrong game password.");
+            HttpResponse::Forbidden().json("Wrong or missing game password.")
+        }
+        Err(AdditionError::NotOpen) => {
+            warn!("Game joining error: the game is not open.");
+            HttpResponse::Conflict().json("The game is no longer open for joining.")
+        }
         Err(AdditionError::UserOrGameDoesNotExist) => {
             warn!("Game joining error: the game or the user does not exist");
             HttpResponse::NotFound().json("Game not found.")
@@ -134,3 +209,152 @@ async fn leave(
         }
     }
 }
+
+#[put("/{name}/max-players")]
+async fn max_players(
+    claims: web::ReqData<Claims>,
+    games: web::Data<Games>,
+    path: web::Path<String>,
+    max_players: web::Json<GameMaxPlayers>,
+) -> impl Responder {
+    let name = path.into_inner();
+
+    let max_players = max_players.into_inner();
+    if let Err(error) = max_players.validate() {
+        warn!("Invalid maximum number of players: {:?}", error);
+        return HttpResponse::BadRequest().json(format!("{error}"));
+    }
+
+    match games
+        .set_max_players(claims.username(), name.as_str(), max_players.max_players())
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().json(()),
+        Err(MaxPlayersError::GameNotFound) => {
+            warn!("Game max players error: the game does not exist.");
+            HttpResponse::NotFound().json("Game not found.")
+        }
+        Err(MaxPlayersError::NotInTheGame) => {
+            warn!("Game max players error: the user is not in the game.");
+            HttpResponse::Forbidden().json("The user is not in the game.")
+        }
+        Err(MaxPlayersError::NotAuthor) => {
+            warn!("Game max players error: the user is not the game author.");
+            HttpResponse::Forbidden()
+                .json("Only the game author can change maximum number of players.")
+        }
+        Err(MaxPlayersError::NotOpen) => {
+            warn!("Game max players error: the game is not open.");
+            HttpResponse::Conflict().json("The game is no longer open.")
+        }
+        Err(MaxPlayersError::MapCapacity(map_max_players)) => {
+            warn!("Game max players error: the map supports at most {map_max_players} players.");
+            HttpResponse::Conflict().json(format!(
+                "The map supports at most {map_max_players} players."
+            ))
+        }
+        Err(MaxPlayersError::SlotOccupied) => {
+            warn!("Game max players error: a player occupies a slot above the new maximum.");
+            HttpResponse::Conflict()
+                .json("A player has joined under an ordinal above the new maximum.")
+        }
+        Err(error) => {
+            error!(
+                "Error while changing maximum number of players: {:?}",
+                error
+            );
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[put("/{name}/start")]
+async fn start(
+    claims: web::ReqData<Claims>,
+    games: web::Data<Games>,
+    path: web::Path<String>,
+) -> impl Responder {
+    set_status(claims, games, path, GameStatus::Started).await
+}
+
+#[put("/{name}/finish")]
+async fn finish(
+    claims: web::ReqData<Claims>,
+    games: web::Data<Games>,
+    path: web::Path<String>,
+) -> impl Responder {
+    set_status(claims, games, path, GameStatus::Finished).await
+}
+
+#[put("/{name}/report")]
+async fn report(
+    claims: web::ReqData<Claims>,
+    games: web::Data<Games>,
+    path: web::Path<String>,
+    game_report: web::Json<GameReport>,
+) -> impl Responder {
+    let name = path.into_inner();
+
+    match games
+        .report(claims.username(), name.as_str(), game_report.outcome())
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().json(()),
+        Err(ReportError::GameNotFound) => {
+            warn!("Game report error: the game does not exist.");
+            HttpResponse::NotFound().json("Game not found.")
+        }
+        Err(ReportError::NotInTheGame) => {
+            warn!("Game report error: the user is not in the game.");
+            HttpResponse::Forbidden().json("The user is not in the game.")
+        }
+        Err(ReportError::NotStarted) => {
+            warn!("Game report error: the game has not started yet.");
+            HttpResponse::Conflict().json("The game has not started yet.")
+        }
+        Err(ReportError::AlreadyReported) => {
+            warn!("Game report error: the outcome was already reported.");
+            HttpResponse::Conflict().json("The user has already reported the game outcome.")
+        }
+        Err(error) => {
+            error!("Error while reporting a game outcome: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+async fn set_status(
+    claims: web::ReqData<Claims>,
+    games: web::Data<Games>,
+    path: web::Path<String>,
+    status: GameStatus,
+) -> HttpResponse {
+    let name = path.into_inner();
+
+    match games
+        .set_status(claims.username(), name.as_str(), status)
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().json(()),
+        Err(StatusError::GameNotFound) => {
+            warn!("Game status error: the game does not exist.");
+            HttpResponse::NotFound().json("Game not found.")
+        }
+        Err(StatusError::NotInTheGame) => {
+            warn!("Game status error: the user is not in the game.");
+            HttpResponse::Forbidden().json("The user is not in the game.")
+        }
+        Err(StatusError::NotAuthor) => {
+            warn!("Game status error: the user is not the game author.");
+            HttpResponse::Forbidden().json("Only the game author can change the game status.")
+        }
+        Err(StatusError::InvalidTransition { from, to }) => {
+            warn!("Game status error: invalid transition from {from:?} to {to:?}.");
+            HttpResponse::Conflict().json(format!("The game cannot move from {from:?} to {to:?}."))
+        }
+        Err(error) => {
+            error!("Error while changing game status: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -3,7 +3,15 @@ CREATE TABLE IF NOT EXISTS games (
     max_players TINYINT NOT NULL,
     map_hash CHARACTER({map_hash_len}) NOT NULL,
     map_name CHARACTER({map_name_len}) NOT NULL,
-    server CHARACTER({server_len}) NOT NULL
+    server CHARACTER({server_len}) NOT NULL,
+    secret CHARACTER({secret_len}),
+    -- Join password of private games, NULL for public games.
+    pass_hash CHARACTER({pass_hash_len}),
+    pass_salt CHARACTER({pass_salt_len}),
+    protocol_version INTEGER NOT NULL,
+    status CHARACTER({status_len}) NOT NULL DEFAULT 'open',
+    -- Unix timestamp of the last change of the game or its players.
+    updated INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER))
 );
 
 CREATE TABLE IF NOT EXISTS players (
@@ -23,6 +31,23 @@ CREATE TABLE IF NOT EXISTS players (
         ON DELETE CASCADE
 );
 
+-- Outcomes of started games reported by their players. Reports outlive
+-- membership of the players in the game, e.g. players leaving a started game
+-- are reported as losers.
+CREATE TABLE IF NOT EXISTS reports (
+    username CHARACTER({username_len}) NOT NULL,
+    game CHARACTER({game_name_len}) NOT NULL,
+    outcome CHARACTER({outcome_len}) NOT NULL,
+
+    PRIMARY KEY (username, game),
+
+    FOREIGN KEY(username) REFERENCES users(username)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE,
+    FOREIGN KEY(game) REFERENCES games(name)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
 
 CREATE TRIGGER IF NOT EXISTS check_ordinal
 BEFORE INSERT ON players
@@ -33,3 +58,29 @@ BEGIN
         THEN RAISE(FAIL, 'TOO-LARGE-ORDINAL')
     END;
 END;
+
+CREATE TRIGGER IF NOT EXISTS check_open
+BEFORE INSERT ON players
+FOR EACH ROW
+BEGIN
+    SELECT CASE
+        WHEN (SELECT status FROM games WHERE name = NEW.game) != 'open'
+        THEN RAISE(FAIL, 'GAME-NOT-OPEN')
+    END;
+END;
+
+CREATE TRIGGER IF NOT EXISTS touch_on_join
+AFTER INSERT ON players
+FOR EACH ROW
+BEGIN
+    UPDATE games SET updated = CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE name = NEW.game;
+END;
+
+CREATE TRIGGER IF NOT EXISTS touch_on_leave
+AFTER DELETE ON players
+FOR EACH ROW
+BEGIN
+    UPDATE games SET updated = CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE name = OLD.game;
+END;
@@ -1,32 +1,124 @@
-use actix_web::web;
-use anyhow::{Context, Result};
+use std::time::Duration;
+
+use actix_web::{rt, web};
+use anyhow::{ensure, Context, Result};
+use de_lobby_model::GameSecret;
+use log::{error, info};
 use sqlx::{Pool, Sqlite};
 
 use self::db::Games;
+use crate::conf;
 
 mod db;
 mod endpoints;
 
+const GAME_KEY_VAR_NAME: &str = "DE_GAME_KEY";
+const MIN_GAME_KEY_LEN: usize = 12;
+const IDLE_TIMEOUT_VAR_NAME: &str = "DE_GAME_IDLE_TIMEOUT";
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 3600;
+const STARTED_IDLE_TIMEOUT_VAR_NAME: &str = "DE_STARTED_GAME_IDLE_TIMEOUT";
+const DEFAULT_STARTED_IDLE_TIMEOUT_SECS: u64 = 6 * 3600;
+const GC_INTERVAL: Duration = Duration::from_secs(60);
+/// Time given to players of a finished game to report its outcome. The match
+/// is recorded with the reports received so far once it elapses.
+const REPORT_DEADLINE: Duration = Duration::from_secs(600);
+
 #[derive(Clone)]
 pub struct GamesService {
     games: Games,
+    key: GameKey,
 }
 
 impl GamesService {
     /// Setup games DB and endpoints.
     ///
-    /// This should be called after [`crate::auth::Auth`].
+    /// This should be called after [`crate::auth::Auth`]. A background task
+    /// periodically removing stale games is spawned on the current runtime.
     pub async fn setup(pool: &'static Pool<Sqlite>) -> Result<Self> {
-        Ok(Self {
-            games: db::Games::init(pool)
-                .await
-                .context("Failed to initialize games")?,
-        })
+        let key: String = conf::optional(GAME_KEY_VAR_NAME, String::new())?;
+        let key = if key.is_empty() {
+            info!("Game secrets are not issued.");
+            GameKey(None)
+        } else {
+            ensure!(
+                key.len() >= MIN_GAME_KEY_LEN,
+                "Game key is too short: {} < {}",
+                key.len(),
+                MIN_GAME_KEY_LEN
+            );
+            GameKey(Some(key))
+        };
+
+        let idle_timeout: u64 = conf::optional(IDLE_TIMEOUT_VAR_NAME, DEFAULT_IDLE_TIMEOUT_SECS)?;
+        let idle_timeout = Duration::from_secs(idle_timeout);
+        info!("Idle games are removed after {idle_timeout:?}.");
+        let started_idle_timeout: u64 = conf::optional(
+            STARTED_IDLE_TIMEOUT_VAR_NAME,
+            DEFAULT_STARTED_IDLE_TIMEOUT_SECS,
+        )?;
+        let started_idle_timeout = Duration::from_secs(started_idle_timeout);
+        info!("Idle started games are removed after {started_idle_timeout:?}.");
+
+        let games = db::Games::init(pool)
+            .await
+            .context("Failed to initialize games")?;
+        rt::spawn(remove_stale(
+            games.clone(),
+            idle_timeout,
+            started_idle_timeout,
+        ));
+
+        Ok(Self { games, key })
     }
 
     /// Configure actix-web application.
     pub fn configure(&self, cfg: &mut web::ServiceConfig) {
         cfg.app_data(web::Data::new(self.games.clone()));
+        cfg.app_data(web::Data::new(self.key.clone()));
         endpoints::configure(cfg);
     }
 }
+
+/// Key shared with DE Connector. Secrets of individual games are derived from
+/// it.
+#[derive(Clone)]
+struct GameKey(Option<String>);
+
+impl GameKey {
+    /// Issues secret of a game. None is returned if the key is not configured.
+    ///
+    /// See [`GameSecret::derive`].
+    fn issue(&self, port: u16, nonce: u64) -> Option<GameSecret> {
+        self.0
+            .as_ref()
+            .map(|key| GameSecret::derive(key.as_bytes(), port, nonce))
+    }
+}
+
+/// Periodically removes open and finished games not updated for at least
+/// `idle_timeout` and started games not updated for at least
+/// `started_idle_timeout`.
+///
+/// Matches of finished games are recorded after [`REPORT_DEADLINE`] (but
+/// before the games are removed) even if not all players reported the
+/// outcome. Matches of removed started games are recorded too if any of the
+/// players reported the outcome, e.g. by leaving the game.
+async fn remove_stale(games: Games, idle_timeout: Duration, started_idle_timeout: Duration) {
+    let mut interval = rt::time::interval(GC_INTERVAL);
+    loop {
+        interval.tick().await;
+        match games
+            .finalize_overdue(REPORT_DEADLINE.min(idle_timeout), started_idle_timeout)
+            .await
+        {
+            Ok(0) => (),
+            Ok(recorded) => info!("Recorded {recorded} matches with missing reports."),
+            Err(error) => error!("Overdue matches recording error: {:?}", error),
+        }
+        match games.remove_stale(idle_timeout, started_idle_timeout).await {
+            Ok(0) => (),
+            Ok(removed) => info!("Removed {removed} stale games."),
+            Err(error) => error!("Stale games removal error: {:?}", error),
+        }
+    }
+}
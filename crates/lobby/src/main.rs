@@ -3,12 +3,16 @@ use anyhow::{Context, Result};
 use auth::{Auth, AuthMiddlewareFactory};
 use games::GamesService;
 use log::info;
+use maps::MapsService;
+use results::ResultsService;
 use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
 
 mod auth;
 mod conf;
 mod db;
 mod games;
+mod maps;
+mod results;
 
 const JSON_PAYLOAD_LIMIT: usize = 10 * 1024;
 const DB_URL_VAR_NAME: &str = "DE_DB_URL";
@@ -42,12 +46,17 @@ async fn main() -> std::io::Result<()> {
     let db_pool = handle_error!(db_pool().await);
     let auth = handle_error!(Auth::setup(db_pool).await);
     let games = handle_error!(GamesService::setup(db_pool).await);
+    let results = handle_error!(ResultsService::setup(db_pool).await);
+    let maps = handle_error!(MapsService::setup(db_pool).await);
 
     HttpServer::new(move || {
         let public_scope = web::scope("/p").configure(|c| auth.configure_public(c));
         let authenticated_scope = web::scope("/a")
             .wrap(AuthMiddlewareFactory)
-            .configure(|c| games.configure(c));
+            .configure(|c| auth.configure_authenticated(c))
+            .configure(|c| games.configure(c))
+            .configure(|c| results.configure(c))
+            .configure(|c| maps.configure(c));
 
         App::new()
             .wrap(Logger::default())
@@ -0,0 +1,135 @@
+use anyhow::{Context, Result};
+use de_lobby_model::{
+    GameMap, MapInfo, MapListing, MAP_HASH_LEN, MAX_MAP_NAME_LEN, MAX_USERNAME_LEN,
+};
+use futures_util::TryStreamExt;
+use log::info;
+use sqlx::{query, sqlite::SqliteRow, Pool, Row, Sqlite};
+
+use crate::db::FromRow;
+
+#[derive(Clone)]
+pub(super) struct Maps {
+    pool: &'static Pool<Sqlite>,
+}
+
+impl Maps {
+    /// This method sets up the database by creating required tables if they do
+    /// not already exist.
+    ///
+    /// It is supposed users were already setup.
+    pub(super) async fn init(pool: &'static Pool<Sqlite>) -> Result<Self> {
+        let init_query = format!(
+            include_str!("init.sql"),
+            map_hash_len = MAP_HASH_LEN,
+            map_name_len = MAX_MAP_NAME_LEN,
+            username_len = MAX_USERNAME_LEN,
+        );
+
+        info!("Initializing maps...");
+        query(&init_query)
+            .execute(pool)
+            .await
+            .context("DB initialization failed")?;
+        Ok(Self { pool })
+    }
+
+    /// Returns all maps available in the repository ordered by their name.
+    pub(super) async fn list(&self) -> Result<MapListing> {
+        let mut rows = query(
+            "SELECT hash, name, max_players, author, uploaded FROM maps ORDER BY name, hash;",
+        )
+        .fetch(self.pool);
+
+        let mut maps = MapListing::empty();
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .context("Failed to retrieve a map from the DB")?
+        {
+            maps.push(MapInfo::try_from_row(row)?);
+        }
+        Ok(maps)
+    }
+
+    /// Returns true if a map with the hash exists in the repository.
+    pub(super) async fn contains(&self, hash: &str) -> Result<bool> {
+        let row = query("SELECT hash FROM maps WHERE hash = ?;")
+            .bind(hash)
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to retrieve a map from the DB")?;
+        Ok(row.is_some())
+    }
+
+    /// Returns content of a map file or None if the map does not exist.
+    pub(super) async fn content(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        let row = query("SELECT content FROM maps WHERE hash = ?;")
+            .bind(hash)
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to retrieve a map from the DB")?;
+
+        let Some(row) = row else { return Ok(None) };
+        Ok(Some(
+            row.try_get("content")
+                .context("Failed to retrieve map content from the DB")?,
+        ))
+    }
+
+    /// Stores a map to the repository. Nothing is stored if a map with the
+    /// same hash already exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `author` - username of the user uploading the map.
+    ///
+    /// * `map` - hash and name of the map.
+    ///
+    /// * `max_players` - maximum number of players of the map.
+    ///
+    /// * `content` - validated map file.
+    pub(super) async fn store(
+        &self,
+        author: &str,
+        map: &GameMap,
+        max_players: u8,
+        content: &[u8],
+    ) -> Result<()> {
+        let result = query(
+            "INSERT OR IGNORE INTO maps (hash, name, max_players, author, content) \
+             VALUES (?, ?, ?, ?, ?);",
+        )
+        .bind(map.hash())
+        .bind(map.name())
+        .bind(max_players)
+        .bind(author)
+        .bind(content)
+        .execute(self.pool)
+        .await
+        .context("Failed to store a map to the DB")?;
+
+        if result.rows_affected() > 0 {
+            info!("Map {} uploaded by {author}.", map.hash());
+        }
+        Ok(())
+    }
+}
+
+impl FromRow for MapInfo {
+    type Error = anyhow::Error;
+
+    fn try_from_row(row: SqliteRow) -> Result<Self, Self::Error> {
+        let hash: String = row.try_get("hash")?;
+        let name: String = row.try_get("name")?;
+        let max_players: u8 = row.try_get("max_players")?;
+        let author: String = row.try_get("author")?;
+        let uploaded: i64 = row.try_get("uploaded")?;
+        Ok(Self::new(
+            GameMap::new(hash, name),
+            max_players,
+            author,
+            uploaded,
+        ))
+    }
+}
@@ -0,0 +1,102 @@
+use actix_web::{get, put, route, web, HttpResponse, Responder};
+use de_lobby_model::{GameMap, Validatable, MAX_MAP_SIZE};
+use de_map::io::read_map;
+use log::{error, warn};
+
+use super::db::Maps;
+use crate::auth::Claims;
+
+/// Registers all map repository endpoints.
+pub(super) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/maps")
+            .app_data(web::PayloadConfig::new(MAX_MAP_SIZE))
+            .service(list)
+            .service(exists)
+            .service(download)
+            .service(upload),
+    );
+}
+
+#[get("")]
+async fn list(maps: web::Data<Maps>) -> impl Responder {
+    match maps.list().await {
+        Ok(maps) => HttpResponse::Ok().json(maps),
+        Err(error) => {
+            error!("Map listing error: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Checks whether a map is present in the repository without downloading it.
+#[route("/{hash}", method = "HEAD")]
+async fn exists(path: web::Path<String>, maps: web::Data<Maps>) -> impl Responder {
+    let hash = path.into_inner();
+    match maps.contains(&hash).await {
+        Ok(true) => HttpResponse::Ok().finish(),
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(error) => {
+            error!("Map existence check error: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[get("/{hash}")]
+async fn download(path: web::Path<String>, maps: web::Data<Maps>) -> impl Responder {
+    let hash = path.into_inner();
+    match maps.content(&hash).await {
+        Ok(Some(content)) => HttpResponse::Ok()
+            .content_type("application/x-tar")
+            .body(content),
+        Ok(None) => HttpResponse::NotFound().json("Map not found"),
+        Err(error) => {
+            error!("Map download error: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Stores a map file to the repository. The map is loaded and validated and
+/// its content must correspond to the hash.
+#[put("/{hash}")]
+async fn upload(
+    claims: web::ReqData<Claims>,
+    path: web::Path<String>,
+    maps: web::Data<Maps>,
+    content: web::Bytes,
+) -> impl Responder {
+    let hash = path.into_inner();
+
+    let map = match read_map(content.as_ref()).await {
+        Ok(map) => map,
+        Err(error) => {
+            warn!("Invalid map {hash} uploaded: {:?}", error);
+            return HttpResponse::BadRequest().json(format!("Invalid map file: {error}"));
+        }
+    };
+
+    if map.compute_hash().to_hex() != hash {
+        warn!("Uploaded map does not correspond to hash {hash}.");
+        return HttpResponse::BadRequest().json("Map hash does not match map content.");
+    }
+
+    let game_map = GameMap::new(hash, map.metadata().name().to_owned());
+    if let Err(error) = game_map.validate() {
+        warn!("Invalid map uploaded: {:?}", error);
+        return HttpResponse::BadRequest().json(format!("{error}"));
+    }
+
+    let max_players = map.metadata().max_player().to_num();
+    match maps
+        .store(claims.username(), &game_map, max_players, content.as_ref())
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().json(()),
+        Err(error) => {
+            error!("Map upload error: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
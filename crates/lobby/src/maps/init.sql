@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS maps (
+    hash CHARACTER({map_hash_len}) NOT NULL PRIMARY KEY,
+    name CHARACTER({map_name_len}) NOT NULL,
+    max_players TINYINT NOT NULL,
+    author CHARACTER({username_len}) NOT NULL,
+    -- Unix timestamp of the map upload.
+    uploaded INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+    -- Map TAR file.
+    content BLOB NOT NULL,
+
+    FOREIGN KEY(author) REFERENCES users(username)
+        ON UPDATE CASCADE
+);
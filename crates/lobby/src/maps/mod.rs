@@ -0,0 +1,32 @@
+use actix_web::web;
+use anyhow::{Context, Result};
+use sqlx::{Pool, Sqlite};
+
+use self::db::Maps;
+
+mod db;
+mod endpoints;
+
+#[derive(Clone)]
+pub struct MapsService {
+    maps: Maps,
+}
+
+impl MapsService {
+    /// Setup map repository DB and endpoints.
+    ///
+    /// This should be called after [`crate::auth::Auth`].
+    pub async fn setup(pool: &'static Pool<Sqlite>) -> Result<Self> {
+        Ok(Self {
+            maps: Maps::init(pool)
+                .await
+                .context("Failed to initialize maps")?,
+        })
+    }
+
+    /// Configure actix-web application.
+    pub fn configure(&self, cfg: &mut web::ServiceConfig) {
+        cfg.app_data(web::Data::new(self.maps.clone()));
+        endpoints::configure(cfg);
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::{anyhow, Context, Result};
+use de_lobby_model::{
+    GameOutcome, Leaderboard, MatchHistory, MatchRecord, PlayerRating, MAX_GAME_NAME_LEN,
+    MAX_MAP_NAME_LEN, MAX_USERNAME_LEN,
+};
+use futures_util::TryStreamExt;
+use log::info;
+use sqlx::{query, sqlite::SqliteRow, Pool, Row, Sqlite, Transaction};
+
+use super::elo;
+use crate::db::FromRow;
+
+// Length of the longest game outcome name.
+pub(crate) const OUTCOME_LEN: usize = 4;
+const HISTORY_LIMIT: u32 = 50;
+const LEADERBOARD_LIMIT: u32 = 50;
+
+#[derive(Clone)]
+pub(super) struct Results {
+    pool: &'static Pool<Sqlite>,
+}
+
+impl Results {
+    /// This method sets up the database by creating required tables if they do
+    /// not already exist.
+    ///
+    /// It is supposed users were already setup.
+    pub(super) async fn init(pool: &'static Pool<Sqlite>) -> Result<Self> {
+        let init_query = format!(
+            include_str!("init.sql"),
+            username_len = MAX_USERNAME_LEN,
+            game_name_len = MAX_GAME_NAME_LEN,
+            map_name_len = MAX_MAP_NAME_LEN,
+            outcome_len = OUTCOME_LEN,
+        );
+
+        info!("Initializing match results...");
+        query(&init_query)
+            .execute(pool)
+            .await
+            .context("DB initialization failed")?;
+        Ok(Self { pool })
+    }
+
+    /// Returns the most recent matches of a user or None if the user does not
+    /// exist.
+    pub(super) async fn history(&self, username: &str) -> Result<Option<MatchHistory>> {
+        if !self.user_exists(username).await? {
+            return Ok(None);
+        }
+
+        let mut rows = query(
+            "SELECT matches.*, match_players.outcome, match_players.rating_before, \
+             match_players.rating_after \
+             FROM match_players \
+             JOIN matches ON (matches.id = match_players.match_id) \
+             WHERE match_players.username = ? \
+             ORDER BY matches.finished DESC, matches.id DESC \
+             LIMIT ?;",
+        )
+        .bind(username)
+        .bind(HISTORY_LIMIT)
+        .fetch(self.pool);
+
+        let mut history = MatchHistory::empty();
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .context("Failed to retrieve a match from the DB")?
+        {
+            history.push(MatchRecord::try_from_row(row)?);
+        }
+        Ok(Some(history))
+    }
+
+    /// Returns the best rated players.
+    pub(super) async fn leaderboard(&self) -> Result<Leaderboard> {
+        let mut rows = query(
+            "SELECT * FROM ratings \
+             ORDER BY rating DESC, matches DESC, username \
+             LIMIT ?;",
+        )
+        .bind(LEADERBOARD_LIMIT)
+        .fetch(self.pool);
+
+        let mut leaderboard = Leaderboard::empty();
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .context("Failed to retrieve a rating from the DB")?
+        {
+            leaderboard.push(PlayerRating::try_from_row(row)?);
+        }
+        Ok(leaderboard)
+    }
+
+    async fn user_exists(&self, username: &str) -> Result<bool> {
+        let row = query("SELECT username FROM users WHERE username = ?;")
+            .bind(username)
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to retrieve a user from the DB")?;
+        Ok(row.is_some())
+    }
+}
+
+/// Stores results of a finished game to the match history and updates
+/// ratings of its players.
+///
+/// The match is marked as disputed, and ratings are left unchanged, unless
+/// exactly one of at least two players won.
+///
+/// # Arguments
+///
+/// * `transaction` - the match is recorded as part of this transaction.
+///
+/// * `game` - name of the finished game.
+///
+/// * `map_name` - name of the map the game was played on.
+///
+/// * `outcomes` - username and reported outcome of each player of the game.
+pub(crate) async fn record(
+    transaction: &mut Transaction<'_, Sqlite>,
+    game: &str,
+    map_name: &str,
+    outcomes: &[(String, GameOutcome)],
+) -> Result<()> {
+    let winners = outcomes
+        .iter()
+        .filter(|(_, outcome)| *outcome == GameOutcome::Won)
+        .count();
+    let disputed = outcomes.len() < 2 || winners != 1;
+
+    let mut players = Vec::with_capacity(outcomes.len());
+    for (username, outcome) in outcomes {
+        let row = query("SELECT rating FROM ratings WHERE username = ?;")
+            .bind(username)
+            .fetch_optional(&mut *transaction)
+            .await
+            .context("Failed to retrieve a rating from the DB")?;
+        let rating: f64 = match row {
+            Some(row) => row.try_get("rating")?,
+            None => elo::INITIAL_RATING,
+        };
+        players.push((rating, *outcome == GameOutcome::Won));
+    }
+
+    let ratings = if disputed {
+        players.iter().map(|&(rating, _)| rating).collect()
+    } else {
+        elo::update(players.as_slice())
+    };
+
+    let match_id = query("INSERT INTO matches (game, map_name, disputed) VALUES (?, ?, ?);")
+        .bind(game)
+        .bind(map_name)
+        .bind(disputed)
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to store a match to the DB")?
+        .last_insert_rowid();
+
+    for (((username, outcome), (before, _)), after) in
+        outcomes.iter().zip(players.iter()).zip(ratings.iter())
+    {
+        query(
+            "INSERT INTO match_players \
+             (match_id, username, outcome, rating_before, rating_after) \
+             VALUES (?, ?, ?, ?, ?);",
+        )
+        .bind(match_id)
+        .bind(username)
+        .bind(outcome_to_str(*outcome))
+        .bind(before)
+        .bind(after)
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to store a match player to the DB")?;
+
+        if !disputed {
+            query(
+                "INSERT INTO ratings (username, rating, matches) VALUES (?, ?, 1) \
+                 ON CONFLICT(username) \
+                 DO UPDATE SET rating = excluded.rating, matches = matches + 1;",
+            )
+            .bind(username)
+            .bind(after)
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to store a rating to the DB")?;
+        }
+    }
+
+    info!("Recorded match of game {game} (disputed: {disputed}).");
+    Ok(())
+}
+
+pub(crate) fn outcome_to_str(outcome: GameOutcome) -> &'static str {
+    match outcome {
+        GameOutcome::Won => "won",
+        GameOutcome::Lost => "lost",
+    }
+}
+
+pub(crate) fn outcome_from_str(outcome: &str) -> Result<GameOutcome> {
+    match outcome {
+        "won" => Ok(GameOutcome::Won),
+        "lost" => Ok(GameOutcome::Lost),
+        _ => Err(anyhow!("Unknown game outcome: {outcome}")),
+    }
+}
+
+impl FromRow for MatchRecord {
+    type Error = anyhow::Error;
+
+    fn try_from_row(row: SqliteRow) -> Result<Self, Self::Error> {
+        let game: String = row.try_get("game")?;
+        let map_name: String = row.try_get("map_name")?;
+        let finished: i64 = row.try_get("finished")?;
+        let outcome: String = row.try_get("outcome")?;
+        let disputed: bool = row.try_get("disputed")?;
+        let rating_before: f64 = row.try_get("rating_before")?;
+        let rating_after: f64 = row.try_get("rating_after")?;
+        Ok(Self::new(
+            game,
+            map_name,
+            finished,
+            outcome_from_str(&outcome)?,
+            disputed,
+            rating_before,
+            rating_after,
+        ))
+    }
+}
+
+impl FromRow for PlayerRating {
+    type Error = anyhow::Error;
+
+    fn try_from_row(row: SqliteRow) -> Result<Self, Self::Error> {
+        let username: String = row.try_get("username")?;
+        let rating: f64 = row.try_get("rating")?;
+        let matches: u32 = row.try_get("matches")?;
+        Ok(Self::new(username, rating, matches))
+    }
+}
@@ -0,0 +1,75 @@
+//! Elo rating of players of (possibly) multi-player games.
+//!
+//! A game with N players is treated as N * (N - 1) / 2 simultaneous duels.
+//! The winner beats each of the other players, the losers draw among
+//! themselves. The rating change of each player is the mean of the changes
+//! from the duels the player took part in.
+
+/// Rating of newly ranked players.
+pub(super) const INITIAL_RATING: f64 = 1500.;
+/// Maximum rating change of a single match.
+const K_FACTOR: f64 = 32.;
+/// Rating difference at which the stronger player is expected to win 10 times
+/// more often than the weaker player.
+const SCALE: f64 = 400.;
+
+/// Returns new ratings of all players of a match.
+///
+/// # Arguments
+///
+/// * `players` - rating of each player before the match and whether the
+///   player won the match.
+pub(super) fn update(players: &[(f64, bool)]) -> Vec<f64> {
+    if players.len() < 2 {
+        return players.iter().map(|&(rating, _)| rating).collect();
+    }
+
+    let duels = (players.len() - 1) as f64;
+    players
+        .iter()
+        .enumerate()
+        .map(|(i, &(rating, won))| {
+            let change: f64 = players
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| i != j)
+                .map(|(_, &(other_rating, other_won))| {
+                    let score = match (won, other_won) {
+                        (true, false) => 1.,
+                        (false, true) => 0.,
+                        _ => 0.5,
+                    };
+                    score - expected(rating, other_rating)
+                })
+                .sum();
+            rating + K_FACTOR * change / duels
+        })
+        .collect()
+}
+
+/// Returns the expected score of a player with rating `rating` in a duel
+/// against a player with rating `other`.
+fn expected(rating: f64, other: f64) -> f64 {
+    1. / (1. + 10f64.powf((other - rating) / SCALE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update() {
+        let ratings = update(&[(1500., true), (1500., false)]);
+        assert_eq!(ratings, vec![1516., 1484.]);
+
+        let ratings = update(&[(1700., false), (1300., true)]);
+        assert!((ratings[0] - 1670.909).abs() < 0.001);
+        assert!((ratings[1] - 1329.091).abs() < 0.001);
+
+        let ratings = update(&[(1500., true), (1500., false), (1500., false)]);
+        assert_eq!(ratings, vec![1516., 1492., 1492.]);
+        assert!((ratings.iter().sum::<f64>() - 4500.).abs() < 0.001);
+
+        assert_eq!(update(&[(1400., true)]), vec![1400.]);
+    }
+}
@@ -0,0 +1,34 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use log::error;
+
+use super::db::Results;
+
+/// Registers all match results endpoints.
+pub(super) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/users").service(history))
+        .service(leaderboard);
+}
+
+#[get("/{name}/history")]
+async fn history(path: web::Path<String>, results: web::Data<Results>) -> impl Responder {
+    let name = path.into_inner();
+    match results.history(&name).await {
+        Ok(Some(history)) => HttpResponse::Ok().json(history),
+        Ok(None) => HttpResponse::NotFound().json("User not found"),
+        Err(error) => {
+            error!("Match history error: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[get("/leaderboard")]
+async fn leaderboard(results: web::Data<Results>) -> impl Responder {
+    match results.leaderboard().await {
+        Ok(leaderboard) => HttpResponse::Ok().json(leaderboard),
+        Err(error) => {
+            error!("Leaderboard error: {:?}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -0,0 +1,34 @@
+CREATE TABLE IF NOT EXISTS ratings (
+    username CHARACTER({username_len}) NOT NULL PRIMARY KEY,
+    rating REAL NOT NULL,
+    matches INTEGER NOT NULL,
+
+    FOREIGN KEY(username) REFERENCES users(username)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS matches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    game CHARACTER({game_name_len}) NOT NULL,
+    map_name CHARACTER({map_name_len}) NOT NULL,
+    -- Unix timestamp of the time the match was recorded.
+    finished INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+    disputed BOOLEAN NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS match_players (
+    match_id INTEGER NOT NULL,
+    username CHARACTER({username_len}) NOT NULL,
+    outcome CHARACTER({outcome_len}) NOT NULL,
+    rating_before REAL NOT NULL,
+    rating_after REAL NOT NULL,
+
+    PRIMARY KEY (match_id, username),
+
+    FOREIGN KEY(match_id) REFERENCES matches(id)
+        ON DELETE CASCADE,
+    FOREIGN KEY(username) REFERENCES users(username)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
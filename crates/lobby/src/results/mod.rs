@@ -0,0 +1,34 @@
+use actix_web::web;
+use anyhow::{Context, Result};
+use sqlx::{Pool, Sqlite};
+
+use self::db::Results;
+pub(crate) use self::db::{outcome_from_str, outcome_to_str, record, OUTCOME_LEN};
+
+mod db;
+mod elo;
+mod endpoints;
+
+#[derive(Clone)]
+pub struct ResultsService {
+    results: Results,
+}
+
+impl ResultsService {
+    /// Setup match results DB and endpoints.
+    ///
+    /// This should be called after [`crate::auth::Auth`].
+    pub async fn setup(pool: &'static Pool<Sqlite>) -> Result<Self> {
+        Ok(Self {
+            results: Results::init(pool)
+                .await
+                .context("Failed to initialize match results")?,
+        })
+    }
+
+    /// Configure actix-web application.
+    pub fn configure(&self, cfg: &mut web::ServiceConfig) {
+        cfg.app_data(web::Data::new(self.results.clone()));
+        endpoints::configure(cfg);
+    }
+}
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, bail, Context, Result};
 use async_compat::Compat;
@@ -7,12 +7,17 @@ use bevy::{
     prelude::*,
     tasks::{IoTaskPool, Task},
 };
+use de_lobby_model::Token;
 use reqwest::{header::HeaderValue, redirect::Policy, Client, Request};
 use url::Url;
 
 use crate::requestable::LobbyRequestCreator;
 
 const USER_AGENT: &str = concat!("DigitalExtinction/", env!("CARGO_PKG_VERSION"));
+/// Tokens are refreshed once this fraction of their lifetime elapses.
+const REFRESH_FRACTION: f64 = 0.8;
+/// Failed token refresh is retried after this delay.
+const REFRESH_RETRY: Duration = Duration::from_secs(30);
 
 #[derive(SystemParam)]
 pub(super) struct AuthenticatedClient<'w> {
@@ -38,6 +43,7 @@ impl<'w> AuthenticatedClient<'w> {
 #[derive(Resource, Default)]
 pub struct Authentication {
     token: Option<String>,
+    refresh_at: Option<Instant>,
 }
 
 impl Authentication {
@@ -49,8 +55,32 @@ impl Authentication {
         self.token.as_deref()
     }
 
-    pub(super) fn set_token(&mut self, token: String) {
-        self.token = Some(token)
+    /// Sets the current token. The token is not refreshed if its expiration
+    /// is unknown.
+    pub(super) fn set_token(&mut self, token: &Token) {
+        self.token = Some(token.token().to_owned());
+        self.refresh_at = token.expires_in().map(|expires_in| {
+            Instant::now() + Duration::from_secs(expires_in).mul_f64(REFRESH_FRACTION)
+        });
+    }
+
+    pub(super) fn unset_token(&mut self) {
+        self.token = None;
+        self.refresh_at = None;
+    }
+
+    /// Returns true if the token should be refreshed now. Once true is
+    /// returned, the refresh is postponed, so that it is retried later in
+    /// case the refresh fails.
+    pub(super) fn schedule_refresh(&mut self) -> bool {
+        let now = Instant::now();
+        match self.refresh_at {
+            Some(refresh_at) if refresh_at <= now => {
+                self.refresh_at = Some(now + REFRESH_RETRY);
+                true
+            }
+            _ => false,
+        }
     }
 }
 
@@ -114,13 +144,11 @@ impl LobbyClient {
 
             let status = resonse.status();
             if status.is_success() {
-                let text = resonse
-                    .text()
+                let body = resonse
+                    .bytes()
                     .await
                     .context("Failed to load server response")?;
-                let response = serde_json::from_str(text.as_str())
-                    .context("Failed to parse server response")?;
-                Ok(response)
+                T::parse(body.as_ref())
             } else if status.is_server_error() {
                 Err(anyhow!("Server side error occurred."))
             } else {
@@ -142,6 +170,32 @@ mod tests {
     use super::*;
     use crate::{ListGamesRequest, SignInRequest};
 
+    #[test]
+    fn test_refresh() {
+        let mut auth = Authentication::default();
+        assert!(!auth.schedule_refresh());
+
+        auth.set_token(&Token::new("some-token".to_owned(), 3600));
+        assert!(auth.is_authenticated());
+        assert!(!auth.schedule_refresh());
+
+        auth.set_token(&Token::new("other-token".to_owned(), 0));
+        assert_eq!(auth.token(), Some("other-token"));
+        assert!(auth.schedule_refresh());
+        // Retried only after a delay.
+        assert!(!auth.schedule_refresh());
+
+        // Token issued by an older lobby without known expiration.
+        let token: Token = serde_json::from_str(r#"{"token": "old-token"}"#).unwrap();
+        auth.set_token(&token);
+        assert_eq!(auth.token(), Some("old-token"));
+        assert!(!auth.schedule_refresh());
+
+        auth.unset_token();
+        assert!(!auth.is_authenticated());
+        assert!(!auth.schedule_refresh());
+    }
+
     #[test]
     fn test_create() {
         let client = LobbyClient::build(Url::parse("https://example.com").unwrap());
@@ -154,7 +208,7 @@ mod tests {
         assert!(request.headers().get("Authorization").is_none());
 
         let request = client
-            .create(Some("some-token"), &ListGamesRequest)
+            .create(Some("some-token"), &ListGamesRequest::new())
             .unwrap();
         assert_eq!(
             request
@@ -1,13 +1,18 @@
 use std::borrow::Cow;
 
 use de_lobby_model::{
-    Game, GameListing, GamePlayerInfo, GameSetup, Token, UserWithPassword, UsernameAndPassword,
+    Game, GameAccess, GameConnection, GameJoinInfo, GameListing, GameMaxPlayers, GamePlayerInfo,
+    GameReport, GameSecret, GameSetup, Leaderboard, MapListing, MatchHistory, PasswordChange,
+    Token, UserWithPassword, UsernameAndPassword,
 };
 use reqwest::{header::HeaderValue, Method, Request};
 use serde::Serialize;
 use url::Url;
 
-use crate::requestable::{LobbyRequest, LobbyRequestCreator};
+use crate::{
+    plugin::Result,
+    requestable::{LobbyRequest, LobbyRequestCreator},
+};
 
 pub struct SignUpRequest(UserWithPassword);
 
@@ -57,6 +62,70 @@ impl LobbyRequestCreator for SignInRequest {
     }
 }
 
+/// Exchanges the current token for a new one. The current token stays valid
+/// until it expires.
+///
+/// The client sends this request automatically before the token expires.
+pub struct RefreshTokenRequest;
+
+impl LobbyRequest for RefreshTokenRequest {
+    type Response = Token;
+}
+
+impl LobbyRequestCreator for RefreshTokenRequest {
+    fn path(&self) -> Cow<str> {
+        "/a/auth/refresh".into()
+    }
+
+    fn create(&self, url: Url) -> Request {
+        Request::new(Method::POST, url)
+    }
+}
+
+/// Revokes the current token. The client is no longer authenticated once
+/// this request succeeds.
+pub struct SignOutRequest;
+
+impl LobbyRequest for SignOutRequest {
+    type Response = ();
+}
+
+impl LobbyRequestCreator for SignOutRequest {
+    fn path(&self) -> Cow<str> {
+        "/a/auth/sign-out".into()
+    }
+
+    fn create(&self, url: Url) -> Request {
+        Request::new(Method::POST, url)
+    }
+}
+
+/// Changes password of the signed-in user. All previously issued tokens of
+/// the user are revoked and the client is authenticated with a new token.
+pub struct ChangePasswordRequest(PasswordChange);
+
+impl ChangePasswordRequest {
+    pub fn new(change: PasswordChange) -> Self {
+        Self(change)
+    }
+}
+
+impl LobbyRequest for ChangePasswordRequest {
+    type Response = Token;
+}
+
+impl LobbyRequestCreator for ChangePasswordRequest {
+    fn path(&self) -> Cow<str> {
+        "/a/auth/password".into()
+    }
+
+    fn create(&self, url: Url) -> Request {
+        let mut request = Request::new(Method::PUT, url);
+        json(&mut request, &self.0);
+        request
+    }
+}
+
 pub struct CreateGameRequest(GameSetup);
 
 impl CreateGameRequest {
@@ -66,7 +135,8 @@ impl CreateGameRequest {
 }
 
 impl LobbyRequest for CreateGameRequest {
-    type Response = ();
+    /// Secret of the game, issued only for secured games.
+    type Response = Option<GameSecret>;
 }
 
 impl LobbyRequestCreator for CreateGameRequest {
@@ -81,7 +151,27 @@ impl LobbyRequestCreator for CreateGameRequest {
     }
 }
 
-pub struct ListGamesRequest;
+pub struct ListGamesRequest {
+    started: bool,
+}
+
+impl ListGamesRequest {
+    /// Creates a request listing only games open for joining.
+    pub fn new() -> Self {
+        Self { started: false }
+    }
+
+    /// Creates a request listing both open and already started games.
+    pub fn with_started() -> Self {
+        Self { started: true }
+    }
+}
+
+impl Default for ListGamesRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl LobbyRequest for ListGamesRequest {
     type Response = GameListing;
@@ -92,7 +182,10 @@ impl LobbyRequestCreator for ListGamesRequest {
         "/a/games".into()
     }
 
-    fn create(&self, url: Url) -> Request {
+    fn create(&self, mut url: Url) -> Request {
+        if self.started {
+            url.query_pairs_mut().append_pair("started", "true");
+        }
         Request::new(Method::GET, url)
     }
 }
@@ -119,14 +212,61 @@ impl LobbyRequestCreator for GetGameRequest {
     }
 }
 
+/// Retrieves the address and the secret of the game server. The password is
+/// required for private games unless the user is already a player of the game.
+pub struct GameConnectionRequest {
+    game: String,
+    access: GameAccess,
+}
+
+impl GameConnectionRequest {
+    pub fn new(game: String) -> Self {
+        Self {
+            game,
+            access: GameAccess::default(),
+        }
+    }
+
+    /// Sets the password of a private game.
+    pub fn with_password(mut self, password: String) -> Self {
+        self.access = GameAccess::new(Some(password));
+        self
+    }
+}
+
+impl LobbyRequest for GameConnectionRequest {
+    type Response = GameConnection;
+}
+
+impl LobbyRequestCreator for GameConnectionRequest {
+    fn path(&self) -> Cow<str> {
+        encode(&["a", "games", self.game.as_str(), "connection"])
+    }
+
+    fn create(&self, url: Url) -> Request {
+        let mut request = Request::new(Method::POST, url);
+        json(&mut request, &self.access);
+        request
+    }
+}
+
 pub struct JoinGameRequest {
     game: String,
-    player: GamePlayerInfo,
+    join_info: GameJoinInfo,
 }
 
 impl JoinGameRequest {
     pub fn new(game: String, player: GamePlayerInfo) -> Self {
-        Self { game, player }
+        Self {
+            game,
+            join_info: GameJoinInfo::new(player),
+        }
+    }
+
+    /// Sets the password of a private game.
+    pub fn with_password(mut self, password: String) -> Self {
+        self.join_info = self.join_info.with_password(password);
+        self
     }
 }
 
@@ -141,7 +281,7 @@ impl LobbyRequestCreator for JoinGameRequest {
 
     fn create(&self, url: Url) -> Request {
         let mut request = Request::new(Method::PUT, url);
-        json(&mut request, &self.player);
+        json(&mut request, &self.join_info);
         request
     }
 }
@@ -168,6 +308,264 @@ impl LobbyRequestCreator for LeaveGameRequest {
     }
 }
 
+/// Changes maximum number of players of an open game. Only the game author is
+/// allowed to do this.
+pub struct SetMaxPlayersRequest {
+    game: String,
+    max_players: GameMaxPlayers,
+}
+
+impl SetMaxPlayersRequest {
+    pub fn new(game: String, max_players: u8) -> Self {
+        Self {
+            game,
+            max_players: GameMaxPlayers::new(max_players),
+        }
+    }
+}
+
+impl LobbyRequest for SetMaxPlayersRequest {
+    type Response = ();
+}
+
+impl LobbyRequestCreator for SetMaxPlayersRequest {
+    fn path(&self) -> Cow<str> {
+        encode(&["a", "games", self.game.as_str(), "max-players"])
+    }
+
+    fn create(&self, url: Url) -> Request {
+        let mut request = Request::new(Method::PUT, url);
+        json(&mut request, &self.max_players);
+        request
+    }
+}
+
+/// Marks a game as started. Only the game author is allowed to do this.
+pub struct StartGameRequest(String);
+
+impl StartGameRequest {
+    pub fn new(name: String) -> Self {
+        Self(name)
+    }
+}
+
+impl LobbyRequest for StartGameRequest {
+    type Response = ();
+}
+
+impl LobbyRequestCreator for StartGameRequest {
+    fn path(&self) -> Cow<str> {
+        encode(&["a", "games", self.0.as_str(), "start"])
+    }
+
+    fn create(&self, url: Url) -> Request {
+        Request::new(Method::PUT, url)
+    }
+}
+
+/// Marks a game as finished.
+pub struct FinishGameRequest(String);
+
+impl FinishGameRequest {
+    pub fn new(name: String) -> Self {
+        Self(name)
+    }
+}
+
+impl LobbyRequest for FinishGameRequest {
+    type Response = ();
+}
+
+impl LobbyRequestCreator for FinishGameRequest {
+    fn path(&self) -> Cow<str> {
+        encode(&["a", "games", self.0.as_str(), "finish"])
+    }
+
+    fn create(&self, url: Url) -> Request {
+        Request::new(Method::PUT, url)
+    }
+}
+
+/// Reports the outcome of a finished game from the point of view of the
+/// signed-in player.
+pub struct ReportGameRequest {
+    game: String,
+    report: GameReport,
+}
+
+impl ReportGameRequest {
+    pub fn new(game: String, report: GameReport) -> Self {
+        Self { game, report }
+    }
+}
+
+impl LobbyRequest for ReportGameRequest {
+    type Response = ();
+}
+
+impl LobbyRequestCreator for ReportGameRequest {
+    fn path(&self) -> Cow<str> {
+        encode(&["a", "games", self.game.as_str(), "report"])
+    }
+
+    fn create(&self, url: Url) -> Request {
+        let mut request = Request::new(Method::PUT, url);
+        json(&mut request, &self.report);
+        request
+    }
+}
+
+pub struct MatchHistoryRequest(String);
+
+impl MatchHistoryRequest {
+    pub fn new(username: String) -> Self {
+        Self(username)
+    }
+}
+
+impl LobbyRequest for MatchHistoryRequest {
+    type Response = MatchHistory;
+}
+
+impl LobbyRequestCreator for MatchHistoryRequest {
+    fn path(&self) -> Cow<str> {
+        encode(&["a", "users", self.0.as_str(), "history"])
+    }
+
+    fn create(&self, url: Url) -> Request {
+        Request::new(Method::GET, url)
+    }
+}
+
+pub struct LeaderboardRequest;
+
+impl LobbyRequest for LeaderboardRequest {
+    type Response = Leaderboard;
+}
+
+impl LobbyRequestCreator for LeaderboardRequest {
+    fn path(&self) -> Cow<str> {
+        "/a/leaderboard".into()
+    }
+
+    fn create(&self, url: Url) -> Request {
+        Request::new(Method::GET, url)
+    }
+}
+
+pub struct ListMapsRequest;
+
+impl LobbyRequest for ListMapsRequest {
+    type Response = MapListing;
+}
+
+impl LobbyRequestCreator for ListMapsRequest {
+    fn path(&self) -> Cow<str> {
+        "/a/maps".into()
+    }
+
+    fn create(&self, url: Url) -> Request {
+        Request::new(Method::GET, url)
+    }
+}
+
+/// Succeeds if a map is present in the map repository.
+pub struct MapExistsRequest(String);
+
+impl MapExistsRequest {
+    /// # Arguments
+    ///
+    /// * `hash` - hex encoded hash of the map.
+    pub fn new(hash: String) -> Self {
+        Self(hash)
+    }
+}
+
+impl LobbyRequest for MapExistsRequest {
+    type Response = ();
+}
+
+impl LobbyRequestCreator for MapExistsRequest {
+    fn path(&self) -> Cow<str> {
+        encode(&["a", "maps", self.0.as_str()])
+    }
+
+    fn create(&self, url: Url) -> Request {
+        Request::new(Method::HEAD, url)
+    }
+
+    fn parse(_body: &[u8]) -> Result<Self::Response> {
+        Ok(())
+    }
+}
+
+pub struct DownloadMapRequest(String);
+
+impl DownloadMapRequest {
+    /// # Arguments
+    ///
+    /// * `hash` - hex encoded hash of the map.
+    pub fn new(hash: String) -> Self {
+        Self(hash)
+    }
+}
+
+impl LobbyRequest for DownloadMapRequest {
+    type Response = Vec<u8>;
+}
+
+impl LobbyRequestCreator for DownloadMapRequest {
+    fn path(&self) -> Cow<str> {
+        encode(&["a", "maps", self.0.as_str()])
+    }
+
+    fn create(&self, url: Url) -> Request {
+        Request::new(Method::GET, url)
+    }
+
+    fn parse(body: &[u8]) -> Result<Self::Response> {
+        Ok(body.to_vec())
+    }
+}
+
+/// Uploads a map file to the map repository. The map is validated by the
+/// lobby and its content must correspond to the hash.
+pub struct UploadMapRequest {
+    hash: String,
+    content: Vec<u8>,
+}
+
+impl UploadMapRequest {
+    /// # Arguments
+    ///
+    /// * `hash` - hex encoded hash of the map.
+    ///
+    /// * `content` - content of the map TAR file.
+    pub fn new(hash: String, content: Vec<u8>) -> Self {
+        Self { hash, content }
+    }
+}
+
+impl LobbyRequest for UploadMapRequest {
+    type Response = ();
+}
+
+impl LobbyRequestCreator for UploadMapRequest {
+    fn path(&self) -> Cow<str> {
+        encode(&["a", "maps", self.hash.as_str()])
+    }
+
+    fn create(&self, url: Url) -> Request {
+        let mut request = Request::new(Method::PUT, url);
+        request.headers_mut().insert(
+            "Content-Type",
+            HeaderValue::try_from("application/x-tar").unwrap(),
+        );
+        *request.body_mut() = Some(self.content.clone().into());
+        request
+    }
+}
+
 fn json<T: Serialize>(request: &mut Request, content: &T) {
     request.headers_mut().insert(
         "Content-Type",
@@ -187,7 +585,7 @@ fn encode(parts: &[&str]) -> Cow<'static, str> {
 
 #[cfg(test)]
 mod tests {
-    use de_lobby_model::{GameConfig, GameMap, User};
+    use de_lobby_model::{GameConfig, GameMap, GameOutcome, User};
 
     use super::*;
 
@@ -231,6 +629,54 @@ mod tests {
         assert_eq!(body, expected_body);
     }
 
+    #[test]
+    fn test_change_password() {
+        let request = ChangePasswordRequest::new(PasswordChange::new(
+            "Obviously 123456".to_owned(),
+            "Obviously 654321".to_owned(),
+        ));
+        assert_eq!(request.path().as_ref(), "/a/auth/password");
+
+        let request = request.create(Url::parse("https://example.com/a/auth/password").unwrap());
+        assert_eq!(request.method().as_str(), "PUT");
+        assert_eq!(
+            request.url().as_str(),
+            "https://example.com/a/auth/password"
+        );
+
+        let body = String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap();
+        let expected_body = r#"{"password":"Obviously 123456","newPassword":"Obviously 654321"}"#;
+        assert_eq!(body, expected_body);
+    }
+
+    #[test]
+    fn test_maps() {
+        let hash = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+        let request = MapExistsRequest::new(hash.to_owned());
+        assert_eq!(request.path(), format!("/a/maps/{hash}"));
+        let request = request.create(Url::parse("http://example.com/a/maps/x").unwrap());
+        assert_eq!(request.method().as_str(), "HEAD");
+        MapExistsRequest::parse(&[]).unwrap();
+
+        let request = DownloadMapRequest::new(hash.to_owned());
+        assert_eq!(request.path(), format!("/a/maps/{hash}"));
+        assert_eq!(
+            DownloadMapRequest::parse(&[1, 2, 3]).unwrap(),
+            vec![1, 2, 3]
+        );
+
+        let request = UploadMapRequest::new(hash.to_owned(), vec![4, 5]);
+        assert_eq!(request.path(), format!("/a/maps/{hash}"));
+        let request = request.create(Url::parse("http://example.com/a/maps/x").unwrap());
+        assert_eq!(request.method().as_str(), "PUT");
+        assert_eq!(
+            request.headers().get("Content-Type").unwrap(),
+            "application/x-tar"
+        );
+        assert_eq!(request.body().unwrap().as_bytes().unwrap(), &[4, 5]);
+    }
+
     #[test]
     fn test_create() {
         let config = GameConfig::new(
@@ -240,6 +686,7 @@ mod tests {
                 "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef".to_owned(),
                 "custom".to_owned(),
             ),
+            1,
         );
         let request =
             CreateGameRequest::new(GameSetup::new("127.0.0.1:8082".parse().unwrap(), config));
@@ -254,12 +701,28 @@ mod tests {
             r#"{"server":"127.0.0.1:8082","config":{"name":"Druhá Hra","maxPlayers":2,"#,
             r#""map":{"hash":"#,
             r#""0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef","#,
-            r#""name":"custom"}}}"#
+            r#""name":"custom"},"protocolVersion":1}}"#
         );
 
         assert_eq!(body, expected_body);
     }
 
+    #[test]
+    fn test_list() {
+        let request = ListGamesRequest::new();
+        assert_eq!(request.path().as_ref(), "/a/games");
+        let request = request.create(Url::parse("http://example.com/a/games").unwrap());
+        assert_eq!(request.method().as_str(), "GET");
+        assert_eq!(request.url().as_str(), "http://example.com/a/games");
+
+        let request = ListGamesRequest::with_started()
+            .create(Url::parse("http://example.com/a/games").unwrap());
+        assert_eq!(
+            request.url().as_str(),
+            "http://example.com/a/games?started=true"
+        );
+    }
+
     #[test]
     fn test_join() {
         let request = JoinGameRequest::new("Cool Game".to_owned(), GamePlayerInfo::new(2));
@@ -268,6 +731,30 @@ mod tests {
         let request = request.create(Url::parse("http://example.com/a/games/123/join").unwrap());
         let body = String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap();
         assert_eq!(body, r#"{"ordinal":2}"#);
+
+        let request = JoinGameRequest::new("Cool Game".to_owned(), GamePlayerInfo::new(2))
+            .with_password("tajne".to_owned())
+            .create(Url::parse("http://example.com/a/games/123/join").unwrap());
+        let body = String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap();
+        assert_eq!(body, r#"{"ordinal":2,"password":"tajne"}"#);
+    }
+
+    #[test]
+    fn test_connection() {
+        let request = GameConnectionRequest::new("Cool Game".to_owned());
+        assert_eq!(request.path().as_ref(), "/a/games/Cool%20Game/connection");
+
+        let request =
+            request.create(Url::parse("http://example.com/a/games/123/connection").unwrap());
+        assert_eq!(request.method().as_str(), "POST");
+        let body = String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap();
+        assert_eq!(body, r#"{}"#);
+
+        let request = GameConnectionRequest::new("Cool Game".to_owned())
+            .with_password("tajne".to_owned())
+            .create(Url::parse("http://example.com/a/games/123/connection").unwrap());
+        let body = String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap();
+        assert_eq!(body, r#"{"password":"tajne"}"#);
     }
 
     #[test]
@@ -275,4 +762,42 @@ mod tests {
         let request = LeaveGameRequest::new("První Hra".to_owned());
         assert_eq!(request.path().as_ref(), "/a/games/Prvn%C3%AD%20Hra/leave");
     }
+
+    #[test]
+    fn test_max_players() {
+        let request = SetMaxPlayersRequest::new("Cool Game".to_owned(), 3);
+        assert_eq!(request.path().as_ref(), "/a/games/Cool%20Game/max-players");
+
+        let request =
+            request.create(Url::parse("http://example.com/a/games/123/max-players").unwrap());
+        assert_eq!(request.method().as_str(), "PUT");
+        let body = String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap();
+        assert_eq!(body, r#"{"maxPlayers":3}"#);
+    }
+
+    #[test]
+    fn test_start_finish() {
+        let request = StartGameRequest::new("První Hra".to_owned());
+        assert_eq!(request.path().as_ref(), "/a/games/Prvn%C3%AD%20Hra/start");
+        let request = FinishGameRequest::new("První Hra".to_owned());
+        assert_eq!(request.path().as_ref(), "/a/games/Prvn%C3%AD%20Hra/finish");
+    }
+
+    #[test]
+    fn test_report() {
+        let request =
+            ReportGameRequest::new("Cool Game".to_owned(), GameReport::new(GameOutcome::Won));
+        assert_eq!(request.path().as_ref(), "/a/games/Cool%20Game/report");
+
+        let request = request.create(Url::parse("http://example.com/a/games/123/report").unwrap());
+        assert_eq!(request.method().as_str(), "PUT");
+        let body = String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap();
+        assert_eq!(body, r#"{"outcome":"won"}"#);
+    }
+
+    #[test]
+    fn test_history() {
+        let request = MatchHistoryRequest::new("Indy".to_owned());
+        assert_eq!(request.path().as_ref(), "/a/users/Indy/history");
+    }
 }
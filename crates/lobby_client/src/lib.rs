@@ -7,6 +7,9 @@
 //! response is received from any endpoint, thus it is sufficient to send
 //! [`RequestEvent<SignInRequest>`] or [`RequestEvent<SignUpRequest>`].
 //!
+//! The token is automatically refreshed before it expires. Send
+//! [`RequestEvent<SignOutRequest>`] to revoke the token and sign out.
+//!
 //! Use [`Authentication`] resource to obtain current authentication state and
 //! detect its changes.
 
@@ -32,10 +35,24 @@ impl PluginGroup for LobbyClientPluginGroup {
             .add(LobbyPlugin)
             .add(EndpointPlugin::<SignUpRequest>::default())
             .add(EndpointPlugin::<SignInRequest>::default())
+            .add(EndpointPlugin::<RefreshTokenRequest>::default())
+            .add(EndpointPlugin::<SignOutRequest>::default())
+            .add(EndpointPlugin::<ChangePasswordRequest>::default())
             .add(EndpointPlugin::<CreateGameRequest>::default())
             .add(EndpointPlugin::<ListGamesRequest>::default())
             .add(EndpointPlugin::<GetGameRequest>::default())
+            .add(EndpointPlugin::<GameConnectionRequest>::default())
             .add(EndpointPlugin::<JoinGameRequest>::default())
             .add(EndpointPlugin::<LeaveGameRequest>::default())
+            .add(EndpointPlugin::<SetMaxPlayersRequest>::default())
+            .add(EndpointPlugin::<StartGameRequest>::default())
+            .add(EndpointPlugin::<FinishGameRequest>::default())
+            .add(EndpointPlugin::<ReportGameRequest>::default())
+            .add(EndpointPlugin::<MatchHistoryRequest>::default())
+            .add(EndpointPlugin::<LeaderboardRequest>::default())
+            .add(EndpointPlugin::<ListMapsRequest>::default())
+            .add(EndpointPlugin::<MapExistsRequest>::default())
+            .add(EndpointPlugin::<DownloadMapRequest>::default())
+            .add(EndpointPlugin::<UploadMapRequest>::default())
     }
 }
@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 
+use anyhow::{Context, Result};
 use reqwest::Request;
 use serde::de::DeserializeOwned;
 use url::Url;
@@ -12,4 +13,9 @@ pub(super) trait LobbyRequestCreator: LobbyRequest {
     fn path(&self) -> Cow<str>;
 
     fn create(&self, url: Url) -> Request;
+
+    /// Parses body of a successful response. JSON is expected by default.
+    fn parse(body: &[u8]) -> Result<Self::Response> {
+        serde_json::from_slice(body).context("Failed to parse server response")
+    }
 }
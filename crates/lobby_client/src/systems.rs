@@ -5,7 +5,8 @@ use de_lobby_model::Token;
 use iyes_progress::prelude::*;
 
 use crate::{
-    client::LobbyClient, Authentication, LobbyRequest, ResponseEvent, SignInRequest, SignUpRequest,
+    client::LobbyClient, Authentication, ChangePasswordRequest, LobbyRequest, RefreshTokenRequest,
+    RequestEvent, ResponseEvent, SignInRequest, SignOutRequest, SignUpRequest,
 };
 
 pub(crate) struct LobbyPlugin;
@@ -20,6 +21,10 @@ impl Plugin for LobbyPlugin {
                     .run_if(in_state(AppState::AppLoading)),
                 set_token::<SignInRequest>,
                 set_token::<SignUpRequest>,
+                set_token::<RefreshTokenRequest>,
+                set_token::<ChangePasswordRequest>,
+                unset_token,
+                refresh_token,
             ),
         );
     }
@@ -50,5 +55,27 @@ where
         return;
     };
     let Ok(token) = event.result() else { return };
-    auth.set_token(token.token().to_owned());
+    auth.set_token(token);
+}
+
+fn unset_token(
+    mut events: EventReader<ResponseEvent<SignOutRequest>>,
+    mut auth: ResMut<Authentication>,
+) {
+    if events.read().any(|event| event.result().is_ok()) {
+        auth.unset_token();
+    }
+}
+
+/// Refreshes the token shortly before it expires.
+fn refresh_token(
+    mut auth: ResMut<Authentication>,
+    mut requests: EventWriter<RequestEvent<RefreshTokenRequest>>,
+) {
+    // Bypass change detection so that systems watching authentication state
+    // are not triggered unless the token itself changes.
+    if auth.bypass_change_detection().schedule_refresh() {
+        info!("Refreshing lobby authentication token.");
+        requests.send(RequestEvent::new("refresh-token", RefreshTokenRequest));
+    }
 }
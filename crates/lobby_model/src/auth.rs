@@ -15,16 +15,29 @@ pub const MAX_USERNAME_LEN: usize = 32;
 #[serde(rename_all = "camelCase")]
 pub struct Token {
     token: String,
+    /// Number of seconds after which the token expires. Older lobby servers
+    /// do not send this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_in: Option<u64>,
 }
 
 impl Token {
-    pub fn new(token: String) -> Self {
-        Self { token }
+    pub fn new(token: String, expires_in: u64) -> Self {
+        Self {
+            token,
+            expires_in: Some(expires_in),
+        }
     }
 
     pub fn token(&self) -> &str {
         self.token.as_str()
     }
+
+    /// Returns number of seconds (since the token was issued) after which the
+    /// token expires or None if the expiration is unknown.
+    pub fn expires_in(&self) -> Option<u64> {
+        self.expires_in
+    }
 }
 
 /// Username & password to be used while signing in.
@@ -72,21 +85,54 @@ impl UserWithPassword {
 
     pub fn validate(&self) -> validation::Result {
         self.user.validate()?;
+        validate_password(self.password.as_str())
+    }
+}
 
-        ensure!(
-            self.password.len() >= MIN_PASSWORD_LEN,
-            "Password must have at least {} characters.",
-            MIN_PASSWORD_LEN
-        );
+/// Current and new password of a user. To be used while changing the
+/// password.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordChange {
+    password: String,
+    new_password: String,
+}
 
-        ensure!(
-            self.password.len() <= MAX_PASSWORD_LEN,
-            "Password must have at most {} bytes.",
-            MAX_PASSWORD_LEN
-        );
+impl PasswordChange {
+    pub fn new(password: String, new_password: String) -> Self {
+        Self {
+            password,
+            new_password,
+        }
+    }
 
-        Ok(())
+    pub fn password(&self) -> &str {
+        self.password.as_str()
     }
+
+    pub fn new_password(&self) -> &str {
+        self.new_password.as_str()
+    }
+
+    pub fn validate(&self) -> validation::Result {
+        validate_password(self.new_password.as_str())
+    }
+}
+
+fn validate_password(password: &str) -> validation::Result {
+    ensure!(
+        password.len() >= MIN_PASSWORD_LEN,
+        "Password must have at least {} characters.",
+        MIN_PASSWORD_LEN
+    );
+
+    ensure!(
+        password.len() <= MAX_PASSWORD_LEN,
+        "Password must have at most {} bytes.",
+        MAX_PASSWORD_LEN
+    );
+
+    Ok(())
 }
 
 /// A complete user info.
@@ -149,4 +195,16 @@ mod tests {
             "Username starting or ending with whitespace is not allowed."
         );
     }
+
+    #[test]
+    fn test_validate_password_change() {
+        let mut change = PasswordChange::new("Old-password".to_owned(), "new".to_owned());
+        assert_eq!(
+            change.validate().err().unwrap().to_string(),
+            "Password must have at least 6 characters."
+        );
+
+        change.new_password = "New-password".to_owned();
+        assert!(change.validate().is_ok());
+    }
 }
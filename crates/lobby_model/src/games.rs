@@ -2,38 +2,154 @@ use std::net::SocketAddr;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{ensure, validation};
+use crate::{ensure, validation, GameSecret, MAX_PASSWORD_LEN};
 
 pub const MAX_GAME_NAME_LEN: usize = 32;
 pub const MAX_MAP_NAME_LEN: usize = 32;
 pub const MAP_HASH_LEN: usize = 64;
 const MAX_PLAYERS: u8 = 4;
 
+/// Complete info about a game.
+///
+/// The game server address and the game secret are not part of the info, see
+/// [`GameConnection`].
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Game {
-    setup: GameSetup,
+    config: GameConfig,
     players: Vec<GamePlayer>,
+    #[serde(default)]
+    status: GameStatus,
+    #[serde(default)]
+    private: bool,
 }
 
 impl Game {
-    /// Creates a new game with the author having ordinal number of 1 and being
-    /// the only player.
-    pub fn from_author(setup: GameSetup, author: String) -> Self {
-        Self::new(setup, vec![GamePlayer::new(author, GamePlayerInfo::new(1))])
+    pub fn new(config: GameConfig, players: Vec<GamePlayer>) -> Self {
+        Self {
+            config,
+            players,
+            status: GameStatus::Open,
+            private: false,
+        }
     }
 
-    pub fn new(setup: GameSetup, players: Vec<GamePlayer>) -> Self {
-        Self { setup, players }
+    /// Sets lifecycle stage of the game. New games are open by default.
+    pub fn with_status(mut self, status: GameStatus) -> Self {
+        self.status = status;
+        self
     }
 
-    pub fn setup(&self) -> &GameSetup {
-        &self.setup
+    /// Marks the game as private, i.e. protected by a password.
+    pub fn with_private(mut self, private: bool) -> Self {
+        self.private = private;
+        self
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
     }
 
     pub fn players(&self) -> &[GamePlayer] {
         self.players.as_slice()
     }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    /// Returns true if a password is required to join the game.
+    pub fn private(&self) -> bool {
+        self.private
+    }
+}
+
+/// Everything needed to connect to the server of a game.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameConnection {
+    server: SocketAddr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret: Option<GameSecret>,
+}
+
+impl GameConnection {
+    pub fn new(server: SocketAddr, secret: Option<GameSecret>) -> Self {
+        Self { server, secret }
+    }
+
+    pub fn server(&self) -> SocketAddr {
+        self.server
+    }
+
+    /// Secret used for authentication with the game server. None if the game
+    /// server does not require authentication.
+    pub fn secret(&self) -> Option<&GameSecret> {
+        self.secret.as_ref()
+    }
+}
+
+/// Password of a private game, to be used while requesting
+/// [`GameConnection`].
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameAccess {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+}
+
+impl GameAccess {
+    pub fn new(password: Option<String>) -> Self {
+        Self { password }
+    }
+
+    pub fn password(&self) -> Option<&str> {
+        self.password.as_deref()
+    }
+}
+
+/// New maximum number of players of an open game, set by the game author.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameMaxPlayers {
+    max_players: u8,
+}
+
+impl GameMaxPlayers {
+    pub fn new(max_players: u8) -> Self {
+        Self { max_players }
+    }
+
+    pub fn max_players(&self) -> u8 {
+        self.max_players
+    }
+}
+
+impl validation::Validatable for GameMaxPlayers {
+    fn validate(&self) -> validation::Result {
+        validate_max_players(self.max_players)
+    }
+}
+
+/// Lifecycle stage of a game. The stages are ordered chronologically.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GameStatus {
+    /// Players might join the game.
+    #[default]
+    Open,
+    /// The game is being played.
+    Started,
+    /// The game is over.
+    Finished,
+}
+
+impl GameStatus {
+    /// Returns true if the game might move from this stage to `next`. The
+    /// game never moves back to an earlier stage.
+    pub fn can_become(self, next: Self) -> bool {
+        self <= next
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -77,6 +193,40 @@ impl GamePlayerInfo {
     }
 }
 
+/// Player info combined with the password of the joined game. To be used
+/// while joining a game.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameJoinInfo {
+    #[serde(flatten)]
+    info: GamePlayerInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+}
+
+impl GameJoinInfo {
+    pub fn new(info: GamePlayerInfo) -> Self {
+        Self {
+            info,
+            password: None,
+        }
+    }
+
+    /// Sets the password of a private game.
+    pub fn with_password(mut self, password: String) -> Self {
+        self.password = Some(password);
+        self
+    }
+
+    pub fn info(&self) -> &GamePlayerInfo {
+        &self.info
+    }
+
+    pub fn password(&self) -> Option<&str> {
+        self.password.as_deref()
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GameListing(Vec<GamePartial>);
@@ -100,13 +250,19 @@ impl GameListing {
 pub struct GamePartial {
     config: GameConfig,
     num_players: u8,
+    #[serde(default)]
+    status: GameStatus,
+    #[serde(default)]
+    private: bool,
 }
 
 impl GamePartial {
-    pub fn new(config: GameConfig, num_players: u8) -> Self {
+    pub fn new(config: GameConfig, num_players: u8, status: GameStatus, private: bool) -> Self {
         Self {
             config,
             num_players,
+            status,
+            private,
         }
     }
 
@@ -117,24 +273,69 @@ impl GamePartial {
     pub fn num_players(&self) -> u8 {
         self.num_players
     }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    /// Returns true if a password is required to join the game.
+    pub fn private(&self) -> bool {
+        self.private
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GameSetup {
     server: SocketAddr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nonce: Option<u64>,
+    /// Password required to join the game. The password is never sent back
+    /// by the lobby.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
     config: GameConfig,
 }
 
 impl GameSetup {
     pub fn new(server: SocketAddr, config: GameConfig) -> Self {
-        Self { server, config }
+        Self {
+            server,
+            nonce: None,
+            password: None,
+            config,
+        }
+    }
+
+    /// Sets the nonce the game secret is derived from (see
+    /// [`GameSecret::derive`]).
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Makes the game private, i.e. only players who know the password can
+    /// join it.
+    pub fn with_password(mut self, password: String) -> Self {
+        self.password = Some(password);
+        self
     }
 
     pub fn server(&self) -> SocketAddr {
         self.server
     }
 
+    /// Random number generated by the game server. None if the game server
+    /// does not require authentication.
+    pub fn nonce(&self) -> Option<u64> {
+        self.nonce
+    }
+
+    /// Password required to join the game. None if the game is public.
+    pub fn password(&self) -> Option<&str> {
+        self.password.as_deref()
+    }
+
     pub fn config(&self) -> &GameConfig {
         &self.config
     }
@@ -142,6 +343,14 @@ impl GameSetup {
 
 impl validation::Validatable for GameSetup {
     fn validate(&self) -> validation::Result {
+        if let Some(password) = self.password.as_ref() {
+            ensure!(!password.is_empty(), "Game password cannot be empty.");
+            ensure!(
+                password.len() <= MAX_PASSWORD_LEN,
+                "Game password must have at most {} bytes.",
+                MAX_PASSWORD_LEN
+            );
+        }
         self.config.validate()
     }
 }
@@ -152,14 +361,27 @@ pub struct GameConfig {
     name: String,
     max_players: u8,
     map: GameMap,
+    protocol_version: u32,
 }
 
 impl GameConfig {
-    pub fn new(name: String, max_players: u8, map: GameMap) -> Self {
+    /// # Arguments
+    ///
+    /// * `name` - unique name of the game.
+    ///
+    /// * `max_players` - maximum number of players.
+    ///
+    /// * `map` - the map the game is played on.
+    ///
+    /// * `protocol_version` - version of the protocol used by the game
+    ///   server and the clients. Clients with a different version cannot
+    ///   join the game.
+    pub fn new(name: String, max_players: u8, map: GameMap, protocol_version: u32) -> Self {
         Self {
             name,
             max_players,
             map,
+            protocol_version,
         }
     }
 
@@ -174,6 +396,10 @@ impl GameConfig {
     pub fn map(&self) -> &GameMap {
         &self.map
     }
+
+    pub fn protocol_version(&self) -> u32 {
+        self.protocol_version
+    }
 }
 
 impl validation::Validatable for GameConfig {
@@ -190,19 +416,28 @@ impl validation::Validatable for GameConfig {
             MAX_GAME_NAME_LEN
         );
 
+        validate_max_players(self.max_players)?;
         ensure!(
-            self.max_players >= 2,
-            "Maximum number of players must be at least 2."
-        );
-        ensure!(
-            self.max_players <= MAX_PLAYERS,
-            "Maximum number of players must be at most {}.",
-            MAX_PLAYERS
+            self.protocol_version > 0,
+            "Protocol version must be a positive number."
         );
         self.map.validate()
     }
 }
 
+fn validate_max_players(max_players: u8) -> validation::Result {
+    ensure!(
+        max_players >= 2,
+        "Maximum number of players must be at least 2."
+    );
+    ensure!(
+        max_players <= MAX_PLAYERS,
+        "Maximum number of players must be at most {}.",
+        MAX_PLAYERS
+    );
+    Ok(())
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct GameMap {
@@ -1,13 +1,20 @@
 pub use auth::{
-    Token, User, UserWithPassword, UsernameAndPassword, MAX_PASSWORD_LEN, MAX_USERNAME_LEN,
-    MIN_PASSWORD_LEN,
+    PasswordChange, Token, User, UserWithPassword, UsernameAndPassword, MAX_PASSWORD_LEN,
+    MAX_USERNAME_LEN, MIN_PASSWORD_LEN,
 };
 pub use games::{
-    Game, GameConfig, GameListing, GameMap, GamePartial, GamePlayer, GamePlayerInfo, GameSetup,
-    MAP_HASH_LEN, MAX_GAME_NAME_LEN, MAX_MAP_NAME_LEN,
+    Game, GameAccess, GameConfig, GameConnection, GameJoinInfo, GameListing, GameMap,
+    GameMaxPlayers, GamePartial, GamePlayer, GamePlayerInfo, GameSetup, GameStatus, MAP_HASH_LEN,
+    MAX_GAME_NAME_LEN, MAX_MAP_NAME_LEN,
 };
+pub use maps::{MapInfo, MapListing, MAX_MAP_SIZE};
+pub use results::{GameOutcome, GameReport, Leaderboard, MatchHistory, MatchRecord, PlayerRating};
+pub use secret::GameSecret;
 pub use validation::Validatable;
 
 mod auth;
 mod games;
+mod maps;
+mod results;
+mod secret;
 mod validation;
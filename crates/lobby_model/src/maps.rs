@@ -0,0 +1,66 @@
+//! Map repository related API objects.
+
+use serde::{Deserialize, Serialize};
+
+use crate::GameMap;
+
+/// Maximum size (in bytes) of a map file accepted by the map repository.
+pub const MAX_MAP_SIZE: usize = 4 * 1024 * 1024;
+
+/// A map available in the map repository.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapInfo {
+    #[serde(flatten)]
+    map: GameMap,
+    max_players: u8,
+    /// Username of the user who uploaded the map.
+    author: String,
+    /// Unix timestamp (in seconds) of the map upload.
+    uploaded: i64,
+}
+
+impl MapInfo {
+    pub fn new(map: GameMap, max_players: u8, author: String, uploaded: i64) -> Self {
+        Self {
+            map,
+            max_players,
+            author,
+            uploaded,
+        }
+    }
+
+    pub fn map(&self) -> &GameMap {
+        &self.map
+    }
+
+    pub fn max_players(&self) -> u8 {
+        self.max_players
+    }
+
+    pub fn author(&self) -> &str {
+        self.author.as_str()
+    }
+
+    pub fn uploaded(&self) -> i64 {
+        self.uploaded
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapListing(Vec<MapInfo>);
+
+impl MapListing {
+    pub fn empty() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn maps(&self) -> &[MapInfo] {
+        self.0.as_slice()
+    }
+
+    pub fn push(&mut self, map: MapInfo) {
+        self.0.push(map)
+    }
+}
@@ -0,0 +1,164 @@
+//! Match results and player rating related API objects.
+
+use serde::{Deserialize, Serialize};
+
+/// Outcome of a finished game from the point of view of a single player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GameOutcome {
+    Won,
+    Lost,
+}
+
+/// Outcome of a finished game as reported by one of its players.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameReport {
+    outcome: GameOutcome,
+}
+
+impl GameReport {
+    pub fn new(outcome: GameOutcome) -> Self {
+        Self { outcome }
+    }
+
+    pub fn outcome(&self) -> GameOutcome {
+        self.outcome
+    }
+}
+
+/// A single match from a match history of a player.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchRecord {
+    game: String,
+    map_name: String,
+    /// Unix timestamp (in seconds) of the time the match was recorded.
+    finished: i64,
+    outcome: GameOutcome,
+    /// True if the reports of the players did not agree with each other. The
+    /// ratings are not changed by disputed matches.
+    disputed: bool,
+    rating_before: f64,
+    rating_after: f64,
+}
+
+impl MatchRecord {
+    pub fn new(
+        game: String,
+        map_name: String,
+        finished: i64,
+        outcome: GameOutcome,
+        disputed: bool,
+        rating_before: f64,
+        rating_after: f64,
+    ) -> Self {
+        Self {
+            game,
+            map_name,
+            finished,
+            outcome,
+            disputed,
+            rating_before,
+            rating_after,
+        }
+    }
+
+    pub fn game(&self) -> &str {
+        self.game.as_str()
+    }
+
+    pub fn map_name(&self) -> &str {
+        self.map_name.as_str()
+    }
+
+    pub fn finished(&self) -> i64 {
+        self.finished
+    }
+
+    pub fn outcome(&self) -> GameOutcome {
+        self.outcome
+    }
+
+    pub fn disputed(&self) -> bool {
+        self.disputed
+    }
+
+    pub fn rating_before(&self) -> f64 {
+        self.rating_before
+    }
+
+    pub fn rating_after(&self) -> f64 {
+        self.rating_after
+    }
+}
+
+/// Matches of a single player, the most recent match first.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchHistory(Vec<MatchRecord>);
+
+impl MatchHistory {
+    pub fn empty() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn matches(&self) -> &[MatchRecord] {
+        self.0.as_slice()
+    }
+
+    pub fn push(&mut self, record: MatchRecord) {
+        self.0.push(record)
+    }
+}
+
+/// Current rating of a player.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerRating {
+    username: String,
+    rating: f64,
+    /// Number of undisputed matches played.
+    matches: u32,
+}
+
+impl PlayerRating {
+    pub fn new(username: String, rating: f64, matches: u32) -> Self {
+        Self {
+            username,
+            rating,
+            matches,
+        }
+    }
+
+    pub fn username(&self) -> &str {
+        self.username.as_str()
+    }
+
+    pub fn rating(&self) -> f64 {
+        self.rating
+    }
+
+    pub fn matches(&self) -> u32 {
+        self.matches
+    }
+}
+
+/// Best rated players, the highest rating first.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Leaderboard(Vec<PlayerRating>);
+
+impl Leaderboard {
+    pub fn empty() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn players(&self) -> &[PlayerRating] {
+        self.0.as_slice()
+    }
+
+    pub fn push(&mut self, rating: PlayerRating) {
+        self.0.push(rating)
+    }
+}
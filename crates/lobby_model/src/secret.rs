@@ -0,0 +1,111 @@
+use std::{fmt, str::FromStr};
+
+use hmac::{Hmac, Mac};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::Sha256;
+
+const SECRET_LEN: usize = 32;
+
+/// A secret used for authentication of all network traffic between DE
+/// Connector and the players of a game.
+///
+/// The secret is issued by the lobby for each secured game and it is derived
+/// from a key shared by the lobby and DE Connector, therefore the game server
+/// obtains the very same secret without any further communication.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct GameSecret([u8; SECRET_LEN]);
+
+impl GameSecret {
+    /// Derives the secret of a game.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - a key shared by the lobby and DE Connector.
+    ///
+    /// * `port` - port of the game server.
+    ///
+    /// * `nonce` - random number generated for the game by DE Connector.
+    pub fn derive(key: &[u8], port: u16, nonce: u64) -> Self {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+        mac.update(b"de-game");
+        mac.update(&port.to_be_bytes());
+        mac.update(&nonce.to_be_bytes());
+        Self(mac.finalize().into_bytes().into())
+    }
+
+    pub fn bytes(&self) -> &[u8; SECRET_LEN] {
+        &self.0
+    }
+}
+
+impl fmt::Display for GameSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for GameSecret {
+    type Err = String;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        if hex.len() != 2 * SECRET_LEN {
+            return Err(format!(
+                "Game secret must have {} hexadecimal characters, got {} UTF-8 bytes.",
+                2 * SECRET_LEN,
+                hex.len()
+            ));
+        }
+
+        let mut secret = [0; SECRET_LEN];
+        for (i, byte) in secret.iter_mut().enumerate() {
+            *byte = hex
+                .get(2 * i..2 * i + 2)
+                .filter(|digits| digits.bytes().all(|d| d.is_ascii_hexdigit()))
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or("Game secret must consist solely of hexadecimal characters.")?;
+        }
+        Ok(Self(secret))
+    }
+}
+
+impl Serialize for GameSecret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for GameSecret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        hex.parse().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive() {
+        let secret = GameSecret::derive(b"key", 1234, 42);
+        assert!(secret == GameSecret::derive(b"key", 1234, 42));
+        assert!(secret != GameSecret::derive(b"key", 1235, 42));
+        assert!(secret != GameSecret::derive(b"key", 1234, 43));
+        assert!(secret != GameSecret::derive(b"other-key", 1234, 42));
+    }
+
+    #[test]
+    fn test_parse() {
+        let hex = "00ff0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e";
+        let secret: GameSecret = hex.parse().unwrap();
+        assert_eq!(secret.bytes()[..3], [0, 255, 1]);
+        assert_eq!(secret.bytes()[31], 30);
+        assert_eq!(secret.to_string(), hex);
+
+        assert!("00ff".parse::<GameSecret>().is_err());
+        assert!(hex.replace("ff", "+f").parse::<GameSecret>().is_err());
+    }
+}
@@ -56,6 +56,18 @@ impl MapHash {
     }
 }
 
+impl From<[u8; 32]> for MapHash {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl From<&MapHash> for [u8; 32] {
+    fn from(hash: &MapHash) -> Self {
+        hash.0
+    }
+}
+
 impl Debug for MapHash {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "0x{}", self.to_hex())
@@ -2,7 +2,7 @@ use std::io;
 
 use async_std::{
     fs::{File, OpenOptions},
-    io::{ReadExt, Write},
+    io::{Read, ReadExt, Write},
     path::Path,
     stream::StreamExt,
 };
@@ -37,6 +37,10 @@ macro_rules! storing_io_error {
 pub const MAP_FILE_SUFFIX: &str = ".dem.tar";
 const METADATA_JSON_ENTRY: &str = "metadata.json";
 const CONTENT_JSON_ENTRY: &str = "content.json";
+/// Maximum size of a single map archive entry. Map archives might come from
+/// untrusted sources, thus entry sizes declared by the archive are not
+/// trusted.
+const MAX_ENTRY_SIZE: u64 = 4 * 1024 * 1024;
 
 type LoadingResult<T> = Result<T, MapLoadingError>;
 type StoringResult = Result<(), MapStoringError>;
@@ -69,7 +73,13 @@ pub async fn load_metadata<P: AsRef<Path>>(path: P) -> LoadingResult<MapMetadata
 /// Load a map TAR file.
 pub async fn load_map<P: AsRef<Path>>(path: P) -> LoadingResult<Map> {
     let mut file = loading_io_error!(File::open(&path).await);
-    let archive = Archive::new(&mut file);
+    read_map(&mut file).await
+}
+
+/// Load a map from a reader of map TAR file content (e.g. a map file received
+/// over network).
+pub async fn read_map<R: Read + Unpin>(reader: R) -> LoadingResult<Map> {
+    let archive = Archive::new(reader);
     let mut entries = loading_io_error!(archive.entries());
 
     let mut map_meta = None;
@@ -102,12 +112,20 @@ pub async fn load_map<P: AsRef<Path>>(path: P) -> LoadingResult<Map> {
     Ok(map)
 }
 
-async fn deserialize_entry<T: DeserializeOwned>(
-    entry: &mut Entry<Archive<&mut File>>,
-) -> LoadingResult<T> {
+async fn deserialize_entry<R, T>(entry: &mut Entry<Archive<R>>) -> LoadingResult<T>
+where
+    R: Read + Unpin,
+    T: DeserializeOwned,
+{
     let entry_size = loading_io_error!(entry.header().entry_size());
-    let mut buf: Vec<u8> = Vec::with_capacity(entry_size.try_into().unwrap());
-    loading_io_error!(entry.read_to_end(&mut buf).await);
+    if entry_size > MAX_ENTRY_SIZE {
+        return Err(MapLoadingError::ArchiveContent(format!(
+            "The map archive contains an entry larger than {MAX_ENTRY_SIZE} bytes."
+        )));
+    }
+
+    let mut buf: Vec<u8> = Vec::new();
+    loading_io_error!(entry.take(MAX_ENTRY_SIZE).read_to_end(&mut buf).await);
     match serde_json::from_slice(buf.as_slice()) {
         Ok(map_inner) => Ok(map_inner),
         Err(error) => Err(MapLoadingError::JsonParsing { source: error }),
@@ -243,6 +261,26 @@ mod test {
             loaded_map.metadata().bounds().aabb(),
             Aabb::new(Point::new(-500., -1000.), Point::new(500., 1000.))
         );
+
+        let bytes = std::fs::read(tmp_dir_path.as_path()).unwrap();
+        let read_map = task::block_on(read_map(bytes.as_slice())).unwrap();
+        assert_eq!(read_map.compute_hash(), map.compute_hash());
+    }
+
+    #[test]
+    fn test_oversized_entry() {
+        let mut header = Header::new_gnu();
+        header.set_path(CONTENT_JSON_ENTRY).unwrap();
+        header.set_entry_type(EntryType::Regular);
+        header.set_size(1 << 40);
+        header.set_cksum();
+
+        let mut bytes = header.as_bytes().to_vec();
+        bytes.extend([0; 1024]);
+        assert!(matches!(
+            task::block_on(read_map(bytes.as_slice())),
+            Err(MapLoadingError::ArchiveContent(_))
+        ));
     }
 
     #[test]
@@ -1,8 +1,12 @@
 use std::f32::consts::TAU;
 
+#[cfg(feature = "bevy")]
 use bevy::prelude::Transform;
+#[cfg(feature = "bevy")]
 use de_types::projection::ToAltitude;
-use glam::{Quat, Vec2};
+#[cfg(feature = "bevy")]
+use glam::Quat;
+use glam::Vec2;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -30,6 +34,7 @@ impl Placement {
 
     /// Produces world to object transform which can be used to position the
     /// object on the map.
+    #[cfg(feature = "bevy")]
     pub fn to_transform(self) -> Transform {
         let rotation = Quat::from_rotation_y(self.heading);
         Transform {
@@ -1,3 +1,4 @@
+#[cfg(feature = "bevy")]
 use bevy::prelude::Resource;
 use glam::Vec2;
 use parry2d::bounding_volume::Aabb;
@@ -7,7 +8,8 @@ use thiserror::Error;
 /// Maximum size of a side of the map in meters.
 pub const MAX_MAP_SIZE: f32 = 8000.;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Resource)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(Resource))]
 pub struct MapBounds(Vec2);
 
 impl MapBounds {
@@ -5,6 +5,7 @@ use mainmenu::MainMenuPlugin;
 use mapselection::MapSelectionPlugin;
 use menu::{MenuPlugin, ScreenStatePlugin};
 use multiplayer::MultiplayerPlugin;
+use replay::ReplayPlugin;
 use singleplayer::SinglePlayerPlugin;
 
 mod aftergame;
@@ -12,6 +13,7 @@ mod mainmenu;
 mod mapselection;
 mod menu;
 mod multiplayer;
+mod replay;
 mod singleplayer;
 
 pub struct MenuPluginGroup;
@@ -26,6 +28,7 @@ impl PluginGroup for MenuPluginGroup {
             .add(MapSelectionPlugin)
             .add(SinglePlayerPlugin)
             .add(MultiplayerPlugin)
+            .add(ReplayPlugin)
             .add(AfterGamePlugin)
     }
 }
@@ -38,6 +41,7 @@ nested_state!(
         MainMenu,
         SinglePlayerGame,
         Multiplayer,
+        Replay,
         AfterGame,
     }
 );
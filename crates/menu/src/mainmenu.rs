@@ -47,6 +47,12 @@ fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
         ButtonAction::SwithState(MenuState::Multiplayer),
         "Multiplayer",
     );
+    button(
+        &mut commands,
+        column_node,
+        ButtonAction::SwithState(MenuState::Replay),
+        "Replays",
+    );
     button(&mut commands, column_node, ButtonAction::Quit, "Quit Game");
 }
 
@@ -5,9 +5,10 @@ use bevy::{
     prelude::*,
     tasks::{futures_lite::future, IoTaskPool, Task},
 };
-use de_core::{assets::asset_path, log_full_error, state::AppState};
+use de_core::{assets::asset_path, fs::maps_dir, log_full_error, state::AppState};
 use de_gui::{ButtonCommands, GuiCommands, OuterStyle};
 use de_map::{
+    hash::MapHash,
     io::{load_metadata, MapLoadingError, MAP_FILE_SUFFIX},
     meta::MapMetadata,
 };
@@ -188,10 +189,46 @@ fn button_system(
     }
 }
 
-async fn load_available_maps() -> Result<Vec<MapEntry>, LoadingError> {
-    let maps_dir = asset_path("maps");
+/// Returns path to the file of a map with a given hash or None if the map is
+/// not available locally.
+///
+/// Maps distributed with the game and maps installed by the user (e.g.
+/// downloaded from DE Lobby) are searched.
+pub(crate) fn find_map(hash: &MapHash) -> Option<PathBuf> {
+    map_dirs()
+        .into_iter()
+        .map(|dir| -> PathBuf { hash.construct_path(dir).into() })
+        .find(|path| path.is_file())
+}
+
+/// Returns all directories with map files.
+fn map_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![asset_path("maps")];
+    match maps_dir() {
+        Ok(dir) => dirs.push(dir.into()),
+        Err(error) => warn!("User maps directory is not available: {error}"),
+    }
+    dirs
+}
 
+async fn load_available_maps() -> Result<Vec<MapEntry>, LoadingError> {
     let mut map_entries = Vec::new();
+    for dir in map_dirs() {
+        load_maps_from_dir(dir.as_path(), &mut map_entries).await?;
+    }
+    map_entries.sort_by(|a, b| a.metadata().name().cmp(b.metadata().name()));
+    Ok(map_entries)
+}
+
+async fn load_maps_from_dir(
+    maps_dir: &Path,
+    map_entries: &mut Vec<MapEntry>,
+) -> Result<(), LoadingError> {
+    // The user maps directory does not exist until a map is installed.
+    if !maps_dir.is_dir() {
+        return Ok(());
+    }
+
     let mut dir_entries = match fs::read_dir(maps_dir).await {
         Ok(entries) => entries,
         Err(err) => return Err(LoadingError::Io { source: err }),
@@ -221,8 +258,7 @@ async fn load_available_maps() -> Result<Vec<MapEntry>, LoadingError> {
         map_entries.push(MapEntry::new(path.into(), metadata));
     }
 
-    map_entries.sort_by(|a, b| a.metadata().name().cmp(b.metadata().name()));
-    Ok(map_entries)
+    Ok(())
 }
 
 fn map_button(commands: &mut GuiCommands, map: MapEntry) -> Entity {
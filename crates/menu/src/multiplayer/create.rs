@@ -5,6 +5,8 @@ use de_gui::{
 };
 use de_lobby_model::{GameConfig, GameMap, Validatable};
 use de_map::hash::MapHash;
+use de_messages::PROTOCOL_VERSION;
+use de_types::player::Player;
 
 use super::{setup::SetupGameEvent, MultiplayerState};
 use crate::{
@@ -50,11 +52,16 @@ enum ButtonAction {
 struct Inputs {
     name: Entity,
     max_players: Entity,
+    password: Entity,
     map: Entity,
 }
 
 #[derive(Resource)]
-struct SelectedMap(GameMap);
+struct SelectedMap {
+    map: GameMap,
+    /// Maximum number of players supported by the map.
+    max_players: Player,
+}
 
 #[derive(Event)]
 struct CreateGameEvent;
@@ -64,10 +71,13 @@ fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
 
     let name_row_id = row(&mut commands, column_id);
 
-    let name_id = text_input(&mut commands, name_row_id, "Name");
+    let name_id = text_input(&mut commands, name_row_id, "Name", false);
 
     let max_players_row_id = row(&mut commands, column_id);
-    let max_players_id = text_input(&mut commands, max_players_row_id, "Max Players");
+    let max_players_id = text_input(&mut commands, max_players_row_id, "Max Players", false);
+
+    let password_row_id = row(&mut commands, column_id);
+    let password_id = text_input(&mut commands, password_row_id, "Password", true);
 
     let map_row_id = row(&mut commands, column_id);
     let map_id = map_button(&mut commands, map_row_id);
@@ -75,6 +85,7 @@ fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
     commands.insert_resource(Inputs {
         name: name_id,
         max_players: max_players_id,
+        password: password_id,
         map: map_id,
     });
 
@@ -136,7 +147,12 @@ fn row(commands: &mut GuiCommands, parent_id: Entity) -> Entity {
     row_id
 }
 
-fn text_input(commands: &mut GuiCommands, parent_id: Entity, caption: &str) -> Entity {
+fn text_input(
+    commands: &mut GuiCommands,
+    parent_id: Entity,
+    caption: &str,
+    secret: bool,
+) -> Entity {
     spawn_caption(commands, parent_id, caption);
 
     let input_id = commands
@@ -146,7 +162,7 @@ fn text_input(commands: &mut GuiCommands, parent_id: Entity, caption: &str) -> E
                 height: Val::Percent(100.),
                 ..default()
             },
-            false,
+            secret,
         )
         .id();
     commands.entity(parent_id).add_child(input_id);
@@ -230,10 +246,10 @@ fn map_selected_system(
     buttons
         .set_text(intpus.map, event.metadata().name().to_owned())
         .unwrap();
-    commands.insert_resource(SelectedMap(GameMap::new(
-        hash.to_hex(),
-        event.metadata().name().to_owned(),
-    )));
+    commands.insert_resource(SelectedMap {
+        map: GameMap::new(hash.to_hex(), event.metadata().name().to_owned()),
+        max_players: event.metadata().max_player(),
+    });
 }
 
 fn create_game_system(
@@ -257,10 +273,29 @@ fn create_game_system(
         }
     };
 
-    let game_config = GameConfig::new(name, max_players, selected_map.0.clone());
+    let game_config = GameConfig::new(
+        name,
+        max_players,
+        selected_map.map.clone(),
+        PROTOCOL_VERSION,
+    );
     if let Err(error) = game_config.validate() {
         toasts.send(ToastEvent::new(format!("{error}")));
         return;
     }
-    setup_events.send(SetupGameEvent::new(game_config));
+    if max_players > selected_map.max_players.to_num() {
+        toasts.send(ToastEvent::new(format!(
+            "The map supports at most {} players.",
+            selected_map.max_players
+        )));
+        return;
+    }
+
+    let mut event = SetupGameEvent::new(game_config, selected_map.max_players);
+    // Games without a password are public.
+    let password = texts.text(inputs.password).unwrap();
+    if !password.is_empty() {
+        event = event.with_password(password.to_string());
+    }
+    setup_events.send(event);
 }
@@ -1,4 +1,11 @@
+use std::net::SocketAddr;
+
 use bevy::prelude::*;
+use de_connector_lib::EmbeddedConnector;
+use de_core::gresult::GameResult;
+use de_lobby_client::{ReportGameRequest, RequestEvent, ResponseEvent};
+use de_lobby_model::{GameOutcome, GameReport};
+use de_messages::RejoinToken;
 
 use crate::MenuState;
 
@@ -6,7 +13,14 @@ pub(super) struct CurrentGamePlugin;
 
 impl Plugin for CurrentGamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnExit(MenuState::Multiplayer), cleanup);
+        app.add_systems(OnExit(MenuState::Multiplayer), cleanup)
+            .add_systems(OnEnter(MenuState::AfterGame), report)
+            // The played game ended without a result, e.g. it was left.
+            .add_systems(OnEnter(MenuState::MainMenu), forget_played_game)
+            .add_systems(
+                Update,
+                handle_report_response.run_if(on_event::<ResponseEvent<ReportGameRequest>>()),
+            );
     }
 }
 
@@ -23,6 +37,132 @@ impl GameNameRes {
     }
 }
 
+/// Last joined game together with the token needed to rejoin it after a
+/// connection loss. Unlike [`GameNameRes`], this resource outlives the game.
+#[derive(Resource)]
+pub(super) struct RejoinRes {
+    game: String,
+    token: RejoinToken,
+}
+
+impl RejoinRes {
+    pub(super) fn new<S: ToString>(game: S, token: RejoinToken) -> Self {
+        Self {
+            game: game.to_string(),
+            token,
+        }
+    }
+
+    pub(super) fn game(&self) -> &str {
+        self.game.as_str()
+    }
+
+    pub(super) fn token(&self) -> RejoinToken {
+        self.token
+    }
+}
+
+/// Started game registered at DE Lobby and played by the local player. Unlike
+/// [`GameNameRes`], this resource outlives the game so that its outcome might
+/// be reported to DE Lobby afterwards.
+#[derive(Resource)]
+pub(super) struct PlayedGameRes(String);
+
+impl PlayedGameRes {
+    pub(super) fn new<S: ToString>(name: S) -> Self {
+        Self(name.to_string())
+    }
+}
+
+/// This resource exists while the multiplayer menu works with games
+/// discovered on the local network instead of games registered at DE Lobby.
+#[derive(Resource)]
+pub(super) struct LanModeRes;
+
+/// DE Connector hosted by the game client. Games created while this resource
+/// exists are opened on the hosted connector instead of the configured one.
+/// The connector stops accepting new games once the resource is removed.
+#[derive(Resource)]
+pub(super) struct HostedConnectorRes(EmbeddedConnector);
+
+impl HostedConnectorRes {
+    pub(super) fn new(connector: EmbeddedConnector) -> Self {
+        Self(connector)
+    }
+
+    /// Port of the main server of the hosted connector.
+    pub(super) fn port(&self) -> u16 {
+        self.0.port()
+    }
+}
+
+/// Game server and map of the current LAN game. In LAN mode, this replaces
+/// the game details otherwise obtained from DE Lobby.
+#[derive(Resource)]
+pub(super) struct LanGameRes {
+    server: SocketAddr,
+    map_hash: [u8; 32],
+}
+
+impl LanGameRes {
+    pub(super) fn new(server: SocketAddr, map_hash: [u8; 32]) -> Self {
+        Self { server, map_hash }
+    }
+
+    /// Address of the game server.
+    pub(super) fn server(&self) -> SocketAddr {
+        self.server
+    }
+
+    pub(super) fn map_hash(&self) -> [u8; 32] {
+        self.map_hash
+    }
+}
+
+fn report(
+    mut commands: Commands,
+    game: Option<Res<PlayedGameRes>>,
+    result: Option<Res<GameResult>>,
+    mut requests: EventWriter<RequestEvent<ReportGameRequest>>,
+) {
+    let Some(game) = game else {
+        return;
+    };
+    commands.remove_resource::<PlayedGameRes>();
+
+    // Games which ended with an error are not reported. The lobby records
+    // them without the local player once the reporting deadline passes.
+    let Some(GameResult::Finished(result)) = result.as_deref() else {
+        return;
+    };
+    let outcome = if result.won() {
+        GameOutcome::Won
+    } else {
+        GameOutcome::Lost
+    };
+
+    info!("Reporting outcome {outcome:?} of game {}...", game.0);
+    requests.send(RequestEvent::new(
+        "report-game",
+        ReportGameRequest::new(game.0.clone(), GameReport::new(outcome)),
+    ));
+}
+
+fn forget_played_game(mut commands: Commands) {
+    commands.remove_resource::<PlayedGameRes>();
+}
+
+fn handle_report_response(mut responses: EventReader<ResponseEvent<ReportGameRequest>>) {
+    for response in responses.read() {
+        if let Err(error) = response.result() {
+            warn!("Game outcome reporting failed: {error:?}");
+        }
+    }
+}
+
 fn cleanup(mut commands: Commands) {
     commands.remove_resource::<GameNameRes>();
+    commands.remove_resource::<LanModeRes>();
+    commands.remove_resource::<LanGameRes>();
+    commands.remove_resource::<HostedConnectorRes>();
 }
@@ -1,11 +1,17 @@
-use std::time::Duration;
+use std::{net::SocketAddr, time::Duration};
 
 use bevy::{prelude::*, time::Stopwatch};
 use de_gui::{ButtonCommands, GuiCommands, LabelCommands, OuterStyle, ToastEvent};
 use de_lobby_client::{ListGamesRequest, RequestEvent, ResponseEvent};
-use de_lobby_model::GamePartial;
+use de_lobby_model::{GamePartial, GameStatus};
+use de_messages::{LanGame, PROTOCOL_VERSION};
+use de_multiplayer::{LanGames, LanServer, StartLanDiscoveryEvent, StopLanDiscoveryEvent};
 
-use super::{current::GameNameRes, MultiplayerState};
+use super::{
+    current::{GameNameRes, LanGameRes, LanModeRes, RejoinRes},
+    joining::JoinModeRes,
+    MultiplayerState,
+};
 use crate::menu::Menu;
 
 const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
@@ -18,7 +24,11 @@ impl Plugin for GameListingPlugin {
             .add_systems(OnExit(MultiplayerState::GameListing), cleanup)
             .add_systems(
                 Update,
-                (refresh_system, list_games_system, button_system)
+                (
+                    (refresh_system, list_games_system).run_if(not(resource_exists::<LanModeRes>)),
+                    list_lan_games_system.run_if(resource_exists_and_changed::<LanGames>),
+                    button_system,
+                )
                     .run_if(in_state(MultiplayerState::GameListing)),
             );
     }
@@ -31,12 +41,18 @@ struct GamesTable(Entity);
 enum ButtonAction {
     Create,
     Join(String),
+    Spectate(String),
+    Rejoin(String),
+    JoinLan(SocketAddr, [u8; 32]),
+    SpectateLan(SocketAddr, [u8; 32]),
 }
 
 fn setup(
     mut commands: GuiCommands,
     menu: Res<Menu>,
+    lan_mode: Option<Res<LanModeRes>>,
     mut requests: EventWriter<RequestEvent<ListGamesRequest>>,
+    mut discovery: EventWriter<StartLanDiscoveryEvent>,
 ) {
     let column_id = commands
         .spawn(NodeBundle {
@@ -57,11 +73,26 @@ fn setup(
     create_game_button(&mut commands, column_id);
     let table_id = table(&mut commands, column_id);
     commands.insert_resource(GamesTable(table_id));
-    requests.send(RequestEvent::new("list-games", ListGamesRequest));
+
+    if lan_mode.is_some() {
+        discovery.send(StartLanDiscoveryEvent);
+    } else {
+        requests.send(RequestEvent::new(
+            "list-games",
+            ListGamesRequest::with_started(),
+        ));
+    }
 }
 
-fn cleanup(mut commands: Commands) {
+fn cleanup(
+    mut commands: Commands,
+    lan_mode: Option<Res<LanModeRes>>,
+    mut discovery: EventWriter<StopLanDiscoveryEvent>,
+) {
     commands.remove_resource::<GamesTable>();
+    if lan_mode.is_some() {
+        discovery.send(StopLanDiscoveryEvent);
+    }
 }
 
 fn create_game_button(commands: &mut GuiCommands, parent_node: Entity) {
@@ -98,7 +129,7 @@ fn table(commands: &mut GuiCommands, parent_node: Entity) -> Entity {
     table_id
 }
 
-fn row(commands: &mut GuiCommands, game: &GamePartial) -> Entity {
+fn row(commands: &mut GuiCommands, game: &GamePartial, rejoin: Option<&RejoinRes>) -> Entity {
     let row_id = commands
         .spawn(NodeBundle {
             style: Style {
@@ -117,21 +148,57 @@ fn row(commands: &mut GuiCommands, game: &GamePartial) -> Entity {
     let name_id = commands
         .spawn_label(
             OuterStyle {
-                width: Val::Percent(80.),
+                width: Val::Percent(60.),
                 height: Val::Percent(100.),
                 margin: UiRect::right(Val::Percent(2.)),
             },
             format!(
-                "{} ({}/{})",
+                "{} ({}/{}){}{}",
                 game.config().name(),
                 game.num_players(),
-                game.config().max_players()
+                game.config().max_players(),
+                if game.private() { " [private]" } else { "" },
+                if game.status() == GameStatus::Open {
+                    ""
+                } else {
+                    " [started]"
+                }
             ),
         )
         .id();
     commands.entity(row_id).add_child(name_id);
 
-    if game.num_players() < game.config().max_players() {
+    let name = game.config().name();
+    if game.config().protocol_version() != PROTOCOL_VERSION {
+        let label_id = commands
+            .spawn_label(
+                OuterStyle {
+                    width: Val::Percent(38.),
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                "Incompatible version",
+            )
+            .id();
+        commands.entity(row_id).add_child(label_id);
+        return row_id;
+    }
+
+    if rejoin.is_some_and(|rejoin| rejoin.game() == name) {
+        let button_id = commands
+            .spawn_button(
+                OuterStyle {
+                    width: Val::Percent(18.),
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                "Rejoin",
+            )
+            .insert(ButtonAction::Rejoin(name.to_owned()))
+            .id();
+        commands.entity(row_id).add_child(button_id);
+    } else if game.status() == GameStatus::Open && game.num_players() < game.config().max_players()
+    {
         let button_id = commands
             .spawn_button(
                 OuterStyle {
@@ -146,6 +213,81 @@ fn row(commands: &mut GuiCommands, game: &GamePartial) -> Entity {
         commands.entity(row_id).add_child(button_id);
     }
 
+    let button_id = commands
+        .spawn_button(
+            OuterStyle {
+                width: Val::Percent(18.),
+                height: Val::Percent(100.),
+                margin: UiRect::left(Val::Percent(2.)),
+            },
+            "Spectate",
+        )
+        .insert(ButtonAction::Spectate(game.config().name().to_owned()))
+        .id();
+    commands.entity(row_id).add_child(button_id);
+
+    row_id
+}
+
+fn lan_row(commands: &mut GuiCommands, server: &LanServer, game: &LanGame) -> Entity {
+    let row_id = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                width: Val::Percent(100.),
+                height: Val::Percent(8.),
+                margin: UiRect::vertical(Val::Percent(0.5)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::FlexStart,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+
+    let addr = SocketAddr::new(server.addr().ip(), game.port());
+    let name_id = commands
+        .spawn_label(
+            OuterStyle {
+                width: Val::Percent(60.),
+                height: Val::Percent(100.),
+                margin: UiRect::right(Val::Percent(2.)),
+            },
+            format!(
+                "{addr} ({}/{})",
+                game.players(),
+                game.max_players().to_num()
+            ),
+        )
+        .id();
+    commands.entity(row_id).add_child(name_id);
+
+    let button_id = commands
+        .spawn_button(
+            OuterStyle {
+                width: Val::Percent(18.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            "Join",
+        )
+        .insert(ButtonAction::JoinLan(addr, *game.map_hash()))
+        .id();
+    commands.entity(row_id).add_child(button_id);
+
+    let button_id = commands
+        .spawn_button(
+            OuterStyle {
+                width: Val::Percent(18.),
+                height: Val::Percent(100.),
+                margin: UiRect::left(Val::Percent(2.)),
+            },
+            "Spectate",
+        )
+        .insert(ButtonAction::SpectateLan(addr, *game.map_hash()))
+        .id();
+    commands.entity(row_id).add_child(button_id);
+
     row_id
 }
 
@@ -157,13 +299,17 @@ fn refresh_system(
     stopwatch.tick(time.delta());
     if stopwatch.elapsed() >= REFRESH_INTERVAL {
         stopwatch.reset();
-        requests.send(RequestEvent::new("list-games", ListGamesRequest));
+        requests.send(RequestEvent::new(
+            "list-games",
+            ListGamesRequest::with_started(),
+        ));
     }
 }
 
 fn list_games_system(
     mut commands: GuiCommands,
     table: Res<GamesTable>,
+    rejoin: Option<Res<RejoinRes>>,
     mut events: EventReader<ResponseEvent<ListGamesRequest>>,
     mut toasts: EventWriter<ToastEvent>,
 ) {
@@ -175,7 +321,7 @@ fn list_games_system(
     match event.result() {
         Ok(games) => {
             for game in games.games() {
-                let row_id = row(&mut commands, game);
+                let row_id = row(&mut commands, game, rejoin.as_deref());
                 commands.entity(table.0).add_child(row_id);
             }
         }
@@ -185,8 +331,25 @@ fn list_games_system(
     }
 }
 
+fn list_lan_games_system(mut commands: GuiCommands, table: Res<GamesTable>, games: Res<LanGames>) {
+    commands.entity(table.0).despawn_descendants();
+
+    for server in games.servers() {
+        // Games of incompatible connectors could not be joined.
+        if server.version() != PROTOCOL_VERSION {
+            continue;
+        }
+
+        for game in server.games() {
+            let row_id = lan_row(&mut commands, server, game);
+            commands.entity(table.0).add_child(row_id);
+        }
+    }
+}
+
 fn button_system(
     mut commands: Commands,
+    rejoin: Option<Res<RejoinRes>>,
     mut next_state: ResMut<NextState<MultiplayerState>>,
     interactions: Query<(&Interaction, &ButtonAction), Changed<Interaction>>,
 ) {
@@ -196,6 +359,34 @@ fn button_system(
                 ButtonAction::Create => next_state.set(MultiplayerState::GameCreation),
                 ButtonAction::Join(name) => {
                     commands.insert_resource(GameNameRes::new(name));
+                    commands.insert_resource(JoinModeRes::Player);
+                    next_state.set(MultiplayerState::GameJoining);
+                }
+                ButtonAction::Spectate(name) => {
+                    commands.insert_resource(GameNameRes::new(name));
+                    commands.insert_resource(JoinModeRes::Spectator);
+                    next_state.set(MultiplayerState::GameJoining);
+                }
+                ButtonAction::Rejoin(name) => {
+                    let Some(rejoin) = rejoin.as_ref().filter(|r| r.game() == name) else {
+                        continue;
+                    };
+
+                    commands.insert_resource(GameNameRes::new(name));
+                    commands.insert_resource(JoinModeRes::Rejoin(rejoin.token()));
+                    commands.remove_resource::<RejoinRes>();
+                    next_state.set(MultiplayerState::GameJoining);
+                }
+                ButtonAction::JoinLan(server, map_hash) => {
+                    commands.insert_resource(GameNameRes::new(server));
+                    commands.insert_resource(LanGameRes::new(*server, *map_hash));
+                    commands.insert_resource(JoinModeRes::Player);
+                    next_state.set(MultiplayerState::GameJoining);
+                }
+                ButtonAction::SpectateLan(server, map_hash) => {
+                    commands.insert_resource(GameNameRes::new(server));
+                    commands.insert_resource(LanGameRes::new(*server, *map_hash));
+                    commands.insert_resource(JoinModeRes::Spectator);
                     next_state.set(MultiplayerState::GameJoining);
                 }
             }
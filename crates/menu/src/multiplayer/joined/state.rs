@@ -5,20 +5,24 @@ use de_core::{
     state::AppState,
 };
 use de_gui::ToastEvent;
-use de_lobby_client::GetGameRequest;
-use de_lobby_model::GameMap;
+use de_lobby_client::{GetGameRequest, SetMaxPlayersRequest, StartGameRequest};
+use de_lobby_model::{GamePlayer, GamePlayerInfo};
 use de_map::hash::MapHash;
 use de_messages::Readiness;
 use de_multiplayer::{
-    GameReadinessEvent, PeerJoinedEvent, PeerLeftEvent, ShutdownMultiplayerEvent,
+    GameLockedEvent, GameMaxPlayersEvent, GameReadinessEvent, PeerJoinedEvent, PeerLeftEvent,
+    ShutdownMultiplayerEvent,
 };
-use de_types::player::Player;
+use de_types::player::{Player, PlayerRange};
 
 use super::ui::RefreshPlayersEvent;
-use crate::multiplayer::{
-    current::GameNameRes,
-    requests::{Receiver, Sender},
-    MultiplayerState,
+use crate::{
+    mapselection::find_map,
+    multiplayer::{
+        current::{GameNameRes, LanGameRes, LanModeRes, PlayedGameRes},
+        requests::{Receiver, Sender},
+        MultiplayerState,
+    },
 };
 
 pub(super) struct JoinedGameStatePlugin;
@@ -26,18 +30,34 @@ pub(super) struct JoinedGameStatePlugin;
 impl Plugin for JoinedGameStatePlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<StartGameEvent>()
-            .add_systems(OnEnter(MultiplayerState::GameJoined), (setup, refresh))
+            .add_systems(
+                OnEnter(MultiplayerState::GameJoined),
+                (
+                    setup,
+                    refresh.run_if(not(resource_exists::<LanModeRes>)),
+                    setup_lan.run_if(resource_exists::<LanModeRes>),
+                ),
+            )
             .add_systems(OnExit(MultiplayerState::GameJoined), cleanup)
             .add_systems(
                 Update,
                 (
-                    refresh
-                        .run_if(on_event::<PeerJoinedEvent>().or_else(on_event::<PeerLeftEvent>())),
+                    (
+                        refresh.run_if(
+                            on_event::<PeerJoinedEvent>().or_else(on_event::<PeerLeftEvent>()),
+                        ),
+                        handle_readiness,
+                        handle_max_players_response,
+                    )
+                        .run_if(not(resource_exists::<LanModeRes>)),
+                    handle_locked.run_if(on_event::<GameLockedEvent>()),
+                    handle_max_players.run_if(on_event::<GameMaxPlayersEvent>()),
+                    (refresh_lan, handle_lan_readiness).run_if(resource_exists::<LanModeRes>),
                     handle_get_response,
                     start
                         .run_if(on_event::<StartGameEvent>())
-                        .after(handle_get_response),
-                    handle_readiness,
+                        .after(handle_get_response)
+                        .after(handle_lan_readiness),
                 )
                     .run_if(in_state(MultiplayerState::GameJoined)),
             );
@@ -45,22 +65,70 @@ impl Plugin for JoinedGameStatePlugin {
 }
 
 #[derive(Event)]
-struct StartGameEvent(GameMap);
+struct StartGameEvent(MapHash);
 
+/// Player number of the local player or None if the game is joined as a
+/// spectator.
 #[derive(Resource)]
-pub(crate) struct LocalPlayerRes(Player);
+pub(crate) struct LocalPlayerRes {
+    player: Option<Player>,
+    rejoined: bool,
+    author: bool,
+}
 
 impl LocalPlayerRes {
-    pub(crate) fn new(player: Player) -> Self {
-        Self(player)
+    /// # Arguments
+    ///
+    /// * `player` - the local player or None if the game is joined as a
+    ///   spectator.
+    ///
+    /// * `rejoined` - whether a running game was joined, i.e. rejoined after
+    ///   a connection loss or joined as a spectator.
+    pub(crate) fn new(player: Option<Player>, rejoined: bool) -> Self {
+        Self {
+            player,
+            rejoined,
+            author: false,
+        }
+    }
+
+    /// Marks the local player as the author of the game, i.e. the player
+    /// who created it.
+    pub(crate) fn authored(mut self) -> Self {
+        self.author = true;
+        self
+    }
+
+    pub(crate) fn is_spectator(&self) -> bool {
+        self.player.is_none()
     }
 }
 
 #[derive(Resource)]
 struct ReadyRes(bool);
 
-fn setup(mut commands: Commands) {
-    commands.insert_resource(ReadyRes(false));
+/// Players of a LAN game known to the local player. In LAN mode, this
+/// replaces the list of players otherwise obtained from DE Lobby.
+#[derive(Resource)]
+struct LanPlayersRes(Vec<Player>);
+
+impl LanPlayersRes {
+    fn to_game_players(&self) -> Vec<GamePlayer> {
+        self.0
+            .iter()
+            .map(|player| {
+                GamePlayer::new(
+                    format!("Player {}", player.to_num()),
+                    GamePlayerInfo::new(player.to_num()),
+                )
+            })
+            .collect()
+    }
+}
+
+fn setup(mut commands: Commands, player: Res<LocalPlayerRes>) {
+    // A rejoined (or spectated) running game is started right away.
+    commands.insert_resource(ReadyRes(player.rejoined));
 }
 
 fn cleanup(
@@ -70,6 +138,7 @@ fn cleanup(
 ) {
     commands.remove_resource::<LocalPlayerRes>();
     commands.remove_resource::<ReadyRes>();
+    commands.remove_resource::<LanPlayersRes>();
 
     if state.as_ref() != &AppState::InGame {
         shutdown.send(ShutdownMultiplayerEvent);
@@ -81,6 +150,65 @@ fn refresh(game_name: Res<GameNameRes>, mut sender: Sender<GetGameRequest>) {
     sender.send(GetGameRequest::new(game_name.name_owned()));
 }
 
+fn setup_lan(
+    mut commands: Commands,
+    player: Res<LocalPlayerRes>,
+    game: Res<LanGameRes>,
+    mut refresh: EventWriter<RefreshPlayersEvent>,
+    mut start_events: EventWriter<StartGameEvent>,
+) {
+    // Player numbers are assigned in ascending order, thus all players with
+    // a lower number have (most likely) joined the game before the local
+    // player.
+    let players = match player.player {
+        Some(local) => PlayerRange::up_to(local).collect(),
+        None => Vec::new(),
+    };
+    let players = LanPlayersRes(players);
+    refresh.send(RefreshPlayersEvent::from_slice(&players.to_game_players()));
+    commands.insert_resource(players);
+
+    if player.rejoined {
+        start_events.send(StartGameEvent(MapHash::from(game.map_hash())));
+    }
+}
+
+fn refresh_lan(
+    mut players: ResMut<LanPlayersRes>,
+    mut joined_events: EventReader<PeerJoinedEvent>,
+    mut left_events: EventReader<PeerLeftEvent>,
+    mut refresh: EventWriter<RefreshPlayersEvent>,
+) {
+    let mut changed = false;
+    for event in joined_events.read() {
+        if let Err(index) = players.0.binary_search(&event.id()) {
+            players.0.insert(index, event.id());
+            changed = true;
+        }
+    }
+    for event in left_events.read() {
+        if let Ok(index) = players.0.binary_search(&event.id()) {
+            players.0.remove(index);
+            changed = true;
+        }
+    }
+
+    if changed {
+        refresh.send(RefreshPlayersEvent::from_slice(&players.to_game_players()));
+    }
+}
+
+fn handle_lan_readiness(
+    mut events: EventReader<GameReadinessEvent>,
+    game: Res<LanGameRes>,
+    mut start_events: EventWriter<StartGameEvent>,
+) {
+    if events.read().all(|e| **e != Readiness::Ready) {
+        return;
+    }
+    start_events.send(StartGameEvent(MapHash::from(game.map_hash())));
+}
+
 fn handle_readiness(
     mut events: EventReader<GameReadinessEvent>,
     game_name: Res<GameNameRes>,
@@ -95,6 +223,55 @@ fn handle_readiness(
     ready.0 = true;
 }
 
+fn handle_locked(mut events: EventReader<GameLockedEvent>, mut toasts: EventWriter<ToastEvent>) {
+    let Some(event) = events.read().last() else {
+        return;
+    };
+
+    let message = if **event {
+        "The game was locked by the host."
+    } else {
+        "The game was unlocked by the host."
+    };
+    toasts.send(ToastEvent::new(message));
+}
+
+fn handle_max_players(
+    mut events: EventReader<GameMaxPlayersEvent>,
+    player: Res<LocalPlayerRes>,
+    game_name: Res<GameNameRes>,
+    lan_mode: Option<Res<LanModeRes>>,
+    mut sender: Sender<SetMaxPlayersRequest>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let Some(event) = events.read().last() else {
+        return;
+    };
+
+    let max_players = event.to_num();
+    if player.author && lan_mode.is_none() {
+        // DE Lobby checks player ordinals against the maximum number of
+        // players, thus it must be kept in sync with the game server.
+        sender.send(SetMaxPlayersRequest::new(
+            game_name.name_owned(),
+            max_players,
+        ));
+    }
+
+    toasts.send(ToastEvent::new(format!(
+        "Maximum number of players changed to {max_players}."
+    )));
+}
+
+fn handle_max_players_response(
+    mut receiver: Receiver<SetMaxPlayersRequest>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    if let Some(Err(error)) = receiver.receive() {
+        toasts.send(ToastEvent::new(error));
+    }
+}
+
 fn handle_get_response(
     mut multi_state: ResMut<NextState<MultiplayerState>>,
     mut receiver: Receiver<GetGameRequest>,
@@ -109,7 +286,15 @@ fn handle_get_response(
                 refresh.send(RefreshPlayersEvent::from_slice(game.players()));
 
                 if ready.0 {
-                    start_events.send(StartGameEvent(game.setup().config().map().clone()));
+                    match MapHash::from_hex(game.config().map().hash()) {
+                        Ok(hash) => {
+                            start_events.send(StartGameEvent(hash));
+                        }
+                        Err(error) => {
+                            toasts.send(ToastEvent::new(error));
+                            multi_state.set(MultiplayerState::SignIn);
+                        }
+                    }
                 }
             }
             Err(error) => {
@@ -124,27 +309,38 @@ fn start(
     mut commands: Commands,
     mut events: EventReader<StartGameEvent>,
     player: Res<LocalPlayerRes>,
+    game_name: Res<GameNameRes>,
+    lan_mode: Option<Res<LanModeRes>>,
+    mut sender: Sender<StartGameRequest>,
     mut app_state: ResMut<NextState<AppState>>,
-    mut multi_state: ResMut<NextState<MultiplayerState>>,
-    mut toasts: EventWriter<ToastEvent>,
 ) {
     let Some(event) = events.read().last() else {
         return;
     };
 
-    let map_path = match MapHash::from_hex(event.0.hash()) {
-        Ok(hash) => hash.construct_path(asset_path("maps")),
-        Err(error) => {
-            toasts.send(ToastEvent::new(error));
-            multi_state.set(MultiplayerState::SignIn);
-            return;
-        }
-    };
+    if player.author && lan_mode.is_none() {
+        // Started games are no longer offered for joining in DE Lobby.
+        sender.send(StartGameRequest::new(game_name.name_owned()));
+    }
 
-    commands.insert_resource(GameConfig::new(
-        map_path,
-        true,
-        LocalPlayers::from_single(player.0),
-    ));
+    // Only outcomes of games registered at DE Lobby are reported.
+    if lan_mode.is_none() && !player.is_spectator() {
+        commands.insert_resource(PlayedGameRes::new(game_name.name_owned()));
+    } else {
+        commands.remove_resource::<PlayedGameRes>();
+    }
+
+    let map_path =
+        find_map(&event.0).unwrap_or_else(|| event.0.construct_path(asset_path("maps")).into());
+
+    let locals = match player.player {
+        Some(player) => LocalPlayers::from_single(player),
+        None => LocalPlayers::observer(),
+    };
+    let mut config = GameConfig::new(map_path, true, locals);
+    if player.rejoined {
+        config = config.rejoined();
+    }
+    commands.insert_resource(config);
     app_state.set(AppState::InGame);
 }
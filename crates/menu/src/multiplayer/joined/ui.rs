@@ -4,6 +4,7 @@ use de_lobby_model::GamePlayer;
 use de_messages::Readiness;
 use de_multiplayer::SetReadinessEvent;
 
+use super::LocalPlayerRes;
 use crate::{menu::Menu, multiplayer::MultiplayerState};
 
 pub(super) struct JoinedGameUiPlugin;
@@ -43,11 +44,14 @@ enum ButtonAction {
     Ready,
 }
 
-fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
+fn setup(mut commands: GuiCommands, menu: Res<Menu>, player: Res<LocalPlayerRes>) {
     let mid_panel_id = mid_panel(&mut commands, menu.root_node());
     let players_box_id = players_box(&mut commands, mid_panel_id);
     commands.insert_resource(PlayersBoxRes(players_box_id));
-    ready_button(&mut commands, mid_panel_id);
+    // The game does not wait for spectators.
+    if !player.is_spectator() {
+        ready_button(&mut commands, mid_panel_id);
+    }
 }
 
 fn cleanup(mut commands: Commands) {
@@ -1,41 +1,116 @@
 use bevy::prelude::*;
-use de_gui::ToastEvent;
-use de_lobby_client::{GetGameRequest, JoinGameRequest};
+use de_conf::Configuration;
+use de_gui::{
+    ButtonCommands, GuiCommands, LabelCommands, OuterStyle, TextBoxCommands, TextBoxQuery,
+    ToastEvent,
+};
+use de_lobby_client::{GameConnectionRequest, GetGameRequest, JoinGameRequest};
 use de_lobby_model::GamePlayerInfo;
+use de_map::hash::MapHash;
+use de_messages::{RejoinToken, PROTOCOL_VERSION};
 use de_multiplayer::{
-    ConnectionType, GameJoinedEvent, NetGameConf, ShutdownMultiplayerEvent, StartMultiplayerEvent,
+    ConnectionType, GameJoinedEvent, NetGameConf, SessionSecret, ShutdownMultiplayerEvent,
+    StartMultiplayerEvent,
 };
 
 use super::{
-    current::GameNameRes,
+    current::{GameNameRes, LanGameRes, LanModeRes, RejoinRes},
     joined::LocalPlayerRes,
+    maps::{FetchMapEvent, MapFetchedEvent},
     requests::{Receiver, Sender},
     MultiplayerState,
 };
+use crate::menu::Menu;
 
 pub(crate) struct JoiningGamePlugin;
 
 impl Plugin for JoiningGamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(MultiplayerState::GameJoining), get_game)
-            .add_systems(OnExit(MultiplayerState::GameJoining), cleanup)
-            .add_systems(
-                Update,
-                (
-                    handle_get_response,
-                    handle_joined_event.run_if(on_event::<GameJoinedEvent>()),
-                    handle_join_response,
-                )
-                    .run_if(in_state(MultiplayerState::GameJoining)),
-            );
+        app.add_systems(
+            OnEnter(MultiplayerState::GameJoining),
+            (
+                get_game.run_if(not(resource_exists::<LanModeRes>)),
+                join_lan_game.run_if(resource_exists::<LanModeRes>),
+            ),
+        )
+        .add_systems(OnExit(MultiplayerState::GameJoining), cleanup)
+        .add_systems(
+            Update,
+            (
+                handle_get_response,
+                handle_map_fetched
+                    .run_if(resource_exists::<FetchingMapRes>)
+                    .run_if(on_event::<MapFetchedEvent>()),
+                password_prompt
+                    .run_if(resource_added::<PendingJoinRes>)
+                    .after(handle_map_fetched),
+                password_button_system.run_if(resource_exists::<PasswordPromptRes>),
+                handle_connection_response,
+                handle_joined_event.run_if(on_event::<GameJoinedEvent>()),
+                handle_join_response,
+            )
+                .run_if(in_state(MultiplayerState::GameJoining)),
+        );
+    }
+}
+
+/// Determines whether the game is joined as a player, as a spectator or
+/// rejoined after a connection loss.
+#[derive(Resource, Clone, Copy, PartialEq, Eq)]
+pub(super) enum JoinModeRes {
+    Player,
+    Spectator,
+    Rejoin(RejoinToken),
+}
+
+impl JoinModeRes {
+    /// Returns the way of connection to a game server listening on `port`.
+    fn connection_type(self, port: u16) -> ConnectionType {
+        match self {
+            Self::Player => ConnectionType::JoinGame(port),
+            Self::Spectator => ConnectionType::SpectateGame(port),
+            Self::Rejoin(token) => ConnectionType::RejoinGame { port, token },
+        }
     }
 }
 
+/// The game map is being fetched. The connection details of the game server
+/// are requested once the map is available locally.
+#[derive(Resource)]
+struct FetchingMapRes {
+    /// Whether a password is required to obtain the connection details.
+    private: bool,
+}
+
+/// Connection details of a private game are to be requested once the password
+/// is entered.
+#[derive(Resource)]
+struct PendingJoinRes;
+
+/// Password prompt displayed before a private game is joined.
+#[derive(Resource)]
+struct PasswordPromptRes {
+    column: Entity,
+    input: Entity,
+}
+
+/// Password entered by the player joining a private game.
+#[derive(Resource)]
+struct JoinPasswordRes(String);
+
+#[derive(Component)]
+struct PasswordButton;
+
 fn cleanup(
     mut commands: Commands,
     state: Res<State<MultiplayerState>>,
     mut shutdown: EventWriter<ShutdownMultiplayerEvent>,
 ) {
+    commands.remove_resource::<JoinModeRes>();
+    commands.remove_resource::<FetchingMapRes>();
+    commands.remove_resource::<PendingJoinRes>();
+    commands.remove_resource::<PasswordPromptRes>();
+    commands.remove_resource::<JoinPasswordRes>();
     if state.as_ref() != &MultiplayerState::GameJoined {
         commands.remove_resource::<LocalPlayerRes>();
         shutdown.send(ShutdownMultiplayerEvent);
@@ -46,20 +121,57 @@ fn get_game(game_name: Res<GameNameRes>, mut sender: Sender<GetGameRequest>) {
     sender.send(GetGameRequest::new(game_name.name_owned()));
 }
 
+fn join_lan_game(
+    config: Res<Configuration>,
+    mode: Res<JoinModeRes>,
+    game: Res<LanGameRes>,
+    mut multiplayer: EventWriter<StartMultiplayerEvent>,
+) {
+    let server = game.server();
+    let mut net_conf = NetGameConf::new(server.ip(), mode.connection_type(server.port()));
+    if let Some(impairment) = config.multiplayer().impairment() {
+        net_conf = net_conf.with_impairment(impairment);
+    }
+    multiplayer.send(StartMultiplayerEvent::new(net_conf));
+}
+
 fn handle_get_response(
+    mut commands: Commands,
+    mode: Res<JoinModeRes>,
     mut next_state: ResMut<NextState<MultiplayerState>>,
     mut receiver: Receiver<GetGameRequest>,
-    mut multiplayer: EventWriter<StartMultiplayerEvent>,
+    mut fetch: EventWriter<FetchMapEvent>,
     mut toasts: EventWriter<ToastEvent>,
 ) {
     while let Some(result) = receiver.receive() {
         match result {
             Ok(game) => {
-                let server = game.setup().server();
-                multiplayer.send(StartMultiplayerEvent::new(NetGameConf::new(
-                    server.ip(),
-                    ConnectionType::JoinGame(server.port()),
-                )));
+                let version = game.config().protocol_version();
+                if version != PROTOCOL_VERSION {
+                    toasts.send(ToastEvent::new(format!(
+                        "The game is not compatible with this version of the game: game \
+                         protocol version is {version}, local version is {PROTOCOL_VERSION}."
+                    )));
+                    next_state.set(MultiplayerState::GameListing);
+                    continue;
+                }
+
+                let map_hash = match MapHash::from_hex(game.config().map().hash()) {
+                    Ok(hash) => hash,
+                    Err(error) => {
+                        toasts.send(ToastEvent::new(error));
+                        next_state.set(MultiplayerState::GameListing);
+                        continue;
+                    }
+                };
+
+                // Rejoining players are still players of the game, thus no
+                // password is needed.
+                let rejoin = matches!(*mode, JoinModeRes::Rejoin(_));
+                commands.insert_resource(FetchingMapRes {
+                    private: game.private() && !rejoin,
+                });
+                fetch.send(FetchMapEvent::new(map_hash));
             }
             Err(error) => {
                 toasts.send(ToastEvent::new(error));
@@ -69,21 +181,205 @@ fn handle_get_response(
     }
 }
 
+fn handle_map_fetched(
+    mut commands: Commands,
+    game_name: Res<GameNameRes>,
+    fetching: Res<FetchingMapRes>,
+    mut events: EventReader<MapFetchedEvent>,
+    mut next_state: ResMut<NextState<MultiplayerState>>,
+    mut sender: Sender<GameConnectionRequest>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let Some(event) = events.read().last() else {
+        return;
+    };
+
+    if let Err(error) = event.result() {
+        toasts.send(ToastEvent::new(error));
+        next_state.set(MultiplayerState::GameListing);
+        return;
+    }
+
+    commands.remove_resource::<FetchingMapRes>();
+    if fetching.private {
+        commands.insert_resource(PendingJoinRes);
+    } else {
+        sender.send(GameConnectionRequest::new(game_name.name_owned()));
+    }
+}
+
+fn password_prompt(mut commands: GuiCommands, menu: Res<Menu>) {
+    let column_id = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                width: Val::Percent(50.),
+                height: Val::Percent(100.),
+                margin: UiRect::all(Val::Auto),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(menu.root_node()).add_child(column_id);
+
+    let input_row_id = row(&mut commands, column_id);
+    let caption_id = commands
+        .spawn_label(
+            OuterStyle {
+                width: Val::Percent(35.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            "Password",
+        )
+        .id();
+    commands.entity(input_row_id).add_child(caption_id);
+    let input_id = commands
+        .spawn_text_box(
+            OuterStyle {
+                width: Val::Percent(65.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            true,
+        )
+        .id();
+    commands.entity(input_row_id).add_child(input_id);
+
+    let button_row_id = row(&mut commands, column_id);
+    let button_id = commands
+        .spawn_button(
+            OuterStyle {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            "Join Game",
+        )
+        .insert(PasswordButton)
+        .id();
+    commands.entity(button_row_id).add_child(button_id);
+
+    commands.insert_resource(PasswordPromptRes {
+        column: column_id,
+        input: input_id,
+    });
+}
+
+fn row(commands: &mut GuiCommands, parent_id: Entity) -> Entity {
+    let row_id = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                width: Val::Percent(100.),
+                height: Val::Percent(8.),
+                margin: UiRect::vertical(Val::Percent(2.)),
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(parent_id).add_child(row_id);
+    row_id
+}
+
+fn password_button_system(
+    mut commands: Commands,
+    game_name: Res<GameNameRes>,
+    prompt: Res<PasswordPromptRes>,
+    texts: TextBoxQuery,
+    interactions: Query<&Interaction, (Changed<Interaction>, With<PasswordButton>)>,
+    mut sender: Sender<GameConnectionRequest>,
+) {
+    if !interactions
+        .iter()
+        .any(|&interaction| interaction == Interaction::Pressed)
+    {
+        return;
+    }
+
+    let password = texts.text(prompt.input).unwrap().to_string();
+    sender.send(GameConnectionRequest::new(game_name.name_owned()).with_password(password.clone()));
+    commands.insert_resource(JoinPasswordRes(password));
+    commands.remove_resource::<PasswordPromptRes>();
+    commands.entity(prompt.column).despawn_recursive();
+}
+
+fn handle_connection_response(
+    config: Res<Configuration>,
+    mode: Res<JoinModeRes>,
+    mut receiver: Receiver<GameConnectionRequest>,
+    mut next_state: ResMut<NextState<MultiplayerState>>,
+    mut multiplayer: EventWriter<StartMultiplayerEvent>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    while let Some(result) = receiver.receive() {
+        match result {
+            Ok(connection) => {
+                let server = connection.server();
+                let mut net_conf =
+                    NetGameConf::new(server.ip(), mode.connection_type(server.port()));
+                if let Some(secret) = connection.secret() {
+                    net_conf = net_conf.with_secret(SessionSecret::new(*secret.bytes()));
+                }
+                if let Some(impairment) = config.multiplayer().impairment() {
+                    net_conf = net_conf.with_impairment(impairment);
+                }
+                multiplayer.send(StartMultiplayerEvent::new(net_conf));
+            }
+            Err(error) => {
+                toasts.send(ToastEvent::new(error));
+                next_state.set(MultiplayerState::GameListing);
+            }
+        }
+    }
+}
+
 fn handle_joined_event(
     mut commands: Commands,
     game_name: Res<GameNameRes>,
+    password: Option<Res<JoinPasswordRes>>,
+    lan_mode: Option<Res<LanModeRes>>,
     mut events: EventReader<GameJoinedEvent>,
     mut sender: Sender<JoinGameRequest>,
+    mut next_state: ResMut<NextState<MultiplayerState>>,
 ) {
     let Some(event) = events.read().last() else {
         return;
     };
 
-    commands.insert_resource(LocalPlayerRes::new(event.player()));
-    sender.send(JoinGameRequest::new(
-        game_name.name_owned(),
-        GamePlayerInfo::new(event.player().to_num()),
-    ));
+    if let Some(token) = event.token() {
+        commands.insert_resource(RejoinRes::new(game_name.name_owned(), token));
+    }
+
+    commands.insert_resource(LocalPlayerRes::new(event.player(), event.is_rejoin()));
+    match event.player() {
+        Some(_) if lan_mode.is_some() => {
+            // LAN games are not registered in the lobby.
+            next_state.set(MultiplayerState::GameJoined);
+        }
+        Some(_) if event.is_rejoin() => {
+            // The player is still registered in the lobby.
+            next_state.set(MultiplayerState::GameJoined);
+        }
+        Some(player) => {
+            let mut request =
+                JoinGameRequest::new(game_name.name_owned(), GamePlayerInfo::new(player.to_num()));
+            if let Some(password) = password {
+                request = request.with_password(password.0.clone());
+            }
+            sender.send(request);
+        }
+        None => {
+            // Spectators are not registered in the lobby.
+            next_state.set(MultiplayerState::GameJoined);
+        }
+    }
 }
 
 fn handle_join_response(
@@ -0,0 +1,273 @@
+use std::path::PathBuf;
+
+use async_std::{fs, io};
+use bevy::{
+    prelude::*,
+    tasks::{futures_lite::future, IoTaskPool, Task},
+};
+use de_core::fs::{maps_dir, DirError};
+use de_gui::ToastEvent;
+use de_lobby_client::{DownloadMapRequest, MapExistsRequest, UploadMapRequest};
+use de_map::{
+    hash::MapHash,
+    io::{read_map, MapLoadingError},
+};
+use thiserror::Error;
+
+use super::requests::{Receiver, Sender};
+use crate::{mapselection::find_map, MenuState};
+
+pub(super) struct MapsPlugin;
+
+impl Plugin for MapsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FetchMapEvent>()
+            .add_event::<MapFetchedEvent>()
+            .add_event::<UploadMapEvent>()
+            .add_systems(OnExit(MenuState::Multiplayer), cleanup)
+            .add_systems(
+                Update,
+                (
+                    fetch_map.run_if(on_event::<FetchMapEvent>()),
+                    handle_download_response,
+                    poll_install.run_if(resource_exists::<InstallTask>),
+                    upload_map.run_if(on_event::<UploadMapEvent>()),
+                    handle_exists_response,
+                    poll_read.run_if(resource_exists::<ReadTask>),
+                    handle_upload_response,
+                )
+                    .run_if(in_state(MenuState::Multiplayer)),
+            );
+    }
+}
+
+/// Send this event to make a map available locally. If the map is missing,
+/// it is downloaded from DE Lobby and installed to the user maps directory.
+///
+/// [`MapFetchedEvent`] is sent once the map is available or the fetching
+/// failed.
+#[derive(Event)]
+pub(super) struct FetchMapEvent(MapHash);
+
+impl FetchMapEvent {
+    pub(super) fn new(hash: MapHash) -> Self {
+        Self(hash)
+    }
+}
+
+#[derive(Event)]
+pub(super) struct MapFetchedEvent(Result<(), MapFetchError>);
+
+impl MapFetchedEvent {
+    pub(super) fn result(&self) -> &Result<(), MapFetchError> {
+        &self.0
+    }
+}
+
+/// Send this event to upload a locally available map to DE Lobby, so that
+/// players who do not have the map can download it. Nothing is uploaded if
+/// the map is already present in the lobby.
+#[derive(Event)]
+pub(super) struct UploadMapEvent(MapHash);
+
+impl UploadMapEvent {
+    pub(super) fn new(hash: MapHash) -> Self {
+        Self(hash)
+    }
+}
+
+#[derive(Error, Debug)]
+pub(super) enum MapFetchError {
+    #[error("Map download failed: {0}")]
+    Download(String),
+    #[error("Downloaded map is invalid: {0}")]
+    Invalid(#[from] MapLoadingError),
+    #[error("Downloaded map does not correspond to the game map.")]
+    HashMismatch,
+    #[error(transparent)]
+    Dir(#[from] DirError),
+    #[error("Failed to store the downloaded map: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Hash of the map being downloaded.
+#[derive(Resource)]
+struct DownloadRes([u8; 32]);
+
+/// Path to the file of the map which is to be uploaded unless it is already
+/// present in the lobby.
+#[derive(Resource)]
+struct UploadRes(MapHash, PathBuf);
+
+#[derive(Resource)]
+struct InstallTask(Task<Result<(), MapFetchError>>);
+
+#[derive(Resource)]
+struct ReadTask(Task<io::Result<(MapHash, Vec<u8>)>>);
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<DownloadRes>();
+    commands.remove_resource::<UploadRes>();
+    commands.remove_resource::<InstallTask>();
+    commands.remove_resource::<ReadTask>();
+}
+
+fn fetch_map(
+    mut commands: Commands,
+    mut events: EventReader<FetchMapEvent>,
+    mut sender: Sender<DownloadMapRequest>,
+    mut fetched: EventWriter<MapFetchedEvent>,
+) {
+    let Some(event) = events.read().last() else {
+        return;
+    };
+
+    if find_map(&event.0).is_some() {
+        fetched.send(MapFetchedEvent(Ok(())));
+    } else {
+        info!("Downloading map {:?}.", event.0);
+        sender.send(DownloadMapRequest::new(event.0.to_hex()));
+        commands.insert_resource(DownloadRes((&event.0).into()));
+    }
+}
+
+fn handle_download_response(
+    mut commands: Commands,
+    download: Option<Res<DownloadRes>>,
+    mut receiver: Receiver<DownloadMapRequest>,
+    mut fetched: EventWriter<MapFetchedEvent>,
+) {
+    let Some(result) = receiver.receive() else {
+        return;
+    };
+    let Some(download) = download else {
+        return;
+    };
+
+    commands.remove_resource::<DownloadRes>();
+    match result {
+        Ok(content) => {
+            let task = IoTaskPool::get().spawn(install_map(download.0.into(), content.clone()));
+            commands.insert_resource(InstallTask(task));
+        }
+        Err(error) => {
+            fetched.send(MapFetchedEvent(Err(MapFetchError::Download(
+                error.to_string(),
+            ))));
+        }
+    }
+}
+
+fn poll_install(
+    mut commands: Commands,
+    mut task: ResMut<InstallTask>,
+    mut fetched: EventWriter<MapFetchedEvent>,
+) {
+    let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
+        return;
+    };
+    commands.remove_resource::<InstallTask>();
+    fetched.send(MapFetchedEvent(result));
+}
+
+/// Validates a downloaded map and stores it to the user maps directory.
+async fn install_map(hash: MapHash, content: Vec<u8>) -> Result<(), MapFetchError> {
+    let map = read_map(content.as_slice()).await?;
+    if map.compute_hash() != hash {
+        return Err(MapFetchError::HashMismatch);
+    }
+
+    let dir = maps_dir()?;
+    fs::create_dir_all(&dir).await?;
+    fs::write(hash.construct_path(dir), content).await?;
+    info!("Map {hash:?} installed.");
+    Ok(())
+}
+
+fn upload_map(
+    mut commands: Commands,
+    mut events: EventReader<UploadMapEvent>,
+    mut sender: Sender<MapExistsRequest>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let Some(event) = events.read().last() else {
+        return;
+    };
+
+    let Some(path) = find_map(&event.0) else {
+        toasts.send(ToastEvent::new("Map file to upload was not found."));
+        return;
+    };
+
+    let hash = MapHash::from(<[u8; 32]>::from(&event.0));
+    sender.send(MapExistsRequest::new(hash.to_hex()));
+    commands.insert_resource(UploadRes(hash, path));
+}
+
+fn handle_exists_response(
+    mut commands: Commands,
+    upload: Option<Res<UploadRes>>,
+    mut receiver: Receiver<MapExistsRequest>,
+) {
+    let Some(result) = receiver.receive() else {
+        return;
+    };
+    let Some(upload) = upload else {
+        return;
+    };
+    commands.remove_resource::<UploadRes>();
+
+    if result.is_ok() {
+        info!("Map {:?} is already present in the lobby.", upload.0);
+        return;
+    }
+
+    // The map is uploaded even if the check failed for another reason than
+    // the map being missing, the lobby ignores already present maps.
+    let hash = MapHash::from(<[u8; 32]>::from(&upload.0));
+    let path = upload.1.clone();
+    let task = IoTaskPool::get().spawn(async move {
+        let content = fs::read(path).await?;
+        Ok((hash, content))
+    });
+    commands.insert_resource(ReadTask(task));
+}
+
+fn poll_read(
+    mut commands: Commands,
+    mut task: ResMut<ReadTask>,
+    mut sender: Sender<UploadMapRequest>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
+        return;
+    };
+    commands.remove_resource::<ReadTask>();
+
+    match result {
+        Ok((hash, content)) => {
+            sender.send(UploadMapRequest::new(hash.to_hex(), content));
+        }
+        Err(error) => {
+            toasts.send(ToastEvent::new(format!(
+                "Failed to read the map file: {error}"
+            )));
+        }
+    }
+}
+
+fn handle_upload_response(
+    mut receiver: Receiver<UploadMapRequest>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let Some(result) = receiver.receive() else {
+        return;
+    };
+
+    match result {
+        Ok(_) => info!("Map successfully uploaded to the lobby."),
+        Err(error) => {
+            toasts.send(ToastEvent::new(format!("Map upload failed: {error}")));
+        }
+    }
+}
@@ -1,14 +1,16 @@
 use bevy::prelude::*;
 use de_core::nested_state;
 use de_lobby_client::{
-    CreateGameRequest, GetGameRequest, JoinGameRequest, SignInRequest, SignUpRequest,
+    CreateGameRequest, DownloadMapRequest, GameConnectionRequest, GetGameRequest, JoinGameRequest,
+    MapExistsRequest, SetMaxPlayersRequest, SignInRequest, SignUpRequest, StartGameRequest,
+    UploadMapRequest,
 };
 use de_multiplayer::MultiplayerShuttingDownEvent;
 
 use self::{
     create::CreateGamePlugin, current::CurrentGamePlugin, gamelisting::GameListingPlugin,
-    joined::JoinedGamePlugin, joining::JoiningGamePlugin, requests::RequestsPlugin,
-    setup::SetupGamePlugin, signin::SignInPlugin,
+    joined::JoinedGamePlugin, joining::JoiningGamePlugin, maps::MapsPlugin,
+    requests::RequestsPlugin, setup::SetupGamePlugin, signin::SignInPlugin,
 };
 use crate::{menu::ScreenStatePlugin, MenuState};
 
@@ -17,6 +19,7 @@ mod current;
 mod gamelisting;
 mod joined;
 mod joining;
+mod maps;
 mod requests;
 mod setup;
 mod signin;
@@ -31,6 +34,7 @@ impl Plugin for MultiplayerPlugin {
             RequestsPlugin::<GetGameRequest>::new(),
             RequestsPlugin::<CreateGameRequest>::new(),
             RequestsPlugin::<JoinGameRequest>::new(),
+            RequestsPlugin::<StartGameRequest>::new(),
             MultiplayerStatePlugin,
             ScreenStatePlugin::<MultiplayerState>::default(),
             CurrentGamePlugin,
@@ -41,6 +45,14 @@ impl Plugin for MultiplayerPlugin {
             JoiningGamePlugin,
             JoinedGamePlugin,
         ))
+        .add_plugins((
+            RequestsPlugin::<GameConnectionRequest>::new(),
+            RequestsPlugin::<MapExistsRequest>::new(),
+            RequestsPlugin::<DownloadMapRequest>::new(),
+            RequestsPlugin::<UploadMapRequest>::new(),
+            RequestsPlugin::<SetMaxPlayersRequest>::new(),
+            MapsPlugin,
+        ))
         .add_systems(
             PostUpdate,
             go_to_sign_in
@@ -1,16 +1,21 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
 use bevy::prelude::*;
 use de_conf::Configuration;
 use de_gui::ToastEvent;
 use de_lobby_client::CreateGameRequest;
 use de_lobby_model::{GameConfig, GameSetup};
+use de_map::hash::MapHash;
 use de_multiplayer::{
-    ConnectionType, GameJoinedEvent, GameOpenedEvent, NetGameConf, ShutdownMultiplayerEvent,
-    StartMultiplayerEvent,
+    ConnectionType, GameJoinedEvent, GameOpenedEvent, NetGameConf, SessionSecret,
+    SetGameSecretEvent, ShutdownMultiplayerEvent, StartMultiplayerEvent,
 };
+use de_types::player::Player;
 
 use super::{
-    current::GameNameRes,
+    current::{GameNameRes, HostedConnectorRes, LanGameRes, LanModeRes},
     joined::LocalPlayerRes,
+    maps::UploadMapEvent,
     requests::{Receiver, Sender},
     MultiplayerState,
 };
@@ -30,7 +35,8 @@ impl Plugin for SetupGamePlugin {
             .add_systems(
                 Update,
                 (
-                    create_game_in_lobby,
+                    create_game_in_lobby.run_if(not(resource_exists::<LanModeRes>)),
+                    register_lan_game.run_if(resource_exists::<LanModeRes>),
                     handle_lobby_response,
                     handle_joined_event,
                 )
@@ -45,22 +51,49 @@ impl Plugin for SetupGamePlugin {
 
 /// Send this event to initiate new multiplayer setup.
 ///
-/// The game will be opened at a DE Connector and registered at a DE Lobby.
+/// The game will be opened at a DE Connector and registered at a DE Lobby
+/// (unless in LAN mode, where the game is announced by the connector itself).
 /// Once this is done, the menu transitions to
 /// [`MultiplayerState::GameJoined`].
 #[derive(Event)]
 pub(super) struct SetupGameEvent {
     config: GameConfig,
+    map_max_players: Player,
+    password: Option<String>,
 }
 
 impl SetupGameEvent {
-    pub(super) fn new(config: GameConfig) -> Self {
-        Self { config }
+    /// # Arguments
+    ///
+    /// * `config` - configuration of the game to be set up.
+    ///
+    /// * `map_max_players` - maximum number of players supported by the map
+    ///   of the game.
+    pub(super) fn new(config: GameConfig, map_max_players: Player) -> Self {
+        Self {
+            config,
+            map_max_players,
+            password: None,
+        }
+    }
+
+    /// Makes the game private, i.e. joinable only with the password.
+    pub(super) fn with_password(mut self, password: String) -> Self {
+        self.password = Some(password);
+        self
     }
 }
 
 #[derive(Resource)]
-pub(crate) struct GameConfigRes(GameConfig);
+pub(crate) struct GameConfigRes {
+    config: GameConfig,
+    map_max_players: Player,
+}
+
+/// Join password of the game being set up. The resource is present only for
+/// private games.
+#[derive(Resource)]
+struct GamePasswordRes(String);
 
 #[derive(Resource)]
 struct JoinedRes(bool);
@@ -74,7 +107,14 @@ fn handle_setup_event(
         return;
     };
 
-    commands.insert_resource(GameConfigRes(event.config.clone()));
+    commands.insert_resource(GameConfigRes {
+        config: event.config.clone(),
+        map_max_players: event.map_max_players,
+    });
+    match event.password {
+        Some(ref password) => commands.insert_resource(GamePasswordRes(password.clone())),
+        None => commands.remove_resource::<GamePasswordRes>(),
+    }
     next_state.set(MultiplayerState::GameSetup);
 }
 
@@ -94,6 +134,7 @@ fn cleanup(
     mut shutdown: EventWriter<ShutdownMultiplayerEvent>,
 ) {
     commands.remove_resource::<GameConfigRes>();
+    commands.remove_resource::<GamePasswordRes>();
     commands.remove_resource::<JoinedRes>();
 
     if state.as_ref() != &MultiplayerState::GameJoined {
@@ -109,52 +150,136 @@ fn setup(mut commands: Commands) {
 fn setup_network(
     config: Res<Configuration>,
     game_config: Res<GameConfigRes>,
+    hosted: Option<Res<HostedConnectorRes>>,
+    mut next_state: ResMut<NextState<MultiplayerState>>,
     mut multiplayer: EventWriter<StartMultiplayerEvent>,
+    mut toasts: EventWriter<ToastEvent>,
 ) {
-    let connector_conf = config.multiplayer().connector();
-    let net_game_conf = NetGameConf::new(
+    let map_hash = match MapHash::from_hex(game_config.config.map().hash()) {
+        Ok(hash) => hash,
+        Err(error) => {
+            toasts.send(ToastEvent::new(error));
+            next_state.set(MultiplayerState::SignIn);
+            return;
+        }
+    };
+
+    let connector_conf = match hosted {
+        Some(hosted) => SocketAddr::from((Ipv4Addr::LOCALHOST, hosted.port())),
+        None => config.multiplayer().connector(),
+    };
+    let mut net_game_conf = NetGameConf::new(
         connector_conf.ip(),
         ConnectionType::CreateGame {
             port: connector_conf.port(),
-            max_players: game_config.0.max_players().try_into().unwrap(),
+            max_players: game_config.config.max_players().try_into().unwrap(),
+            map_max_players: game_config.map_max_players,
+            map_hash: (&map_hash).into(),
         },
     );
+    if let Some(impairment) = config.multiplayer().impairment() {
+        net_game_conf = net_game_conf.with_impairment(impairment);
+    }
     multiplayer.send(StartMultiplayerEvent::new(net_game_conf));
 }
 
 fn create_game_in_lobby(
     mut commands: Commands,
     config: Res<GameConfigRes>,
+    password: Option<Res<GamePasswordRes>>,
     mut opened_events: EventReader<GameOpenedEvent>,
     mut sender: Sender<CreateGameRequest>,
+    mut uploads: EventWriter<UploadMapEvent>,
 ) {
     let Some(opened_event) = opened_events.read().last() else {
         return;
     };
 
-    let game_config = config.0.clone();
+    // Players who do not have the map download it from the lobby. The hash
+    // was already validated during the network setup.
+    if let Ok(map_hash) = MapHash::from_hex(config.config.map().hash()) {
+        uploads.send(UploadMapEvent::new(map_hash));
+    }
+
+    let game_config = config.config.clone();
     commands.insert_resource(GameNameRes::new(game_config.name()));
-    let game_setup = GameSetup::new(opened_event.0, game_config);
+    let mut game_setup = GameSetup::new(opened_event.addr(), game_config);
+    if let Some(nonce) = opened_event.nonce() {
+        game_setup = game_setup.with_nonce(nonce);
+    }
+    if let Some(password) = password {
+        game_setup = game_setup.with_password(password.0.clone());
+    }
     sender.send(CreateGameRequest::new(game_setup));
 }
 
+fn register_lan_game(
+    mut commands: Commands,
+    config: Res<GameConfigRes>,
+    password: Option<Res<GamePasswordRes>>,
+    mut joined: ResMut<JoinedRes>,
+    mut next_state: ResMut<NextState<MultiplayerState>>,
+    mut opened_events: EventReader<GameOpenedEvent>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let Some(opened_event) = opened_events.read().last() else {
+        return;
+    };
+
+    if opened_event.nonce().is_some() {
+        // Game secrets are distributed by DE Lobby.
+        toasts.send(ToastEvent::new(
+            "The connector requires game authentication, which is not available in LAN mode.",
+        ));
+        next_state.set(MultiplayerState::SignIn);
+        return;
+    }
+
+    if password.is_some() {
+        // Game passwords are checked by DE Lobby.
+        toasts.send(ToastEvent::new(
+            "Private games are not available in LAN mode.",
+        ));
+        next_state.set(MultiplayerState::SignIn);
+        return;
+    }
+
+    let map_hash = match MapHash::from_hex(config.config.map().hash()) {
+        Ok(hash) => hash,
+        Err(error) => {
+            toasts.send(ToastEvent::new(error));
+            next_state.set(MultiplayerState::SignIn);
+            return;
+        }
+    };
+
+    info!("LAN game successfully created.");
+    commands.insert_resource(GameNameRes::new(opened_event.addr()));
+    commands.insert_resource(LanGameRes::new(opened_event.addr(), (&map_hash).into()));
+    joined.0 = true;
+}
+
 fn handle_joined_event(mut commands: Commands, mut events: EventReader<GameJoinedEvent>) {
     let Some(event) = events.read().last() else {
         return;
     };
-    commands.insert_resource(LocalPlayerRes::new(event.player()));
+    commands.insert_resource(LocalPlayerRes::new(event.player(), false).authored());
 }
 
 fn handle_lobby_response(
     mut joined: ResMut<JoinedRes>,
     mut next_state: ResMut<NextState<MultiplayerState>>,
     mut receiver: Receiver<CreateGameRequest>,
+    mut secrets: EventWriter<SetGameSecretEvent>,
     mut toasts: EventWriter<ToastEvent>,
 ) {
     while let Some(result) = receiver.receive() {
         match result {
-            Ok(_) => {
+            Ok(secret) => {
                 info!("Game successfully created.");
+                if let Some(secret) = secret {
+                    secrets.send(SetGameSecretEvent::new(SessionSecret::new(*secret.bytes())));
+                }
                 joined.0 = true;
             }
             Err(error) => {
@@ -1,12 +1,18 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
 use bevy::prelude::*;
+use de_conf::Configuration;
+use de_connector_lib::{ConnectorConf, EmbeddedConnector};
 use de_gui::{
     ButtonCommands, GuiCommands, LabelCommands, OuterStyle, SetFocusEvent, TextBoxCommands,
     TextBoxQuery, ToastEvent,
 };
 use de_lobby_client::{Authentication, LobbyRequest, SignInRequest, SignUpRequest};
 use de_lobby_model::{User, UserWithPassword, UsernameAndPassword};
+use de_messages::LAN_DISCOVERY_PORT;
 
 use super::{
+    current::{HostedConnectorRes, LanModeRes},
     requests::{Receiver, Sender},
     MultiplayerState,
 };
@@ -22,6 +28,8 @@ impl Plugin for SignInPlugin {
                 Update,
                 (
                     button_system.run_if(resource_exists::<Inputs>),
+                    lan_button_system,
+                    host_button_system,
                     response_system::<SignInRequest>,
                     response_system::<SignUpRequest>,
                     auth_system,
@@ -43,7 +51,19 @@ enum Action {
     SignUp,
 }
 
+/// Marker of the button switching to games discovered on the local network.
+#[derive(Component)]
+struct LanButton;
+
+/// Marker of the button starting a DE Connector within the game and creating
+/// a game on it.
+#[derive(Component)]
+struct HostButton;
+
 fn setup(mut commands: GuiCommands, menu: Res<Menu>, mut focus: EventWriter<SetFocusEvent>) {
+    commands.remove_resource::<LanModeRes>();
+    commands.remove_resource::<HostedConnectorRes>();
+
     let column = root_column(&mut commands);
     commands.entity(menu.root_node()).add_child(column);
 
@@ -57,6 +77,9 @@ fn setup(mut commands: GuiCommands, menu: Res<Menu>, mut focus: EventWriter<SetF
     let buttons_row = row(&mut commands, column);
     buttons(&mut commands, buttons_row);
 
+    let lan_row = row(&mut commands, column);
+    lan_buttons(&mut commands, lan_row);
+
     commands.insert_resource(Inputs {
         username: input_text_box,
         password: password_text_box,
@@ -160,6 +183,28 @@ fn button(commands: &mut GuiCommands, parent: Entity, action: Action) {
     commands.entity(parent).add_child(id);
 }
 
+fn lan_buttons(commands: &mut GuiCommands, parent: Entity) {
+    let id = commands
+        .spawn_button(lan_button_style(), "LAN Games")
+        .insert(LanButton)
+        .id();
+    commands.entity(parent).add_child(id);
+
+    let id = commands
+        .spawn_button(lan_button_style(), "Host Game")
+        .insert(HostButton)
+        .id();
+    commands.entity(parent).add_child(id);
+}
+
+fn lan_button_style() -> OuterStyle {
+    OuterStyle {
+        width: Val::Percent(48.),
+        height: Val::Percent(100.),
+        ..default()
+    }
+}
+
 fn button_system(
     inputs: Res<Inputs>,
     texts: TextBoxQuery,
@@ -191,6 +236,51 @@ fn button_system(
     }
 }
 
+fn lan_button_system(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<MultiplayerState>>,
+    interactions: Query<&Interaction, (Changed<Interaction>, With<LanButton>)>,
+) {
+    if interactions
+        .iter()
+        .any(|&interaction| interaction == Interaction::Pressed)
+    {
+        commands.insert_resource(LanModeRes);
+        next_state.set(MultiplayerState::GameListing);
+    }
+}
+
+fn host_button_system(
+    mut commands: Commands,
+    config: Res<Configuration>,
+    mut next_state: ResMut<NextState<MultiplayerState>>,
+    interactions: Query<&Interaction, (Changed<Interaction>, With<HostButton>)>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    if interactions
+        .iter()
+        .all(|&interaction| interaction != Interaction::Pressed)
+    {
+        return;
+    }
+
+    // Other players on the local network find the game via LAN discovery.
+    let conf = ConnectorConf::new(config.multiplayer().connector().port())
+        .with_lan_announce(SocketAddr::from((Ipv4Addr::BROADCAST, LAN_DISCOVERY_PORT)));
+    match EmbeddedConnector::start(conf) {
+        Ok(connector) => {
+            commands.insert_resource(LanModeRes);
+            commands.insert_resource(HostedConnectorRes::new(connector));
+            next_state.set(MultiplayerState::GameCreation);
+        }
+        Err(error) => {
+            toasts.send(ToastEvent::new(format!(
+                "Failed to start game server: {error}"
+            )));
+        }
+    }
+}
+
 fn response_system<T>(mut receiver: Receiver<T>, mut toasts: EventWriter<ToastEvent>)
 where
     T: LobbyRequest,
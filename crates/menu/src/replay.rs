@@ -0,0 +1,230 @@
+use async_std::{
+    fs, io,
+    path::{Path, PathBuf},
+    stream::StreamExt,
+};
+use bevy::{
+    prelude::*,
+    tasks::{futures_lite::future, IoTaskPool, Task},
+};
+use de_core::{
+    assets::asset_path,
+    fs::{replays_dir, DirError},
+    gconfig::{GameConfig, LocalPlayers},
+    state::AppState,
+};
+use de_gui::{ButtonCommands, GuiCommands, OuterStyle, ToastEvent};
+use de_map::hash::MapHash;
+use de_messages::{decode_replay, ReplayError, ReplayRecord, REPLAY_FILE_SUFFIX};
+use de_multiplayer::StartReplayEvent;
+use thiserror::Error;
+
+use crate::{mapselection::find_map, menu::Menu, MenuState};
+
+pub(crate) struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(MenuState::Replay), setup)
+            .add_systems(OnExit(MenuState::Replay), cleanup)
+            .add_systems(
+                Update,
+                (init_buttons, button_system, start_system).run_if(in_state(MenuState::Replay)),
+            );
+    }
+}
+
+#[derive(Resource)]
+struct ColumnNode(Entity);
+
+#[derive(Resource)]
+struct ListingTask(Task<Result<Vec<PathBuf>, ReplayLoadingError>>);
+
+#[derive(Resource)]
+struct LoadingTask(Task<Result<LoadedReplay, ReplayLoadingError>>);
+
+struct LoadedReplay {
+    map_path: PathBuf,
+    records: Vec<ReplayRecord>,
+}
+
+#[derive(Component)]
+struct ReplayEntry(PathBuf);
+
+#[derive(Error, Debug)]
+enum ReplayLoadingError {
+    #[error(transparent)]
+    Dir(#[from] DirError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Invalid replay: {0}")]
+    Replay(#[from] ReplayError),
+    #[error("Map of the replay is not available: {0:?}")]
+    MissingMap(PathBuf),
+}
+
+fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
+    let task = IoTaskPool::get().spawn(list_replays());
+    commands.insert_resource(ListingTask(task));
+
+    let column_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                width: Val::Percent(25.),
+                height: Val::Percent(100.),
+                margin: UiRect::all(Val::Auto),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(menu.root_node()).add_child(column_node);
+    commands.insert_resource(ColumnNode(column_node));
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ColumnNode>();
+    commands.remove_resource::<ListingTask>();
+    commands.remove_resource::<LoadingTask>();
+}
+
+fn init_buttons(
+    mut commands: GuiCommands,
+    node: Res<ColumnNode>,
+    task: Option<ResMut<ListingTask>>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let Some(mut task) = task else { return };
+    let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
+        return;
+    };
+    commands.remove_resource::<ListingTask>();
+
+    let replays = match result {
+        Ok(replays) => replays,
+        Err(error) => {
+            toasts.send(ToastEvent::new(format!("Replay listing error: {error}")));
+            return;
+        }
+    };
+
+    if replays.is_empty() {
+        toasts.send(ToastEvent::new("No replays found."));
+        return;
+    }
+
+    for path in replays {
+        let caption = path
+            .file_name()
+            .map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+        let button = commands
+            .spawn_button(
+                OuterStyle {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(8.),
+                    margin: UiRect::new(
+                        Val::Percent(0.),
+                        Val::Percent(0.),
+                        Val::Percent(2.),
+                        Val::Percent(2.),
+                    ),
+                },
+                caption,
+            )
+            .insert(ReplayEntry(path))
+            .id();
+        commands.entity(node.0).add_child(button);
+    }
+}
+
+fn button_system(
+    mut commands: Commands,
+    interactions: Query<(&Interaction, &ReplayEntry), Changed<Interaction>>,
+    loading: Option<Res<LoadingTask>>,
+) {
+    if loading.is_some() {
+        return;
+    }
+
+    for (&interaction, entry) in interactions.iter() {
+        if let Interaction::Pressed = interaction {
+            let task = IoTaskPool::get().spawn(load_replay(entry.0.clone()));
+            commands.insert_resource(LoadingTask(task));
+            break;
+        }
+    }
+}
+
+fn start_system(
+    mut commands: Commands,
+    task: Option<ResMut<LoadingTask>>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut replay_events: EventWriter<StartReplayEvent>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let Some(mut task) = task else { return };
+    let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
+        return;
+    };
+    commands.remove_resource::<LoadingTask>();
+
+    let replay = match result {
+        Ok(replay) => replay,
+        Err(error) => {
+            toasts.send(ToastEvent::new(format!("Replay loading error: {error}")));
+            return;
+        }
+    };
+
+    commands.insert_resource(GameConfig::new(
+        replay.map_path,
+        true,
+        LocalPlayers::observer(),
+    ));
+    replay_events.send(StartReplayEvent::new(replay.records));
+    app_state.set(AppState::InGame);
+}
+
+/// Returns paths to all replay files, the most recent first.
+async fn list_replays() -> Result<Vec<PathBuf>, ReplayLoadingError> {
+    let dir = replays_dir()?;
+    if !dir.is_dir().await {
+        return Ok(Vec::new());
+    }
+
+    let mut replays = Vec::new();
+    let mut dir_entries = fs::read_dir(dir).await?;
+    while let Some(entry) = dir_entries.next().await {
+        let path = entry?.path();
+        if is_replay(path.as_path()).await {
+            replays.push(path);
+        }
+    }
+
+    // File names start with a UNIX timestamp.
+    replays.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(replays)
+}
+
+async fn is_replay(path: &Path) -> bool {
+    path.is_file().await
+        && path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.ends_with(REPLAY_FILE_SUFFIX))
+}
+
+async fn load_replay(path: PathBuf) -> Result<LoadedReplay, ReplayLoadingError> {
+    let data = fs::read(path).await?;
+    let (header, records) = decode_replay(data.as_slice())?;
+    let map_hash = MapHash::from(*header.map_hash());
+    let Some(map_path) = find_map(&map_hash).map(PathBuf::from) else {
+        return Err(ReplayLoadingError::MissingMap(
+            map_hash.construct_path(asset_path("maps")),
+        ));
+    };
+    Ok(LoadedReplay { map_path, records })
+}
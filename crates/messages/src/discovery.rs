@@ -0,0 +1,146 @@
+use bincode::{
+    config::{BigEndian, Configuration, Limit, Varint},
+    decode_from_slice, encode_to_vec,
+    error::{DecodeError, EncodeError},
+    Decode, Encode,
+};
+use de_types::player::Player;
+
+use crate::PROTOCOL_VERSION;
+
+/// Default UDP port at which game clients listen to [`LanAnnouncement`]s.
+pub const LAN_DISCOVERY_PORT: u16 = 8083;
+
+/// Maximum size of an encoded [`LanAnnouncement`]. An announcement must fit
+/// into a single datagram.
+pub const MAX_ANNOUNCEMENT_SIZE: usize = 1024;
+
+const DISCOVERY_CONF: Configuration<BigEndian, Varint, Limit<MAX_ANNOUNCEMENT_SIZE>> =
+    bincode::config::standard()
+        .with_big_endian()
+        .with_variable_int_encoding()
+        .with_limit::<MAX_ANNOUNCEMENT_SIZE>();
+
+/// Message periodically sent by DE Connector to the local network so that
+/// game clients might discover it and its open games without DE Lobby.
+///
+/// Unlike all other messages, announcements are sent as plain datagrams,
+/// i.e. outside of the DE Connector network protocol.
+#[derive(Debug, Encode, Decode)]
+pub struct LanAnnouncement {
+    version: u32,
+    port: u16,
+    games: Vec<LanGame>,
+}
+
+impl LanAnnouncement {
+    /// # Arguments
+    ///
+    /// * `port` - port of the main server of the announcing DE Connector.
+    ///
+    /// * `games` - games which might be joined.
+    pub fn new(port: u16, games: Vec<LanGame>) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            port,
+            games,
+        }
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        decode_from_slice(data, DISCOVERY_CONF).map(|(announcement, _)| announcement)
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, EncodeError> {
+        encode_to_vec(self, DISCOVERY_CONF)
+    }
+
+    /// Protocol version of the announcing DE Connector, see
+    /// [`PROTOCOL_VERSION`].
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Port of the main server of the announcing DE Connector.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn games(&self) -> &[LanGame] {
+        self.games.as_slice()
+    }
+}
+
+/// A game open for joining announced on the local network.
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct LanGame {
+    port: u16,
+    max_players: Player,
+    players: u8,
+    map_hash: [u8; 32],
+}
+
+impl LanGame {
+    /// # Arguments
+    ///
+    /// * `port` - port of the game server.
+    ///
+    /// * `max_players` - maximum number of players of the game.
+    ///
+    /// * `players` - number of currently joined players.
+    ///
+    /// * `map_hash` - hash of the map the game is played on.
+    pub fn new(port: u16, max_players: Player, players: u8, map_hash: [u8; 32]) -> Self {
+        Self {
+            port,
+            max_players,
+            players,
+            map_hash,
+        }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn max_players(&self) -> Player {
+        self.max_players
+    }
+
+    pub fn players(&self) -> u8 {
+        self.players
+    }
+
+    pub fn map_hash(&self) -> &[u8; 32] {
+        &self.map_hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_announcement() {
+        let announcement = LanAnnouncement::new(
+            8082,
+            vec![
+                LanGame::new(8084, Player::Player4, 2, [3; 32]),
+                LanGame::new(8085, Player::Player2, 1, [4; 32]),
+            ],
+        );
+        let data = announcement.encode().unwrap();
+        assert!(data.len() <= MAX_ANNOUNCEMENT_SIZE);
+
+        let decoded = LanAnnouncement::decode(data.as_slice()).unwrap();
+        assert_eq!(decoded.version(), PROTOCOL_VERSION);
+        assert_eq!(decoded.port(), 8082);
+        assert_eq!(decoded.games().len(), 2);
+        assert_eq!(decoded.games()[1].port(), 8085);
+        assert_eq!(decoded.games()[1].max_players(), Player::Player2);
+        assert_eq!(decoded.games()[1].players(), 1);
+        assert_eq!(decoded.games()[1].map_hash(), &[4; 32]);
+
+        assert!(LanAnnouncement::decode(&data[..data.len() - 1]).is_err());
+    }
+}
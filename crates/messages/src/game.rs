@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use bincode::{Decode, Encode};
 use de_types::player::Player;
 
@@ -8,16 +10,97 @@ pub enum ToGame {
     /// Prompts the server to respond [`FromGame::Pong`] with the same ping ID.
     Ping(u32),
     /// Connect the player to the game.
-    Join,
+    Join {
+        /// Protocol version of the client, see [`crate::PROTOCOL_VERSION`].
+        version: u32,
+    },
     /// Disconnect the player from the game.
     ///
     /// The game is automatically closed once all players disconnect.
+    ///
+    /// Unlike a lost connection, a player leaving voluntarily is not given a
+    /// chance to rejoin the game (see [`ToGame::Rejoin`]).
     Leave,
     /// Sets readiness of the client.
     ///
     /// New readiness must be greater by one or equal to the current readiness.
     /// See [`Readiness::progress`].
+    ///
+    /// Readiness of spectators is ignored.
     Readiness(Readiness),
+    /// Connect the client to the game as a spectator.
+    ///
+    /// Spectators receive all messages sent among the players and all game
+    /// lifecycle messages but they cannot send any messages to the players.
+    /// Spectators do not occupy any player slot and the game does not wait for
+    /// them to progress its readiness.
+    ///
+    /// Spectators might join a running game as well. Such a spectator should
+    /// request the entities of the game with [`ToGame::Restore`] once the game
+    /// is loaded on their side, exactly like a rejoined player.
+    JoinSpectator {
+        /// Protocol version of the client, see [`crate::PROTOCOL_VERSION`].
+        version: u32,
+    },
+    /// Connect the player back to a running game after their connection was
+    /// lost.
+    ///
+    /// The player slot of a player whose connection to a running game (see
+    /// [`Readiness::Initialized`]) was lost is reserved for a grace period. During the period, the player might rejoin the game
+    /// under the original player number with the token received in
+    /// [`FromGame::Joined`]. Other players are informed via
+    /// [`FromGame::PeerLeft`] only once the grace period expires.
+    Rejoin {
+        /// Protocol version of the client, see [`crate::PROTOCOL_VERSION`].
+        version: u32,
+        token: RejoinToken,
+    },
+    /// Requests a snapshot of all entities in the game. This is meant to be
+    /// sent by a rejoined player once the game is loaded on their side.
+    ///
+    /// Player messages sent among the players are not delivered to a rejoined
+    /// player before this message is received by the server. The server
+    /// responds with player messages (as if sent by the simulating players)
+    /// spawning all entities in the game and bringing them to their last
+    /// known state, followed by [`FromGame::Restored`].
+    ///
+    /// Entities simulated by the rejoined player itself are part of the
+    /// snapshot. Their simulation should be resumed by the player.
+    Restore,
+    /// Removes a player from the game. Only the host (see
+    /// [`FromGame::Joined`]) might send this message.
+    ///
+    /// The kicked player receives [`FromGame::Kicked`] and the other players
+    /// receive [`FromGame::PeerLeft`]. Kicked players cannot join the game
+    /// again, see [`JoinError::Kicked`].
+    Kick(Player),
+    /// Locks (true) or unlocks (false) the game. No player or spectator might
+    /// join a locked game. Only the host might send this message and only
+    /// before the game starts (i.e. before the game readiness progresses from
+    /// [`Readiness::NotReady`]).
+    ///
+    /// All players and spectators receive [`FromGame::GameLocked`].
+    SetLocked(bool),
+    /// Changes the maximum number of players of the game. Only the host might
+    /// send this message and only before the game starts.
+    ///
+    /// The new maximum must not be lower than the number of any currently
+    /// joined player and it must not exceed the capacity of the map. All
+    /// players and spectators receive [`FromGame::GameMaxPlayers`].
+    SetMaxPlayers(Player),
+    /// Response to [`FromGame::Heartbeat`].
+    Heartbeat,
+    /// Requests a pause of a running game (see [`Readiness::Initialized`]).
+    ///
+    /// Each player has a limited pause budget, which is consumed while the
+    /// game is paused by the player. The game is automatically resumed once
+    /// the budget is exhausted. All players and spectators receive
+    /// [`FromGame::GamePaused`].
+    Pause,
+    /// Votes for resumption of the paused game. The game is resumed once all
+    /// connected players vote for it. All players and spectators receive
+    /// [`FromGame::ResumeVoted`] and eventually [`FromGame::GameResumed`].
+    Resume,
 }
 
 /// Message to be sent from a game server to a player/client (inside of a
@@ -35,13 +118,28 @@ pub enum FromGame {
     NotJoined,
     /// Informs the player that they were just connected to the game under the
     /// player number.
-    Joined(Player),
+    ///
+    /// The client who opened the game (always joined as [`Player::Player1`])
+    /// is the host of the game. The host might send host commands, e.g.
+    /// [`ToGame::Kick`]. The host role is not passed to any other player
+    /// once the host leaves.
+    Joined {
+        player: Player,
+        /// Token to be used to rejoin the game after a connection loss. See
+        /// [`ToGame::Rejoin`].
+        token: RejoinToken,
+    },
     /// Informs the player that they were not connected to the game due to an
     /// error.
     JoinError(JoinError),
-    /// Informs the player the they is no longer part of the game. They might
-    /// has been kicked out of the game or left voluntarily.
+    /// Informs the player the they is no longer part of the game, for example
+    /// after they left voluntarily. See also [`FromGame::Kicked`].
     Left,
+    /// Informs the player that they were kicked out of the game, either by
+    /// the host (see [`ToGame::Kick`]) or by the server due to repeatedly
+    /// sent invalid messages. The player is no longer part of the game and
+    /// cannot join it again.
+    Kicked,
     /// Informs the player that another player just connected to the same game
     /// under the given player number.
     PeerJoined(Player),
@@ -50,10 +148,72 @@ pub enum FromGame {
     PeerLeft(Player),
     /// Game readiness has changed.
     GameReadiness(Readiness),
+    /// Informs the client that they were just connected to the game as a
+    /// spectator. See [`ToGame::JoinSpectator`].
+    JoinedSpectator {
+        /// All players currently connected to the game. Further changes are
+        /// announced with [`FromGame::PeerJoined`] and [`FromGame::PeerLeft`].
+        players: Vec<Player>,
+        /// Current readiness of the game.
+        readiness: Readiness,
+    },
+    /// Informs the player that they were just connected back to the game. See
+    /// [`ToGame::Rejoin`].
+    Rejoined {
+        player: Player,
+        /// Number of times the player rejoined the game, including this time.
+        /// See [`crate::NetEntityIndex`].
+        session: u8,
+    },
+    /// All entities requested by [`ToGame::Restore`] were already sent.
+    Restored,
+    /// The game was locked (true) or unlocked (false) by the host. See
+    /// [`ToGame::SetLocked`].
+    GameLocked(bool),
+    /// The maximum number of players was changed by the host. See
+    /// [`ToGame::SetMaxPlayers`].
+    GameMaxPlayers(Player),
+    /// Informs the player that a host command was not executed due to an
+    /// error.
+    HostError(HostError),
+    /// Sent by the server to clients which have not sent any message for a
+    /// while. The client should respond with [`ToGame::Heartbeat`].
+    ///
+    /// Clients which do not send any message to the server for a
+    /// (server configured) timeout are considered dead and treated as if
+    /// their connection was lost.
+    Heartbeat,
+    /// The game was paused by a player, see [`ToGame::Pause`]. The game-play
+    /// should be frozen until [`FromGame::GameResumed`] is received.
+    GamePaused {
+        player: Player,
+        /// Remaining pause budget of the player. The game is resumed once it
+        /// is exhausted.
+        budget: Duration,
+    },
+    /// A player voted for resumption of the paused game, see
+    /// [`ToGame::Resume`].
+    ResumeVoted(Player),
+    /// The paused game was resumed.
+    GameResumed,
+    /// Informs the player that their pause or resume request was refused.
+    PauseError(PauseError),
+}
+
+/// Secret token identifying a player of a game. See [`ToGame::Rejoin`].
+#[derive(Clone, Copy, Debug, Encode, Decode, PartialEq, Eq, Hash)]
+pub struct RejoinToken(u128);
+
+impl RejoinToken {
+    pub fn new(token: u128) -> Self {
+        Self(token)
+    }
 }
 
 #[derive(Debug, Encode, Decode)]
 pub enum JoinError {
+    /// All player slots are occupied, or the maximum number of spectators is
+    /// reached when joining as a spectator.
     GameFull,
     /// The game is no longer opened.
     GameNotOpened,
@@ -61,6 +221,47 @@ pub enum JoinError {
     AlreadyJoined,
     /// The player already participates on a different game.
     DifferentGame,
+    /// The rejoin token is not valid. The grace period of the disconnected
+    /// player might have already expired.
+    InvalidToken,
+    /// The client uses a protocol version not supported by the server.
+    IncompatibleVersion {
+        /// Protocol version of the server.
+        server: u32,
+    },
+    /// The game was locked by the host, see [`ToGame::SetLocked`].
+    GameLocked,
+    /// The client was kicked out of the game, see [`FromGame::Kicked`].
+    Kicked,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub enum HostError {
+    /// The player is not the host of the game.
+    NotHost,
+    /// The command is allowed only before the game starts.
+    GameStarted,
+    /// The player to be kicked is not part of the game or it is the host
+    /// itself.
+    InvalidPlayer,
+    /// The maximum number of players cannot be lowered below the number of
+    /// an already joined player.
+    SlotOccupied,
+    /// The maximum number of players cannot be raised above the capacity of
+    /// the map, see [`crate::ToServer::OpenGame`].
+    MapCapacity,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub enum PauseError {
+    /// The game is not running, i.e. it has not started yet.
+    GameNotRunning,
+    /// The game is already paused.
+    AlreadyPaused,
+    /// The game is not paused.
+    NotPaused,
+    /// The pause budget of the player is exhausted.
+    BudgetExhausted,
 }
 
 /// Readiness of an individual client or the game as a whole. It consists of a
@@ -1,14 +1,32 @@
 //! This crate implements messages to be exchanged among players and DE
 //! Connector during multiplayer game.
 
-pub use game::{FromGame, JoinError, Readiness, ToGame};
+pub use discovery::{LanAnnouncement, LanGame, LAN_DISCOVERY_PORT, MAX_ANNOUNCEMENT_SIZE};
+pub use game::{FromGame, HostError, JoinError, PauseError, Readiness, RejoinToken, ToGame};
 pub use players::{
-    BorrowedFromPlayers, ChatMessage, ChatMessageError, EntityNet, FromPlayers, HealthDelta,
-    NetEntityIndex, NetProjectile, PathError, PathNet, ToPlayers, TransformNet, Vec2Net, Vec3Net,
-    Vec4Net, MAX_CHAT_LEN,
+    BorrowedFromPlayers, ChatMessage, ChatMessageError, ChecksumsError, ChecksumsNet,
+    EntityChecksumNet, EntityDeltaNet, EntityNet, EntityStateNet, FromPlayers, HealthDelta,
+    NetEntityIndex, NetEntityIndexError, NetProjectile, PathError, PathNet, PathProgressNet,
+    SnapshotError, SnapshotPartNet, ToPlayers, TransformNet, Vec2Net, Vec3Net, Vec4Net,
+    MAX_CHAT_LEN, MAX_CHECKSUMS, MAX_SNAPSHOT_PART_LEN,
+};
+pub use replay::{
+    decode_replay, encode_replay_item, BorrowedReplayEvent, BorrowedReplayRecord, ReplayError,
+    ReplayEvent, ReplayHeader, ReplayRecord, REPLAY_FILE_SUFFIX,
 };
 pub use server::{FromServer, GameOpenError, ToServer};
 
+/// Version of the protocol, i.e. of the messages, implemented by this crate.
+/// It must be increased with every backward incompatible change.
+///
+/// The version is the first field of all messages initiating communication
+/// with the server (see [`ToServer::OpenGame`], [`ToGame::Join`],
+/// [`ToGame::JoinSpectator`] and [`ToGame::Rejoin`]). This keeps it decodable
+/// even when the rest of the message is not.
+pub const PROTOCOL_VERSION: u32 = 5;
+
+mod discovery;
 mod game;
 mod players;
+mod replay;
 mod server;
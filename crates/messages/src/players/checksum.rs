@@ -0,0 +1,107 @@
+use bincode::{Decode, Encode};
+use thiserror::Error;
+
+use super::{EntityNet, Vec2Net};
+
+/// Maximum number of entity checksums in a single checksum message.
+pub const MAX_CHECKSUMS: usize = 2048;
+
+/// Checksums of all entities simulated by a player.
+#[derive(Debug, Encode, Decode)]
+pub struct ChecksumsNet(Vec<EntityChecksumNet>);
+
+impl ChecksumsNet {
+    pub fn entities(&self) -> &[EntityChecksumNet] {
+        self.0.as_slice()
+    }
+}
+
+impl TryFrom<Vec<EntityChecksumNet>> for ChecksumsNet {
+    type Error = ChecksumsError;
+
+    fn try_from(entities: Vec<EntityChecksumNet>) -> Result<Self, Self::Error> {
+        if entities.len() > MAX_CHECKSUMS {
+            Err(ChecksumsError::TooMany {
+                len: entities.len(),
+                max_len: MAX_CHECKSUMS,
+            })
+        } else {
+            Ok(Self(entities))
+        }
+    }
+}
+
+/// Summary of the state of a single entity.
+#[derive(Clone, Copy, Debug, Encode, Decode)]
+pub struct EntityChecksumNet {
+    entity: EntityNet,
+    position: Vec2Net,
+    checksum: u32,
+}
+
+impl EntityChecksumNet {
+    /// # Arguments
+    ///
+    /// * `entity` - the summarized entity.
+    ///
+    /// * `position` - position of the entity projected to the map plane. It
+    ///   is not part of the checksum because it is synchronized only
+    ///   approximately and thus must be compared with a tolerance.
+    ///
+    /// * `checksum` - hash of the exactly synchronized state of the entity.
+    pub fn new(entity: EntityNet, position: Vec2Net, checksum: u32) -> Self {
+        Self {
+            entity,
+            position,
+            checksum,
+        }
+    }
+
+    pub fn entity(&self) -> EntityNet {
+        self.entity
+    }
+
+    pub fn position(&self) -> Vec2Net {
+        self.position
+    }
+
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ChecksumsError {
+    #[error("Too many entity checksums: {len} > {max_len}")]
+    TooMany { len: usize, max_len: usize },
+}
+
+#[cfg(test)]
+mod tests {
+    use de_types::player::Player;
+
+    use super::*;
+    use crate::NetEntityIndex;
+
+    #[test]
+    fn test_try_from() {
+        let checksum = EntityChecksumNet::new(
+            EntityNet::new(Player::Player1, NetEntityIndex::new(1, 0).unwrap()),
+            Vec2Net::from(glam::Vec2::new(1., 2.)),
+            42,
+        );
+
+        let checksums = ChecksumsNet::try_from(vec![checksum; MAX_CHECKSUMS]).unwrap();
+        assert_eq!(checksums.entities().len(), MAX_CHECKSUMS);
+        assert_eq!(checksums.entities()[0].checksum(), 42);
+
+        assert!(ChecksumsNet::try_from(Vec::new()).is_ok());
+        assert!(matches!(
+            ChecksumsNet::try_from(vec![checksum; MAX_CHECKSUMS + 1]),
+            Err(ChecksumsError::TooMany {
+                len: 2049,
+                max_len: MAX_CHECKSUMS
+            })
+        ));
+    }
+}
@@ -2,6 +2,7 @@
 use bevy::ecs::entity::Entity;
 use bincode::{Decode, Encode};
 use de_types::player::Player;
+use thiserror::Error;
 
 /// Bevy ECS Entity derived identification of an entity.
 #[derive(Clone, Copy, Debug, Encode, Decode, Hash, PartialEq, Eq)]
@@ -30,9 +31,51 @@ impl EntityNet {
     }
 }
 
+/// Index of an entity unique among all entities simulated by a single player.
+///
+/// The index is composed of the index of the local ECS entity and of the
+/// session of the simulating player. The session is increased every time the
+/// player rejoins the game (see [`crate::ToGame::Rejoin`]), which keeps
+/// indices of entities spawned after a rejoin distinct from indices of
+/// entities spawned before it.
 #[derive(Clone, Copy, Debug, Encode, Decode, Hash, PartialEq, Eq)]
 pub struct NetEntityIndex(u32);
 
+impl NetEntityIndex {
+    const SESSION_SHIFT: u32 = 24;
+    const INDEX_MASK: u32 = (1 << Self::SESSION_SHIFT) - 1;
+
+    /// # Arguments
+    ///
+    /// * `index` - index of the local ECS entity.
+    ///
+    /// * `session` - number of times the simulating player rejoined the game.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `index` does not fit to 24 bits.
+    pub fn new(index: u32, session: u8) -> Result<Self, NetEntityIndexError> {
+        if index > Self::INDEX_MASK {
+            return Err(NetEntityIndexError::TooLarge {
+                index,
+                max: Self::INDEX_MASK,
+            });
+        }
+        Ok(Self(index | (u32::from(session) << Self::SESSION_SHIFT)))
+    }
+
+    /// Index of the local ECS entity on the simulating computer.
+    pub fn entity_index(self) -> u32 {
+        self.0 & Self::INDEX_MASK
+    }
+
+    /// Number of times the simulating player rejoined the game before the
+    /// entity was spawned.
+    pub fn session(self) -> u8 {
+        (self.0 >> Self::SESSION_SHIFT) as u8
+    }
+}
+
 impl From<NetEntityIndex> for u32 {
     fn from(index: NetEntityIndex) -> u32 {
         index.0
@@ -40,8 +83,44 @@ impl From<NetEntityIndex> for u32 {
 }
 
 #[cfg(feature = "bevy")]
-impl From<Entity> for NetEntityIndex {
-    fn from(entity: Entity) -> Self {
-        Self(entity.index())
+impl TryFrom<Entity> for NetEntityIndex {
+    type Error = NetEntityIndexError;
+
+    /// Creates an index of an entity spawned during the first session of the
+    /// player, see [`NetEntityIndex::new`].
+    fn try_from(entity: Entity) -> Result<Self, Self::Error> {
+        Self::new(entity.index(), 0)
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum NetEntityIndexError {
+    #[error("Entity index {index} is too large: max {max}")]
+    TooLarge { index: u32, max: u32 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index() {
+        let index = NetEntityIndex::new(42, 0).unwrap();
+        assert_eq!(u32::from(index), 42);
+        assert_eq!(index.entity_index(), 42);
+        assert_eq!(index.session(), 0);
+
+        let index = NetEntityIndex::new(0xff_ffff, 3).unwrap();
+        assert_eq!(index.entity_index(), 0xff_ffff);
+        assert_eq!(index.session(), 3);
+        assert_ne!(index, NetEntityIndex::new(0xff_ffff, 2).unwrap());
+
+        assert_eq!(
+            NetEntityIndex::new(0x100_0000, 0),
+            Err(NetEntityIndexError::TooLarge {
+                index: 0x100_0000,
+                max: 0xff_ffff
+            })
+        );
     }
 }
@@ -8,12 +8,27 @@ use nalgebra::{Point2, Point3, Point4, Vector2, Vector3, Vector4};
 
 /// Network representation of translation and rotation. Note that scale is
 /// assumed to be always 1.0 along all axes.
-#[derive(Debug, Encode, Decode)]
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
 pub struct TransformNet {
     translation: Vec3Net,
     rotation: Vec4Net,
 }
 
+impl TransformNet {
+    /// # Arguments
+    ///
+    /// * `translation` - translation of the object.
+    ///
+    /// * `rotation` - rotation of the object represented by a unit quaternion
+    ///   (x, y, z, w).
+    pub fn new(translation: Vec3Net, rotation: Vec4Net) -> Self {
+        Self {
+            translation,
+            rotation,
+        }
+    }
+}
+
 #[cfg(feature = "bevy")]
 impl From<&Transform> for TransformNet {
     fn from(transform: &Transform) -> Self {
@@ -47,7 +62,7 @@ impl From<&TransformNet> for Transform {
     }
 }
 
-#[derive(Clone, Copy, Debug, Encode, Decode)]
+#[derive(Clone, Copy, Debug, PartialEq, Encode, Decode)]
 pub struct Vec2Net {
     x: f32,
     y: f32,
@@ -95,7 +110,7 @@ impl From<Vec2Net> for Vector2<f32> {
     }
 }
 
-#[derive(Clone, Copy, Debug, Encode, Decode)]
+#[derive(Clone, Copy, Debug, PartialEq, Encode, Decode)]
 pub struct Vec3Net {
     x: f32,
     y: f32,
@@ -150,7 +165,7 @@ impl From<Vec3Net> for Vector3<f32> {
     }
 }
 
-#[derive(Clone, Copy, Debug, Encode, Decode)]
+#[derive(Clone, Copy, Debug, PartialEq, Encode, Decode)]
 pub struct Vec4Net {
     x: f32,
     y: f32,
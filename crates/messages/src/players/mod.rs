@@ -1,16 +1,25 @@
+use std::time::Duration;
+
 use bincode::{Decode, Encode};
 pub use chat::{ChatMessage, ChatMessageError, MAX_CHAT_LEN};
+pub use checksum::{ChecksumsError, ChecksumsNet, EntityChecksumNet, MAX_CHECKSUMS};
 use de_types::{objects::ActiveObjectType, player::Player};
-pub use entity::{EntityNet, NetEntityIndex};
+pub use entity::{EntityNet, NetEntityIndex, NetEntityIndexError};
 pub use geom::{TransformNet, Vec2Net, Vec3Net, Vec4Net};
 pub use path::{PathError, PathNet};
 pub use projectile::NetProjectile;
+pub use snapshot::{
+    EntityDeltaNet, EntityStateNet, PathProgressNet, SnapshotError, SnapshotPartNet,
+    MAX_SNAPSHOT_PART_LEN,
+};
 
 mod chat;
+mod checksum;
 mod entity;
 mod geom;
 mod path;
 mod projectile;
+mod snapshot;
 
 /// Messages to be sent by a player/client or occasionally the game server to
 /// other players.
@@ -80,6 +89,9 @@ pub enum ToPlayers {
     /// then continues following the path.
     Transform {
         entity: EntityNet,
+        /// Simulation time of the sending player at which the object had the
+        /// transform.
+        time: Duration,
         transform: TransformNet,
     },
     /// Changes entity health by an amount.
@@ -89,6 +101,29 @@ pub enum ToPlayers {
     },
     /// Some kind of projectile was spawned (e.g. rocket, laser trail).
     Projectile(NetProjectile),
+    /// Periodic summary of the state of all objects simulated by the sending
+    /// player. Other players compare it with the state of their replicas to
+    /// detect desynchronization.
+    Checksum {
+        /// Sequence number of the summary, increased with every sent
+        /// checksum message.
+        round: u32,
+        entities: ChecksumsNet,
+    },
+    /// Asks the player simulating an object to re-send its transform and
+    /// path.
+    Resync {
+        entity: EntityNet,
+    },
+    /// A part of a periodic snapshot of the state of all entities simulated
+    /// by the sending player.
+    Snapshot(SnapshotPartNet),
+    /// Acknowledges that all parts of a snapshot were received.
+    SnapshotAck {
+        /// The player who sent the snapshot.
+        player: Player,
+        sequence: u32,
+    },
 }
 
 #[derive(Debug, Encode, Decode)]
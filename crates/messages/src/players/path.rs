@@ -7,9 +7,9 @@ use thiserror::Error;
 
 use super::Vec2Net;
 
-const MAX_PATH_SIZE: usize = 480;
+const MAX_PATH_SIZE: usize = 16 * 1024;
 
-#[derive(Debug, Encode, Decode)]
+#[derive(Clone, Debug, Encode, Decode)]
 pub struct PathNet(Vec<Vec2Net>);
 
 impl TryFrom<&Path> for PathNet {
@@ -0,0 +1,377 @@
+use std::time::Duration;
+
+use bincode::{Decode, Encode};
+use thiserror::Error;
+
+use super::{EntityNet, TransformNet, Vec2Net};
+
+/// Maximum number of changed and removed entities in a single snapshot part.
+/// This keeps each part within a single unreliable package.
+pub const MAX_SNAPSHOT_PART_LEN: usize = 6;
+/// Maximum number of parts of a single snapshot.
+const MAX_SNAPSHOT_PARTS: usize = u8::MAX as usize;
+
+/// A part of a periodic snapshot of the state of all entities simulated by a
+/// player.
+///
+/// Snapshots are delta-encoded against a baseline snapshot, i.e. only
+/// entities which changed since the baseline are included together with
+/// entities which no longer exist. A snapshot without a baseline includes all
+/// entities.
+///
+/// A snapshot is split into multiple parts so that each part fits into a
+/// single package. Every entity is included in at most one part, thus each
+/// part might be applied on its own against the baseline. The snapshot is
+/// complete, and might be used as a baseline, only once all its parts are
+/// received.
+#[derive(Clone, Debug, Encode, Decode)]
+pub struct SnapshotPartNet {
+    sequence: u32,
+    baseline: Option<u32>,
+    time: Duration,
+    part: u8,
+    parts: u8,
+    changed: Vec<EntityDeltaNet>,
+    removed: Vec<EntityNet>,
+}
+
+impl SnapshotPartNet {
+    /// Splits a snapshot into parts.
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - sequence number of the snapshot, increased with every
+    ///   snapshot.
+    ///
+    /// * `baseline` - sequence number of the snapshot this snapshot is
+    ///   delta-encoded against.
+    ///
+    /// * `time` - simulation time of the sending player at which the
+    ///   snapshot was taken.
+    ///
+    /// * `changed` - entities which changed since the baseline.
+    ///
+    /// * `removed` - entities which are part of the baseline but no longer
+    ///   exist.
+    pub fn split(
+        sequence: u32,
+        baseline: Option<u32>,
+        time: Duration,
+        changed: Vec<EntityDeltaNet>,
+        removed: Vec<EntityNet>,
+    ) -> Result<Vec<Self>, SnapshotError> {
+        let len = changed.len() + removed.len();
+        let parts = len.div_ceil(MAX_SNAPSHOT_PART_LEN).max(1);
+        if parts > MAX_SNAPSHOT_PARTS {
+            return Err(SnapshotError::TooLarge {
+                len,
+                max_len: MAX_SNAPSHOT_PARTS * MAX_SNAPSHOT_PART_LEN,
+            });
+        }
+
+        let mut changed = changed.into_iter();
+        let mut removed = removed.into_iter();
+        Ok((0..parts)
+            .map(|part| {
+                let part_changed: Vec<EntityDeltaNet> =
+                    changed.by_ref().take(MAX_SNAPSHOT_PART_LEN).collect();
+                let part_removed: Vec<EntityNet> = removed
+                    .by_ref()
+                    .take(MAX_SNAPSHOT_PART_LEN - part_changed.len())
+                    .collect();
+
+                Self {
+                    sequence,
+                    baseline,
+                    time,
+                    part: part as u8,
+                    parts: parts as u8,
+                    changed: part_changed,
+                    removed: part_removed,
+                }
+            })
+            .collect())
+    }
+
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    pub fn baseline(&self) -> Option<u32> {
+        self.baseline
+    }
+
+    /// Simulation time of the sending player at which the snapshot was
+    /// taken.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// Index of this part.
+    pub fn part(&self) -> u8 {
+        self.part
+    }
+
+    /// Total number of parts of the snapshot.
+    pub fn parts(&self) -> u8 {
+        self.parts
+    }
+
+    pub fn changed(&self) -> &[EntityDeltaNet] {
+        self.changed.as_slice()
+    }
+
+    pub fn removed(&self) -> &[EntityNet] {
+        self.removed.as_slice()
+    }
+}
+
+/// Replicated state of a single entity.
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
+pub struct EntityStateNet {
+    transform: TransformNet,
+    health: f32,
+    path: Option<PathProgressNet>,
+}
+
+impl EntityStateNet {
+    /// # Arguments
+    ///
+    /// * `transform` - transform of the entity.
+    ///
+    /// * `health` - current (not clamped) health of the entity.
+    ///
+    /// * `path` - progress along the path followed by the entity, if any.
+    pub fn new(transform: TransformNet, health: f32, path: Option<PathProgressNet>) -> Self {
+        Self {
+            transform,
+            health,
+            path,
+        }
+    }
+
+    pub fn transform(&self) -> &TransformNet {
+        &self.transform
+    }
+
+    pub fn health(&self) -> f32 {
+        self.health
+    }
+
+    pub fn path(&self) -> Option<&PathProgressNet> {
+        self.path.as_ref()
+    }
+}
+
+/// Progress of an entity along its path.
+#[derive(Clone, Copy, Debug, PartialEq, Encode, Decode)]
+pub struct PathProgressNet {
+    destination: Vec2Net,
+    progress: u16,
+}
+
+impl PathProgressNet {
+    /// # Arguments
+    ///
+    /// * `destination` - final point of the path.
+    ///
+    /// * `progress` - number of path way-points not yet reached.
+    pub fn new(destination: Vec2Net, progress: u16) -> Self {
+        Self {
+            destination,
+            progress,
+        }
+    }
+
+    pub fn destination(&self) -> Vec2Net {
+        self.destination
+    }
+
+    pub fn progress(&self) -> u16 {
+        self.progress
+    }
+}
+
+/// Changes of the state of a single entity since a baseline snapshot.
+#[derive(Clone, Debug, Encode, Decode)]
+pub struct EntityDeltaNet {
+    entity: EntityNet,
+    transform: Option<TransformNet>,
+    health: Option<f32>,
+    /// None if the path did not change.
+    path: Option<Option<PathProgressNet>>,
+}
+
+impl EntityDeltaNet {
+    /// Returns changes of the state of an entity or None if the state did not
+    /// change.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - the entity.
+    ///
+    /// * `baseline` - state of the entity in the baseline snapshot. All state
+    ///   is included in the delta if this is None.
+    ///
+    /// * `current` - current state of the entity.
+    pub fn new(
+        entity: EntityNet,
+        baseline: Option<&EntityStateNet>,
+        current: &EntityStateNet,
+    ) -> Option<Self> {
+        let delta = Self {
+            entity,
+            transform: baseline
+                .is_none_or(|b| b.transform != current.transform)
+                .then(|| current.transform.clone()),
+            health: baseline
+                .is_none_or(|b| b.health != current.health)
+                .then_some(current.health),
+            path: baseline
+                .is_none_or(|b| b.path != current.path)
+                .then_some(current.path),
+        };
+
+        if delta.transform.is_none() && delta.health.is_none() && delta.path.is_none() {
+            None
+        } else {
+            Some(delta)
+        }
+    }
+
+    pub fn entity(&self) -> EntityNet {
+        self.entity
+    }
+
+    /// New transform of the entity, None if it did not change.
+    pub fn transform(&self) -> Option<&TransformNet> {
+        self.transform.as_ref()
+    }
+
+    /// Applies the changes to the baseline state of the entity.
+    ///
+    /// Returns None if there is no baseline state and the delta does not
+    /// include the complete state.
+    pub fn apply(&self, baseline: Option<&EntityStateNet>) -> Option<EntityStateNet> {
+        Some(EntityStateNet {
+            transform: self
+                .transform
+                .clone()
+                .or_else(|| baseline.map(|b| b.transform.clone()))?,
+            health: self.health.or_else(|| baseline.map(|b| b.health))?,
+            path: self.path.or_else(|| baseline.map(|b| b.path))?,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Too many entities in a snapshot: {len} > {max_len}")]
+    TooLarge { len: usize, max_len: usize },
+}
+
+#[cfg(test)]
+mod tests {
+    use bincode::{config, encode_to_vec};
+    use de_types::player::Player;
+    use glam::{Vec2, Vec3, Vec4};
+
+    use super::*;
+    use crate::{NetEntityIndex, ToPlayers};
+
+    fn entity(index: u32) -> EntityNet {
+        EntityNet::new(Player::Player4, NetEntityIndex::new(index, 3).unwrap())
+    }
+
+    fn state(x: f32, health: f32, path: Option<PathProgressNet>) -> EntityStateNet {
+        EntityStateNet::new(
+            TransformNet::new(Vec3::new(x, 1., 2.).into(), Vec4::W.into()),
+            health,
+            path,
+        )
+    }
+
+    #[test]
+    fn test_delta() {
+        let path = PathProgressNet::new(Vec2::new(1., 2.).into(), 3);
+        let baseline = state(1., 10., Some(path));
+
+        assert!(EntityDeltaNet::new(entity(1), Some(&baseline), &baseline).is_none());
+
+        let current = state(1., 8., Some(path));
+        let delta = EntityDeltaNet::new(entity(1), Some(&baseline), &current).unwrap();
+        assert_eq!(delta.entity(), entity(1));
+        assert!(delta.transform.is_none());
+        assert_eq!(delta.health, Some(8.));
+        assert!(delta.path.is_none());
+        assert_eq!(delta.apply(Some(&baseline)).unwrap(), current);
+        assert!(delta.apply(None).is_none());
+
+        let current = state(2., 10., None);
+        let delta = EntityDeltaNet::new(entity(1), Some(&baseline), &current).unwrap();
+        assert!(delta.health.is_none());
+        assert_eq!(delta.path, Some(None));
+        assert_eq!(delta.apply(Some(&baseline)).unwrap(), current);
+
+        let delta = EntityDeltaNet::new(entity(1), None, &baseline).unwrap();
+        assert_eq!(delta.apply(None).unwrap(), baseline);
+        assert_eq!(delta.apply(Some(&current)).unwrap(), baseline);
+    }
+
+    #[test]
+    fn test_split() {
+        let parts =
+            SnapshotPartNet::split(7, Some(5), Duration::from_secs(3), Vec::new(), Vec::new())
+                .unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].sequence(), 7);
+        assert_eq!(parts[0].baseline(), Some(5));
+        assert_eq!(parts[0].time(), Duration::from_secs(3));
+        assert_eq!(parts[0].part(), 0);
+        assert_eq!(parts[0].parts(), 1);
+        assert!(parts[0].changed().is_empty());
+        assert!(parts[0].removed().is_empty());
+
+        let changed: Vec<EntityDeltaNet> = (0..10)
+            .map(|i| EntityDeltaNet::new(entity(i), None, &state(1e6, 1e6, None)).unwrap())
+            .collect();
+        let removed: Vec<EntityNet> = (10..20).map(entity).collect();
+        let parts =
+            SnapshotPartNet::split(8, None, Duration::from_secs(3600), changed, removed).unwrap();
+        assert_eq!(parts.len(), 4);
+        for (i, part) in parts.iter().enumerate() {
+            assert_eq!(part.part(), i as u8);
+            assert_eq!(part.parts(), 4);
+
+            let len = encode_to_vec(
+                ToPlayers::Snapshot(part.clone()),
+                config::standard()
+                    .with_big_endian()
+                    .with_variable_int_encoding(),
+            )
+            .unwrap()
+            .len();
+            assert!(len < 400, "{len}");
+        }
+        assert_eq!(parts[0].changed().len(), 6);
+        assert!(parts[0].removed().is_empty());
+        assert_eq!(parts[1].changed().len(), 4);
+        assert_eq!(parts[1].removed().len(), 2);
+        assert_eq!(parts[1].removed()[0], entity(10));
+        assert_eq!(parts[3].removed().len(), 2);
+
+        assert!(matches!(
+            SnapshotPartNet::split(
+                9,
+                None,
+                Duration::ZERO,
+                Vec::new(),
+                (0..2000).map(entity).collect()
+            ),
+            Err(SnapshotError::TooLarge {
+                len: 2000,
+                max_len: 1530
+            })
+        ));
+    }
+}
@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use bincode::{
+    config::{BigEndian, Configuration, Varint},
+    decode_from_slice, encode_into_std_write,
+    error::{DecodeError, EncodeError},
+    Decode, Encode,
+};
+use de_types::player::Player;
+use thiserror::Error;
+
+use crate::{BorrowedFromPlayers, FromPlayers, Readiness};
+
+/// Canonical file name suffix of replay files.
+pub const REPLAY_FILE_SUFFIX: &str = ".dereplay";
+
+/// Version of the replay format. It is increased whenever a backward
+/// incompatible change to the replay format or to any of the recorded
+/// messages is made.
+const REPLAY_VERSION: u16 = 1;
+
+const REPLAY_CONF: Configuration<BigEndian, Varint> = bincode::config::standard()
+    .with_big_endian()
+    .with_variable_int_encoding();
+
+/// Header of a replay file.
+///
+/// A replay file consists of the header followed by zero or more replay
+/// records ([`BorrowedReplayRecord`] when writing and [`ReplayRecord`] when
+/// reading), all encoded one after another till the end of the file.
+#[derive(Debug, Encode, Decode)]
+pub struct ReplayHeader {
+    version: u16,
+    map_hash: [u8; 32],
+    max_players: Player,
+}
+
+impl ReplayHeader {
+    /// # Arguments
+    ///
+    /// * `map_hash` - hash of the map the game is played on.
+    ///
+    /// * `max_players` - maximum number of players of the recorded game.
+    pub fn new(map_hash: [u8; 32], max_players: Player) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            map_hash,
+            max_players,
+        }
+    }
+
+    pub fn map_hash(&self) -> &[u8; 32] {
+        &self.map_hash
+    }
+
+    pub fn max_players(&self) -> Player {
+        self.max_players
+    }
+}
+
+/// A single replay record.
+#[derive(Debug, Decode)]
+pub struct ReplayRecord {
+    /// Time elapsed since the game was opened.
+    time: Duration,
+    event: ReplayEvent,
+}
+
+impl ReplayRecord {
+    /// Time elapsed since the recorded game was opened.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    pub fn event(&self) -> &ReplayEvent {
+        &self.event
+    }
+
+    pub fn into_event(self) -> ReplayEvent {
+        self.event
+    }
+}
+
+/// A single replay record.
+#[derive(Debug, Encode)]
+pub struct BorrowedReplayRecord<'a> {
+    time: Duration,
+    event: BorrowedReplayEvent<'a>,
+}
+
+impl<'a> BorrowedReplayRecord<'a> {
+    /// # Arguments
+    ///
+    /// * `time` - time elapsed since the recorded game was opened.
+    ///
+    /// * `event` - recorded event.
+    pub fn new(time: Duration, event: BorrowedReplayEvent<'a>) -> Self {
+        Self { time, event }
+    }
+}
+
+#[derive(Debug, Decode)]
+pub enum ReplayEvent {
+    /// A player joined the game.
+    PeerJoined(Player),
+    /// A player left the game.
+    PeerLeft(Player),
+    /// Readiness of the game as a whole has changed.
+    GameReadiness(Readiness),
+    /// A message was relayed among the players.
+    Message(FromPlayers),
+}
+
+#[derive(Debug, Encode, Clone, Copy)]
+pub enum BorrowedReplayEvent<'a> {
+    /// See [`ReplayEvent::PeerJoined`].
+    PeerJoined(Player),
+    /// See [`ReplayEvent::PeerLeft`].
+    PeerLeft(Player),
+    /// See [`ReplayEvent::GameReadiness`].
+    GameReadiness(Readiness),
+    /// See [`ReplayEvent::Message`].
+    Message(BorrowedFromPlayers<'a>),
+}
+
+/// Encodes a replay header or a replay record and appends it to `data`.
+pub fn encode_replay_item<E: Encode>(item: &E, data: &mut Vec<u8>) -> Result<(), EncodeError> {
+    encode_into_std_write(item, data, REPLAY_CONF).map(|_| ())
+}
+
+/// Decodes whole replay file.
+pub fn decode_replay(data: &[u8]) -> Result<(ReplayHeader, Vec<ReplayRecord>), ReplayError> {
+    let (header, mut offset): (ReplayHeader, usize) = decode_from_slice(data, REPLAY_CONF)?;
+    if header.version != REPLAY_VERSION {
+        return Err(ReplayError::Version(header.version));
+    }
+
+    let mut records = Vec::new();
+    while offset < data.len() {
+        let (record, len) = decode_from_slice(&data[offset..], REPLAY_CONF)?;
+        records.push(record);
+        offset += len;
+    }
+
+    Ok((header, records))
+}
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("Unsupported replay version {0}.")]
+    Version(u16),
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChatMessage, ToPlayers};
+
+    #[test]
+    fn test_replay() {
+        let message = ToPlayers::Chat(ChatMessage::try_from("Hello!".to_owned()).unwrap());
+
+        let mut data = Vec::new();
+        encode_replay_item(&ReplayHeader::new([7; 32], Player::Player3), &mut data).unwrap();
+        encode_replay_item(
+            &BorrowedReplayRecord::new(
+                Duration::from_millis(10),
+                BorrowedReplayEvent::PeerJoined(Player::Player2),
+            ),
+            &mut data,
+        )
+        .unwrap();
+        encode_replay_item(
+            &BorrowedReplayRecord::new(
+                Duration::from_millis(1500),
+                BorrowedReplayEvent::Message(BorrowedFromPlayers::new(Player::Player2, &message)),
+            ),
+            &mut data,
+        )
+        .unwrap();
+
+        let (header, records) = decode_replay(data.as_slice()).unwrap();
+        assert_eq!(header.map_hash(), &[7; 32]);
+        assert_eq!(header.max_players(), Player::Player3);
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].time(), Duration::from_millis(10));
+        assert!(matches!(
+            records[0].event(),
+            ReplayEvent::PeerJoined(Player::Player2)
+        ));
+
+        assert_eq!(records[1].time(), Duration::from_millis(1500));
+        let ReplayEvent::Message(message) = records[1].event() else {
+            panic!("Unexpected replay event: {:?}", records[1].event());
+        };
+        assert_eq!(message.source(), Player::Player2);
+        let ToPlayers::Chat(chat) = message.message() else {
+            panic!("Unexpected message: {:?}", message.message());
+        };
+        assert_eq!(format!("{chat:?}"), "ChatMessage(\"Hello!\")");
+    }
+
+    #[test]
+    fn test_version() {
+        let mut header = ReplayHeader::new([0; 32], Player::Player2);
+        header.version = REPLAY_VERSION + 1;
+
+        let mut data = Vec::new();
+        encode_replay_item(&header, &mut data).unwrap();
+        assert!(matches!(
+            decode_replay(data.as_slice()),
+            Err(ReplayError::Version(version)) if version == REPLAY_VERSION + 1
+        ));
+    }
+}
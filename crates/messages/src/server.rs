@@ -9,7 +9,18 @@ pub enum ToServer {
     Ping(u32),
     /// This message opens a new game on the server. The server responds with
     /// [`FromServer::GameOpened`].
-    OpenGame { max_players: Player },
+    OpenGame {
+        /// Protocol version of the client, see [`crate::PROTOCOL_VERSION`].
+        version: u32,
+        max_players: Player,
+        /// Maximum number of players supported by the map. It must not be
+        /// lower than `max_players` and the host cannot raise the maximum
+        /// number of players above it, see [`crate::ToGame::SetMaxPlayers`].
+        map_max_players: Player,
+        /// Hash of the map the game is going to be played on. It is used for
+        /// game replay recording.
+        map_hash: [u8; 32],
+    },
 }
 
 /// Message to be sent from a main server to a player/client (outside of a
@@ -22,6 +33,12 @@ pub enum FromServer {
     GameOpened {
         /// Port at which players may connect to join the game.
         port: u16,
+        /// Random number the game secret is derived from by the lobby. All
+        /// datagrams exchanged with the game server must be authenticated
+        /// with the secret.
+        ///
+        /// None if the game server does not require authentication.
+        nonce: Option<u64>,
     },
     GameOpenError(GameOpenError),
 }
@@ -30,4 +47,11 @@ pub enum FromServer {
 pub enum GameOpenError {
     /// The player opening the game has already joined a different game.
     DifferentGame,
+    /// The client uses a protocol version not supported by the server.
+    IncompatibleVersion {
+        /// Protocol version of the server.
+        server: u32,
+    },
+    /// The maximum number of players exceeds the capacity of the map.
+    TooManyPlayers,
 }
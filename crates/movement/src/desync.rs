@@ -0,0 +1,471 @@
+use std::time::Duration;
+
+use ahash::{AHashMap, AHashSet};
+use bevy::prelude::*;
+use de_core::{
+    gamestate::GameState,
+    gconfig::{is_multiplayer, GameConfig},
+    objects::{Active, Local},
+    schedule::Movement,
+    state::AppState,
+};
+use de_messages::{ChecksumsNet, EntityChecksumNet, EntityNet, ToPlayers, MAX_CHECKSUMS};
+use de_multiplayer::{NetEntities, NetRecvChecksumEvent, NetRecvResyncEvent, ToPlayersEvent};
+use de_objects::Health;
+use de_pathing::ScheduledPath;
+use de_types::{player::Player, projection::ToFlat};
+
+use crate::movement::MovementSet;
+
+const CHECKSUM_PERIOD: Duration = Duration::from_secs(5);
+/// Number of consecutive checksum rounds an entity must differ in before the
+/// difference is reported. Replicas lag behind the simulating player and
+/// health changes caused by third players might arrive in different order,
+/// thus a single differing round is not conclusive.
+const CONFIRMATIONS: u8 = 2;
+/// Maximum distance in meters between an entity and its replica.
+const POSITION_TOLERANCE: f32 = 16.;
+/// Size of a quantization step of path coordinates in meters.
+const PATH_QUANTUM: f32 = 0.1;
+/// Number of distinguished health levels.
+const HEALTH_LEVELS: f32 = 1000.;
+
+/// This plugin periodically sends checksums of locally simulated entities to
+/// other players and compares checksums received from other players with the
+/// local replicas. See [`DesyncEvent`].
+pub(crate) struct DesyncPlugin;
+
+impl Plugin for DesyncPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DesyncEvent>()
+            .init_resource::<DesyncConf>()
+            .add_systems(OnEnter(AppState::InGame), setup.run_if(is_multiplayer))
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(
+                Movement,
+                (
+                    send_checksums.run_if(resource_exists::<ChecksumTimer>),
+                    compare_checksums
+                        .run_if(resource_exists::<DesyncTracker>)
+                        .run_if(on_event::<NetRecvChecksumEvent>()),
+                    resync.run_if(on_event::<NetRecvResyncEvent>()),
+                )
+                    .run_if(in_state(GameState::Playing))
+                    .after(MovementSet::UpdateTransform),
+            );
+    }
+}
+
+/// Configuration of desynchronization handling.
+#[derive(Resource)]
+pub struct DesyncConf {
+    resync: bool,
+}
+
+impl DesyncConf {
+    /// # Arguments
+    ///
+    /// * `resync` - if true, the simulating player is asked to re-send
+    ///   transform and path of each entity whose replica differs.
+    pub fn new(resync: bool) -> Self {
+        Self { resync }
+    }
+
+    pub fn resync(&self) -> bool {
+        self.resync
+    }
+}
+
+impl Default for DesyncConf {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+/// This event is sent when a locally replicated entity of another player
+/// persistently differs from the entity simulated by the player.
+#[derive(Event)]
+pub struct DesyncEvent {
+    player: Player,
+    entity: EntityNet,
+    local: Option<Entity>,
+    kind: DesyncKind,
+}
+
+impl DesyncEvent {
+    /// The player simulating the entity.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    pub fn entity(&self) -> EntityNet {
+        self.entity
+    }
+
+    /// The local replica of the entity, if there is any.
+    pub fn local(&self) -> Option<Entity> {
+        self.local
+    }
+
+    pub fn kind(&self) -> DesyncKind {
+        self.kind
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DesyncKind {
+    /// The replica is farther from the entity than a tolerance.
+    Position,
+    /// Health or path of the replica differs.
+    State,
+    /// The entity is not replicated locally.
+    Missing,
+    /// The replica exists but the simulating player does not simulate the
+    /// entity.
+    Unexpected,
+}
+
+#[derive(Resource)]
+struct ChecksumTimer {
+    round: u32,
+    next: Duration,
+}
+
+/// Tracks replicas which differ from the simulated entities.
+#[derive(Resource, Default)]
+struct DesyncTracker(AHashMap<EntityNet, Divergence>);
+
+impl DesyncTracker {
+    /// Updates the tracker with the results of a checksum round of a player
+    /// and returns newly confirmed divergences.
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - the player simulating the checked entities.
+    ///
+    /// * `differing` - all entities of the player which differed in the
+    ///   round.
+    fn update(
+        &mut self,
+        player: Player,
+        differing: &[(EntityNet, DesyncKind)],
+    ) -> Vec<(EntityNet, DesyncKind)> {
+        let current: AHashSet<EntityNet> = differing.iter().map(|&(entity, _)| entity).collect();
+        // Entities which are in sync again.
+        self.0
+            .retain(|entity, _| entity.player() != player || current.contains(entity));
+
+        let mut confirmed = Vec::new();
+        for &(entity, kind) in differing {
+            let divergence = self.0.entry(entity).or_default();
+            divergence.rounds = divergence.rounds.saturating_add(1);
+            if divergence.rounds >= CONFIRMATIONS && !divergence.reported {
+                divergence.reported = true;
+                confirmed.push((entity, kind));
+            }
+        }
+        confirmed
+    }
+}
+
+#[derive(Default)]
+struct Divergence {
+    rounds: u8,
+    /// True if the divergence has been already reported. It is reported only
+    /// once until the entity gets back in sync.
+    reported: bool,
+}
+
+fn setup(mut commands: Commands, config: Res<GameConfig>, time: Res<Time>) {
+    // Observers simulate no entities.
+    if !config.locals().is_observer() {
+        commands.insert_resource(ChecksumTimer {
+            round: 0,
+            next: time.elapsed() + CHECKSUM_PERIOD,
+        });
+    }
+    commands.init_resource::<DesyncTracker>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ChecksumTimer>();
+    commands.remove_resource::<DesyncTracker>();
+}
+
+type ChecksumQuery<'w, 's, F> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        Option<&'static Health>,
+        Option<&'static ScheduledPath>,
+    ),
+    F,
+>;
+
+fn send_checksums(
+    time: Res<Time>,
+    mut timer: ResMut<ChecksumTimer>,
+    net_entities: NetEntities,
+    entities: ChecksumQuery<(With<Active>, With<Local>)>,
+    mut net_events: EventWriter<ToPlayersEvent>,
+) {
+    let time = time.elapsed();
+    if time < timer.next {
+        return;
+    }
+    timer.next = time + CHECKSUM_PERIOD;
+    timer.round = timer.round.wrapping_add(1);
+
+    let mut checksums: Vec<EntityChecksumNet> = entities
+        .iter()
+        .filter_map(|(entity, transform, health, path)| {
+            Some(EntityChecksumNet::new(
+                net_entities.local_net_id(entity)?,
+                transform.translation.to_flat().into(),
+                checksum(health, path),
+            ))
+        })
+        .collect();
+
+    if checksums.len() > MAX_CHECKSUMS {
+        warn!(
+            "Too many local entities for a checksum, only {} out of {} are checked.",
+            MAX_CHECKSUMS,
+            checksums.len()
+        );
+        checksums.truncate(MAX_CHECKSUMS);
+    }
+
+    net_events.send(ToPlayersEvent::new(ToPlayers::Checksum {
+        round: timer.round,
+        entities: ChecksumsNet::try_from(checksums).unwrap(),
+    }));
+}
+
+fn compare_checksums(
+    conf: Res<DesyncConf>,
+    mut tracker: ResMut<DesyncTracker>,
+    net_entities: NetEntities,
+    replicas: ChecksumQuery<(With<Active>, Without<Local>)>,
+    mut checksum_events: EventReader<NetRecvChecksumEvent>,
+    mut desync_events: EventWriter<DesyncEvent>,
+    mut net_events: EventWriter<ToPlayersEvent>,
+) {
+    for event in checksum_events.read() {
+        let mut differing = Vec::new();
+        let mut locals = AHashMap::with_capacity(event.entities().len());
+
+        for entity_checksum in event.entities() {
+            let entity = entity_checksum.entity();
+            let Some((local, transform, health, path)) = entity_checksum
+                .local()
+                .and_then(|local| replicas.get(local).ok())
+            else {
+                differing.push((entity, DesyncKind::Missing));
+                continue;
+            };
+            locals.insert(entity, local);
+
+            if checksum(health, path) != entity_checksum.checksum() {
+                differing.push((entity, DesyncKind::State));
+            } else if transform
+                .translation
+                .to_flat()
+                .distance(entity_checksum.position())
+                > POSITION_TOLERANCE
+            {
+                differing.push((entity, DesyncKind::Position));
+            }
+        }
+
+        // The checksum message is limited in size, thus unexpected replicas
+        // are detected only if the message is not full.
+        if event.entities().len() < MAX_CHECKSUMS {
+            for (local, ..) in replicas.iter() {
+                let Some(entity) = net_entities.net_id(local) else {
+                    continue;
+                };
+                if entity.player() == event.player() && !locals.contains_key(&entity) {
+                    locals.insert(entity, local);
+                    differing.push((entity, DesyncKind::Unexpected));
+                }
+            }
+        }
+
+        let confirmed = tracker.update(event.player(), &differing);
+        if confirmed.is_empty() {
+            continue;
+        }
+
+        warn!(
+            "Desync with {} detected in checksum round {}: {:?}",
+            event.player(),
+            event.round(),
+            confirmed
+        );
+
+        for (entity, kind) in confirmed {
+            if conf.resync() && matches!(kind, DesyncKind::Position | DesyncKind::State) {
+                net_events.send(ToPlayersEvent::new(ToPlayers::Resync { entity }));
+            }
+
+            desync_events.send(DesyncEvent {
+                player: event.player(),
+                entity,
+                local: locals.get(&entity).copied(),
+                kind,
+            });
+        }
+    }
+}
+
+fn resync(
+    time: Res<Time>,
+    net_entities: NetEntities,
+    entities: Query<(&Transform, Option<&ScheduledPath>), With<Local>>,
+    mut resync_events: EventReader<NetRecvResyncEvent>,
+    mut net_events: EventWriter<ToPlayersEvent>,
+) {
+    for event in resync_events.read() {
+        let Ok((transform, path)) = entities.get(event.entity()) else {
+            continue;
+        };
+
+        let Some(entity) = net_entities.local_net_id(event.entity()) else {
+            continue;
+        };
+        net_events.send(ToPlayersEvent::new(ToPlayers::Transform {
+            entity,
+            time: time.elapsed(),
+            transform: transform.into(),
+        }));
+        net_events.send(ToPlayersEvent::new(ToPlayers::SetPath {
+            entity,
+            waypoints: path.map(|path| {
+                (&path.remaining(transform.translation.to_flat()))
+                    .try_into()
+                    .unwrap()
+            }),
+        }));
+    }
+}
+
+/// Computes checksum of the exactly synchronized state of an entity.
+fn checksum(health: Option<&Health>, path: Option<&ScheduledPath>) -> u32 {
+    let mut hasher = Fnv1a::new();
+
+    match health {
+        Some(health) => {
+            hasher.write_u8(1);
+            hasher.write_u32((health.fraction() * HEALTH_LEVELS).round() as u32);
+        }
+        None => hasher.write_u8(0),
+    }
+
+    match path {
+        Some(path) => {
+            let destination = (path.destination() / PATH_QUANTUM).round();
+            hasher.write_u8(1);
+            hasher.write_u32(destination.x as i32 as u32);
+            hasher.write_u32(destination.y as i32 as u32);
+        }
+        None => hasher.write_u8(0),
+    }
+
+    hasher.finish()
+}
+
+/// 32-bit FNV-1a hash. A hash with a fixed algorithm and no seed is needed
+/// because the checksums are compared among different game instances.
+struct Fnv1a(u32);
+
+impl Fnv1a {
+    const OFFSET: u32 = 0x811c9dc5;
+    const PRIME: u32 = 0x01000193;
+
+    fn new() -> Self {
+        Self(Self::OFFSET)
+    }
+
+    fn write_u8(&mut self, value: u8) {
+        self.0 = (self.0 ^ u32::from(value)).wrapping_mul(Self::PRIME);
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        for byte in value.to_le_bytes() {
+            self.write_u8(byte);
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de_messages::NetEntityIndex;
+
+    use super::*;
+
+    #[test]
+    fn test_fnv1a() {
+        let mut hasher = Fnv1a::new();
+        for byte in b"foobar" {
+            hasher.write_u8(*byte);
+        }
+        assert_eq!(hasher.finish(), 0xbf9cf968);
+    }
+
+    #[test]
+    fn test_tracker() {
+        let entity_a = EntityNet::new(Player::Player2, NetEntityIndex::new(1, 0).unwrap());
+        let entity_b = EntityNet::new(Player::Player2, NetEntityIndex::new(2, 0).unwrap());
+        let entity_c = EntityNet::new(Player::Player3, NetEntityIndex::new(1, 0).unwrap());
+
+        let mut tracker = DesyncTracker::default();
+        assert!(tracker
+            .update(Player::Player3, &[(entity_c, DesyncKind::Missing)])
+            .is_empty());
+        assert!(tracker
+            .update(
+                Player::Player2,
+                &[
+                    (entity_a, DesyncKind::State),
+                    (entity_b, DesyncKind::Position)
+                ]
+            )
+            .is_empty());
+        // Entity B got back in sync.
+        assert_eq!(
+            tracker.update(Player::Player2, &[(entity_a, DesyncKind::State)]),
+            vec![(entity_a, DesyncKind::State)]
+        );
+        // Already reported.
+        assert!(tracker
+            .update(
+                Player::Player2,
+                &[
+                    (entity_a, DesyncKind::State),
+                    (entity_b, DesyncKind::Position)
+                ]
+            )
+            .is_empty());
+        assert_eq!(
+            tracker.update(Player::Player2, &[(entity_b, DesyncKind::Position)]),
+            vec![(entity_b, DesyncKind::Position)]
+        );
+        // Rounds of other players do not affect each other.
+        assert_eq!(
+            tracker.update(Player::Player3, &[(entity_c, DesyncKind::Missing)]),
+            vec![(entity_c, DesyncKind::Missing)]
+        );
+        // Entity A was in sync for a round, thus it needs to be confirmed
+        // again.
+        assert!(tracker
+            .update(Player::Player2, &[(entity_a, DesyncKind::State)])
+            .is_empty());
+    }
+}
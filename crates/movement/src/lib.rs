@@ -1,5 +1,6 @@
 mod altitude;
 mod cache;
+mod desync;
 mod disc;
 mod kinematics;
 mod movement;
@@ -12,6 +13,7 @@ use std::f32::consts::PI;
 
 use altitude::AltitudePlugin;
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
+use desync::DesyncPlugin;
 use kinematics::KinematicsPlugin;
 use movement::MovementPlugin;
 use obstacles::ObstaclesPlugin;
@@ -19,6 +21,8 @@ use pathing::PathingPlugin;
 use repulsion::RepulsionPlugin;
 use syncing::SyncingPlugin;
 
+pub use crate::desync::{DesyncConf, DesyncEvent, DesyncKind};
+
 /// Maximum object horizontal speed in meters per second.
 const MAX_H_SPEED: f32 = 10.;
 /// Maximum object vertical ascending / descending rate in meters per second.
@@ -46,5 +50,6 @@ impl PluginGroup for MovementPluginGroup {
             .add(KinematicsPlugin)
             .add(AltitudePlugin)
             .add(SyncingPlugin)
+            .add(DesyncPlugin)
     }
 }
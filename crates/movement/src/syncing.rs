@@ -1,105 +1,488 @@
-use std::time::Duration;
+use std::{collections::VecDeque, time::Duration};
 
+use ahash::AHashMap;
 use bevy::prelude::*;
+use de_conf::Configuration;
 use de_core::{
     gamestate::GameState,
-    gconfig::is_multiplayer,
-    objects::{Local, MovableSolid},
+    gconfig::{is_multiplayer, GameConfig},
+    objects::{Active, Local, MovableSolid},
     schedule::{Movement, PreMovement},
     state::AppState,
 };
-use de_messages::ToPlayers;
-use de_multiplayer::{NetEntities, NetRecvTransformEvent, ToPlayersEvent};
+use de_messages::{EntityStateNet, PathProgressNet, ToPlayers};
+use de_multiplayer::{
+    NetEntities, NetRecvSnapshotEvent, NetRecvTransformEvent, SendSnapshotEvent, ToPlayersEvent,
+};
+use de_objects::Health;
+use de_pathing::ScheduledPath;
+use de_types::player::Player;
 
 use crate::movement::MovementSet;
 
-const MIN_SYNC_PERIOD: Duration = Duration::from_millis(800);
-const SYNC_RANDOMIZATION_MS: u64 = 250;
+const SNAPSHOT_PERIOD: Duration = Duration::from_millis(500);
+/// Number of the latest received states kept for each replica.
+const STATE_BUFFER_LEN: usize = 4;
+/// Number of the latest clock offset samples kept for each remote player.
+/// This corresponds to several seconds of snapshots, which lets the offset
+/// recover after a temporary shift, e.g. after a game pause.
+const CLOCK_SAMPLES: usize = 16;
+/// Maximum distance in meters between path destinations of a replica and
+/// the state from a snapshot for the paths to be considered the same.
+const DESTINATION_TOLERANCE: f32 = 0.1;
 
 pub(crate) struct SyncingPlugin;
 
 impl Plugin for SyncingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            PreMovement,
-            setup_entities
-                .run_if(is_multiplayer)
-                .run_if(in_state(AppState::InGame)),
-        )
-        .add_systems(
-            Movement,
-            (
-                receive_transforms
-                    .run_if(on_event::<NetRecvTransformEvent>())
-                    .after(MovementSet::UpdateTransform),
-                send_transforms
+        app.add_systems(OnEnter(AppState::InGame), setup.run_if(is_multiplayer))
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(
+                PreMovement,
+                setup_replicas
                     .run_if(is_multiplayer)
-                    .after(MovementSet::UpdateTransform),
+                    .run_if(in_state(AppState::InGame)),
             )
-                .run_if(in_state(GameState::Playing)),
-        );
+            .add_systems(
+                Movement,
+                (
+                    receive_transforms
+                        .run_if(on_event::<NetRecvTransformEvent>())
+                        .after(MovementSet::UpdateTransform)
+                        .before(correct_replicas),
+                    send_snapshot
+                        .run_if(resource_exists::<SnapshotTimer>)
+                        .after(MovementSet::UpdateTransform),
+                    reconcile
+                        .run_if(on_event::<NetRecvSnapshotEvent>())
+                        .after(MovementSet::UpdateTransform)
+                        .before(correct_replicas),
+                    correct_replicas
+                        .run_if(is_multiplayer)
+                        .after(MovementSet::UpdateTransform),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
-#[derive(Component)]
-struct SyncTimer(Duration);
+#[derive(Resource)]
+struct SnapshotTimer(Duration);
+
+/// Offsets of simulation clocks of remote players from the local simulation
+/// clock.
+#[derive(Resource, Default)]
+struct RemoteClocks(AHashMap<Player, RemoteClock>);
+
+/// Latest samples of the difference between the local time of reception of
+/// a state and the remote simulation time of the state.
+#[derive(Default)]
+struct RemoteClock(VecDeque<f64>);
+
+impl RemoteClock {
+    /// Records a newly received state and returns local simulation time
+    /// corresponding to the remote simulation time of the state.
+    ///
+    /// The smallest of the latest offsets is used, i.e. the offset of the
+    /// state delivered with the lowest latency. This keeps network jitter out
+    /// of the timestamps.
+    ///
+    /// # Arguments
+    ///
+    /// * `local` - local simulation time of the reception.
+    ///
+    /// * `remote` - remote simulation time of the state.
+    fn local_time(&mut self, local: Duration, remote: Duration) -> Duration {
+        if self.0.len() >= CLOCK_SAMPLES {
+            self.0.pop_front();
+        }
+        self.0.push_back(local.as_secs_f64() - remote.as_secs_f64());
 
-impl SyncTimer {
-    fn schedule(time: Duration) -> Duration {
-        let jitter = Duration::from_millis(fastrand::u64(0..SYNC_RANDOMIZATION_MS));
-        time + MIN_SYNC_PERIOD + jitter
+        let offset = self.0.iter().copied().fold(f64::INFINITY, f64::min);
+        Duration::from_secs_f64((remote.as_secs_f64() + offset).max(0.))
     }
+}
+
+/// Buffer of the latest states of a remotely simulated entity received from
+/// the simulating player. The replica is continuously corrected towards the
+/// state estimated from the buffer.
+#[derive(Component, Default)]
+struct RemoteMotion(VecDeque<RemoteState>);
 
-    fn new(time: Duration) -> Self {
-        Self(Self::schedule(time))
+impl RemoteMotion {
+    /// Adds a newly received state.
+    ///
+    /// States older than the last buffered state (e.g. delivered out of
+    /// order) are ignored. A state with the same time replaces the last
+    /// buffered state.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - local time corresponding to the remote simulation time of
+    ///   the state.
+    ///
+    /// * `transform` - transform of the simulated entity.
+    ///
+    /// * `moving` - whether the simulated entity is moving, i.e. following a
+    ///   path. Motion of stationary entities is not extrapolated.
+    fn push(&mut self, time: Duration, transform: &Transform, moving: bool) {
+        if let Some(last) = self.0.back() {
+            if time < last.time {
+                return;
+            }
+            if time == last.time {
+                self.0.pop_back();
+            }
+        }
+        if self.0.len() >= STATE_BUFFER_LEN {
+            self.0.pop_front();
+        }
+        self.0.push_back(RemoteState {
+            time,
+            translation: transform.translation,
+            rotation: transform.rotation,
+            moving,
+        });
     }
 
-    /// Sets sync expiration to the future relative to the current time.
-    fn refresh(&mut self, time: Duration) {
-        self.0 = Self::schedule(time);
+    /// Estimates the state of the simulated entity at a given time.
+    ///
+    /// The state is interpolated between the surrounding received states. It
+    /// is extrapolated from the last two states if the time is past the last
+    /// state, but at most by `max_extrapolation`.
+    fn estimate(&self, time: Duration, max_extrapolation: Duration) -> Option<Transform> {
+        let last = self.0.back()?;
+
+        if time >= last.time {
+            let mut translation = last.translation;
+            if let Some(previous) = self.0.iter().rev().nth(1).filter(|_| last.moving) {
+                let velocity = (last.translation - previous.translation)
+                    / (last.time - previous.time).as_secs_f32();
+                let extrapolation = (time - last.time).min(max_extrapolation);
+                translation += velocity * extrapolation.as_secs_f32();
+            }
+            return Some(Transform {
+                translation,
+                rotation: last.rotation,
+                ..default()
+            });
+        }
+
+        let Some(index) = self.0.iter().position(|state| state.time > time) else {
+            unreachable!("The time is before the last state.");
+        };
+        let next = &self.0[index];
+        if index == 0 {
+            return Some(next.transform());
+        }
+
+        let previous = &self.0[index - 1];
+        let fraction =
+            (time - previous.time).as_secs_f32() / (next.time - previous.time).as_secs_f32();
+        Some(Transform {
+            translation: previous.translation.lerp(next.translation, fraction),
+            rotation: previous.rotation.slerp(next.rotation, fraction),
+            ..default()
+        })
     }
+}
 
-    /// Returns true if transform sync is already due.
-    fn outdated(&self, time: Duration) -> bool {
-        time >= self.0
+struct RemoteState {
+    time: Duration,
+    translation: Vec3,
+    rotation: Quat,
+    moving: bool,
+}
+
+impl RemoteState {
+    fn transform(&self) -> Transform {
+        Transform {
+            translation: self.translation,
+            rotation: self.rotation,
+            ..default()
+        }
     }
 }
 
-type NotSetUp = (With<MovableSolid>, With<Local>, Without<SyncTimer>);
+fn setup(mut commands: Commands, config: Res<GameConfig>, time: Res<Time>) {
+    commands.init_resource::<RemoteClocks>();
+    // Observers simulate no entities.
+    if !config.locals().is_observer() {
+        commands.insert_resource(SnapshotTimer(time.elapsed() + SNAPSHOT_PERIOD));
+    }
+}
 
-fn setup_entities(mut commands: Commands, time: Res<Time>, entities: Query<Entity, NotSetUp>) {
-    let time = time.elapsed();
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<SnapshotTimer>();
+    commands.remove_resource::<RemoteClocks>();
+}
+
+type NotSetUp = (With<MovableSolid>, Without<Local>, Without<RemoteMotion>);
+
+fn setup_replicas(mut commands: Commands, entities: Query<Entity, NotSetUp>) {
     for entity in entities.iter() {
-        commands.entity(entity).insert(SyncTimer::new(time));
+        commands.entity(entity).insert(RemoteMotion::default());
     }
 }
 
 fn receive_transforms(
-    mut entities: Query<&mut Transform>,
+    time: Res<Time>,
+    mut clocks: ResMut<RemoteClocks>,
+    mut entities: Query<(
+        &mut Transform,
+        Option<&mut RemoteMotion>,
+        Option<&ScheduledPath>,
+    )>,
     mut events: EventReader<NetRecvTransformEvent>,
 ) {
     for event in events.read() {
-        if let Ok(mut transform) = entities.get_mut(event.entity()) {
-            *transform = event.transform();
+        let Ok((mut transform, motion, path)) = entities.get_mut(event.entity()) else {
+            continue;
+        };
+
+        match motion {
+            Some(mut motion) => {
+                let clock = clocks.0.entry(event.player()).or_default();
+                motion.push(
+                    clock.local_time(time.elapsed(), event.time()),
+                    &event.transform(),
+                    path.is_some(),
+                );
+            }
+            None => *transform = event.transform(),
         }
     }
 }
 
-fn send_transforms(
+type SnapshotQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static Health,
+        Option<&'static ScheduledPath>,
+    ),
+    (With<Active>, With<Local>),
+>;
+
+fn send_snapshot(
     time: Res<Time>,
+    mut timer: ResMut<SnapshotTimer>,
     net_entities: NetEntities,
-    mut entities: Query<(Entity, &mut SyncTimer, &Transform)>,
-    mut net_events: EventWriter<ToPlayersEvent>,
+    entities: SnapshotQuery,
+    mut snapshot_events: EventWriter<SendSnapshotEvent>,
 ) {
     let time = time.elapsed();
-    for (entity, mut sync, transform) in entities.iter_mut() {
-        if sync.outdated(time) {
-            sync.refresh(time);
-
-            net_events.send(ToPlayersEvent::new(ToPlayers::Transform {
-                entity: net_entities.local_net_id(entity),
-                transform: transform.into(),
-            }));
+    if time < timer.0 {
+        return;
+    }
+    timer.0 = time + SNAPSHOT_PERIOD;
+
+    let snapshot = entities
+        .iter()
+        .filter_map(|(entity, transform, health, path)| {
+            let entity = net_entities.local_net_id(entity)?;
+            let path = path.map(|path| {
+                PathProgressNet::new(
+                    path.destination().into(),
+                    path.progress().try_into().unwrap_or(u16::MAX),
+                )
+            });
+            Some((
+                entity,
+                EntityStateNet::new(transform.into(), health.current(), path),
+            ))
+        })
+        .collect();
+    snapshot_events.send(SendSnapshotEvent::new(time, snapshot));
+}
+
+/// Records received states of replicas and catches up with their path
+/// schedules.
+///
+/// If the path of a replica differs and the path of the simulated entity did
+/// not change since the previous snapshot, the simulating player is asked to
+/// re-send it. Paths which changed recently might differ only because the
+/// path update is still on its way.
+fn reconcile(
+    time: Res<Time>,
+    mut clocks: ResMut<RemoteClocks>,
+    mut replicas: Query<(Option<&mut RemoteMotion>, Option<&mut ScheduledPath>), Without<Local>>,
+    mut snapshot_events: EventReader<NetRecvSnapshotEvent>,
+    mut net_events: EventWriter<ToPlayersEvent>,
+) {
+    for event in snapshot_events.read() {
+        let state_time = clocks
+            .0
+            .entry(event.player())
+            .or_default()
+            .local_time(time.elapsed(), event.time());
+
+        for snapshot in event.entities() {
+            let Ok((motion, path)) = replicas.get_mut(snapshot.local()) else {
+                continue;
+            };
+            let state = snapshot.state();
+
+            if let Some(mut motion) = motion {
+                motion.push(
+                    state_time,
+                    &Transform::from(state.transform()),
+                    state.path().is_some(),
+                );
+            }
+
+            let stable = snapshot
+                .previous()
+                .is_some_and(|previous| same_path(previous.path(), state.path()));
+
+            match (path, state.path()) {
+                (Some(mut path), Some(progress))
+                    if same_destination(path.destination(), progress) =>
+                {
+                    if path.progress() != usize::from(progress.progress()) {
+                        path.set_progress(progress.progress().into());
+                    }
+                }
+                (None, None) => (),
+                _ => {
+                    if stable {
+                        net_events.send(ToPlayersEvent::new(ToPlayers::Resync {
+                            entity: snapshot.entity(),
+                        }));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Smoothly corrects replicas towards the estimated state of the simulated
+/// entities. Replicas which are too far are moved instantaneously.
+fn correct_replicas(
+    time: Res<Time>,
+    config: Res<Configuration>,
+    mut replicas: Query<(&mut Transform, &RemoteMotion)>,
+) {
+    let conf = config.replication();
+    let Some(render_time) = time.elapsed().checked_sub(conf.interpolation_delay()) else {
+        return;
+    };
+    let factor = (conf.correction_rate() * time.delta_seconds()).min(1.);
+
+    for (mut transform, motion) in replicas.iter_mut() {
+        let Some(target) = motion.estimate(render_time, conf.max_extrapolation()) else {
+            continue;
+        };
+
+        if transform.translation.distance(target.translation) > conf.snap_distance().into() {
+            transform.translation = target.translation;
+            transform.rotation = target.rotation;
+        } else {
+            transform.translation = transform.translation.lerp(target.translation, factor);
+            transform.rotation = transform.rotation.slerp(target.rotation, factor);
+        }
+    }
+}
+
+fn same_path(a: Option<&PathProgressNet>, b: Option<&PathProgressNet>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => same_destination(a.destination().into(), b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn same_destination(destination: Vec2, progress: &PathProgressNet) -> bool {
+    destination.distance(progress.destination().into()) <= DESTINATION_TOLERANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform(x: f32, angle: f32) -> Transform {
+        Transform::from_xyz(x, 0., 0.).with_rotation(Quat::from_rotation_y(angle))
+    }
+
+    fn secs(secs: f32) -> Duration {
+        Duration::from_secs_f32(secs)
+    }
+
+    #[test]
+    fn test_remote_motion() {
+        let max_extrapolation = secs(0.5);
+        let mut motion = RemoteMotion::default();
+        assert!(motion.estimate(secs(1.), max_extrapolation).is_none());
+
+        motion.push(secs(1.), &transform(2., 0.), true);
+        // A single state cannot be extrapolated.
+        let estimate = motion.estimate(secs(1.5), max_extrapolation).unwrap();
+        assert_eq!(estimate.translation, Vec3::new(2., 0., 0.));
+
+        motion.push(secs(2.), &transform(4., 1.), true);
+        let estimate = motion.estimate(secs(0.5), max_extrapolation).unwrap();
+        assert_eq!(estimate.translation, Vec3::new(2., 0., 0.));
+        let estimate = motion.estimate(secs(1.5), max_extrapolation).unwrap();
+        assert!(estimate.translation.distance(Vec3::new(3., 0., 0.)) < 0.001);
+        assert!(estimate.rotation.angle_between(Quat::from_rotation_y(0.5)) < 0.001);
+        let estimate = motion.estimate(secs(2.25), max_extrapolation).unwrap();
+        assert!(estimate.translation.distance(Vec3::new(4.5, 0., 0.)) < 0.001);
+        assert!(estimate.rotation.angle_between(Quat::from_rotation_y(1.)) < 0.001);
+        // Extrapolation is bounded.
+        let estimate = motion.estimate(secs(10.), max_extrapolation).unwrap();
+        assert!(estimate.translation.distance(Vec3::new(5., 0., 0.)) < 0.001);
+
+        // Stationary entities are not extrapolated.
+        motion.push(secs(3.), &transform(5., 1.), false);
+        let estimate = motion.estimate(secs(3.2), max_extrapolation).unwrap();
+        assert_eq!(estimate.translation, Vec3::new(5., 0., 0.));
+
+        for i in 0..STATE_BUFFER_LEN {
+            motion.push(secs(4. + i as f32), &transform(6., 1.), false);
+        }
+        assert_eq!(motion.0.len(), STATE_BUFFER_LEN);
+        let estimate = motion.estimate(secs(2.), max_extrapolation).unwrap();
+        assert_eq!(estimate.translation, Vec3::new(6., 0., 0.));
+    }
+
+    #[test]
+    fn test_remote_motion_out_of_order() {
+        let max_extrapolation = secs(0.5);
+        let mut motion = RemoteMotion::default();
+        motion.push(secs(1.), &transform(2., 0.), true);
+        motion.push(secs(3.), &transform(6., 0.), true);
+
+        // A stale state does not discard newer ones.
+        motion.push(secs(2.), &transform(100., 0.), true);
+        assert_eq!(motion.0.len(), 2);
+        let estimate = motion.estimate(secs(2.), max_extrapolation).unwrap();
+        assert!(estimate.translation.distance(Vec3::new(4., 0., 0.)) < 0.001);
+        let estimate = motion.estimate(secs(3.), max_extrapolation).unwrap();
+        assert_eq!(estimate.translation, Vec3::new(6., 0., 0.));
+
+        // A state with the same time replaces the last one.
+        motion.push(secs(3.), &transform(8., 0.), true);
+        assert_eq!(motion.0.len(), 2);
+        let estimate = motion.estimate(secs(3.), max_extrapolation).unwrap();
+        assert_eq!(estimate.translation, Vec3::new(8., 0., 0.));
+    }
+
+    #[test]
+    fn test_remote_clock() {
+        let mut clock = RemoteClock::default();
+        assert_eq!(clock.local_time(secs(10.), secs(2.)), secs(10.));
+        // A delayed state is timestamped by the lowest latency so far.
+        assert!((clock.local_time(secs(10.8), secs(2.5)).as_secs_f32() - 10.5).abs() < 0.001);
+        // A faster delivery lowers the offset.
+        assert!((clock.local_time(secs(10.9), secs(3.)).as_secs_f32() - 10.9).abs() < 0.001);
+
+        // Old samples are forgotten.
+        for i in 0..CLOCK_SAMPLES {
+            let remote = secs(4. + i as f32);
+            clock.local_time(remote + secs(9.), remote);
         }
+        assert!((clock.local_time(secs(30.), secs(21.)).as_secs_f32() - 30.).abs() < 0.001);
     }
 }
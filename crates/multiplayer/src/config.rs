@@ -1,10 +1,14 @@
 use std::net::IpAddr;
 
+use de_messages::RejoinToken;
+use de_net::SessionSecret;
 use de_types::player::Player;
 
 pub struct NetGameConf {
     server_host: IpAddr,
     connection_type: ConnectionType,
+    secret: Option<SessionSecret>,
+    impairment: Option<String>,
 }
 
 impl NetGameConf {
@@ -12,9 +16,32 @@ impl NetGameConf {
         Self {
             server_host,
             connection_type,
+            secret: None,
+            impairment: None,
         }
     }
 
+    /// Authenticate all communication with the game server with the given
+    /// secret. This is applicable only to already opened games, see
+    /// [`crate::SetGameSecretEvent`] for newly created games.
+    pub fn with_secret(mut self, secret: SessionSecret) -> Self {
+        self.secret = Some(secret);
+        self
+    }
+
+    /// Simulate degraded network conditions on all datagrams sent to the game
+    /// server. This is intended for testing only.
+    ///
+    /// # Arguments
+    ///
+    /// * `impairment` - network impairment specification, see
+    ///   [`de_net::Impairment`]. An invalid specification results in a fatal
+    ///   error once the connection is being established.
+    pub fn with_impairment(mut self, impairment: impl Into<String>) -> Self {
+        self.impairment = Some(impairment.into());
+        self
+    }
+
     /// Address of DE Connector server.
     pub(crate) fn server_host(&self) -> IpAddr {
         self.server_host
@@ -23,6 +50,14 @@ impl NetGameConf {
     pub(crate) fn connection_type(&self) -> ConnectionType {
         self.connection_type
     }
+
+    pub(crate) fn secret(&self) -> Option<SessionSecret> {
+        self.secret
+    }
+
+    pub(crate) fn impairment(&self) -> Option<&str> {
+        self.impairment.as_deref()
+    }
 }
 
 /// Type of to be established connection to DE Connector.
@@ -37,9 +72,28 @@ pub enum ConnectionType {
         port: u16,
         /// Maximum number of players to be configured for the new game.
         max_players: Player,
+        /// Maximum number of players supported by the map.
+        map_max_players: Player,
+        /// Hash of the map the new game is going to be played on.
+        map_hash: [u8; 32],
     },
     /// Join a game server at the given port.
     ///
     /// This is a game server with other players potentially already connected.
     JoinGame(u16),
+    /// Join a game server at the given port as a spectator.
+    ///
+    /// Spectators receive all messages from the players but they do not
+    /// control any entities.
+    SpectateGame(u16),
+    /// Join a running game server at the given port after a connection loss.
+    ///
+    /// The player is connected under their original player number and the
+    /// game state is restored from the game server.
+    RejoinGame {
+        /// Port of the game server.
+        port: u16,
+        /// Token received when the game was joined originally.
+        token: RejoinToken,
+    },
 }
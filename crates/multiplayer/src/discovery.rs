@@ -0,0 +1,239 @@
+//! Discovery of DE Connectors and their open games on the local network.
+//!
+//! The discovery runs between [`StartLanDiscoveryEvent`] and
+//! [`StopLanDiscoveryEvent`] and its results are available in [`LanGames`].
+
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    time::{Duration, Instant},
+};
+
+use async_std::{
+    channel::{bounded, Receiver, Sender, TryRecvError},
+    future::timeout,
+    net::UdpSocket,
+};
+use bevy::{prelude::*, tasks::IoTaskPool};
+use de_gui::ToastEvent;
+use de_messages::{LanAnnouncement, LanGame, LAN_DISCOVERY_PORT, MAX_ANNOUNCEMENT_SIZE};
+
+/// Connectors which have not been announced for this long are forgotten.
+const SERVER_TTL: Duration = Duration::from_secs(3);
+/// The listener checks this often whether the discovery was stopped.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(crate) struct DiscoveryPlugin;
+
+impl Plugin for DiscoveryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StartLanDiscoveryEvent>()
+            .add_event::<StopLanDiscoveryEvent>()
+            .add_systems(
+                Update,
+                (
+                    start
+                        .run_if(not(resource_exists::<Announcements>))
+                        .run_if(on_event::<StartLanDiscoveryEvent>()),
+                    stop.run_if(on_event::<StopLanDiscoveryEvent>()),
+                    recv.run_if(resource_exists::<Announcements>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Send this event to start listening to LAN announcements of DE Connectors
+/// (see [`LanAnnouncement`]) on [`LAN_DISCOVERY_PORT`].
+#[derive(Event)]
+pub struct StartLanDiscoveryEvent;
+
+/// Send this event to stop listening to LAN announcements.
+#[derive(Event)]
+pub struct StopLanDiscoveryEvent;
+
+/// DE Connectors (and their open games) discovered on the local network. The
+/// resource exists only while the discovery is running.
+#[derive(Resource, Default)]
+pub struct LanGames(Vec<LanServer>);
+
+impl LanGames {
+    /// Discovered connectors sorted by their address.
+    pub fn servers(&self) -> &[LanServer] {
+        self.0.as_slice()
+    }
+
+    fn update(&mut self, addr: SocketAddr, announcement: LanAnnouncement) {
+        let server = LanServer {
+            addr,
+            version: announcement.version(),
+            games: announcement.games().to_vec(),
+            last_seen: Instant::now(),
+        };
+
+        match self.0.binary_search_by_key(&addr, |server| server.addr) {
+            Ok(index) => self.0[index] = server,
+            Err(index) => self.0.insert(index, server),
+        }
+    }
+
+    fn is_expired(&self, time: Instant) -> bool {
+        self.0
+            .iter()
+            .any(|server| time - server.last_seen > SERVER_TTL)
+    }
+
+    fn expire(&mut self, time: Instant) {
+        self.0
+            .retain(|server| time - server.last_seen <= SERVER_TTL);
+    }
+}
+
+/// A DE Connector discovered on the local network.
+pub struct LanServer {
+    addr: SocketAddr,
+    version: u32,
+    games: Vec<LanGame>,
+    last_seen: Instant,
+}
+
+impl LanServer {
+    /// Address of the main server of the connector.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Protocol version of the connector.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Games which might be joined.
+    pub fn games(&self) -> &[LanGame] {
+        self.games.as_slice()
+    }
+}
+
+/// Announcements received by the listener task.
+#[derive(Resource)]
+struct Announcements(Receiver<io::Result<(SocketAddr, LanAnnouncement)>>);
+
+fn start(mut commands: Commands) {
+    info!("Starting LAN discovery on port {LAN_DISCOVERY_PORT}.");
+
+    let (sender, receiver) = bounded(16);
+    IoTaskPool::get().spawn(listen(sender)).detach();
+    commands.insert_resource(Announcements(receiver));
+    commands.init_resource::<LanGames>();
+}
+
+fn stop(mut commands: Commands) {
+    info!("Stopping LAN discovery.");
+    // The listener finishes once it notices that the channel is closed.
+    commands.remove_resource::<Announcements>();
+    commands.remove_resource::<LanGames>();
+}
+
+fn recv(
+    mut commands: Commands,
+    announcements: Res<Announcements>,
+    mut games: ResMut<LanGames>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    loop {
+        match announcements.0.try_recv() {
+            Ok(Ok((source, announcement))) => {
+                let addr = SocketAddr::new(source.ip(), announcement.port());
+                trace!("Received LAN announcement from {addr}.");
+                games.update(addr, announcement);
+            }
+            Ok(Err(error)) => {
+                toasts.send(ToastEvent::new(format!("LAN discovery failed: {error}")));
+            }
+            Err(TryRecvError::Empty) => break,
+            Err(TryRecvError::Closed) => {
+                commands.remove_resource::<Announcements>();
+                break;
+            }
+        }
+    }
+
+    let time = Instant::now();
+    // Avoid needless change detection triggers.
+    if games.bypass_change_detection().is_expired(time) {
+        games.expire(time);
+    }
+}
+
+async fn listen(sender: Sender<io::Result<(SocketAddr, LanAnnouncement)>>) {
+    let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, LAN_DISCOVERY_PORT)).await {
+        Ok(socket) => socket,
+        Err(error) => {
+            let _ = sender.send(Err(error)).await;
+            return;
+        }
+    };
+
+    let mut buf = [0; MAX_ANNOUNCEMENT_SIZE];
+    loop {
+        let (len, source) = match timeout(STOP_CHECK_INTERVAL, socket.recv_from(&mut buf)).await {
+            Ok(Ok(received)) => received,
+            Ok(Err(error)) => {
+                let _ = sender.send(Err(error)).await;
+                return;
+            }
+            Err(_) => {
+                if sender.is_closed() {
+                    return;
+                }
+                continue;
+            }
+        };
+
+        match LanAnnouncement::decode(&buf[..len]) {
+            Ok(announcement) => {
+                if sender.send(Ok((source, announcement))).await.is_err() {
+                    return;
+                }
+            }
+            Err(error) => {
+                debug!("Received an invalid LAN announcement from {source}: {error:?}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de_types::player::Player;
+
+    use super::*;
+
+    #[test]
+    fn test_lan_games() {
+        let first: SocketAddr = "192.168.0.2:8082".parse().unwrap();
+        let second: SocketAddr = "192.168.0.1:8082".parse().unwrap();
+
+        let mut games = LanGames::default();
+        games.update(first, LanAnnouncement::new(8082, Vec::new()));
+        games.update(second, LanAnnouncement::new(8082, Vec::new()));
+        games.update(
+            first,
+            LanAnnouncement::new(8082, vec![LanGame::new(8084, Player::Player2, 1, [1; 32])]),
+        );
+
+        assert_eq!(games.servers().len(), 2);
+        assert_eq!(games.servers()[0].addr(), second);
+        assert!(games.servers()[0].games().is_empty());
+        assert_eq!(games.servers()[1].addr(), first);
+        assert_eq!(games.servers()[1].games().len(), 1);
+        assert_eq!(games.servers()[1].games()[0].port(), 8084);
+
+        let now = Instant::now();
+        assert!(!games.is_expired(now));
+        let later = now + SERVER_TTL + Duration::from_secs(1);
+        assert!(games.is_expired(later));
+        games.expire(later);
+        assert!(games.servers().is_empty());
+    }
+}
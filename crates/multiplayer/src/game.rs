@@ -2,8 +2,11 @@ use std::net::SocketAddr;
 
 use bevy::prelude::*;
 use de_core::schedule::PreMovement;
-use de_messages::{FromGame, FromServer, GameOpenError, JoinError, Readiness, ToGame, ToServer};
-use de_net::Reliability;
+use de_messages::{
+    FromGame, FromServer, GameOpenError, HostError, JoinError, Readiness, RejoinToken, ToGame,
+    ToServer, PROTOCOL_VERSION,
+};
+use de_net::{Reliability, SessionSecret};
 use de_types::player::Player;
 
 use crate::{
@@ -14,6 +17,8 @@ use crate::{
         ToMainServerEvent,
     },
     netstate::NetState,
+    network::AuthenticationRes,
+    playermsg::NetSessionRes,
 };
 
 pub(crate) struct GamePlugin;
@@ -25,7 +30,12 @@ impl Plugin for GamePlugin {
             .add_event::<PeerJoinedEvent>()
             .add_event::<PeerLeftEvent>()
             .add_event::<GameReadinessEvent>()
+            .add_event::<GameLockedEvent>()
+            .add_event::<GameMaxPlayersEvent>()
             .add_event::<SetReadinessEvent>()
+            .add_event::<HostCommandEvent>()
+            .add_event::<SetGameSecretEvent>()
+            .add_systems(OnEnter(NetState::None), cleanup)
             .add_systems(OnEnter(NetState::Connected), open_or_join)
             .add_systems(
                 PreMovement,
@@ -40,37 +50,99 @@ impl Plugin for GamePlugin {
             )
             .add_systems(
                 PostUpdate,
-                set_readiness
-                    .run_if(in_state(NetState::Joined))
-                    .run_if(on_event::<SetReadinessEvent>())
+                (
+                    set_readiness
+                        .run_if(in_state(NetState::Joined))
+                        .run_if(on_event::<SetReadinessEvent>()),
+                    host_command
+                        .run_if(in_state(NetState::Joined))
+                        .run_if(on_event::<HostCommandEvent>()),
+                    set_secret
+                        .run_if(resource_exists::<AuthenticationRes>)
+                        .run_if(on_event::<SetGameSecretEvent>()),
+                )
                     .before(MessagesSet::SendMessages),
             )
             .add_systems(OnEnter(NetState::ShuttingDown), leave);
     }
 }
 
-/// A new game on the given socket address was just opened.
+/// A new game was just opened.
 #[derive(Event)]
-pub struct GameOpenedEvent(pub SocketAddr);
+pub struct GameOpenedEvent {
+    addr: SocketAddr,
+    nonce: Option<u64>,
+}
+
+impl GameOpenedEvent {
+    /// Socket address of the game server.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// If not None, the game is secured and its secret is derived from this
+    /// nonce. Once the secret is obtained (from the lobby), it has to be set
+    /// via [`SetGameSecretEvent`].
+    pub fn nonce(&self) -> Option<u64> {
+        self.nonce
+    }
+}
 
 /// A game was just joined.
 #[derive(Event)]
 pub struct GameJoinedEvent {
-    player: Player,
+    player: Option<(Player, RejoinToken)>,
+    rejoined: bool,
 }
 
 impl GameJoinedEvent {
-    fn new(player: Player) -> Self {
-        Self { player }
+    fn joined(player: Player, token: RejoinToken) -> Self {
+        Self {
+            player: Some((player, token)),
+            rejoined: false,
+        }
+    }
+
+    /// # Arguments
+    ///
+    /// * `started` - whether the joined game has already started.
+    fn spectator(started: bool) -> Self {
+        Self {
+            player: None,
+            rejoined: started,
+        }
+    }
+
+    fn rejoined(player: Player, token: RejoinToken) -> Self {
+        Self {
+            player: Some((player, token)),
+            rejoined: true,
+        }
     }
 
-    pub fn player(&self) -> Player {
-        self.player
+    /// Player number of the local player or None if the game was joined as a
+    /// spectator.
+    pub fn player(&self) -> Option<Player> {
+        self.player.map(|(player, _)| player)
+    }
+
+    /// Token which might be used to rejoin the game after a connection loss
+    /// (see [`crate::ConnectionType::RejoinGame`]). None if the game was
+    /// joined as a spectator.
+    pub fn token(&self) -> Option<RejoinToken> {
+        self.player.map(|(_, token)| token)
+    }
+
+    /// True if the game was rejoined after a connection loss or if an already
+    /// started game was joined as a spectator. The game is already running
+    /// in such a case.
+    pub fn is_rejoin(&self) -> bool {
+        self.rejoined
     }
 }
 
 #[derive(Event)]
-pub struct PeerJoinedEvent(Player);
+pub struct PeerJoinedEvent(pub(crate) Player);
 
 impl PeerJoinedEvent {
     pub fn id(&self) -> Player {
@@ -79,7 +151,7 @@ impl PeerJoinedEvent {
 }
 
 #[derive(Event)]
-pub struct PeerLeftEvent(Player);
+pub struct PeerLeftEvent(pub(crate) Player);
 
 impl PeerLeftEvent {
     pub fn id(&self) -> Player {
@@ -89,11 +161,47 @@ impl PeerLeftEvent {
 
 /// This event is sent when game readiness stage of the joined game changes.
 #[derive(Event, Deref)]
-pub struct GameReadinessEvent(Readiness);
+pub struct GameReadinessEvent(pub(crate) Readiness);
+
+/// This event is sent when the host locks (true) or unlocks (false) the
+/// joined game.
+#[derive(Event, Deref)]
+pub struct GameLockedEvent(pub(crate) bool);
+
+/// This event is sent when the host changes the maximum number of players of
+/// the joined game.
+#[derive(Event, Deref)]
+pub struct GameMaxPlayersEvent(pub(crate) Player);
+
+/// Send this event to control the joined game. Only the host of the game,
+/// i.e. the local player joined as [`Player::Player1`], might do this.
+#[derive(Event)]
+pub enum HostCommandEvent {
+    /// Removes a player from the game.
+    Kick(Player),
+    /// Locks (true) or unlocks (false) the game. Nobody might join a locked
+    /// game. This is possible only before the game starts.
+    SetLocked(bool),
+    /// Changes the maximum number of players. This is possible only before
+    /// the game starts.
+    SetMaxPlayers(Player),
+}
+
+/// Send this event to set secret of a newly opened secured game (see
+/// [`GameOpenedEvent::nonce`]). The game server does not communicate with the
+/// local player until the secret is set.
+#[derive(Event)]
+pub struct SetGameSecretEvent(SessionSecret);
+
+impl SetGameSecretEvent {
+    pub fn new(secret: SessionSecret) -> Self {
+        Self(secret)
+    }
+}
 
 /// Send this event to change player readiness stage.
 #[derive(Event)]
-pub struct SetReadinessEvent(Readiness);
+pub struct SetReadinessEvent(pub(crate) Readiness);
 
 impl From<Readiness> for SetReadinessEvent {
     fn from(readiness: Readiness) -> Self {
@@ -101,21 +209,86 @@ impl From<Readiness> for SetReadinessEvent {
     }
 }
 
+/// This resource exists when the game does not wait for the local client to
+/// progress its readiness. This is the case when the game is joined as a
+/// spectator or when a running game is rejoined.
+#[derive(Resource)]
+struct PassiveRes {
+    /// Last known readiness of the game.
+    game_readiness: Readiness,
+    /// True if a running game was (re)joined and its state has not yet been
+    /// restored from the game server.
+    restore: bool,
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<PassiveRes>();
+    commands.remove_resource::<NetSessionRes>();
+}
+
 fn open_or_join(
     conf: Res<NetGameConfRes>,
+    authentication: Res<AuthenticationRes>,
     mut main_server: EventWriter<ToMainServerEvent>,
     mut game_server: EventWriter<ToGameServerEvent>,
 ) {
+    if let Some(secret) = conf.secret() {
+        match conf.connection_type() {
+            ConnectionType::CreateGame { .. } => {
+                warn!("Secret of a not yet opened game is ignored.");
+            }
+            ConnectionType::JoinGame(port)
+            | ConnectionType::SpectateGame(port)
+            | ConnectionType::RejoinGame { port, .. } => {
+                authentication.register(SocketAddr::new(conf.server_host(), port), secret);
+            }
+        }
+    }
+
     match conf.connection_type() {
-        ConnectionType::CreateGame { max_players, .. } => {
+        ConnectionType::CreateGame {
+            max_players,
+            map_max_players,
+            map_hash,
+            ..
+        } => {
             info!("Sending a open-game request.");
-            main_server.send(ToServer::OpenGame { max_players }.into());
+            main_server.send(
+                ToServer::OpenGame {
+                    version: PROTOCOL_VERSION,
+                    max_players,
+                    map_max_players,
+                    map_hash,
+                }
+                .into(),
+            );
         }
         ConnectionType::JoinGame(_) => {
             info!("Sending a join-game request.");
             game_server.send(ToGameServerEvent::new(
                 Reliability::SemiOrdered,
-                ToGame::Join,
+                ToGame::Join {
+                    version: PROTOCOL_VERSION,
+                },
+            ));
+        }
+        ConnectionType::SpectateGame(_) => {
+            info!("Sending a join-game request as a spectator.");
+            game_server.send(ToGameServerEvent::new(
+                Reliability::SemiOrdered,
+                ToGame::JoinSpectator {
+                    version: PROTOCOL_VERSION,
+                },
+            ));
+        }
+        ConnectionType::RejoinGame { token, .. } => {
+            info!("Sending a rejoin-game request.");
+            game_server.send(ToGameServerEvent::new(
+                Reliability::SemiOrdered,
+                ToGame::Rejoin {
+                    version: PROTOCOL_VERSION,
+                    token,
+                },
             ));
         }
     }
@@ -134,7 +307,7 @@ fn process_from_server(
             FromServer::Pong(id) => {
                 info!("Pong {} received from server.", *id);
             }
-            FromServer::GameOpened { port } => match ports.init_game_port(*port) {
+            FromServer::GameOpened { port, nonce } => match ports.init_game_port(*port) {
                 Ok(_) => {
                     info!("Game on port {} opened.", *port);
                     // Send something to open NAT.
@@ -142,7 +315,10 @@ fn process_from_server(
                         Reliability::Unordered,
                         ToGame::Ping(u32::MAX),
                     ));
-                    opened.send(GameOpenedEvent(SocketAddr::new(conf.server_host(), *port)));
+                    opened.send(GameOpenedEvent {
+                        addr: SocketAddr::new(conf.server_host(), *port),
+                        nonce: *nonce,
+                    });
                 }
                 Err(err) => {
                     fatals.send(FatalErrorEvent::new(format!("Invalid GameOpened: {err:?}")));
@@ -154,6 +330,14 @@ fn process_from_server(
                         "Cannot open game, the player already joined a game.",
                     ));
                 }
+                GameOpenError::IncompatibleVersion { server } => {
+                    fatals.send(FatalErrorEvent::new(incompatible_version(*server)));
+                }
+                GameOpenError::TooManyPlayers => {
+                    fatals.send(FatalErrorEvent::new(
+                        "Cannot open game, the map does not support that many players.",
+                    ));
+                }
             },
         }
     }
@@ -161,13 +345,19 @@ fn process_from_server(
 
 #[allow(clippy::too_many_arguments)]
 fn process_from_game(
+    mut commands: Commands,
+    conf: Res<NetGameConfRes>,
     mut inputs: EventReader<FromGameServerEvent>,
+    mut passive: Option<ResMut<PassiveRes>>,
     mut fatals: EventWriter<FatalErrorEvent>,
     state: Res<State<NetState>>,
     mut joined_events: EventWriter<GameJoinedEvent>,
     mut peer_joined_events: EventWriter<PeerJoinedEvent>,
     mut peer_left_events: EventWriter<PeerLeftEvent>,
     mut readiness_events: EventWriter<GameReadinessEvent>,
+    mut locked_events: EventWriter<GameLockedEvent>,
+    mut max_players_events: EventWriter<GameMaxPlayersEvent>,
+    mut outputs: EventWriter<ToGameServerEvent>,
     mut next_state: ResMut<NextState<NetState>>,
 ) {
     for event in inputs.read() {
@@ -175,15 +365,61 @@ fn process_from_game(
             FromGame::Pong(id) => {
                 trace!("Received Pong({id}).");
             }
+            FromGame::Heartbeat => {
+                trace!("Received Heartbeat.");
+                outputs.send(ToGameServerEvent::new(
+                    Reliability::Unordered,
+                    ToGame::Heartbeat,
+                ));
+            }
             FromGame::NotJoined => {
                 fatals.send(FatalErrorEvent::new(
                     "Player is no longer part of the game.",
                 ));
             }
-            FromGame::Joined(player) => {
+            FromGame::Joined { player, token } => {
                 info!("Joined game as {player}.");
                 next_state.set(NetState::Joined);
-                joined_events.send(GameJoinedEvent::new(*player));
+                joined_events.send(GameJoinedEvent::joined(*player, *token));
+            }
+            FromGame::JoinedSpectator { players, readiness } => {
+                info!("Joined game as a spectator (game readiness {readiness:?}).");
+                commands.insert_resource(PassiveRes {
+                    game_readiness: *readiness,
+                    restore: *readiness == Readiness::Initialized,
+                });
+                next_state.set(NetState::Joined);
+                joined_events.send(GameJoinedEvent::spectator(*readiness >= Readiness::Ready));
+                for &id in players {
+                    peer_joined_events.send(PeerJoinedEvent(id));
+                }
+            }
+            FromGame::Rejoined { player, session } => {
+                let ConnectionType::RejoinGame { token, .. } = conf.connection_type() else {
+                    fatals.send(FatalErrorEvent::new(
+                        "Rejoined a game without a rejoin request.",
+                    ));
+                    continue;
+                };
+
+                info!("Rejoined game as {player} (session {session}).");
+                commands.insert_resource(PassiveRes {
+                    game_readiness: Readiness::Initialized,
+                    restore: true,
+                });
+                commands.insert_resource(NetSessionRes(*session));
+                next_state.set(NetState::Joined);
+                joined_events.send(GameJoinedEvent::rejoined(*player, token));
+            }
+            FromGame::Restored => {
+                info!("Game state restored.");
+                match passive.as_mut() {
+                    Some(passive) if passive.restore => {
+                        passive.restore = false;
+                        readiness_events.send(GameReadinessEvent(Readiness::Initialized));
+                    }
+                    _ => warn!("Received an unexpected game restoration."),
+                }
             }
             FromGame::JoinError(error) => match error {
                 JoinError::GameFull => {
@@ -204,8 +440,29 @@ fn process_from_game(
                         "Player already joined a different game.",
                     ));
                 }
+                JoinError::InvalidToken => {
+                    fatals.send(FatalErrorEvent::new(
+                        "Cannot rejoin the game, it is no longer possible.",
+                    ));
+                }
+                JoinError::IncompatibleVersion { server } => {
+                    fatals.send(FatalErrorEvent::new(incompatible_version(*server)));
+                }
+                JoinError::GameLocked => {
+                    fatals.send(FatalErrorEvent::new("Game is locked, cannot join."));
+                }
+                JoinError::Kicked => {
+                    fatals.send(FatalErrorEvent::new(
+                        "Player was kicked from the game, cannot join.",
+                    ));
+                }
             },
             FromGame::Left => {
+                if state.get() < &NetState::ShuttingDown {
+                    fatals.send(FatalErrorEvent::new("Player was removed from the game."));
+                }
+            }
+            FromGame::Kicked => {
                 if state.get() < &NetState::ShuttingDown {
                     fatals.send(FatalErrorEvent::new("Player was kicked from the game."));
                 }
@@ -220,26 +477,125 @@ fn process_from_game(
             }
             FromGame::GameReadiness(readiness) => {
                 info!("Game readiness changed to: {readiness:?}");
+                if let Some(passive) = passive.as_mut() {
+                    passive.game_readiness = *readiness;
+                }
                 readiness_events.send(GameReadinessEvent(*readiness));
             }
+            FromGame::GameLocked(locked) => {
+                info!("Game locked: {locked}.");
+                locked_events.send(GameLockedEvent(*locked));
+            }
+            FromGame::GameMaxPlayers(max_players) => {
+                info!("Maximum number of players changed to {max_players}.");
+                max_players_events.send(GameMaxPlayersEvent(*max_players));
+            }
+            FromGame::HostError(error) => match error {
+                HostError::NotHost => {
+                    warn!("Host command refused: the local player is not the host.");
+                }
+                HostError::GameStarted => {
+                    warn!("Host command refused: the game has already started.");
+                }
+                HostError::InvalidPlayer => {
+                    warn!("Host command refused: the player cannot be kicked.");
+                }
+                HostError::SlotOccupied => {
+                    warn!("Host command refused: a player slot above the maximum is occupied.");
+                }
+                HostError::MapCapacity => {
+                    warn!("Host command refused: the map does not support that many players.");
+                }
+            },
+            FromGame::GamePaused { .. }
+            | FromGame::ResumeVoted(_)
+            | FromGame::GameResumed
+            | FromGame::PauseError(_) => {
+                // Handled by the pause plugin.
+            }
         }
     }
 }
 
+fn incompatible_version(server: u32) -> String {
+    format!(
+        "The game server is not compatible with this version of the game: server protocol \
+         version is {server}, local version is {PROTOCOL_VERSION}."
+    )
+}
+
+fn set_secret(
+    conf: Res<NetGameConfRes>,
+    ports: Res<Ports>,
+    authentication: Res<AuthenticationRes>,
+    mut secret_events: EventReader<SetGameSecretEvent>,
+    mut message_events: EventWriter<ToGameServerEvent>,
+) {
+    let Some(event) = secret_events.read().last() else {
+        return;
+    };
+    let Some(port) = ports.game() else {
+        warn!("Game secret set before the game was opened.");
+        return;
+    };
+
+    info!("Setting game secret.");
+    authentication.register(SocketAddr::new(conf.server_host(), port), event.0);
+    // Send something to establish the authenticated session.
+    message_events.send(ToGameServerEvent::new(
+        Reliability::Unordered,
+        ToGame::Ping(u32::MAX),
+    ));
+}
+
 fn set_readiness(
+    passive: Option<Res<PassiveRes>>,
     mut readiness_events: EventReader<SetReadinessEvent>,
     mut message_events: EventWriter<ToGameServerEvent>,
+    mut game_readiness_events: EventWriter<GameReadinessEvent>,
 ) {
     let Some(readiness) = readiness_events.read().last() else {
         return;
     };
 
+    if let Some(passive) = passive {
+        if passive.restore && readiness.0 == Readiness::Initialized {
+            // The game-play starts once the game state is restored, see
+            // FromGame::Restored.
+            info!("Requesting game state restoration.");
+            message_events.send(ToGameServerEvent::new(
+                Reliability::SemiOrdered,
+                ToGame::Restore,
+            ));
+        } else if passive.game_readiness >= readiness.0 {
+            // The game does not wait for the client, thus it might already
+            // be further than the client. Readiness change events of such
+            // stages might have been missed so they are repeated here.
+            game_readiness_events.send(GameReadinessEvent(readiness.0));
+        }
+        return;
+    }
+
     message_events.send(ToGameServerEvent::new(
         Reliability::SemiOrdered,
         ToGame::Readiness(readiness.0),
     ));
 }
 
+fn host_command(
+    mut command_events: EventReader<HostCommandEvent>,
+    mut message_events: EventWriter<ToGameServerEvent>,
+) {
+    for event in command_events.read() {
+        let message = match *event {
+            HostCommandEvent::Kick(player) => ToGame::Kick(player),
+            HostCommandEvent::SetLocked(locked) => ToGame::SetLocked(locked),
+            HostCommandEvent::SetMaxPlayers(max_players) => ToGame::SetMaxPlayers(max_players),
+        };
+        message_events.send(ToGameServerEvent::new(Reliability::SemiOrdered, message));
+    }
+}
+
 fn leave(mut server: EventWriter<ToGameServerEvent>) {
     info!("Sending leave game message.");
     // Send this even if not yet joined because the join / open-game request
@@ -6,38 +6,58 @@
 //!
 //! After a multiplayer game ends, the multiplayer functionality should be shut
 //! down via [`ShutdownMultiplayerEvent`].
+//!
+//! A game recorded by DE Connector might be played back via
+//! [`StartReplayEvent`].
+//!
+//! DE Connectors and their open games might be discovered on the local
+//! network (i.e. without DE Lobby) via [`StartLanDiscoveryEvent`].
 
 use bevy::{app::PluginGroupBuilder, prelude::*};
+use discovery::DiscoveryPlugin;
 use game::GamePlugin;
 use lifecycle::LifecyclePlugin;
 use messages::MessagesPlugin;
+use pause::PausePlugin;
 use playermsg::PlayerMsgPlugin;
+use replay::ReplayPlugin;
+use snapshot::SnapshotPlugin;
 use stats::StatsPlugin;
 
+pub use de_net::SessionSecret;
+
 pub use crate::{
     config::{ConnectionType, NetGameConf},
+    discovery::{LanGames, LanServer, StartLanDiscoveryEvent, StopLanDiscoveryEvent},
     game::{
-        GameJoinedEvent, GameOpenedEvent, GameReadinessEvent, PeerJoinedEvent, PeerLeftEvent,
-        SetReadinessEvent,
+        GameJoinedEvent, GameLockedEvent, GameMaxPlayersEvent, GameOpenedEvent, GameReadinessEvent,
+        HostCommandEvent, PeerJoinedEvent, PeerLeftEvent, SetGameSecretEvent, SetReadinessEvent,
     },
     lifecycle::{MultiplayerShuttingDownEvent, ShutdownMultiplayerEvent, StartMultiplayerEvent},
     messages::{MessagesSet, ToPlayersEvent},
     netstate::NetState,
+    pause::{GamePausedEvent, GameResumedEvent, ResumeVotedEvent},
     playermsg::{
-        GameNetSet, NetEntities, NetEntityCommands, NetRecvDespawnActiveEvent, NetRecvHealthEvent,
-        NetRecvProjectileEvent, NetRecvSetPathEvent, NetRecvSpawnActiveEvent,
-        NetRecvTransformEvent,
+        GameNetSet, NetEntities, NetEntityChecksum, NetEntityCommands, NetRecvChecksumEvent,
+        NetRecvDespawnActiveEvent, NetRecvHealthEvent, NetRecvProjectileEvent, NetRecvResyncEvent,
+        NetRecvSetPathEvent, NetRecvSpawnActiveEvent, NetRecvTransformEvent,
     },
+    replay::StartReplayEvent,
+    snapshot::{NetEntitySnapshot, NetRecvSnapshotEvent, SendSnapshotEvent},
 };
 use crate::{netstate::NetStatePlugin, network::NetworkPlugin};
 
 mod config;
+mod discovery;
 mod game;
 mod lifecycle;
 mod messages;
 mod netstate;
 mod network;
+mod pause;
 mod playermsg;
+mod replay;
+mod snapshot;
 mod stats;
 
 pub struct MultiplayerPluginGroup;
@@ -50,7 +70,11 @@ impl PluginGroup for MultiplayerPluginGroup {
             .add(NetworkPlugin)
             .add(MessagesPlugin)
             .add(GamePlugin)
+            .add(PausePlugin)
             .add(StatsPlugin)
             .add(PlayerMsgPlugin)
+            .add(ReplayPlugin)
+            .add(SnapshotPlugin)
+            .add(DiscoveryPlugin)
     }
 }
@@ -145,6 +145,10 @@ impl ToMessage for ToPlayersEvent {
             ToPlayers::Transform { .. } => Reliability::Unreliable,
             ToPlayers::ChangeHealth { .. } => Reliability::SemiOrdered,
             ToPlayers::Projectile(_) => Reliability::Unreliable,
+            ToPlayers::Checksum { .. } => Reliability::SemiOrdered,
+            ToPlayers::Resync { .. } => Reliability::Unordered,
+            ToPlayers::Snapshot(_) => Reliability::Unreliable,
+            ToPlayers::SnapshotAck { .. } => Reliability::Unreliable,
         }
     }
 
@@ -206,6 +210,12 @@ impl InMessageEvent for FromGameServerEvent {
 #[derive(Event, Deref)]
 pub(crate) struct FromPlayersEvent(FromPlayers);
 
+impl From<FromPlayers> for FromPlayersEvent {
+    fn from(message: FromPlayers) -> Self {
+        Self(message)
+    }
+}
+
 impl InMessageEvent for FromPlayersEvent {
     type M = FromPlayers;
 
@@ -263,7 +273,7 @@ impl Ports {
     }
 
     /// Returns port of the game server if known.
-    fn game(&self) -> Option<u16> {
+    pub(crate) fn game(&self) -> Option<u16> {
         match self {
             Self::Game(port) => Some(*port),
             Self::Both { game, .. } => Some(*game),
@@ -281,7 +291,9 @@ impl From<ConnectionType> for Ports {
     fn from(game_type: ConnectionType) -> Self {
         match game_type {
             ConnectionType::CreateGame { port, .. } => Self::Main(port),
-            ConnectionType::JoinGame(port) => Self::Game(port),
+            ConnectionType::JoinGame(port)
+            | ConnectionType::SpectateGame(port)
+            | ConnectionType::RejoinGame { port, .. } => Self::Game(port),
         }
     }
 }
@@ -399,6 +411,8 @@ mod tests {
         let mut ports = Ports::from(ConnectionType::CreateGame {
             port: 2,
             max_players: Player::Player1,
+            map_max_players: Player::Player2,
+            map_hash: [0; 32],
         });
         assert_eq!(ports.main(), Some(2));
         assert_eq!(ports.game(), None);
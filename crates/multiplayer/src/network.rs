@@ -1,5 +1,6 @@
-use std::ops::Deref;
+use std::{net::SocketAddr, ops::Deref, time::Instant};
 
+use ahash::AHashMap;
 use async_std::channel::{TryRecvError, TrySendError};
 use bevy::{
     prelude::*,
@@ -7,11 +8,15 @@ use bevy::{
 };
 use de_core::schedule::PreMovement;
 use de_net::{
-    startup, ConnErrorReceiver, InPackage, OutPackage, PackageReceiver, PackageSender, Socket,
+    startup, Authentication, ConnErrorReceiver, Impairment, InPackage, OutPackage, PackageReceiver,
+    PackageSender, Socket,
 };
 use iyes_progress::prelude::*;
 
-use crate::{lifecycle::FatalErrorEvent, netstate::NetState};
+use crate::{
+    lifecycle::{FatalErrorEvent, NetGameConfRes},
+    netstate::NetState,
+};
 
 const MAX_RECV_PER_UPDATE: usize = 100;
 
@@ -81,11 +86,16 @@ impl PackageReceivedEvent {
     }
 }
 
+/// Authentication of the communication with the game server. Secrets of
+/// secured games are registered to it.
+#[derive(Resource, Deref)]
+pub(crate) struct AuthenticationRes(Authentication);
+
 #[derive(Resource)]
 struct NetworkStartup(Task<(PackageSender, PackageReceiver, ConnErrorReceiver)>);
 
 #[derive(Resource)]
-struct Sender(PackageSender);
+pub(crate) struct Sender(PackageSender);
 
 impl Deref for Sender {
     type Target = PackageSender;
@@ -117,16 +127,38 @@ impl Deref for Errors {
     }
 }
 
-fn setup(mut commands: Commands) {
+fn setup(
+    mut commands: Commands,
+    conf: Res<NetGameConfRes>,
+    mut fatals: EventWriter<FatalErrorEvent>,
+) {
+    let impairment = match conf.impairment().map(str::parse::<Impairment>).transpose() {
+        Ok(impairment) => impairment,
+        Err(err) => {
+            fatals.send(FatalErrorEvent::new(format!(
+                "Invalid network impairment specification: {err}"
+            )));
+            return;
+        }
+    };
+
+    let authentication = Authentication::client();
+    commands.insert_resource(AuthenticationRes(authentication.clone()));
+
     let pool = IoTaskPool::get();
     let task = pool.spawn(async {
         let socket = Socket::bind(None).await.unwrap();
-        startup(|t| pool.spawn(t).detach(), socket)
+        let socket = match impairment {
+            Some(impairment) => socket.with_impairment(impairment),
+            None => socket,
+        };
+        startup(|t| pool.spawn(t).detach(), socket, authentication)
     });
     commands.insert_resource(NetworkStartup(task));
 }
 
 fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<AuthenticationRes>();
     commands.remove_resource::<NetworkStartup>();
     commands.remove_resource::<Sender>();
     commands.remove_resource::<Receiver>();
@@ -148,13 +180,42 @@ fn wait_for_network(mut commands: Commands, mut task: ResMut<NetworkStartup>) ->
     true.into()
 }
 
+/// Sends all packages to the network stack.
+///
+/// Unreliable packages which would exceed the send budget of their target are
+/// dropped. Their content is either superseded by subsequent updates or it is
+/// not crucial. Reliable packages are always sent and they take precedence
+/// over the unreliable packages.
 fn send_packages(
     mut events: ResMut<Events<SendPackageEvent>>,
     sender: Res<Sender>,
     mut fatals: EventWriter<FatalErrorEvent>,
 ) {
-    for event in events.drain() {
-        if let Err(err) = sender.try_send(event.0) {
+    let now = Instant::now();
+    let mut budgets: AHashMap<SocketAddr, usize> = AHashMap::new();
+    let mut dropped = 0;
+
+    let (reliable, unreliable): (Vec<_>, Vec<_>) = events
+        .drain()
+        .map(|event| event.0)
+        .partition(|package| package.reliability().is_reliable());
+
+    for package in reliable.into_iter().chain(unreliable) {
+        let target = package.target();
+        let budget = budgets.entry(target).or_insert_with(|| {
+            sender
+                .stats(target)
+                .map_or(usize::MAX, |stats| stats.budget(now))
+        });
+
+        let len = package.data_slice().len();
+        if !package.reliability().is_reliable() && *budget < len {
+            dropped += 1;
+            continue;
+        }
+        *budget = budget.saturating_sub(len);
+
+        if let Err(err) = sender.try_send(package) {
             match err {
                 TrySendError::Full(_) => {
                     fatals.send(FatalErrorEvent::new("Network stack is not keeping up."));
@@ -167,6 +228,10 @@ fn send_packages(
             }
         }
     }
+
+    if dropped > 0 {
+        debug!("{dropped} unreliable packages dropped due to exhausted send budget.");
+    }
 }
 
 fn recv_packages(
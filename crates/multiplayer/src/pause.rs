@@ -0,0 +1,183 @@
+//! Synchronization of game-play pauses among clients of a multiplayer game.
+//!
+//! Pause and resume requests of the local player (see [`RequestPauseEvent`]
+//! and [`RequestResumeEvent`]) are passed to the game server. The game state
+//! is switched between [`GameState::Playing`] and [`GameState::Paused`] only
+//! upon reception of the respective game server messages so that the game is
+//! paused and resumed on all clients at the same moment.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use de_core::{
+    gamestate::GameState,
+    pause::{RequestPauseEvent, RequestResumeEvent},
+    schedule::PreMovement,
+};
+use de_messages::{FromGame, PauseError, ToGame};
+use de_net::Reliability;
+use de_types::player::Player;
+
+use crate::{
+    messages::{FromGameServerEvent, MessagesSet, ToGameServerEvent},
+    netstate::NetState,
+};
+
+pub(crate) struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GamePausedEvent>()
+            .add_event::<ResumeVotedEvent>()
+            .add_event::<GameResumedEvent>()
+            .add_systems(OnEnter(NetState::None), cleanup)
+            .add_systems(
+                PreMovement,
+                process_from_game
+                    .run_if(on_event::<FromGameServerEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_systems(
+                Update,
+                (
+                    pause
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(resource_exists::<PausedRes>),
+                    resume
+                        .run_if(in_state(GameState::Paused))
+                        .run_if(not(resource_exists::<PausedRes>)),
+                )
+                    .run_if(in_state(NetState::Joined)),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    request_pause.run_if(on_event::<RequestPauseEvent>()),
+                    request_resume.run_if(on_event::<RequestResumeEvent>()),
+                )
+                    .run_if(in_state(NetState::Joined))
+                    .before(MessagesSet::SendMessages),
+            );
+    }
+}
+
+/// This event is sent when a player pauses the joined game.
+#[derive(Event)]
+pub struct GamePausedEvent {
+    player: Player,
+    budget: Duration,
+}
+
+impl GamePausedEvent {
+    /// The player who paused the game.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// Remaining pause budget of the pausing player. The game is resumed
+    /// automatically once the budget is exhausted.
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+}
+
+/// This event is sent when a player votes for resumption of the paused game.
+#[derive(Event)]
+pub struct ResumeVotedEvent(Player);
+
+impl ResumeVotedEvent {
+    pub fn id(&self) -> Player {
+        self.0
+    }
+}
+
+/// This event is sent when the paused game is resumed.
+#[derive(Event)]
+pub struct GameResumedEvent;
+
+/// This resource exists while the joined game is paused. The game might be
+/// paused before the local game-play starts (e.g. when a paused game is
+/// rejoined), thus the pause is applied whenever the game-play is running.
+#[derive(Resource)]
+struct PausedRes;
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<PausedRes>();
+}
+
+fn process_from_game(
+    mut commands: Commands,
+    mut inputs: EventReader<FromGameServerEvent>,
+    mut paused_events: EventWriter<GamePausedEvent>,
+    mut voted_events: EventWriter<ResumeVotedEvent>,
+    mut resumed_events: EventWriter<GameResumedEvent>,
+) {
+    for event in inputs.read() {
+        match event.message() {
+            FromGame::GamePaused { player, budget } => {
+                info!("Game paused by player {player}, remaining budget is {budget:?}.");
+                commands.insert_resource(PausedRes);
+                paused_events.send(GamePausedEvent {
+                    player: *player,
+                    budget: *budget,
+                });
+            }
+            FromGame::ResumeVoted(player) => {
+                info!("Player {player} voted for resumption of the game.");
+                voted_events.send(ResumeVotedEvent(*player));
+            }
+            FromGame::GameResumed => {
+                info!("Game resumed.");
+                commands.remove_resource::<PausedRes>();
+                resumed_events.send(GameResumedEvent);
+            }
+            FromGame::PauseError(error) => match error {
+                PauseError::GameNotRunning => {
+                    warn!("Pause request refused: the game is not running.");
+                }
+                PauseError::AlreadyPaused => {
+                    warn!("Pause request refused: the game is already paused.");
+                }
+                PauseError::NotPaused => {
+                    warn!("Resume request refused: the game is not paused.");
+                }
+                PauseError::BudgetExhausted => {
+                    warn!("Pause request refused: the pause budget is exhausted.");
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+fn pause(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Paused);
+}
+
+fn resume(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Playing);
+}
+
+fn request_pause(
+    mut request_events: EventReader<RequestPauseEvent>,
+    mut message_events: EventWriter<ToGameServerEvent>,
+) {
+    request_events.clear();
+    info!("Requesting a game pause.");
+    message_events.send(ToGameServerEvent::new(
+        Reliability::SemiOrdered,
+        ToGame::Pause,
+    ));
+}
+
+fn request_resume(
+    mut request_events: EventReader<RequestResumeEvent>,
+    mut message_events: EventWriter<ToGameServerEvent>,
+) {
+    request_events.clear();
+    info!("Voting for resumption of the game.");
+    message_events.send(ToGameServerEvent::new(
+        Reliability::SemiOrdered,
+        ToGame::Resume,
+    ));
+}
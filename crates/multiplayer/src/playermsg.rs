@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use ahash::AHashMap;
 use bevy::{
     ecs::{entity::Entities, system::SystemParam},
@@ -20,6 +22,8 @@ impl Plugin for PlayerMsgPlugin {
             .add_event::<NetRecvTransformEvent>()
             .add_event::<NetRecvSetPathEvent>()
             .add_event::<NetRecvProjectileEvent>()
+            .add_event::<NetRecvChecksumEvent>()
+            .add_event::<NetRecvResyncEvent>()
             .add_systems(OnEnter(AppState::InGame), setup)
             .add_systems(OnExit(AppState::InGame), cleanup)
             .add_systems(
@@ -39,8 +43,9 @@ pub enum GameNetSet {
 }
 
 /// This event is sent when a new entity of a non-local player is to be
-/// spawned. An empty ECS entity is spawned to obtain local entity ID. The rest
-/// is kept to the handling event systems.
+/// spawned, or when an entity of a local player is restored after a rejoin of
+/// the game. An empty ECS entity is spawned to obtain local entity ID. The
+/// rest is kept to the handling event systems.
 ///
 /// This event is send during [`GameNetSet::Messages`] set.
 #[derive(Event)]
@@ -49,6 +54,7 @@ pub struct NetRecvSpawnActiveEvent {
     entity: Entity,
     object_type: ActiveObjectType,
     transform: Transform,
+    local: bool,
 }
 
 impl NetRecvSpawnActiveEvent {
@@ -57,12 +63,14 @@ impl NetRecvSpawnActiveEvent {
         entity: Entity,
         object_type: ActiveObjectType,
         transform: Transform,
+        local: bool,
     ) -> Self {
         Self {
             player,
             entity,
             object_type,
             transform,
+            local,
         }
     }
 
@@ -82,6 +90,12 @@ impl NetRecvSpawnActiveEvent {
     pub fn transform(&self) -> Transform {
         self.transform
     }
+
+    /// True if the entity is to be simulated locally. This is the case only
+    /// for restored entities of a rejoined game.
+    pub fn is_local(&self) -> bool {
+        self.local
+    }
 }
 
 /// This event is sent when an active entity of a non-local player is to be
@@ -130,18 +144,36 @@ impl NetRecvHealthEvent {
 #[derive(Event)]
 pub struct NetRecvTransformEvent {
     entity: Entity,
+    player: Player,
+    time: Duration,
     transform: Transform,
 }
 
 impl NetRecvTransformEvent {
-    fn new(entity: Entity, transform: Transform) -> Self {
-        Self { entity, transform }
+    fn new(entity: Entity, player: Player, time: Duration, transform: Transform) -> Self {
+        Self {
+            entity,
+            player,
+            time,
+            transform,
+        }
     }
 
     pub fn entity(&self) -> Entity {
         self.entity
     }
 
+    /// The player simulating the entity.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// Simulation time of the simulating player at which the entity had the
+    /// transform.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
     pub fn transform(&self) -> Transform {
         self.transform
     }
@@ -170,6 +202,83 @@ impl NetRecvSetPathEvent {
 #[derive(Event, Deref)]
 pub struct NetRecvProjectileEvent(NetProjectile);
 
+/// This event is sent when a non-local player sends checksums of the entities
+/// it simulates.
+#[derive(Event)]
+pub struct NetRecvChecksumEvent {
+    player: Player,
+    round: u32,
+    entities: Vec<NetEntityChecksum>,
+}
+
+impl NetRecvChecksumEvent {
+    fn new(player: Player, round: u32, entities: Vec<NetEntityChecksum>) -> Self {
+        Self {
+            player,
+            round,
+            entities,
+        }
+    }
+
+    /// The player simulating the entities.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    pub fn entities(&self) -> &[NetEntityChecksum] {
+        self.entities.as_slice()
+    }
+}
+
+/// Checksum of a single remotely simulated entity.
+pub struct NetEntityChecksum {
+    entity: EntityNet,
+    local: Option<Entity>,
+    position: Vec2,
+    checksum: u32,
+}
+
+impl NetEntityChecksum {
+    pub fn entity(&self) -> EntityNet {
+        self.entity
+    }
+
+    /// Local replica of the entity or None if the entity is not known
+    /// locally.
+    pub fn local(&self) -> Option<Entity> {
+        self.local
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+}
+
+/// This event is sent when another player asks for a re-send of the state of
+/// a locally simulated entity.
+#[derive(Event)]
+pub struct NetRecvResyncEvent {
+    entity: Entity,
+}
+
+impl NetRecvResyncEvent {
+    fn new(entity: Entity) -> Self {
+        Self { entity }
+    }
+
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
 #[derive(SystemParam)]
 pub struct NetEntities<'w> {
     config: Res<'w, GameConfig>,
@@ -181,9 +290,12 @@ impl<'w> NetEntities<'w> {
     /// locally simulated and non-local entities.
     ///
     /// It is assumed that the entity exists.
-    pub fn net_id(&self, entity: Entity) -> EntityNet {
+    ///
+    /// None is returned if the entity cannot be identified over the network,
+    /// see [`Self::local_net_id`].
+    pub fn net_id(&self, entity: Entity) -> Option<EntityNet> {
         match self.map.translate_local(entity) {
-            Some(id) => id,
+            Some(id) => Some(id),
             None => self.local_net_id(entity),
         }
     }
@@ -192,9 +304,32 @@ impl<'w> NetEntities<'w> {
     /// locally simulated entities.
     ///
     /// It is assumed that the entity exists.
-    pub fn local_net_id(&self, entity: Entity) -> EntityNet {
-        let player = self.config.locals().playable();
-        EntityNet::new(player, entity.into())
+    ///
+    /// None is returned (and a warning is logged) if the index of the entity
+    /// is too large to be sent over the network.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the game is only observed, i.e. there are no locally
+    /// simulated entities.
+    pub fn local_net_id(&self, entity: Entity) -> Option<EntityNet> {
+        // Entities restored after a rejoin keep their original IDs.
+        if let Some(id) = self.map.translate_local(entity) {
+            return Some(id);
+        }
+
+        let player = self
+            .config
+            .locals()
+            .playable()
+            .expect("There are no local entities in an observed game.");
+        match NetEntityIndex::new(entity.index(), self.map.session) {
+            Ok(index) => Some(EntityNet::new(player, index)),
+            Err(error) => {
+                warn!("Entity {entity:?} cannot be identified over the network: {error}");
+                None
+            }
+        }
     }
 }
 
@@ -218,26 +353,42 @@ impl<'w> NetEntityCommands<'w> {
     }
 
     fn local_id(&self, entity: EntityNet) -> Option<Entity> {
-        self.remote_local_id(entity)
-            .or_else(|| self.entities.resolve_from_id(entity.index().into()))
+        self.remote_local_id(entity).or_else(|| {
+            let index = entity.index();
+            if index.session() == self.map.session {
+                self.entities.resolve_from_id(index.entity_index())
+            } else {
+                None
+            }
+        })
     }
 
-    fn remote_local_id(&self, entity: EntityNet) -> Option<Entity> {
+    pub(crate) fn remote_local_id(&self, entity: EntityNet) -> Option<Entity> {
         self.map.translate_remote(entity)
     }
 }
 
+/// Number of times the local player rejoined the current game. See
+/// [`NetEntityIndex`].
+#[derive(Resource)]
+pub(crate) struct NetSessionRes(pub(crate) u8);
+
 /// Mapping between remote and local entity IDs for non-locally simulated
-/// entities.
+/// entities and for locally simulated entities restored after a rejoin of the
+/// game.
 #[derive(Resource)]
 struct EntityIdMapRes {
+    /// Session of the local player, it is part of net IDs of all entities
+    /// spawned locally.
+    session: u8,
     remote_to_local: AHashMap<Player, PlayerNetToLocal>,
     local_to_remote: AHashMap<Entity, EntityNet>,
 }
 
 impl EntityIdMapRes {
-    fn new() -> Self {
+    fn new(session: u8) -> Self {
         Self {
+            session,
             remote_to_local: AHashMap::new(),
             local_to_remote: AHashMap::new(),
         }
@@ -280,13 +431,13 @@ impl EntityIdMapRes {
     }
 
     /// Translates local entity ID to a remote entity ID in case the entity is
-    /// not locally simulated.
+    /// registered.
     fn translate_local(&self, local: Entity) -> Option<EntityNet> {
         self.local_to_remote.get(&local).copied()
     }
 
     /// Translates remote entity ID to a local entity ID in case the entity is
-    /// not locally simulated.
+    /// registered.
     fn translate_remote(&self, remote: EntityNet) -> Option<Entity> {
         self.remote_to_local
             .get(&remote.player())
@@ -339,8 +490,9 @@ impl PlayerNetToLocal {
     }
 }
 
-fn setup(mut commands: Commands) {
-    commands.insert_resource(EntityIdMapRes::new());
+fn setup(mut commands: Commands, session: Option<Res<NetSessionRes>>) {
+    let session = session.map_or(0, |s| s.0);
+    commands.insert_resource(EntityIdMapRes::new(session));
 }
 
 fn cleanup(mut commands: Commands) {
@@ -350,6 +502,7 @@ fn cleanup(mut commands: Commands) {
 #[allow(clippy::too_many_arguments)]
 fn recv_messages(
     mut commands: Commands,
+    config: Res<GameConfig>,
     mut net_commands: NetEntityCommands,
     mut inputs: EventReader<FromPlayersEvent>,
     mut spawn_events: EventWriter<NetRecvSpawnActiveEvent>,
@@ -358,6 +511,8 @@ fn recv_messages(
     mut transform_events: EventWriter<NetRecvTransformEvent>,
     mut health_events: EventWriter<NetRecvHealthEvent>,
     mut projectile_events: EventWriter<NetRecvProjectileEvent>,
+    mut checksum_events: EventWriter<NetRecvChecksumEvent>,
+    mut resync_events: EventWriter<NetRecvResyncEvent>,
 ) {
     for input in inputs.read() {
         match input.message() {
@@ -375,6 +530,9 @@ fn recv_messages(
                     local,
                     *object_type,
                     transform.into(),
+                    // Entities of the local player are received only when the
+                    // game is being restored after a rejoin.
+                    config.locals().is_local(entity.player()),
                 ));
             }
             ToPlayers::Despawn { entity } => {
@@ -393,9 +551,18 @@ fn recv_messages(
                     waypoints.as_ref().map(|p| p.into()),
                 ));
             }
-            ToPlayers::Transform { entity, transform } => {
+            ToPlayers::Transform {
+                entity,
+                time,
+                transform,
+            } => {
                 if let Some(local) = net_commands.remote_local_id(*entity) {
-                    transform_events.send(NetRecvTransformEvent::new(local, transform.into()));
+                    transform_events.send(NetRecvTransformEvent::new(
+                        local,
+                        entity.player(),
+                        *time,
+                        transform.into(),
+                    ));
                 }
             }
             ToPlayers::ChangeHealth { entity, delta } => {
@@ -409,6 +576,34 @@ fn recv_messages(
             ToPlayers::Projectile(projectile) => {
                 projectile_events.send(NetRecvProjectileEvent(*projectile));
             }
+            ToPlayers::Checksum { round, entities } => {
+                if config.locals().is_local(input.source()) {
+                    continue;
+                }
+
+                let entities = entities
+                    .entities()
+                    .iter()
+                    .filter(|checksum| checksum.entity().player() == input.source())
+                    .map(|checksum| NetEntityChecksum {
+                        entity: checksum.entity(),
+                        local: net_commands.remote_local_id(checksum.entity()),
+                        position: checksum.position().into(),
+                        checksum: checksum.checksum(),
+                    })
+                    .collect();
+
+                checksum_events.send(NetRecvChecksumEvent::new(input.source(), *round, entities));
+            }
+            ToPlayers::Resync { entity } => {
+                if !config.locals().is_local(entity.player()) {
+                    continue;
+                }
+
+                if let Some(local) = net_commands.local_id(*entity) {
+                    resync_events.send(NetRecvResyncEvent::new(local));
+                }
+            }
             _ => (),
         }
     }
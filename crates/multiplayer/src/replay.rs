@@ -0,0 +1,173 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+use de_core::{gamestate::GameState, schedule::PreMovement, state::AppState};
+use de_messages::{Readiness, ReplayEvent, ReplayRecord};
+
+use crate::{
+    game::{GameReadinessEvent, PeerJoinedEvent, PeerLeftEvent, SetReadinessEvent},
+    messages::{FromPlayersEvent, MessagesSet},
+};
+
+/// This plugin plays back a recorded game (see [`StartReplayEvent`]).
+pub(crate) struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StartReplayEvent>()
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(
+                OnTransition {
+                    from: GameState::Waiting,
+                    to: GameState::Playing,
+                },
+                start_clock,
+            )
+            .add_systems(Update, start.run_if(on_event::<StartReplayEvent>()))
+            .add_systems(
+                PostUpdate,
+                readiness
+                    .run_if(resource_exists::<ReplayRes>)
+                    .run_if(on_event::<SetReadinessEvent>()),
+            )
+            .add_systems(
+                PreMovement,
+                play.run_if(resource_exists::<ReplayRes>)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(MessagesSet::RecvMessages),
+            );
+    }
+}
+
+/// Send this event to play back a recorded game. Player messages from the
+/// replay are fed to the same events as messages received during a live
+/// multiplayer game.
+///
+/// The game must be configured as an observed multiplayer game (see
+/// [`de_core::gconfig::LocalPlayers::observer`]) and started right after this
+/// event is sent. The playback is stopped once the game is exited.
+#[derive(Event)]
+pub struct StartReplayEvent {
+    records: Vec<ReplayRecord>,
+}
+
+impl StartReplayEvent {
+    /// # Arguments
+    ///
+    /// * `records` - all records of the replay.
+    pub fn new(records: Vec<ReplayRecord>) -> Self {
+        Self { records }
+    }
+}
+
+#[derive(Resource)]
+struct ReplayRes {
+    /// Replay time corresponding to the current frame.
+    clock: Duration,
+    records: VecDeque<ReplayRecord>,
+}
+
+impl ReplayRes {
+    fn new(records: Vec<ReplayRecord>) -> Self {
+        Self {
+            clock: Duration::ZERO,
+            records: records.into(),
+        }
+    }
+
+    /// Moves the clock to the time when the recorded game was fully
+    /// initialized, i.e. when the game-play started.
+    fn skip_initialization(&mut self) {
+        let start = self.records.iter().find_map(|r| match r.event() {
+            ReplayEvent::GameReadiness(Readiness::Initialized) => Some(r.time()),
+            _ => None,
+        });
+        if let Some(start) = start {
+            self.clock = start;
+        }
+    }
+
+    /// Advances the clock by `delta`.
+    fn tick(&mut self, delta: Duration) {
+        self.clock += delta;
+    }
+
+    /// Removes and returns the oldest record which is not newer than the
+    /// clock.
+    fn pop(&mut self) -> Option<ReplayRecord> {
+        if self.records.front()?.time() <= self.clock {
+            self.records.pop_front()
+        } else {
+            None
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+fn start(mut commands: Commands, mut events: ResMut<Events<StartReplayEvent>>) {
+    let Some(event) = events.drain().last() else {
+        return;
+    };
+
+    info!(
+        "Starting replay playback with {} records.",
+        event.records.len()
+    );
+    commands.insert_resource(ReplayRes::new(event.records));
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ReplayRes>();
+}
+
+fn start_clock(replay: Option<ResMut<ReplayRes>>) {
+    if let Some(mut replay) = replay {
+        replay.skip_initialization();
+    }
+}
+
+/// There are no other players to wait for during a replay playback, thus
+/// game readiness follows the local readiness.
+fn readiness(
+    mut set_events: EventReader<SetReadinessEvent>,
+    mut game_events: EventWriter<GameReadinessEvent>,
+) {
+    for event in set_events.read() {
+        game_events.send(GameReadinessEvent(event.0));
+    }
+}
+
+fn play(
+    time: Res<Time>,
+    mut replay: ResMut<ReplayRes>,
+    mut players: EventWriter<FromPlayersEvent>,
+    mut peer_joined: EventWriter<PeerJoinedEvent>,
+    mut peer_left: EventWriter<PeerLeftEvent>,
+) {
+    if replay.is_finished() {
+        return;
+    }
+
+    replay.tick(time.delta());
+    while let Some(record) = replay.pop() {
+        match record.into_event() {
+            ReplayEvent::PeerJoined(player) => {
+                peer_joined.send(PeerJoinedEvent(player));
+            }
+            ReplayEvent::PeerLeft(player) => {
+                peer_left.send(PeerLeftEvent(player));
+            }
+            ReplayEvent::GameReadiness(_) => (),
+            ReplayEvent::Message(message) => {
+                players.send(message.into());
+            }
+        }
+    }
+
+    if replay.is_finished() {
+        info!("Replay playback finished.");
+    }
+}
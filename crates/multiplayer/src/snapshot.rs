@@ -0,0 +1,638 @@
+use std::{collections::VecDeque, time::Duration};
+
+use ahash::AHashMap;
+use bevy::prelude::*;
+use de_core::{gconfig::GameConfig, schedule::PreMovement, state::AppState};
+use de_messages::{
+    EntityDeltaNet, EntityNet, EntityStateNet, SnapshotError, SnapshotPartNet, ToPlayers,
+};
+use de_types::player::Player;
+
+use crate::{
+    game::PeerLeftEvent,
+    messages::{FromPlayersEvent, MessagesSet, ToPlayersEvent},
+    playermsg::{GameNetSet, NetEntityCommands},
+};
+
+/// Number of the latest snapshots kept on both the sending and the receiving
+/// side. Older snapshots cannot be used as a baseline.
+const HISTORY: usize = 32;
+/// Every n-th snapshot is sent without a baseline. This allows players who
+/// have not received any snapshot yet (e.g. players who just rejoined the
+/// game) and spectators to catch up.
+const KEYFRAME_INTERVAL: u32 = 16;
+
+/// This plugin replicates periodic snapshots of the state of locally
+/// simulated entities. The snapshots are delta-encoded against the latest
+/// snapshot acknowledged by all other players.
+pub(crate) struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SendSnapshotEvent>()
+            .add_event::<NetRecvSnapshotEvent>()
+            .add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(
+                PostUpdate,
+                send_snapshot
+                    .run_if(resource_exists::<OutSnapshots>)
+                    .run_if(on_event::<SendSnapshotEvent>())
+                    .before(MessagesSet::SendMessages),
+            )
+            .add_systems(
+                PreMovement,
+                (
+                    forget_players
+                        .run_if(resource_exists::<OutSnapshots>)
+                        .run_if(on_event::<PeerLeftEvent>()),
+                    recv_messages
+                        .run_if(resource_exists::<OutSnapshots>)
+                        .run_if(on_event::<FromPlayersEvent>())
+                        .in_set(GameNetSet::Messages)
+                        .after(MessagesSet::RecvMessages),
+                ),
+            );
+    }
+}
+
+/// Send this event to send a snapshot of the state of all locally simulated
+/// entities to other players.
+///
+/// This event should not be send during single player games.
+#[derive(Event)]
+pub struct SendSnapshotEvent {
+    time: Duration,
+    entities: Vec<(EntityNet, EntityStateNet)>,
+}
+
+impl SendSnapshotEvent {
+    /// # Arguments
+    ///
+    /// * `time` - current simulation time.
+    ///
+    /// * `entities` - current state of all locally simulated entities.
+    pub fn new(time: Duration, entities: Vec<(EntityNet, EntityStateNet)>) -> Self {
+        Self { time, entities }
+    }
+}
+
+/// This event is sent when a part of a snapshot of the entities simulated by
+/// a non-local player is received. Each part is applied on its own against
+/// its baseline, thus a lost part does not prevent the other parts of the
+/// snapshot from being used. Parts of snapshots older than an already received
+/// snapshot part are dropped.
+///
+/// This event is send during [`GameNetSet::Messages`] set.
+#[derive(Event)]
+pub struct NetRecvSnapshotEvent {
+    player: Player,
+    time: Duration,
+    entities: Vec<NetEntitySnapshot>,
+}
+
+impl NetRecvSnapshotEvent {
+    /// The player simulating the entities.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// Simulation time of the simulating player at which the snapshot was
+    /// taken.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// Snapshots of all locally replicated entities included in the snapshot
+    /// part, i.e. entities which changed since the baseline.
+    pub fn entities(&self) -> &[NetEntitySnapshot] {
+        self.entities.as_slice()
+    }
+}
+
+/// State of a single remotely simulated entity.
+pub struct NetEntitySnapshot {
+    entity: EntityNet,
+    local: Entity,
+    state: EntityStateNet,
+    previous: Option<EntityStateNet>,
+}
+
+impl NetEntitySnapshot {
+    pub fn entity(&self) -> EntityNet {
+        self.entity
+    }
+
+    /// Local replica of the entity.
+    pub fn local(&self) -> Entity {
+        self.local
+    }
+
+    /// State of the entity on the side of the simulating player.
+    pub fn state(&self) -> &EntityStateNet {
+        &self.state
+    }
+
+    /// State of the entity in the latest completely received snapshot, if it
+    /// was part of it.
+    pub fn previous(&self) -> Option<&EntityStateNet> {
+        self.previous.as_ref()
+    }
+}
+
+type Snapshot = AHashMap<EntityNet, EntityStateNet>;
+
+/// Snapshots of locally simulated entities sent to other players.
+#[derive(Resource, Default)]
+struct OutSnapshots {
+    sequence: u32,
+    history: VecDeque<(u32, Snapshot)>,
+    /// Latest snapshot acknowledged by each player.
+    acks: AHashMap<Player, u32>,
+}
+
+impl OutSnapshots {
+    fn ack(&mut self, player: Player, sequence: u32) {
+        let ack = self.acks.entry(player).or_insert(sequence);
+        *ack = (*ack).max(sequence);
+    }
+
+    /// Forgets acknowledgements of a player. This should be called once the
+    /// player leaves the game.
+    fn forget(&mut self, player: Player) {
+        self.acks.remove(&player);
+    }
+
+    /// Records a new snapshot and returns its delta-encoded parts.
+    fn push(
+        &mut self,
+        time: Duration,
+        snapshot: Snapshot,
+    ) -> Result<Vec<SnapshotPartNet>, SnapshotError> {
+        self.sequence = self.sequence.wrapping_add(1);
+
+        let baseline = if self.sequence.is_multiple_of(KEYFRAME_INTERVAL) {
+            None
+        } else {
+            // Players who have not acknowledged any snapshot are ignored, they
+            // catch up with the next keyframe.
+            self.acks
+                .values()
+                .min()
+                .and_then(|&acked| self.history.iter().find(|(sequence, _)| *sequence == acked))
+        };
+
+        let changed = snapshot
+            .iter()
+            .filter_map(|(&entity, state)| {
+                EntityDeltaNet::new(
+                    entity,
+                    baseline.and_then(|(_, baseline)| baseline.get(&entity)),
+                    state,
+                )
+            })
+            .collect();
+        let removed = match baseline {
+            Some((_, baseline)) => baseline
+                .keys()
+                .filter(|entity| !snapshot.contains_key(entity))
+                .copied()
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let parts = SnapshotPartNet::split(
+            self.sequence,
+            baseline.map(|&(sequence, _)| sequence),
+            time,
+            changed,
+            removed,
+        )?;
+
+        if self.history.len() >= HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back((self.sequence, snapshot));
+
+        Ok(parts)
+    }
+}
+
+/// Snapshots received from other players.
+#[derive(Resource, Default)]
+struct InSnapshots(AHashMap<Player, PlayerSnapshots>);
+
+#[derive(Default)]
+struct PlayerSnapshots {
+    /// Completely received snapshots, the latest is the last. Only these
+    /// are acknowledged and might serve as a baseline.
+    history: VecDeque<(u32, Snapshot)>,
+    pending: Option<PendingSnapshot>,
+}
+
+impl PlayerSnapshots {
+    /// Applies a snapshot part against its baseline. Returns None if the part
+    /// is outdated or if its baseline is unknown.
+    fn receive(&mut self, part: &SnapshotPartNet) -> Option<ReceivedPart> {
+        if self
+            .latest()
+            .is_some_and(|(sequence, _)| sequence >= part.sequence())
+        {
+            return None;
+        }
+
+        if self
+            .pending
+            .as_ref()
+            .is_none_or(|pending| pending.sequence < part.sequence())
+        {
+            let snapshot = match part.baseline() {
+                Some(baseline) => self
+                    .history
+                    .iter()
+                    .find(|(sequence, _)| *sequence == baseline)
+                    .map(|(_, snapshot)| snapshot.clone())?,
+                None => Snapshot::new(),
+            };
+            self.pending = Some(PendingSnapshot::new(part, snapshot));
+        }
+
+        let pending = self.pending.as_mut().unwrap();
+        if pending.sequence != part.sequence() {
+            return None;
+        }
+        let entities = pending.insert(part)?;
+        let entities = entities
+            .into_iter()
+            .map(|(entity, state)| {
+                let previous = self
+                    .latest()
+                    .and_then(|(_, latest)| latest.get(&entity))
+                    .cloned();
+                (entity, state, previous)
+            })
+            .collect();
+
+        let completed = if self.pending.as_ref().unwrap().missing == 0 {
+            let pending = self.pending.take().unwrap();
+            if self.history.len() >= HISTORY {
+                self.history.pop_front();
+            }
+            self.history.push_back((pending.sequence, pending.snapshot));
+            Some(pending.sequence)
+        } else {
+            None
+        };
+
+        Some(ReceivedPart {
+            entities,
+            completed,
+        })
+    }
+
+    /// Returns the latest completely received snapshot.
+    fn latest(&self) -> Option<(u32, &Snapshot)> {
+        self.history
+            .back()
+            .map(|(sequence, snapshot)| (*sequence, snapshot))
+    }
+}
+
+/// A successfully applied snapshot part.
+struct ReceivedPart {
+    /// New states of the entities included in the part, each with its state
+    /// from the latest completely received snapshot.
+    entities: Vec<(EntityNet, EntityStateNet, Option<EntityStateNet>)>,
+    /// Sequence number of the snapshot if the part completed it.
+    completed: Option<u32>,
+}
+
+/// A snapshot whose parts are being received.
+struct PendingSnapshot {
+    sequence: u32,
+    baseline: Option<u32>,
+    /// The baseline with all already received parts applied.
+    snapshot: Snapshot,
+    received: Vec<bool>,
+    missing: usize,
+}
+
+impl PendingSnapshot {
+    fn new(part: &SnapshotPartNet, baseline: Snapshot) -> Self {
+        let parts = usize::from(part.parts());
+        Self {
+            sequence: part.sequence(),
+            baseline: part.baseline(),
+            snapshot: baseline,
+            received: vec![false; parts],
+            missing: parts,
+        }
+    }
+
+    /// Applies a part of the snapshot and returns new states of the entities
+    /// included in it. Returns None if the part was already received or if
+    /// it is inconsistent with the other parts.
+    fn insert(&mut self, part: &SnapshotPartNet) -> Option<Vec<(EntityNet, EntityStateNet)>> {
+        if part.baseline() != self.baseline || usize::from(part.parts()) != self.received.len() {
+            warn!(
+                "Received snapshot part inconsistent with other parts of snapshot {}.",
+                self.sequence
+            );
+            return None;
+        }
+
+        let received = self.received.get_mut(usize::from(part.part()))?;
+        if *received {
+            return None;
+        }
+        *received = true;
+        self.missing -= 1;
+
+        // Each entity is included in at most one part of a snapshot, thus
+        // the deltas are applied against the baseline state.
+        let mut entities = Vec::with_capacity(part.changed().len());
+        for delta in part.changed() {
+            let entity = delta.entity();
+            if let Some(state) = delta.apply(self.snapshot.get(&entity)) {
+                self.snapshot.insert(entity, state.clone());
+                entities.push((entity, state));
+            }
+        }
+        for entity in part.removed() {
+            self.snapshot.remove(entity);
+        }
+
+        Some(entities)
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<OutSnapshots>();
+    commands.init_resource::<InSnapshots>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<OutSnapshots>();
+    commands.remove_resource::<InSnapshots>();
+}
+
+fn send_snapshot(
+    mut snapshots: ResMut<OutSnapshots>,
+    mut events: EventReader<SendSnapshotEvent>,
+    mut net_events: EventWriter<ToPlayersEvent>,
+) {
+    for event in events.read() {
+        let parts = match snapshots.push(event.time, event.entities.iter().cloned().collect()) {
+            Ok(parts) => parts,
+            Err(error) => {
+                warn!("Failed to send a snapshot: {error}");
+                continue;
+            }
+        };
+
+        for part in parts {
+            net_events.send(ToPlayersEvent::new(ToPlayers::Snapshot(part)));
+        }
+    }
+}
+
+fn forget_players(mut snapshots: ResMut<OutSnapshots>, mut events: EventReader<PeerLeftEvent>) {
+    for event in events.read() {
+        snapshots.forget(event.id());
+    }
+}
+
+fn recv_messages(
+    config: Res<GameConfig>,
+    net_commands: NetEntityCommands,
+    mut out_snapshots: ResMut<OutSnapshots>,
+    mut in_snapshots: ResMut<InSnapshots>,
+    mut inputs: EventReader<FromPlayersEvent>,
+    mut snapshot_events: EventWriter<NetRecvSnapshotEvent>,
+    mut net_events: EventWriter<ToPlayersEvent>,
+) {
+    for input in inputs.read() {
+        match input.message() {
+            ToPlayers::Snapshot(part) => {
+                let player = input.source();
+                if config.locals().is_local(player) {
+                    continue;
+                }
+
+                let player_snapshots = in_snapshots.0.entry(player).or_default();
+                let Some(received) = player_snapshots.receive(part) else {
+                    continue;
+                };
+
+                // Spectators do not send any player messages.
+                if let Some(sequence) = received
+                    .completed
+                    .filter(|_| config.locals().playable().is_some())
+                {
+                    net_events.send(ToPlayersEvent::new(ToPlayers::SnapshotAck {
+                        player,
+                        sequence,
+                    }));
+                }
+
+                let entities = received
+                    .entities
+                    .into_iter()
+                    .filter(|(entity, _, _)| entity.player() == player)
+                    .filter_map(|(entity, state, previous)| {
+                        net_commands
+                            .remote_local_id(entity)
+                            .map(|local| NetEntitySnapshot {
+                                entity,
+                                local,
+                                state,
+                                previous,
+                            })
+                    })
+                    .collect();
+                snapshot_events.send(NetRecvSnapshotEvent {
+                    player,
+                    time: part.time(),
+                    entities,
+                });
+            }
+            ToPlayers::SnapshotAck { player, sequence } if config.locals().is_local(*player) => {
+                out_snapshots.ack(input.source(), *sequence);
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de_messages::{NetEntityIndex, TransformNet};
+
+    use super::*;
+
+    fn entity(index: u32) -> EntityNet {
+        EntityNet::new(Player::Player1, NetEntityIndex::new(index, 0).unwrap())
+    }
+
+    fn state(x: f32) -> EntityStateNet {
+        EntityStateNet::new(
+            TransformNet::new(Vec3::new(x, 0., 0.).into(), Vec4::W.into()),
+            10.,
+            None,
+        )
+    }
+
+    fn snapshot(entities: &[(u32, f32)]) -> Snapshot {
+        entities
+            .iter()
+            .map(|&(index, x)| (entity(index), state(x)))
+            .collect()
+    }
+
+    /// Receives all parts and returns true if they completed a snapshot.
+    fn receive(receiver: &mut PlayerSnapshots, parts: &[SnapshotPartNet]) -> bool {
+        parts
+            .iter()
+            .filter_map(|part| receiver.receive(part))
+            .any(|received| received.completed.is_some())
+    }
+
+    #[test]
+    fn test_replication() {
+        let mut sender = OutSnapshots::default();
+        let mut receiver = PlayerSnapshots::default();
+
+        let parts = sender
+            .push(Duration::ZERO, snapshot(&[(1, 1.), (2, 2.)]))
+            .unwrap();
+        assert_eq!(parts[0].baseline(), None);
+        assert!(receive(&mut receiver, &parts));
+        let (sequence, latest) = receiver.latest().unwrap();
+        assert_eq!(sequence, 1);
+        assert_eq!(latest, &snapshot(&[(1, 1.), (2, 2.)]));
+
+        // Nothing was acknowledged yet.
+        let parts = sender
+            .push(Duration::ZERO, snapshot(&[(1, 3.), (2, 2.)]))
+            .unwrap();
+        assert_eq!(parts[0].baseline(), None);
+        assert_eq!(parts[0].changed().len(), 2);
+        assert!(receive(&mut receiver, &parts));
+
+        sender.ack(Player::Player2, 2);
+        sender.ack(Player::Player2, 1);
+        let parts = sender
+            .push(Duration::ZERO, snapshot(&[(1, 4.), (3, 5.)]))
+            .unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].baseline(), Some(2));
+        assert_eq!(parts[0].changed().len(), 2);
+        assert_eq!(parts[0].removed(), &[entity(2)]);
+
+        // Lost snapshot.
+        let lost = sender
+            .push(Duration::ZERO, snapshot(&[(1, 6.), (3, 5.)]))
+            .unwrap();
+        assert_eq!(lost[0].baseline(), Some(2));
+
+        let received = receiver.receive(&parts[0]).unwrap();
+        assert_eq!(received.completed, Some(3));
+        let (_, current, previous) = received
+            .entities
+            .iter()
+            .find(|(net_entity, _, _)| *net_entity == entity(1))
+            .unwrap();
+        assert_eq!(current, &state(4.));
+        assert_eq!(previous.as_ref(), Some(&state(3.)));
+        let (sequence, latest) = receiver.latest().unwrap();
+        assert_eq!(sequence, 3);
+        assert_eq!(latest, &snapshot(&[(1, 4.), (3, 5.)]));
+
+        // Late snapshot.
+        assert!(!receive(&mut receiver, &parts));
+
+        sender.ack(Player::Player2, 3);
+        // Player 3 acknowledged an older snapshot.
+        sender.ack(Player::Player3, 1);
+        let parts = sender
+            .push(Duration::ZERO, snapshot(&[(1, 7.), (3, 5.)]))
+            .unwrap();
+        assert_eq!(parts[0].baseline(), Some(1));
+        assert!(receive(&mut receiver, &parts));
+        assert_eq!(receiver.latest().unwrap().1, &snapshot(&[(1, 7.), (3, 5.)]));
+
+        sender.forget(Player::Player3);
+        let parts = sender.push(Duration::ZERO, snapshot(&[(3, 5.)])).unwrap();
+        assert_eq!(parts[0].baseline(), Some(3));
+        assert!(parts[0].changed().is_empty());
+        assert_eq!(parts[0].removed(), &[entity(1)]);
+        assert!(receive(&mut receiver, &parts));
+        assert_eq!(receiver.latest().unwrap().1, &snapshot(&[(3, 5.)]));
+    }
+
+    #[test]
+    fn test_parts() {
+        let mut sender = OutSnapshots::default();
+        let mut receiver = PlayerSnapshots::default();
+
+        let entities: Vec<(u32, f32)> = (0..20).map(|i| (i, i as f32)).collect();
+        let parts = sender.push(Duration::ZERO, snapshot(&entities)).unwrap();
+        assert!(parts.len() > 2);
+
+        // Parts might be received in any order and each is applied on its
+        // own.
+        for part in parts.iter().skip(1) {
+            let received = receiver.receive(part).unwrap();
+            assert!(received.completed.is_none());
+            assert_eq!(received.entities.len(), part.changed().len());
+        }
+        assert!(receiver.latest().is_none());
+        assert_eq!(receiver.receive(&parts[0]).unwrap().completed, Some(1));
+        assert_eq!(receiver.latest().unwrap().1, &snapshot(&entities));
+        // Duplicate part.
+        assert!(receiver.receive(&parts[0]).is_none());
+
+        // A snapshot with a lost part is still applied, but it is neither
+        // completed nor used as a baseline.
+        sender.ack(Player::Player2, 1);
+        let moved: Vec<(u32, f32)> = entities.iter().map(|&(i, x)| (i, x + 100.)).collect();
+        let lossy = sender.push(Duration::ZERO, snapshot(&moved)).unwrap();
+        assert_eq!(lossy[0].baseline(), Some(1));
+        for part in &lossy[..lossy.len() - 1] {
+            let received = receiver.receive(part).unwrap();
+            assert!(received.completed.is_none());
+            for (net_entity, current, previous) in received.entities {
+                let x = net_entity.index().entity_index() as f32;
+                assert_eq!(current, state(x + 100.));
+                assert_eq!(previous, Some(state(x)));
+            }
+        }
+        assert_eq!(receiver.latest().unwrap().0, 1);
+
+        // An incomplete snapshot is superseded by a newer one.
+        let parts = sender
+            .push(Duration::ZERO, snapshot(&entities[2..]))
+            .unwrap();
+        assert_eq!(parts[0].baseline(), Some(1));
+        assert!(receive(&mut receiver, &parts));
+        assert_eq!(receiver.latest().unwrap().0, 3);
+        assert_eq!(receiver.latest().unwrap().1, &snapshot(&entities[2..]));
+        assert!(!receive(&mut receiver, &lossy));
+    }
+
+    #[test]
+    fn test_keyframe() {
+        let mut sender = OutSnapshots::default();
+        sender.ack(Player::Player2, 1);
+        for sequence in 1..=(2 * KEYFRAME_INTERVAL) {
+            let parts = sender.push(Duration::ZERO, snapshot(&[(1, 1.)])).unwrap();
+            sender.ack(Player::Player2, sequence);
+
+            if sequence.is_multiple_of(KEYFRAME_INTERVAL) {
+                assert_eq!(parts[0].baseline(), None);
+            } else if sequence > 1 {
+                assert_eq!(parts[0].baseline(), Some(sequence - 1));
+            }
+        }
+    }
+}
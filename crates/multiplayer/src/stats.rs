@@ -1,5 +1,6 @@
 use std::{
     collections::VecDeque,
+    net::SocketAddr,
     time::{Duration, Instant},
 };
 
@@ -10,8 +11,10 @@ use de_net::Reliability;
 use tracing::{debug, info, trace};
 
 use crate::{
-    messages::{FromGameServerEvent, MessagesSet, ToGameServerEvent},
+    lifecycle::NetGameConfRes,
+    messages::{FromGameServerEvent, MessagesSet, Ports, ToGameServerEvent},
     netstate::NetState,
+    network::Sender,
 };
 
 const RELIABLE_PING_INTERVAL: Duration = Duration::from_secs(10);
@@ -63,6 +66,7 @@ impl Plugin for StatsPlugin {
                     delivery_rate
                         .after(StatsSet::StatsTick)
                         .after(StatsSet::Unresolved),
+                    connection_stats.after(StatsSet::StatsTick),
                 )
                     .run_if(in_state(NetState::Joined)),
             );
@@ -215,7 +219,7 @@ fn cleanup_spec<const R: bool>(mut commands: Commands) {
 }
 
 fn ping<const R: bool>(
-    time: Res<Time>,
+    time: Res<Time<Real>>,
     mut timer: ResMut<PingTimer<R>>,
     mut counter: ResMut<Counter>,
     mut tracker: ResMut<PingTracker<R>>,
@@ -287,7 +291,7 @@ fn unresolved<const R: bool>(mut buffer: Local<Vec<u32>>, mut tracker: ResMut<Pi
     }
 }
 
-fn stats_tick(time: Res<Time>, mut timer: ResMut<StatsTimer>) {
+fn stats_tick(time: Res<Time<Real>>, mut timer: ResMut<StatsTimer>) {
     timer.0.tick(time.delta());
 }
 
@@ -310,6 +314,42 @@ fn delivery_rate(timer: ResMut<StatsTimer>, tracker: Res<PingTracker<false>>) {
     }
 }
 
+fn connection_stats(
+    timer: Res<StatsTimer>,
+    conf: Res<NetGameConfRes>,
+    ports: Res<Ports>,
+    sender: Res<Sender>,
+) {
+    if !timer.0.just_finished() {
+        return;
+    }
+    let Some(port) = ports.game() else {
+        return;
+    };
+    let Some(stats) = sender.stats(SocketAddr::new(conf.server_host(), port)) else {
+        return;
+    };
+
+    info!(
+        "Game server connection estimates: {{ RTT: {}, loss: {:.1}%, send rate: {:.1}kB/s }}",
+        stats.rtt().map_or_else(
+            || "unknown".to_owned(),
+            |rtt| format!("{}ms", rtt.as_millis())
+        ),
+        stats.loss() * 100.,
+        stats.send_rate() / 1000.,
+    );
+
+    let compression = sender.compression_stats();
+    info!(
+        "Network payload compression: {{ sent: {:.1}kB saved {:.1}%, received: {:.1}kB saved {:.1}% }}",
+        compression.sent() as f64 / 1000.,
+        compression.sent_saving() * 100.,
+        compression.received() as f64 / 1000.,
+        compression.received_saving() * 100.,
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
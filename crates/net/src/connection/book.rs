@@ -66,6 +66,11 @@ impl<T: Connection> ConnectionBook<T> {
         }
     }
 
+    /// Returns true if a record of the connection is stored.
+    pub(super) fn contains(&self, addr: SocketAddr) -> bool {
+        self.records.contains_key(&addr)
+    }
+
     /// Yields an element (one by one) from the book. Once all elements are
     /// yielded, None is returned and the "iterator" is restarted.
     pub(super) fn next(&mut self) -> Option<(SocketAddr, &mut T)> {
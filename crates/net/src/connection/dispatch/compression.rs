@@ -0,0 +1,194 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use ahash::AHashSet;
+
+/// Per connection negotiation of payload compression and compression
+/// statistics of all connections. It is shared by the sending and receiving
+/// parts of the networking stack and with the user of the networking stack.
+///
+/// Payloads are compressed only if sent to a peer which advertises support of
+/// compressed payloads in the header of its datagrams.
+#[derive(Clone)]
+pub(crate) struct Compression(Arc<Inner>);
+
+struct Inner {
+    /// Peers which are able to receive compressed payloads.
+    peers: RwLock<AHashSet<SocketAddr>>,
+    sent_raw: AtomicU64,
+    sent: AtomicU64,
+    received_raw: AtomicU64,
+    received: AtomicU64,
+}
+
+impl Compression {
+    pub(super) fn new() -> Self {
+        Self(Arc::new(Inner {
+            peers: RwLock::new(AHashSet::new()),
+            sent_raw: AtomicU64::new(0),
+            sent: AtomicU64::new(0),
+            received_raw: AtomicU64::new(0),
+            received: AtomicU64::new(0),
+        }))
+    }
+
+    /// Returns true if payloads sent to the peer might be compressed.
+    pub(crate) fn supported(&self, addr: SocketAddr) -> bool {
+        self.0.peers.read().unwrap().contains(&addr)
+    }
+
+    /// Updates support of compressed payloads by a peer. This should be
+    /// called on each received package datagram.
+    pub(crate) fn negotiate(&self, addr: SocketAddr, supported: bool) {
+        if self.supported(addr) == supported {
+            return;
+        }
+
+        let mut peers = self.0.peers.write().unwrap();
+        if supported {
+            peers.insert(addr);
+        } else {
+            peers.remove(&addr);
+        }
+    }
+
+    pub(super) fn retain<F>(&self, f: F)
+    where
+        F: Fn(SocketAddr) -> bool,
+    {
+        self.0.peers.write().unwrap().retain(|&addr| f(addr));
+    }
+
+    /// Records a sent package.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - size of the package payload before compression.
+    ///
+    /// * `sent` - size of the package payload as sent.
+    pub(crate) fn sent(&self, raw: usize, sent: usize) {
+        self.0.sent_raw.fetch_add(raw as u64, Ordering::Relaxed);
+        self.0.sent.fetch_add(sent as u64, Ordering::Relaxed);
+    }
+
+    /// Records a received package.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - size of the package payload after decompression.
+    ///
+    /// * `received` - size of the package payload as received.
+    pub(crate) fn received(&self, raw: usize, received: usize) {
+        self.0.received_raw.fetch_add(raw as u64, Ordering::Relaxed);
+        self.0
+            .received
+            .fetch_add(received as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(&self) -> CompressionStats {
+        CompressionStats {
+            sent_raw: self.0.sent_raw.load(Ordering::Relaxed),
+            sent: self.0.sent.load(Ordering::Relaxed),
+            received_raw: self.0.received_raw.load(Ordering::Relaxed),
+            received: self.0.received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Total payload sizes of all packages sent and received by the networking
+/// stack, before and after compression.
+///
+/// Re-sent datagrams and datagram headers are not included.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompressionStats {
+    sent_raw: u64,
+    sent: u64,
+    received_raw: u64,
+    received: u64,
+}
+
+impl CompressionStats {
+    /// Number of payload bytes of sent packages before compression.
+    pub fn sent_raw(&self) -> u64 {
+        self.sent_raw
+    }
+
+    /// Number of payload bytes of sent packages as they were sent.
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// Number of payload bytes of received packages after decompression.
+    pub fn received_raw(&self) -> u64 {
+        self.received_raw
+    }
+
+    /// Number of payload bytes of received packages as they were received.
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Fraction of sent payload bytes saved by compression.
+    pub fn sent_saving(&self) -> f64 {
+        saving(self.sent_raw, self.sent)
+    }
+
+    /// Fraction of received payload bytes saved by compression.
+    pub fn received_saving(&self) -> f64 {
+        saving(self.received_raw, self.received)
+    }
+}
+
+fn saving(raw: u64, transferred: u64) -> f64 {
+    if raw == 0 {
+        0.
+    } else {
+        1. - (transferred as f64 / raw as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let addr_a = "127.0.0.1:1111".parse::<SocketAddr>().unwrap();
+        let addr_b = "127.0.0.1:1112".parse::<SocketAddr>().unwrap();
+
+        let compression = Compression::new();
+        assert!(!compression.supported(addr_a));
+
+        compression.negotiate(addr_a, true);
+        compression.negotiate(addr_b, true);
+        assert!(compression.supported(addr_a));
+        assert!(compression.supported(addr_b));
+
+        compression.negotiate(addr_a, false);
+        assert!(!compression.supported(addr_a));
+
+        compression.retain(|addr| addr != addr_b);
+        assert!(!compression.supported(addr_b));
+    }
+
+    #[test]
+    fn test_stats() {
+        let compression = Compression::new();
+        assert_eq!(compression.stats().sent_saving(), 0.);
+
+        compression.sent(100, 40);
+        compression.sent(100, 60);
+        compression.received(10, 10);
+
+        let stats = compression.stats();
+        assert_eq!(stats.sent_raw(), 200);
+        assert_eq!(stats.sent(), 100);
+        assert_eq!(stats.sent_saving(), 0.5);
+        assert_eq!(stats.received_saving(), 0.);
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+
+use crate::{header::HEADER_SIZE, MAX_DATAGRAM_SIZE};
+
+/// Initial retransmission timeout, used until the first round-trip time sample
+/// is available.
+const INITIAL_RTO: Duration = Duration::from_millis(220);
+// Confirmations are buffered for up to 100ms by the receiving side, therefore
+// the timeout must not drop much below this value.
+pub(super) const MIN_RTO: Duration = Duration::from_millis(150);
+const MAX_RTO: Duration = Duration::from_secs(5);
+
+/// Send rate (bytes per second) used until the rate is adjusted by the
+/// congestion control.
+const INITIAL_RATE: f64 = 128. * 1024.;
+const MIN_RATE: f64 = 16. * 1024.;
+const MAX_RATE: f64 = 4. * 1024. * 1024.;
+/// Send rate increase (bytes per second) per round trip without any loss.
+const RATE_INCREASE: f64 = 16. * 1024.;
+/// Send rate is multiplied by this factor on a loss.
+const RATE_DECREASE: f64 = 0.7;
+
+/// Maximum time worth of send rate which can be accumulated in the budget.
+const BURST: Duration = Duration::from_millis(100);
+/// Minimum budget capacity in bytes.
+const MIN_BURST: f64 = (4 * MAX_DATAGRAM_SIZE) as f64;
+
+/// Weight of a new sample in the loss estimate.
+const LOSS_WEIGHT: f32 = 1. / 8.;
+
+/// Estimation of network conditions and AIMD (additive increase,
+/// multiplicative decrease) based send rate control of a single connection.
+pub(super) struct Congestion {
+    /// Smoothed round-trip time and its variance, None until the first sample.
+    rtt: Option<(Duration, Duration)>,
+    loss: f32,
+    /// Send rate in bytes per second.
+    rate: f64,
+    /// Last time the send rate was adjusted.
+    adjusted: Instant,
+    /// Number of bytes which might be sent as of `refilled` time. This is
+    /// negative when the budget has been overdrawn.
+    budget: f64,
+    refilled: Instant,
+    resends: u64,
+}
+
+impl Congestion {
+    pub(super) fn new(now: Instant) -> Self {
+        Self {
+            rtt: None,
+            loss: 0.,
+            rate: INITIAL_RATE,
+            adjusted: now,
+            budget: Self::capacity(INITIAL_RATE),
+            refilled: now,
+            resends: 0,
+        }
+    }
+
+    /// Retransmission timeout computed from the round-trip time estimate as
+    /// described in RFC 6298.
+    pub(super) fn rto(&self) -> Duration {
+        match self.rtt {
+            Some((srtt, rttvar)) => (srtt + 4 * rttvar).clamp(MIN_RTO, MAX_RTO),
+            None => INITIAL_RTO,
+        }
+    }
+
+    /// Processes a round-trip time sample, id est a time between sending of a
+    /// reliable package and reception of its confirmation.
+    ///
+    /// Only packages sent exactly once may be sampled, otherwise it is not
+    /// known which of the sent datagrams is being confirmed.
+    pub(super) fn delivered(&mut self, rtt: Duration, now: Instant) {
+        self.rtt = Some(match self.rtt {
+            Some((srtt, rttvar)) => ((7 * srtt + rtt) / 8, (3 * rttvar + srtt.abs_diff(rtt)) / 4),
+            None => (rtt, rtt / 2),
+        });
+        self.loss -= LOSS_WEIGHT * self.loss;
+
+        if now.saturating_duration_since(self.adjusted) >= self.srtt() {
+            self.rate = MAX_RATE.min(self.rate + RATE_INCREASE);
+            self.adjusted = now;
+        }
+    }
+
+    /// Processes a (likely) loss of a reliable package, id est expiration of
+    /// its retransmission timeout.
+    pub(super) fn lost(&mut self, now: Instant) {
+        self.loss += LOSS_WEIGHT * (1. - self.loss);
+        self.resends += 1;
+
+        // All packages lost within a single round trip are considered to be a
+        // single congestion event.
+        if now.saturating_duration_since(self.adjusted) >= self.srtt() {
+            self.rate = MIN_RATE.max(self.rate * RATE_DECREASE);
+            self.budget = self.budget.min(Self::capacity(self.rate));
+            self.adjusted = now;
+        }
+    }
+
+    /// Draws a sent datagram from the send budget.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - length of the datagram payload in bytes.
+    ///
+    /// * `now` - send time.
+    pub(super) fn spend(&mut self, len: usize, now: Instant) {
+        self.refill(now);
+        self.budget -= (HEADER_SIZE + len) as f64;
+    }
+
+    pub(super) fn stats(&self) -> PeerStats {
+        PeerStats {
+            rtt: self.rtt.map(|(srtt, _)| srtt),
+            loss: self.loss,
+            rate: self.rate,
+            budget: self.budget,
+            refilled: self.refilled,
+            resends: self.resends,
+        }
+    }
+
+    fn srtt(&self) -> Duration {
+        self.rtt.map_or(INITIAL_RTO, |(srtt, _)| srtt)
+    }
+
+    fn refill(&mut self, now: Instant) {
+        self.budget = refilled(
+            self.budget,
+            self.rate,
+            now.saturating_duration_since(self.refilled),
+        );
+        self.refilled = now;
+    }
+
+    fn capacity(rate: f64) -> f64 {
+        MIN_BURST.max(rate * BURST.as_secs_f64())
+    }
+}
+
+/// Network conditions of a connection to a peer as estimated by the
+/// networking stack.
+#[derive(Clone, Copy, Debug)]
+pub struct PeerStats {
+    rtt: Option<Duration>,
+    loss: f32,
+    rate: f64,
+    budget: f64,
+    refilled: Instant,
+    resends: u64,
+}
+
+impl PeerStats {
+    /// Smoothed round-trip time or None if it has not yet been measured.
+    ///
+    /// The time includes buffering of package confirmations on the side of
+    /// the peer.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Estimated fraction of lost reliably sent packages.
+    pub fn loss(&self) -> f32 {
+        self.loss
+    }
+
+    /// Total number of re-sent reliable packages.
+    pub fn resends(&self) -> u64 {
+        self.resends
+    }
+
+    /// Send rate (in bytes per second) which the connection is estimated to
+    /// sustain.
+    pub fn send_rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Number of bytes which might be sent to the peer at `now` without
+    /// exceeding the send rate. Reliably sent and re-sent packages are
+    /// included, therefore this is zero while the connection is congested.
+    pub fn budget(&self, now: Instant) -> usize {
+        let budget = refilled(
+            self.budget,
+            self.rate,
+            now.saturating_duration_since(self.refilled),
+        );
+        budget.max(0.) as usize
+    }
+}
+
+/// Latest network condition estimates of all connections. It is shared with
+/// the user of the networking stack.
+#[derive(Clone)]
+pub(crate) struct SharedStats(Arc<RwLock<AHashMap<SocketAddr, PeerStats>>>);
+
+impl SharedStats {
+    pub(super) fn new() -> Self {
+        Self(Arc::new(RwLock::new(AHashMap::new())))
+    }
+
+    /// Returns the estimates of a connection or None if nothing has been sent
+    /// to the peer recently.
+    pub(crate) fn get(&self, addr: SocketAddr) -> Option<PeerStats> {
+        self.0.read().unwrap().get(&addr).copied()
+    }
+
+    pub(super) fn update(&self, addr: SocketAddr, stats: PeerStats) {
+        self.0.write().unwrap().insert(addr, stats);
+    }
+
+    pub(super) fn remove(&self, addr: SocketAddr) {
+        self.0.write().unwrap().remove(&addr);
+    }
+
+    pub(super) fn retain<F>(&self, f: F)
+    where
+        F: Fn(SocketAddr) -> bool,
+    {
+        self.0.write().unwrap().retain(|&addr, _| f(addr));
+    }
+}
+
+fn refilled(budget: f64, rate: f64, elapsed: Duration) -> f64 {
+    Congestion::capacity(rate).min(budget + rate * elapsed.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rtt() {
+        let mut time = Instant::now();
+        let mut congestion = Congestion::new(time);
+        assert_eq!(congestion.rto(), INITIAL_RTO);
+        assert!(congestion.stats().rtt().is_none());
+
+        for _ in 0..50 {
+            time += Duration::from_millis(10);
+            congestion.delivered(Duration::from_millis(300), time);
+        }
+        let rtt = congestion.stats().rtt().unwrap();
+        assert_eq!(rtt, Duration::from_millis(300));
+        assert!(congestion.rto() > rtt);
+        assert!(congestion.rto() < Duration::from_millis(320));
+
+        congestion.delivered(Duration::from_millis(20), time);
+        assert!(congestion.stats().rtt().unwrap() < rtt);
+        assert!(congestion.rto() > Duration::from_millis(320));
+    }
+
+    #[test]
+    fn test_rate() {
+        let mut time = Instant::now();
+        let mut congestion = Congestion::new(time);
+
+        time += Duration::from_secs(1);
+        congestion.delivered(Duration::from_millis(100), time);
+        let rate = congestion.stats().send_rate();
+        assert!(rate > INITIAL_RATE);
+        assert_eq!(congestion.stats().loss(), 0.);
+
+        // Multiple deliveries within a round trip increase the rate only once.
+        time += Duration::from_millis(10);
+        congestion.delivered(Duration::from_millis(100), time);
+        assert_eq!(congestion.stats().send_rate(), rate);
+
+        time += Duration::from_millis(200);
+        congestion.lost(time);
+        let decreased = congestion.stats().send_rate();
+        assert!(decreased < rate);
+        assert!(congestion.stats().loss() > 0.);
+
+        // Multiple losses within a round trip decrease the rate only once.
+        congestion.lost(time + Duration::from_millis(10));
+        assert_eq!(congestion.stats().send_rate(), decreased);
+
+        for _ in 0..100 {
+            time += Duration::from_secs(1);
+            congestion.lost(time);
+        }
+        assert_eq!(congestion.stats().send_rate(), MIN_RATE);
+        assert_eq!(congestion.stats().resends(), 102);
+    }
+
+    #[test]
+    fn test_budget() {
+        let time = Instant::now();
+        let mut congestion = Congestion::new(time);
+
+        let capacity = congestion.stats().budget(time);
+        assert_eq!(capacity, (INITIAL_RATE * BURST.as_secs_f64()) as usize);
+        // The budget does not accumulate beyond its capacity.
+        assert_eq!(
+            congestion.stats().budget(time + Duration::from_secs(10)),
+            capacity
+        );
+
+        for _ in 0..100 {
+            congestion.spend(500, time);
+        }
+        let stats = congestion.stats();
+        assert_eq!(stats.budget(time), 0);
+        assert_eq!(stats.budget(time + Duration::from_millis(10)), 0);
+        assert!(stats.budget(time + Duration::from_millis(500)) > 0);
+        assert_eq!(stats.budget(time + Duration::from_secs(10)), capacity);
+    }
+}
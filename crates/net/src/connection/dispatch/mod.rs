@@ -1,14 +1,16 @@
-use std::{
-    net::SocketAddr,
-    time::{Duration, Instant},
-};
+use std::{net::SocketAddr, time::Instant};
 
 use async_std::{
     channel::{SendError, Sender},
     sync::{Arc, Mutex},
 };
 
-use self::resends::{RescheduleResult, Resends, START_BACKOFF_MS};
+pub(crate) use self::{compression::Compression, congestion::SharedStats};
+pub use self::{compression::CompressionStats, congestion::PeerStats};
+use self::{
+    congestion::{Congestion, MIN_RTO},
+    resends::{RescheduleResult, Resends},
+};
 use super::book::{Connection, ConnectionBook};
 use crate::{
     header::{DatagramHeader, PackageHeader, PackageId, PackageIdRange},
@@ -16,27 +18,44 @@ use crate::{
     MAX_PACKAGE_SIZE,
 };
 
+mod compression;
+mod congestion;
 mod resends;
 
 #[derive(Clone)]
 pub(crate) struct DispatchHandler {
     book: Arc<Mutex<ConnectionBook<ConnDispatchHandler>>>,
+    stats: SharedStats,
+    compression: Compression,
 }
 
 impl DispatchHandler {
     pub(crate) fn new() -> Self {
         Self {
             book: Arc::new(Mutex::new(ConnectionBook::new())),
+            stats: SharedStats::new(),
+            compression: Compression::new(),
         }
     }
 
+    /// Returns network condition estimates of all connections. These are
+    /// updated as packages are sent and confirmed.
+    pub(crate) fn stats(&self) -> SharedStats {
+        self.stats.clone()
+    }
+
+    /// Returns compression negotiation state of all connections.
+    pub(crate) fn compression(&self) -> Compression {
+        self.compression.clone()
+    }
+
     /// Returns ID to be given to a to-be-send package.
     ///
     /// It is assumed that this is called exactly once before each reliably
     /// send package and that all generated IDs are used.
     pub(crate) async fn next_package_id(&mut self, time: Instant, addr: SocketAddr) -> PackageId {
         let mut book = self.book.lock().await;
-        let handler = book.update(time, addr, ConnDispatchHandler::new);
+        let handler = book.update(time, addr, || ConnDispatchHandler::new(time));
         handler.next_package_id()
     }
 
@@ -54,8 +73,25 @@ impl DispatchHandler {
     ) {
         assert!(data.len() <= MAX_PACKAGE_SIZE);
         let mut book = self.book.lock().await;
-        let handler = book.update(time, addr, ConnDispatchHandler::new);
-        handler.resends.push(header, data, time);
+        let handler = book.update(time, addr, || ConnDispatchHandler::new(time));
+        handler
+            .resends
+            .push(header, data, time, handler.congestion.rto());
+        handler.congestion.spend(data.len(), time);
+        self.stats.update(addr, handler.congestion.stats());
+    }
+
+    /// Registers an unreliably sent package so that it is accounted in the
+    /// send budget of the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - length of the package payload in bytes.
+    pub(crate) async fn sent_unreliable(&mut self, time: Instant, addr: SocketAddr, len: usize) {
+        let mut book = self.book.lock().await;
+        let handler = book.update(time, addr, || ConnDispatchHandler::new(time));
+        handler.congestion.spend(len, time);
+        self.stats.update(addr, handler.congestion.stats());
     }
 
     /// Processes data with package confirmations.
@@ -64,13 +100,17 @@ impl DispatchHandler {
     /// can be forgotten.
     pub(crate) async fn confirmed(&mut self, time: Instant, addr: SocketAddr, data: &[u8]) {
         let mut book = self.book.lock().await;
-        let handler = book.update(time, addr, ConnDispatchHandler::new);
+        let handler = book.update(time, addr, || ConnDispatchHandler::new(time));
 
         for i in 0..data.len() / 3 {
             let offset = i * 3;
             let id = PackageId::from_bytes(&data[offset..offset + 3]);
-            handler.resends.resolve(id);
+            if let Some(rtt) = handler.resends.resolve(id, time) {
+                handler.congestion.delivered(rtt, time);
+            }
         }
+
+        self.stats.update(addr, handler.congestion.stats());
     }
 
     /// Re-send all packages already due for re-sending.
@@ -83,15 +123,21 @@ impl DispatchHandler {
         let mut result = ResendResult {
             failures: Vec::new(),
             pending: 0,
-            next: time + Duration::from_millis(START_BACKOFF_MS),
+            next: time + MIN_RTO,
         };
 
         let mut book = self.book.lock().await;
 
         while let Some((addr, handler)) = book.next() {
             let failure = loop {
-                match handler.resends.reschedule(buf, time) {
+                match handler
+                    .resends
+                    .reschedule(buf, time, handler.congestion.rto())
+                {
                     RescheduleResult::Resend { len, header } => {
+                        handler.congestion.lost(time);
+                        handler.congestion.spend(len, time);
+
                         datagrams
                             .send(OutDatagram::from_slice(
                                 DatagramHeader::Package(header),
@@ -116,9 +162,11 @@ impl DispatchHandler {
 
             if failure {
                 book.remove_current();
+                self.stats.remove(addr);
                 result.failures.push(addr);
             } else {
                 result.pending += handler.resends.len();
+                self.stats.update(addr, handler.congestion.stats());
             }
         }
 
@@ -126,7 +174,10 @@ impl DispatchHandler {
     }
 
     pub(crate) async fn clean(&mut self, time: Instant) {
-        self.book.lock().await.clean(time);
+        let mut book = self.book.lock().await;
+        book.clean(time);
+        self.stats.retain(|addr| book.contains(addr));
+        self.compression.retain(|addr| book.contains(addr));
     }
 }
 
@@ -142,13 +193,15 @@ pub(crate) struct ResendResult {
 struct ConnDispatchHandler {
     resends: Resends,
     package_ids: PackageIdRange,
+    congestion: Congestion,
 }
 
 impl ConnDispatchHandler {
-    fn new() -> Self {
+    fn new(time: Instant) -> Self {
         Self {
             resends: Resends::new(),
             package_ids: PackageIdRange::counter(),
+            congestion: Congestion::new(time),
         }
     }
 
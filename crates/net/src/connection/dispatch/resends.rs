@@ -12,9 +12,8 @@ use crate::{
     MAX_PACKAGE_SIZE,
 };
 
-pub(super) const START_BACKOFF_MS: u64 = 220;
 const MAX_TRIES: u8 = 6;
-const MAX_BASE_RESEND_INTERVAL_MS: u64 = (MAX_CONN_AGE.as_millis() / 2) as u64;
+const MAX_BASE_RESEND_INTERVAL: Duration = Duration::from_secs(MAX_CONN_AGE.as_secs() / 2);
 
 /// This struct governs reliable package re-sending (until each package is
 /// confirmed).
@@ -45,14 +44,24 @@ impl Resends {
 
     /// Registers new package for re-sending until it is resolved.
     ///
+    /// # Arguments
+    ///
+    /// * `header` - header of the sent package.
+    ///
+    /// * `data` - package payload.
+    ///
+    /// * `now` - time of the first send of the package.
+    ///
+    /// * `rto` - current retransmission timeout of the connection.
+    ///
     /// # Panics
     ///
     /// * If the package (ID) is already stored.
     ///
     /// * If data is longer than [`MAX_PACKAGE_SIZE`].
-    pub(super) fn push(&mut self, header: PackageHeader, data: &[u8], now: Instant) {
+    pub(super) fn push(&mut self, header: PackageHeader, data: &[u8], now: Instant, rto: Duration) {
         assert!(data.len() <= MAX_PACKAGE_SIZE);
-        let result = self.queue.push(header.id(), Timing::new(now));
+        let result = self.queue.push(header.id(), Timing::new(now, rto));
         assert!(result.is_none());
         self.headers.insert(header.id(), header);
         self.data.push(header.id(), data);
@@ -60,11 +69,18 @@ impl Resends {
 
     /// Marks a package as delivered. No more re-sends will be scheduled and
     /// package data will be dropped.
-    pub(super) fn resolve(&mut self, id: PackageId) {
-        let result = self.queue.remove(&id);
-        if result.is_some() {
-            self.headers.remove(&id);
-            self.data.remove(id);
+    ///
+    /// Round-trip time of the package is returned if it was resolved by this
+    /// call and it has never been re-sent.
+    pub(super) fn resolve(&mut self, id: PackageId, now: Instant) -> Option<Duration> {
+        let (_, timing) = self.queue.remove(&id)?;
+        self.headers.remove(&id);
+        self.data.remove(id);
+
+        if timing.attempt == 0 {
+            Some(now.saturating_duration_since(timing.sent))
+        } else {
+            None
         }
     }
 
@@ -81,15 +97,23 @@ impl Resends {
     ///
     /// * `now` - current time, used for the retry scheduling.
     ///
+    /// * `rto` - current retransmission timeout of the connection. The backoff
+    ///   is based on this value.
+    ///
     /// # Panics
     ///
     /// Panics if `buf` is smaller than the retrieved package payload.
-    pub(super) fn reschedule(&mut self, buf: &mut [u8], now: Instant) -> RescheduleResult {
+    pub(super) fn reschedule(
+        &mut self,
+        buf: &mut [u8],
+        now: Instant,
+        rto: Duration,
+    ) -> RescheduleResult {
         match self.queue.peek() {
             Some((&id, timing)) => {
                 let until = timing.expiration();
                 if until <= now {
-                    match timing.another(now) {
+                    match timing.another(now, rto) {
                         Some(backoff) => {
                             self.queue.change_priority(&id, backoff);
                             let len = self.data.get(id, buf).unwrap();
@@ -133,14 +157,17 @@ pub(crate) enum RescheduleResult {
 #[derive(Eq)]
 struct Timing {
     attempt: u8,
+    /// Time of the first send of the package.
+    sent: Instant,
     expiration: Instant,
 }
 
 impl Timing {
-    fn new(now: Instant) -> Self {
+    fn new(now: Instant, rto: Duration) -> Self {
         Self {
             attempt: 0,
-            expiration: Self::schedule(0, now),
+            sent: now,
+            expiration: Self::schedule(0, now, rto),
         }
     }
 
@@ -148,29 +175,30 @@ impl Timing {
         self.expiration
     }
 
-    fn another(&self, now: Instant) -> Option<Self> {
+    fn another(&self, now: Instant, rto: Duration) -> Option<Self> {
         let attempt = self.attempt + 1;
         if attempt == MAX_TRIES {
             None
         } else {
             Some(Self {
                 attempt,
-                expiration: Self::schedule(attempt, now),
+                sent: self.sent,
+                expiration: Self::schedule(attempt, now, rto),
             })
         }
     }
 
-    fn schedule(attempt: u8, now: Instant) -> Instant {
-        let millis = Self::jitter(Self::backoff(attempt));
-        now + Duration::from_millis(millis)
+    fn schedule(attempt: u8, now: Instant, rto: Duration) -> Instant {
+        now + Self::jitter(Self::backoff(attempt, rto))
     }
 
-    fn backoff(attempt: u8) -> u64 {
-        MAX_BASE_RESEND_INTERVAL_MS.min(START_BACKOFF_MS * 2u64.pow(attempt as u32))
+    fn backoff(attempt: u8, rto: Duration) -> Duration {
+        MAX_BASE_RESEND_INTERVAL.min(rto * 2u32.pow(attempt as u32))
     }
 
-    fn jitter(millis: u64) -> u64 {
-        millis + fastrand::u64(0..millis / 2)
+    fn jitter(backoff: Duration) -> Duration {
+        let millis = backoff.as_millis() as u64;
+        backoff + Duration::from_millis(fastrand::u64(0..millis / 2))
     }
 }
 
@@ -200,6 +228,8 @@ mod tests {
     use super::*;
     use crate::{Peers, Reliability, MAX_PACKAGE_SIZE};
 
+    const RTO: Duration = Duration::from_millis(220);
+
     #[test]
     fn test_resends() {
         let time = Instant::now();
@@ -216,6 +246,7 @@ mod tests {
             ),
             &[4, 5, 8],
             time,
+            RTO,
         );
         resends.push(
             PackageHeader::new(
@@ -225,6 +256,7 @@ mod tests {
             ),
             &[4, 5, 8, 9],
             time + Duration::from_millis(10_010),
+            RTO,
         );
         resends.push(
             PackageHeader::new(
@@ -234,11 +266,12 @@ mod tests {
             ),
             &[4, 5, 8, 9, 10],
             time + Duration::from_millis(50_020),
+            RTO,
         );
         assert_eq!(resends.len(), 3);
 
         assert_eq!(
-            resends.reschedule(&mut buf, time + Duration::from_secs(20)),
+            resends.reschedule(&mut buf, time + Duration::from_secs(20), RTO),
             RescheduleResult::Resend {
                 len: 3,
                 header: PackageHeader::new(
@@ -249,10 +282,16 @@ mod tests {
             }
         );
         assert_eq!(&buf[..3], &[4, 5, 8]);
-        resends.resolve(PackageId::from_bytes(&[0, 0, 0]));
+        assert_eq!(
+            resends.resolve(
+                PackageId::from_bytes(&[0, 0, 0]),
+                time + Duration::from_secs(21)
+            ),
+            None
+        );
 
         assert_eq!(
-            resends.reschedule(&mut buf, time + Duration::from_secs(20)),
+            resends.reschedule(&mut buf, time + Duration::from_secs(20), RTO),
             RescheduleResult::Resend {
                 len: 4,
                 header: PackageHeader::new(
@@ -263,16 +302,16 @@ mod tests {
             }
         );
         assert_eq!(&buf[..4], &[4, 5, 8, 9]);
-        resends.resolve(PackageId::from_bytes(&[0, 0, 1]));
+        resends.resolve(PackageId::from_bytes(&[0, 0, 1]), time);
 
         assert!(matches!(
-            resends.reschedule(&mut buf, time + Duration::from_secs(20)),
+            resends.reschedule(&mut buf, time + Duration::from_secs(20), RTO),
             RescheduleResult::Waiting(_)
         ));
 
         // 1st resend
         assert_eq!(
-            resends.reschedule(&mut buf, time + Duration::from_secs(1000)),
+            resends.reschedule(&mut buf, time + Duration::from_secs(1000), RTO),
             RescheduleResult::Resend {
                 len: 5,
                 header: PackageHeader::new(
@@ -284,7 +323,7 @@ mod tests {
         );
         // 2nd resend
         assert_eq!(
-            resends.reschedule(&mut buf, time + Duration::from_secs(2000)),
+            resends.reschedule(&mut buf, time + Duration::from_secs(2000), RTO),
             RescheduleResult::Resend {
                 len: 5,
                 header: PackageHeader::new(
@@ -296,7 +335,7 @@ mod tests {
         );
         // 3rd resend
         assert_eq!(
-            resends.reschedule(&mut buf, time + Duration::from_secs(3000)),
+            resends.reschedule(&mut buf, time + Duration::from_secs(3000), RTO),
             RescheduleResult::Resend {
                 len: 5,
                 header: PackageHeader::new(
@@ -308,7 +347,7 @@ mod tests {
         );
         // 4th resend
         assert_eq!(
-            resends.reschedule(&mut buf, time + Duration::from_secs(4000)),
+            resends.reschedule(&mut buf, time + Duration::from_secs(4000), RTO),
             RescheduleResult::Resend {
                 len: 5,
                 header: PackageHeader::new(
@@ -320,7 +359,7 @@ mod tests {
         );
         // 5th resend
         assert_eq!(
-            resends.reschedule(&mut buf, time + Duration::from_secs(5000)),
+            resends.reschedule(&mut buf, time + Duration::from_secs(5000), RTO),
             RescheduleResult::Resend {
                 len: 5,
                 header: PackageHeader::new(
@@ -332,21 +371,43 @@ mod tests {
         );
         // 6th resend (7th try) => failure
         assert_eq!(
-            resends.reschedule(&mut buf, time + Duration::from_secs(6000)),
+            resends.reschedule(&mut buf, time + Duration::from_secs(6000), RTO),
             RescheduleResult::Failed
         );
 
         assert_eq!(
-            resends.reschedule(&mut buf, time + Duration::from_secs(7000)),
+            resends.reschedule(&mut buf, time + Duration::from_secs(7000), RTO),
             RescheduleResult::Empty
         );
     }
 
+    #[test]
+    fn test_resolve() {
+        let time = Instant::now();
+        let mut resends = Resends::new();
+        let header = PackageHeader::new(
+            Reliability::Unordered,
+            Peers::Server,
+            PackageId::from_bytes(&[0, 0, 3]),
+        );
+
+        resends.push(header, &[1, 2], time, RTO);
+        assert_eq!(
+            resends.resolve(header.id(), time + Duration::from_millis(80)),
+            Some(Duration::from_millis(80))
+        );
+        // Already resolved.
+        assert_eq!(
+            resends.resolve(header.id(), time + Duration::from_millis(90)),
+            None
+        );
+    }
+
     #[test]
     fn test_timing() {
         let time = Instant::now();
-        let first = Timing::new(time);
-        let second = Timing::new(time + Duration::from_secs(3600));
+        let first = Timing::new(time, RTO);
+        let second = Timing::new(time + Duration::from_secs(3600), RTO);
         assert_eq!(first.cmp(&second), Ordering::Greater);
     }
 }
@@ -1,5 +1,6 @@
 pub(crate) use delivery::{DeliveryHandler, ReceivedIdError};
-pub(crate) use dispatch::DispatchHandler;
+pub(crate) use dispatch::{Compression, DispatchHandler, SharedStats};
+pub use dispatch::{CompressionStats, PeerStats};
 
 mod book;
 mod databuf;
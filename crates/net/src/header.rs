@@ -7,9 +7,22 @@ pub(crate) const HEADER_SIZE: usize = 4;
 
 /// This bit is set in protocol control datagrams.
 const CONTROL_BIT: u8 = 0b1000_0000;
+/// First byte of session handshake `Hello` datagrams, see [`crate::session`].
+pub(crate) const HELLO_MASK: u8 = CONTROL_BIT | 0b0000_0001;
+/// First byte of session handshake `Welcome` datagrams, see
+/// [`crate::session`].
+pub(crate) const WELCOME_MASK: u8 = CONTROL_BIT | 0b0000_0010;
 /// This bit is set on datagrams which are sent to the server instead of other
 /// players.
 const SERVER_PEER_BIT: u8 = 0b0001_0000;
+/// This bit is set on reliable datagrams which carry a single fragment of a
+/// package too large to fit into a datagram.
+const FRAGMENT_BIT: u8 = 0b0000_1000;
+/// This bit is set on datagrams whose payload is compressed.
+const COMPRESSED_BIT: u8 = 0b0000_0100;
+/// This bit is set on all package datagrams sent by peers able to receive
+/// compressed payloads.
+const COMPRESSION_SUPPORT_BIT: u8 = 0b0000_0010;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) enum DatagramHeader {
@@ -32,6 +45,15 @@ impl DatagramHeader {
                 if matches!(package_header.peers, Peers::Server) {
                     mask |= SERVER_PEER_BIT;
                 }
+                if package_header.fragment {
+                    mask |= FRAGMENT_BIT;
+                }
+                if package_header.compressed {
+                    mask |= COMPRESSED_BIT;
+                }
+                if package_header.compression_support {
+                    mask |= COMPRESSION_SUPPORT_BIT;
+                }
                 (mask, package_header.id.to_bytes())
             }
         };
@@ -63,9 +85,16 @@ impl DatagramHeader {
             } else {
                 Peers::Players
             };
+            let fragment = mask & FRAGMENT_BIT > 0;
+            if fragment && !reliability.is_reliable() {
+                return Err(HeaderError::Invalid);
+            }
             Ok(Self::Package(PackageHeader {
                 reliability,
                 peers,
+                fragment,
+                compressed: mask & COMPRESSED_BIT > 0,
+                compression_support: mask & COMPRESSION_SUPPORT_BIT > 0,
                 id: PackageId::from_bytes(&data[1..HEADER_SIZE]),
             }))
         }
@@ -79,8 +108,8 @@ impl fmt::Display for DatagramHeader {
             Self::Package(header) => {
                 write!(
                     f,
-                    "Package {{ reliability: {}, peers: {}, id: {} }}",
-                    header.reliability, header.peers, header.id
+                    "Package {{ reliability: {}, peers: {}, fragment: {}, compressed: {}, id: {} }}",
+                    header.reliability, header.peers, header.fragment, header.compressed, header.id
                 )
             }
         }
@@ -92,6 +121,12 @@ pub(crate) struct PackageHeader {
     /// True if the package is delivered reliably.
     reliability: Reliability,
     peers: Peers,
+    /// True if the datagram carries a fragment of a larger package.
+    fragment: bool,
+    /// True if the package payload is compressed.
+    compressed: bool,
+    /// True if the sender is able to receive compressed payloads.
+    compression_support: bool,
     id: PackageId,
 }
 
@@ -100,10 +135,26 @@ impl PackageHeader {
         Self {
             reliability,
             peers,
+            fragment: false,
+            compressed: false,
+            compression_support: false,
             id,
         }
     }
 
+    /// Returns the header with the fragment flag set.
+    ///
+    /// # Panics
+    ///
+    /// If the package is not delivered reliably.
+    pub(crate) fn fragmented(self) -> Self {
+        assert!(self.reliability.is_reliable());
+        Self {
+            fragment: true,
+            ..self
+        }
+    }
+
     pub(crate) fn reliability(&self) -> Reliability {
         self.reliability
     }
@@ -112,6 +163,35 @@ impl PackageHeader {
         self.peers
     }
 
+    /// Returns the header with the compressed payload flag set.
+    pub(crate) fn compressed(self) -> Self {
+        Self {
+            compressed: true,
+            ..self
+        }
+    }
+
+    /// Returns the header with the flag advertising support of compressed
+    /// payloads set.
+    pub(crate) fn supporting_compression(self) -> Self {
+        Self {
+            compression_support: true,
+            ..self
+        }
+    }
+
+    pub(crate) fn is_fragment(&self) -> bool {
+        self.fragment
+    }
+
+    pub(crate) fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    pub(crate) fn supports_compression(&self) -> bool {
+        self.compression_support
+    }
+
     pub(crate) fn id(&self) -> PackageId {
         self.id
     }
@@ -225,6 +305,13 @@ impl PackageId {
         }
     }
 
+    /// Returns an ID which precedes this one by `distance`. It wraps around
+    /// zero back to the maximum value.
+    pub(crate) fn preceding(self, distance: u32) -> Self {
+        let modulus = Self::MAX + 1;
+        Self((self.0 + modulus - distance % modulus) % modulus)
+    }
+
     /// # Panics
     ///
     /// If not exactly 3 bytes are passed.
@@ -383,6 +470,29 @@ mod tests {
         .write(&mut buf);
         assert_eq![&buf[0..4], &[0b0000_0000, 0, 4, 9]];
         assert_eq![&buf[4..], &[0; 252]];
+
+        DatagramHeader::Package(
+            PackageHeader::new(
+                Reliability::Unordered,
+                Peers::Players,
+                2.try_into().unwrap(),
+            )
+            .fragmented(),
+        )
+        .write(&mut buf);
+        assert_eq![&buf[0..4], &[0b0010_1000, 0, 0, 2]];
+
+        DatagramHeader::Package(
+            PackageHeader::new(
+                Reliability::Unreliable,
+                Peers::Server,
+                3.try_into().unwrap(),
+            )
+            .compressed()
+            .supporting_compression(),
+        )
+        .write(&mut buf);
+        assert_eq![&buf[0..4], &[0b0001_0110, 0, 0, 3]];
     }
 
     #[test]
@@ -418,6 +528,25 @@ mod tests {
                 2.try_into().unwrap()
             ))
         );
+
+        buf[0..4].copy_from_slice(&[72, 0, 0, 7]);
+        let DatagramHeader::Package(header) = DatagramHeader::read(&buf).unwrap() else {
+            panic!("Package header expected.");
+        };
+        assert!(header.is_fragment());
+        assert_eq!(header.reliability(), Reliability::SemiOrdered);
+
+        buf[0..4].copy_from_slice(&[6, 0, 0, 7]);
+        let DatagramHeader::Package(header) = DatagramHeader::read(&buf).unwrap() else {
+            panic!("Package header expected.");
+        };
+        assert!(header.is_compressed());
+        assert!(header.supports_compression());
+        assert!(!header.is_fragment());
+
+        // Only reliable packages might be fragmented.
+        buf[0..4].copy_from_slice(&[8, 0, 0, 7]);
+        assert!(DatagramHeader::read(&buf).is_err());
     }
 
     #[test]
@@ -429,6 +558,17 @@ mod tests {
         assert_eq!(id.incremented(), 0.try_into().unwrap());
     }
 
+    #[test]
+    fn test_preceding() {
+        let id = PackageId::from_bytes(&[0, 1, 3]);
+        assert_eq!(id.preceding(0), id);
+        assert_eq!(id.preceding(3).to_bytes(), [0, 1, 0]);
+        assert_eq!(id.preceding(4).to_bytes(), [0, 0, 255]);
+
+        let id = PackageId::from_bytes(&[0, 0, 1]);
+        assert_eq!(id.preceding(2), 0xffffff.try_into().unwrap());
+    }
+
     #[test]
     fn test_ordering() {
         assert_eq!(
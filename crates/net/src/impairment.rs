@@ -0,0 +1,409 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_std::{
+    channel::{unbounded, Receiver, Sender},
+    future::timeout,
+    net::UdpSocket,
+    task,
+};
+use fastrand::Rng;
+use thiserror::Error;
+use tracing::warn;
+
+/// Configuration of simulated network conditions applied to datagrams sent
+/// via a [`crate::Socket`].
+///
+/// All random decisions are drawn from a generator seeded with a configured
+/// seed, thus a sequence of sent datagrams is always impaired in the same way.
+///
+/// The configuration can be parsed from a comma separated list of `key=value`
+/// pairs, for example `seed=42,loss=0.05,latency=100ms,jitter=20ms`. The keys
+/// are:
+///
+/// * `seed` – seed of the random number generator, defaults to 0,
+/// * `loss` – probability of a datagram being dropped,
+/// * `latency` – delay of each datagram,
+/// * `jitter` – maximum random deviation of the delay from `latency`,
+/// * `duplication` – probability of a datagram being sent twice,
+/// * `reordering` – probability of a datagram being held back by
+///   `reorder_delay`, so that it is overtaken by subsequently sent datagrams,
+/// * `reorder_delay` – defaults to 50ms.
+///
+/// Durations are given in milliseconds (`ms` suffix) or seconds (`s` suffix).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Impairment {
+    seed: u64,
+    loss: f32,
+    latency: Duration,
+    jitter: Duration,
+    duplication: f32,
+    reordering: f32,
+    reorder_delay: Duration,
+}
+
+impl Impairment {
+    /// Creates a new configuration which does not impair the traffic at all.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            loss: 0.,
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            duplication: 0.,
+            reordering: 0.,
+            reorder_delay: Duration::from_millis(50),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `probability` is not between 0 and 1.
+    pub fn with_loss(mut self, probability: f32) -> Self {
+        assert_probability(probability);
+        self.loss = probability;
+        self
+    }
+
+    pub fn with_latency(mut self, latency: Duration, jitter: Duration) -> Self {
+        self.latency = latency;
+        self.jitter = jitter;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `probability` is not between 0 and 1.
+    pub fn with_duplication(mut self, probability: f32) -> Self {
+        assert_probability(probability);
+        self.duplication = probability;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `probability` is not between 0 and 1.
+    pub fn with_reordering(mut self, probability: f32, delay: Duration) -> Self {
+        assert_probability(probability);
+        self.reordering = probability;
+        self.reorder_delay = delay;
+        self
+    }
+}
+
+impl FromStr for Impairment {
+    type Err = ImpairmentParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut impairment = Self::new(0);
+
+        for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let Some((key, value)) = item.split_once('=') else {
+                return Err(ImpairmentParseError::Syntax(item.to_owned()));
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            match key {
+                "seed" => {
+                    impairment.seed = value
+                        .parse()
+                        .map_err(|_| ImpairmentParseError::value(key, value))?
+                }
+                "loss" => impairment.loss = parse_probability(key, value)?,
+                "latency" => impairment.latency = parse_duration(key, value)?,
+                "jitter" => impairment.jitter = parse_duration(key, value)?,
+                "duplication" => impairment.duplication = parse_probability(key, value)?,
+                "reordering" => impairment.reordering = parse_probability(key, value)?,
+                "reorder_delay" => impairment.reorder_delay = parse_duration(key, value)?,
+                _ => return Err(ImpairmentParseError::UnknownKey(key.to_owned())),
+            }
+        }
+
+        Ok(impairment)
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ImpairmentParseError {
+    #[error("expected `key=value`, got `{0}`")]
+    Syntax(String),
+    #[error("unknown key `{0}`")]
+    UnknownKey(String),
+    #[error("invalid value `{value}` of `{key}`")]
+    InvalidValue { key: String, value: String },
+}
+
+impl ImpairmentParseError {
+    fn value(key: &str, value: &str) -> Self {
+        Self::InvalidValue {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+}
+
+fn parse_probability(key: &str, value: &str) -> Result<f32, ImpairmentParseError> {
+    match value.parse::<f32>() {
+        Ok(probability) if (0. ..=1.).contains(&probability) => Ok(probability),
+        _ => Err(ImpairmentParseError::value(key, value)),
+    }
+}
+
+fn parse_duration(key: &str, value: &str) -> Result<Duration, ImpairmentParseError> {
+    let result = if let Some(millis) = value.strip_suffix("ms") {
+        millis.trim().parse().map(Duration::from_millis)
+    } else if let Some(secs) = value.strip_suffix('s') {
+        secs.trim().parse().map(Duration::from_secs)
+    } else {
+        value.parse().map(Duration::from_millis)
+    };
+    result.map_err(|_| ImpairmentParseError::value(key, value))
+}
+
+fn assert_probability(probability: f32) {
+    assert!(
+        (0. ..=1.).contains(&probability),
+        "Probability must be between 0 and 1, got {probability}."
+    );
+}
+
+/// Applies [`Impairment`] to datagrams sent via a socket.
+pub(crate) struct Impairer {
+    schedule: Mutex<Schedule>,
+    delayed: Sender<Delayed>,
+}
+
+impl Impairer {
+    /// Creates a new impairer and spawns a task which sends delayed datagrams
+    /// via `socket`. The task finishes once the impairer is dropped and all
+    /// delayed datagrams are sent.
+    pub(crate) fn new(impairment: Impairment, socket: Arc<UdpSocket>) -> Self {
+        let (sender, receiver) = unbounded();
+        task::spawn(deliver(socket, receiver));
+        Self {
+            schedule: Mutex::new(Schedule::new(impairment)),
+            delayed: sender,
+        }
+    }
+
+    /// Returns delays of all copies of a datagram to be sent. An empty vector
+    /// is returned if the datagram is lost.
+    pub(crate) fn delays(&self) -> Vec<Duration> {
+        self.schedule.lock().unwrap().next()
+    }
+
+    /// Sends the datagram to the target after the given delay.
+    pub(crate) fn delay(&self, target: SocketAddr, data: &[u8], delay: Duration) {
+        let mut schedule = self.schedule.lock().unwrap();
+        let delayed = Delayed {
+            due: Instant::now() + delay,
+            seq: schedule.seq(),
+            target,
+            data: data.to_vec(),
+        };
+        // The receiver lives until the sender is dropped.
+        self.delayed.try_send(delayed).unwrap();
+    }
+}
+
+/// Seeded source of impairment decisions.
+struct Schedule {
+    impairment: Impairment,
+    rng: Rng,
+    seq: u64,
+}
+
+impl Schedule {
+    fn new(impairment: Impairment) -> Self {
+        let rng = Rng::with_seed(impairment.seed);
+        Self {
+            impairment,
+            rng,
+            seq: 0,
+        }
+    }
+
+    fn next(&mut self) -> Vec<Duration> {
+        if self.rng.f32() < self.impairment.loss {
+            return Vec::new();
+        }
+
+        let copies = if self.rng.f32() < self.impairment.duplication {
+            2
+        } else {
+            1
+        };
+
+        (0..copies)
+            .map(|_| {
+                let jitter = self.impairment.jitter;
+                let mut delay = (self.impairment.latency + jitter.mul_f32(2. * self.rng.f32()))
+                    .saturating_sub(jitter);
+                if self.rng.f32() < self.impairment.reordering {
+                    delay += self.impairment.reorder_delay;
+                }
+                delay
+            })
+            .collect()
+    }
+
+    fn seq(&mut self) -> u64 {
+        self.seq = self.seq.wrapping_add(1);
+        self.seq
+    }
+}
+
+struct Delayed {
+    due: Instant,
+    seq: u64,
+    target: SocketAddr,
+    data: Vec<u8>,
+}
+
+impl PartialEq for Delayed {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Delayed {}
+
+impl PartialOrd for Delayed {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Delayed {
+    /// Delayed datagrams are ordered from the latest to the earliest (in
+    /// reverse) so that the earliest is at the top of a max-heap.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .due
+            .cmp(&self.due)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+async fn deliver(socket: Arc<UdpSocket>, datagrams: Receiver<Delayed>) {
+    let mut queue: BinaryHeap<Delayed> = BinaryHeap::new();
+    let mut closed = false;
+
+    loop {
+        let wait = match queue.peek() {
+            Some(next) => {
+                let now = Instant::now();
+                if next.due <= now {
+                    let next = queue.pop().unwrap();
+                    if let Err(err) = socket.send_to(&next.data, next.target).await {
+                        warn!("Failed to send delayed datagram: {err:?}");
+                    }
+                    continue;
+                }
+                Some(next.due - now)
+            }
+            None if closed => break,
+            None => None,
+        };
+
+        let received = match (wait, closed) {
+            (Some(wait), true) => {
+                task::sleep(wait).await;
+                continue;
+            }
+            (Some(wait), false) => match timeout(wait, datagrams.recv()).await {
+                Ok(received) => received,
+                Err(_) => continue,
+            },
+            (None, _) => datagrams.recv().await,
+        };
+
+        match received {
+            Ok(delayed) => queue.push(delayed),
+            Err(_) => closed = true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let impairment: Impairment =
+            "seed=42, loss=0.05,latency=100ms,jitter=20,duplication=0.01,reordering=0.5,reorder_delay=1s"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            impairment,
+            Impairment::new(42)
+                .with_loss(0.05)
+                .with_latency(Duration::from_millis(100), Duration::from_millis(20))
+                .with_duplication(0.01)
+                .with_reordering(0.5, Duration::from_secs(1))
+        );
+
+        assert_eq!("".parse::<Impairment>().unwrap(), Impairment::new(0));
+        assert_eq!(
+            "loss".parse::<Impairment>().unwrap_err(),
+            ImpairmentParseError::Syntax("loss".to_owned())
+        );
+        assert_eq!(
+            "delay=1ms".parse::<Impairment>().unwrap_err(),
+            ImpairmentParseError::UnknownKey("delay".to_owned())
+        );
+        assert_eq!(
+            "loss=1.5".parse::<Impairment>().unwrap_err(),
+            ImpairmentParseError::value("loss", "1.5")
+        );
+        assert_eq!(
+            "latency=fast".parse::<Impairment>().unwrap_err(),
+            ImpairmentParseError::value("latency", "fast")
+        );
+    }
+
+    #[test]
+    fn test_schedule() {
+        let impairment = Impairment::new(7)
+            .with_loss(0.2)
+            .with_latency(Duration::from_millis(100), Duration::from_millis(30))
+            .with_duplication(0.1)
+            .with_reordering(0.1, Duration::from_millis(500));
+
+        let mut schedule_a = Schedule::new(impairment.clone());
+        let mut schedule_b = Schedule::new(impairment);
+
+        let mut lost = 0;
+        let mut duplicated = 0;
+        for _ in 0..1000 {
+            let delays = schedule_a.next();
+            assert_eq!(delays, schedule_b.next());
+
+            match delays.len() {
+                0 => lost += 1,
+                1 => (),
+                2 => duplicated += 1,
+                _ => unreachable!(),
+            }
+            for delay in delays {
+                assert!(delay >= Duration::from_millis(70));
+                assert!(delay <= Duration::from_millis(630));
+            }
+        }
+
+        assert!((150..250).contains(&lost));
+        assert!((50..110).contains(&duplicated));
+
+        let mut unimpaired = Schedule::new(Impairment::new(7));
+        for _ in 0..100 {
+            assert_eq!(unimpaired.next(), vec![Duration::ZERO]);
+        }
+    }
+}
@@ -1,5 +1,8 @@
+pub use connection::{CompressionStats, PeerStats};
 pub use header::{Peers, Reliability};
-pub use protocol::MAX_PACKAGE_SIZE;
+pub use impairment::{Impairment, ImpairmentParseError};
+pub use protocol::{MAX_PACKAGE_SIZE, MAX_RELIABLE_PACKAGE_SIZE};
+pub use session::{Authentication, SessionSecret};
 pub use socket::{RecvError, SendError, Socket, MAX_DATAGRAM_SIZE};
 pub use tasks::{
     startup, ConnErrorReceiver, ConnectionError, InPackage, MessageDecoder, OutPackage,
@@ -8,7 +11,9 @@ pub use tasks::{
 
 mod connection;
 mod header;
+mod impairment;
 mod protocol;
 mod record;
+mod session;
 mod socket;
 mod tasks;
@@ -1,28 +1,53 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
 
-use async_std::sync::Arc;
+use async_std::{future::timeout, sync::Arc};
 use thiserror::Error;
-use tracing::{error, trace};
+use tracing::{trace, warn};
 
 use crate::{
     header::{DatagramHeader, HeaderError, HEADER_SIZE},
+    session::{Authentication, Opening, Sealing, SEAL_SIZE},
     socket, SendError, Socket, MAX_DATAGRAM_SIZE,
 };
 
 /// Maximum number of bytes of a single package payload.
-pub const MAX_PACKAGE_SIZE: usize = MAX_DATAGRAM_SIZE - HEADER_SIZE;
+///
+/// Room for the authentication seal is reserved even when datagrams are not
+/// sealed. Whether a datagram is sealed is decided per target only once it is
+/// sent (a session might be established while the datagram waits for a
+/// resend), long after the package was built and fragmented. Both peers must
+/// also agree on the fragment size regardless of their authentication
+/// settings. A single limit costs a few bytes of each unsealed datagram.
+pub const MAX_PACKAGE_SIZE: usize = MAX_DATAGRAM_SIZE - HEADER_SIZE - SEAL_SIZE;
+/// Maximum number of datagrams a single reliable package might be split into.
+pub(crate) const MAX_FRAGMENTS: usize = 255;
+/// Number of bytes at the beginning of each fragment payload used up by the
+/// fragment index and fragment count.
+pub(crate) const FRAGMENT_PREFIX_SIZE: usize = 2;
+/// Maximum number of bytes of a single reliable package payload. Reliable
+/// packages larger than [`MAX_PACKAGE_SIZE`] are transparently split into
+/// multiple datagrams and reassembled on the receiving side.
+pub const MAX_RELIABLE_PACKAGE_SIZE: usize =
+    MAX_FRAGMENTS * (MAX_PACKAGE_SIZE - FRAGMENT_PREFIX_SIZE);
+
+/// Number of attempts to establish a session with a peer.
+const HANDSHAKE_ATTEMPTS: usize = 5;
+/// Time to wait for a reply to a single handshake attempt.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(500);
 
 /// A thin layer over a UDP socket translating between UDP datagrams and
 /// header-payload pairs.
 #[derive(Clone)]
 pub(crate) struct ProtocolSocket {
     socket: Arc<Socket>,
+    authentication: Authentication,
 }
 
 impl ProtocolSocket {
-    pub(crate) fn new(socket: Socket) -> Self {
+    pub(crate) fn new(socket: Socket, authentication: Authentication) -> Self {
         Self {
             socket: Arc::new(socket),
+            authentication,
         }
     }
 
@@ -30,6 +55,9 @@ impl ProtocolSocket {
     ///
     /// The sending is done in parallel.
     ///
+    /// A session is established with the target first if the target requires
+    /// authentication and there is no session yet.
+    ///
     /// # Arguments
     ///
     /// * `buf` - buffer used for datagram construction. First [`HEADER_SIZE`]
@@ -45,15 +73,51 @@ impl ProtocolSocket {
         header: DatagramHeader,
         buf: &mut [u8],
         target: SocketAddr,
-    ) -> Result<(), SendError> {
+    ) -> Result<(), MsgSendError> {
         trace!("Going to send datagram {}", header);
         header.write(buf);
-        self.socket.send(target, buf).await?;
-        Ok(())
+
+        let mut sealed = [0u8; MAX_DATAGRAM_SIZE];
+        loop {
+            match self.authentication.seal(target, buf, &mut sealed) {
+                Sealing::Plain => {
+                    self.socket.send(target, buf).await?;
+                    return Ok(());
+                }
+                Sealing::Sealed(len) => {
+                    self.socket.send(target, &sealed[..len]).await?;
+                    return Ok(());
+                }
+                Sealing::Handshake => self.handshake(target).await?,
+                Sealing::Unavailable => return Err(MsgSendError::NoSession(target)),
+            }
+        }
+    }
+
+    /// Establishes a session with the target.
+    async fn handshake(&self, target: SocketAddr) -> Result<(), MsgSendError> {
+        for attempt in 1..=HANDSHAKE_ATTEMPTS {
+            let Some((hello, established)) = self.authentication.hello(target) else {
+                return Ok(());
+            };
+
+            trace!("Sending handshake attempt {attempt} to {target}.");
+            self.socket.send(target, &hello).await?;
+            // The channel is closed once the session is established.
+            if timeout(HANDSHAKE_TIMEOUT, established.recv()).await.is_ok() {
+                return Ok(());
+            }
+        }
+
+        Err(MsgSendError::Handshake(target))
     }
 
     /// Receive a single datagram.
     ///
+    /// Session handshake datagrams are processed internally and never
+    /// returned. Sequence counters and authentication tags are stripped from
+    /// the returned payload.
+    ///
     /// # Arguments
     ///
     /// * `buf` - the data is written to this buffer. The buffer must be at
@@ -71,7 +135,23 @@ impl ProtocolSocket {
         &self,
         buf: &'a mut [u8],
     ) -> Result<(SocketAddr, DatagramHeader, &'a [u8]), MsgRecvError> {
-        let (stop, source) = self.socket.recv(buf).await.map_err(MsgRecvError::from)?;
+        let (stop, source) = loop {
+            let (stop, source) = self.socket.recv(buf).await.map_err(MsgRecvError::from)?;
+
+            match self.authentication.open(source, &buf[0..stop]) {
+                Opening::Plain => break (stop, source),
+                Opening::Authentic(stop) => break (stop, source),
+                Opening::Handshake(reply) => {
+                    trace!("Received handshake datagram from {source}.");
+                    if let Some(reply) = reply {
+                        if let Err(err) = self.socket.send(source, &reply).await {
+                            warn!("Failed to send handshake reply to {source}: {err:?}");
+                        }
+                    }
+                }
+                Opening::Rejected => return Err(MsgRecvError::Unauthenticated(source)),
+            }
+        };
 
         let header = DatagramHeader::read(&buf[0..stop]).map_err(MsgRecvError::from)?;
         trace!("Received datagram with ID {header}");
@@ -80,10 +160,22 @@ impl ProtocolSocket {
     }
 }
 
+#[derive(Error, Debug)]
+pub(crate) enum MsgSendError {
+    #[error("failed to establish a session with {0}")]
+    Handshake(SocketAddr),
+    #[error("there is no session with {0}")]
+    NoSession(SocketAddr),
+    #[error("error while sending data to the socket")]
+    SendError(#[from] SendError),
+}
+
 #[derive(Error, Debug)]
 pub(crate) enum MsgRecvError {
     #[error(transparent)]
     InvalidHeader(#[from] HeaderError),
+    #[error("datagram from {0} is not authentic")]
+    Unauthenticated(SocketAddr),
     #[error("error while receiving data from the socket")]
     RecvError(#[from] socket::RecvError),
 }
@@ -0,0 +1,793 @@
+//! Optional session layer authenticating all datagrams exchanged with a peer.
+//!
+//! A session between two peers is established with a handshake keyed by a
+//! shared secret:
+//!
+//! 1. The connecting peer (client) sends a `Hello` datagram with a random
+//!    client nonce.
+//!
+//! 2. The accepting peer (server) replies with a `Welcome` datagram with a
+//!    random server nonce.
+//!
+//! Both datagrams are authenticated with the shared secret. Session keys are
+//! derived from the secret and both nonces, there is a distinct key for each
+//! direction of the communication. From then on, a sequence counter and an
+//! authentication tag are appended to each datagram exchanged between the
+//! peers. The counter is covered by the tag and datagrams with already seen
+//! counters are rejected, thus captured datagrams cannot be replayed.
+//!
+//! A session is never replaced by a new handshake once the peer has proven
+//! possession of the session keys by sending an authentic datagram, unless
+//! the peer has been silent for a long time.
+//!
+//! The server accepts each client nonce only once regardless of the source
+//! address of the `Hello` datagram, thus a captured `Hello` cannot be
+//! replayed from other addresses. Sessions of long silent peers are
+//! forgotten.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use ahash::{AHashMap, AHashSet};
+use async_std::channel::{bounded, Receiver, Sender};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::header::{HEADER_SIZE, HELLO_MASK, WELCOME_MASK};
+
+/// Number of bytes of the authentication tag.
+const TAG_SIZE: usize = 16;
+/// Number of bytes of the sequence counter.
+const COUNTER_SIZE: usize = 8;
+/// Number of bytes appended to each datagram sent within a session.
+pub(crate) const SEAL_SIZE: usize = COUNTER_SIZE + TAG_SIZE;
+/// Number of most recent sequence counters tracked by the replay window.
+const WINDOW_SIZE: u64 = 64;
+/// A confirmed session with a peer might be replaced by a new handshake only
+/// after the peer has been silent for at least this long.
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Sessions of peers silent for at least this long are removed on the server.
+/// Sessions never confirmed by the peer are removed after
+/// [`SESSION_IDLE_TIMEOUT`].
+const PEER_IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+/// Minimum time between two consecutive removals of silent peers.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+/// Maximum number of remembered client nonces of accepted handshakes.
+const MAX_USED_NONCES: usize = 1 << 16;
+const NONCE_SIZE: usize = 16;
+const HELLO_SIZE: usize = HEADER_SIZE + NONCE_SIZE + TAG_SIZE;
+const WELCOME_SIZE: usize = HEADER_SIZE + 2 * NONCE_SIZE + TAG_SIZE;
+
+const HELLO_LABEL: &[u8] = b"de-net hello";
+const WELCOME_LABEL: &[u8] = b"de-net welcome";
+const INITIATOR_LABEL: &[u8] = b"de-net initiator";
+const RESPONDER_LABEL: &[u8] = b"de-net responder";
+
+type HmacSha256 = Hmac<Sha256>;
+type Nonce = [u8; NONCE_SIZE];
+
+/// A secret shared among all peers of a session.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SessionSecret([u8; 32]);
+
+impl SessionSecret {
+    pub fn new(secret: [u8; 32]) -> Self {
+        Self(secret)
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.0).unwrap()
+    }
+
+    /// Returns truncated MAC of the concatenation of all `parts`.
+    fn tag(&self, parts: &[&[u8]]) -> [u8; TAG_SIZE] {
+        let mut mac = self.mac();
+        for part in parts {
+            mac.update(part);
+        }
+        truncate(mac)
+    }
+
+    /// Verifies truncated MAC of the concatenation of all `parts`.
+    fn verify(&self, parts: &[&[u8]], tag: &[u8]) -> bool {
+        let mut mac = self.mac();
+        for part in parts {
+            mac.update(part);
+        }
+        mac.verify_truncated_left(tag).is_ok()
+    }
+}
+
+impl fmt::Debug for SessionSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The secret must not leak to logs.
+        write!(f, "SessionSecret(..)")
+    }
+}
+
+/// Authentication of datagrams sent and received over a network stack. See
+/// [`crate::startup`].
+///
+/// The struct might be cheaply cloned, all clones share the same sessions.
+#[derive(Clone)]
+pub struct Authentication(Arc<Mutex<Sessions>>);
+
+impl Authentication {
+    /// No datagram is authenticated.
+    pub fn disabled() -> Self {
+        Self::new(Role::Disabled)
+    }
+
+    /// All datagrams are authenticated. Sessions are established on request
+    /// of the peers, datagrams targeted to peers without a session are not
+    /// sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - secret shared with all peers.
+    pub fn server(secret: SessionSecret) -> Self {
+        Self::new(Role::Server(secret))
+    }
+
+    /// Only datagrams exchanged with peers registered via [`Self::register`]
+    /// are authenticated. A session with a registered peer is established
+    /// just before the first datagram is sent to the peer.
+    pub fn client() -> Self {
+        Self::new(Role::Client)
+    }
+
+    fn new(role: Role) -> Self {
+        Self(Arc::new(Mutex::new(Sessions {
+            role,
+            peers: AHashMap::new(),
+            used_nonces: UsedNonces::default(),
+            last_pruned: Instant::now(),
+        })))
+    }
+
+    /// Registers a peer all datagrams exchanged with which are to be
+    /// authenticated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is not a client authentication.
+    pub fn register(&self, peer: SocketAddr, secret: SessionSecret) {
+        let mut sessions = self.0.lock().unwrap();
+        assert!(matches!(sessions.role, Role::Client));
+        sessions.peers.insert(peer, Peer::new(secret));
+    }
+
+    /// Prepares a datagram for sending.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - recipient of the datagram.
+    ///
+    /// * `data` - full datagram data (including the header).
+    ///
+    /// * `buf` - buffer to which sealed datagram is written. It must be at
+    ///   least [`SEAL_SIZE`] bytes longer than `data`.
+    pub(crate) fn seal(&self, target: SocketAddr, data: &[u8], buf: &mut [u8]) -> Sealing {
+        let mut sessions = self.0.lock().unwrap();
+        let role = sessions.role;
+        if matches!(role, Role::Disabled) {
+            return Sealing::Plain;
+        }
+
+        let Some(peer) = sessions.peers.get_mut(&target) else {
+            return match role {
+                Role::Server(_) => Sealing::Unavailable,
+                Role::Client | Role::Disabled => Sealing::Plain,
+            };
+        };
+
+        match peer.state {
+            PeerState::Established(ref mut session) => {
+                session.sent += 1;
+                let counter = session.sent.to_be_bytes();
+
+                let counter_end = data.len() + COUNTER_SIZE;
+                let len = counter_end + TAG_SIZE;
+                buf[..data.len()].copy_from_slice(data);
+                buf[data.len()..counter_end].copy_from_slice(&counter);
+                let tag = truncated(&session.keys.outgoing, &buf[..counter_end]);
+                buf[counter_end..len].copy_from_slice(&tag);
+                Sealing::Sealed(len)
+            }
+            PeerState::Idle | PeerState::Pending { .. } => match role {
+                Role::Server(_) => Sealing::Unavailable,
+                Role::Client | Role::Disabled => Sealing::Handshake,
+            },
+        }
+    }
+
+    /// Initiates a new handshake with a registered peer.
+    ///
+    /// # Returns
+    ///
+    /// Returns None if the session is already established. Otherwise, it
+    /// returns the `Hello` datagram to be sent to the peer and a receiver
+    /// which is closed once the session is established.
+    pub(crate) fn hello(&self, target: SocketAddr) -> Option<(Vec<u8>, Receiver<()>)> {
+        let mut sessions = self.0.lock().unwrap();
+        let peer = sessions.peers.get_mut(&target)?;
+        if let PeerState::Established(_) = peer.state {
+            return None;
+        }
+
+        let nonce = random_nonce();
+        let (sender, receiver) = bounded(1);
+        peer.state = PeerState::Pending {
+            nonce,
+            _established: sender,
+        };
+
+        let mut datagram = Vec::with_capacity(HELLO_SIZE);
+        datagram.extend([HELLO_MASK, 0, 0, 0]);
+        datagram.extend(nonce);
+        datagram.extend(peer.secret.tag(&[HELLO_LABEL, &nonce]));
+        Some((datagram, receiver))
+    }
+
+    /// Processes a received datagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - sender of the datagram.
+    ///
+    /// * `data` - full datagram data (including the header).
+    pub(crate) fn open(&self, source: SocketAddr, data: &[u8]) -> Opening {
+        let mut sessions = self.0.lock().unwrap();
+
+        let role = sessions.role;
+        if let Role::Disabled = role {
+            return Opening::Plain;
+        }
+
+        match data.first() {
+            Some(&HELLO_MASK) => match role {
+                Role::Server(secret) => sessions.accept(source, secret, data),
+                Role::Client | Role::Disabled => Opening::Rejected,
+            },
+            Some(&WELCOME_MASK) => match role {
+                Role::Client => sessions.confirm(source, data),
+                Role::Server(_) | Role::Disabled => Opening::Rejected,
+            },
+            _ => {
+                let Some(peer) = sessions.peers.get_mut(&source) else {
+                    return match role {
+                        Role::Server(_) => Opening::Rejected,
+                        Role::Client | Role::Disabled => Opening::Plain,
+                    };
+                };
+
+                let PeerState::Established(ref mut session) = peer.state else {
+                    return Opening::Rejected;
+                };
+                if data.len() < HEADER_SIZE + SEAL_SIZE {
+                    return Opening::Rejected;
+                }
+
+                let counter_end = data.len() - TAG_SIZE;
+                let len = counter_end - COUNTER_SIZE;
+                let mut mac = session.keys.incoming.clone();
+                mac.update(&data[..counter_end]);
+                if mac.verify_truncated_left(&data[counter_end..]).is_err() {
+                    return Opening::Rejected;
+                }
+
+                let counter = u64::from_be_bytes(data[len..counter_end].try_into().unwrap());
+                if !session.window.accept(counter) {
+                    return Opening::Rejected;
+                }
+
+                session.last_authentic = Some(Instant::now());
+                Opening::Authentic(len)
+            }
+        }
+    }
+}
+
+/// Result of datagram preparation for sending.
+pub(crate) enum Sealing {
+    /// The datagram is to be sent as is.
+    Plain,
+    /// Sealed datagram of the given length was written to the buffer.
+    Sealed(usize),
+    /// A session must be established with the target first.
+    Handshake,
+    /// The datagram cannot be sent because there is no session with the
+    /// target.
+    Unavailable,
+}
+
+/// Result of processing of a received datagram.
+pub(crate) enum Opening {
+    /// The datagram is not authenticated and might be processed as is.
+    Plain,
+    /// The datagram is authentic. The datagram without the authentication tag
+    /// has the given length.
+    Authentic(usize),
+    /// The datagram was a handshake datagram. The optional reply is to be
+    /// sent back to the source of the datagram.
+    Handshake(Option<Vec<u8>>),
+    /// The datagram was not authentic and must be dropped.
+    Rejected,
+}
+
+struct Sessions {
+    role: Role,
+    peers: AHashMap<SocketAddr, Peer>,
+    /// Client nonces of handshakes accepted from any peer.
+    used_nonces: UsedNonces,
+    last_pruned: Instant,
+}
+
+impl Sessions {
+    /// Processes `Hello` datagram and establishes a new session with the peer.
+    fn accept(&mut self, source: SocketAddr, secret: SessionSecret, data: &[u8]) -> Opening {
+        if data.len() != HELLO_SIZE {
+            return Opening::Rejected;
+        }
+
+        let client_nonce: Nonce = data[HEADER_SIZE..HEADER_SIZE + NONCE_SIZE]
+            .try_into()
+            .unwrap();
+        let tag = &data[HEADER_SIZE + NONCE_SIZE..];
+        if !secret.verify(&[HELLO_LABEL, &client_nonce], tag) {
+            return Opening::Rejected;
+        }
+
+        self.prune();
+        if self.used_nonces.contains(&client_nonce) {
+            // Replayed Hello would otherwise reset the session or establish
+            // a new one with a spoofed source address.
+            return Opening::Rejected;
+        }
+
+        let peer = self
+            .peers
+            .entry(source)
+            .or_insert_with(|| Peer::new(secret));
+        // Anybody knowing the secret could otherwise take over the session
+        // of a connected peer.
+        if let PeerState::Established(ref session) = peer.state {
+            if session.is_active() {
+                return Opening::Rejected;
+            }
+        }
+        self.used_nonces.insert(client_nonce);
+
+        let server_nonce = random_nonce();
+        peer.state = PeerState::Established(Box::new(Session::new(Keys::derive(
+            secret,
+            &client_nonce,
+            &server_nonce,
+            false,
+        ))));
+
+        let mut reply = Vec::with_capacity(WELCOME_SIZE);
+        reply.extend([WELCOME_MASK, 0, 0, 0]);
+        reply.extend(client_nonce);
+        reply.extend(server_nonce);
+        reply.extend(secret.tag(&[WELCOME_LABEL, &client_nonce, &server_nonce]));
+        Opening::Handshake(Some(reply))
+    }
+
+    /// Removes sessions of long silent peers. This is done at most once per
+    /// [`PRUNE_INTERVAL`].
+    fn prune(&mut self) {
+        if self.last_pruned.elapsed() < PRUNE_INTERVAL {
+            return;
+        }
+        self.last_pruned = Instant::now();
+
+        self.peers.retain(|_, peer| match peer.state {
+            PeerState::Established(ref session) => !session.is_stale(),
+            PeerState::Idle | PeerState::Pending { .. } => false,
+        });
+    }
+
+    /// Processes `Welcome` datagram and establishes the pending session with
+    /// the peer.
+    fn confirm(&mut self, source: SocketAddr, data: &[u8]) -> Opening {
+        if data.len() != WELCOME_SIZE {
+            return Opening::Rejected;
+        }
+        let Some(peer) = self.peers.get_mut(&source) else {
+            return Opening::Rejected;
+        };
+        let PeerState::Pending { nonce, .. } = peer.state else {
+            return Opening::Rejected;
+        };
+
+        let client_nonce = &data[HEADER_SIZE..HEADER_SIZE + NONCE_SIZE];
+        let server_nonce = &data[HEADER_SIZE + NONCE_SIZE..HEADER_SIZE + 2 * NONCE_SIZE];
+        let tag = &data[HEADER_SIZE + 2 * NONCE_SIZE..];
+        if client_nonce != nonce
+            || !peer
+                .secret
+                .verify(&[WELCOME_LABEL, client_nonce, server_nonce], tag)
+        {
+            return Opening::Rejected;
+        }
+
+        // Dropping the pending state closes the channel and thus notifies
+        // the waiting sender.
+        peer.state = PeerState::Established(Box::new(Session::new(Keys::derive(
+            peer.secret,
+            client_nonce,
+            server_nonce,
+            true,
+        ))));
+        Opening::Handshake(None)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Role {
+    Disabled,
+    Server(SessionSecret),
+    Client,
+}
+
+struct Peer {
+    secret: SessionSecret,
+    state: PeerState,
+}
+
+impl Peer {
+    fn new(secret: SessionSecret) -> Self {
+        Self {
+            secret,
+            state: PeerState::Idle,
+        }
+    }
+}
+
+/// Set of client nonces bounded to [`MAX_USED_NONCES`] most recently
+/// inserted nonces.
+#[derive(Default)]
+struct UsedNonces {
+    set: AHashSet<Nonce>,
+    order: VecDeque<Nonce>,
+}
+
+impl UsedNonces {
+    fn contains(&self, nonce: &Nonce) -> bool {
+        self.set.contains(nonce)
+    }
+
+    fn insert(&mut self, nonce: Nonce) {
+        if !self.set.insert(nonce) {
+            return;
+        }
+
+        self.order.push_back(nonce);
+        if self.order.len() > MAX_USED_NONCES {
+            let oldest = self.order.pop_front().unwrap();
+            self.set.remove(&oldest);
+        }
+    }
+}
+
+enum PeerState {
+    Idle,
+    Pending {
+        nonce: Nonce,
+        /// The channel is closed once the state is left.
+        _established: Sender<()>,
+    },
+    Established(Box<Session>),
+}
+
+struct Session {
+    keys: Keys,
+    created: Instant,
+    /// Sequence counter of the last datagram sent to the peer.
+    sent: u64,
+    window: ReplayWindow,
+    /// Time of the last authentic datagram received from the peer. None if
+    /// the peer has not yet proven possession of the session keys.
+    last_authentic: Option<Instant>,
+}
+
+impl Session {
+    fn new(keys: Keys) -> Self {
+        Self {
+            keys,
+            created: Instant::now(),
+            sent: 0,
+            window: ReplayWindow::default(),
+            last_authentic: None,
+        }
+    }
+
+    /// Returns true if the peer has recently proven possession of the
+    /// session keys.
+    fn is_active(&self) -> bool {
+        self.last_authentic
+            .is_some_and(|time| time.elapsed() < SESSION_IDLE_TIMEOUT)
+    }
+
+    /// Returns true if the peer has been silent for so long that the session
+    /// might be forgotten.
+    fn is_stale(&self) -> bool {
+        match self.last_authentic {
+            Some(time) => time.elapsed() >= PEER_IDLE_TIMEOUT,
+            None => self.created.elapsed() >= SESSION_IDLE_TIMEOUT,
+        }
+    }
+}
+
+/// Sliding window of sequence counters of received datagrams.
+#[derive(Default)]
+struct ReplayWindow {
+    /// Highest received counter. Counters start at 1, thus 0 means that
+    /// nothing has been received yet.
+    highest: u64,
+    /// N-th bit is set if counter `highest - N` has been received.
+    received: u64,
+}
+
+impl ReplayWindow {
+    /// Marks the counter as received. Returns false if the counter has
+    /// already been received or if it is too old to be tracked.
+    fn accept(&mut self, counter: u64) -> bool {
+        if counter == 0 {
+            return false;
+        }
+
+        if counter > self.highest {
+            let shift = counter - self.highest;
+            self.received = if shift >= WINDOW_SIZE {
+                0
+            } else {
+                self.received << shift
+            };
+            self.received |= 1;
+            self.highest = counter;
+            return true;
+        }
+
+        let offset = self.highest - counter;
+        if offset >= WINDOW_SIZE {
+            return false;
+        }
+
+        let bit = 1 << offset;
+        if self.received & bit != 0 {
+            return false;
+        }
+        self.received |= bit;
+        true
+    }
+}
+
+struct Keys {
+    outgoing: HmacSha256,
+    incoming: HmacSha256,
+}
+
+impl Keys {
+    /// # Arguments
+    ///
+    /// * `initiator` - true on the side which initiated the handshake.
+    fn derive(
+        secret: SessionSecret,
+        client_nonce: &[u8],
+        server_nonce: &[u8],
+        initiator: bool,
+    ) -> Self {
+        let derive = |label: &[u8]| {
+            let mut mac = secret.mac();
+            mac.update(label);
+            mac.update(client_nonce);
+            mac.update(server_nonce);
+            HmacSha256::new_from_slice(&mac.finalize().into_bytes()).unwrap()
+        };
+
+        let initiator_key = derive(INITIATOR_LABEL);
+        let responder_key = derive(RESPONDER_LABEL);
+        if initiator {
+            Self {
+                outgoing: initiator_key,
+                incoming: responder_key,
+            }
+        } else {
+            Self {
+                outgoing: responder_key,
+                incoming: initiator_key,
+            }
+        }
+    }
+}
+
+fn truncated(key: &HmacSha256, data: &[u8]) -> [u8; TAG_SIZE] {
+    let mut mac = key.clone();
+    mac.update(data);
+    truncate(mac)
+}
+
+fn truncate(mac: HmacSha256) -> [u8; TAG_SIZE] {
+    mac.finalize().into_bytes()[..TAG_SIZE].try_into().unwrap()
+}
+
+fn random_nonce() -> Nonce {
+    let mut nonce = [0; NONCE_SIZE];
+    getrandom::getrandom(&mut nonce).expect("Failed to generate a random nonce.");
+    nonce
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session() {
+        let server_addr: SocketAddr = "127.0.0.1:8082".parse().unwrap();
+        let client_addr: SocketAddr = "127.0.0.1:1111".parse().unwrap();
+        let secret = SessionSecret::new([7; 32]);
+
+        let server = Authentication::server(secret);
+        let client = Authentication::client();
+        let mut buf = [0u8; 64];
+
+        let data = [0u8, 0, 0, 1, 42];
+        assert!(matches!(
+            server.seal(client_addr, &data, &mut buf),
+            Sealing::Unavailable
+        ));
+        assert!(matches!(server.open(client_addr, &data), Opening::Rejected));
+        assert!(matches!(
+            client.seal(server_addr, &data, &mut buf),
+            Sealing::Plain
+        ));
+
+        client.register(server_addr, secret);
+        assert!(matches!(
+            client.seal(server_addr, &data, &mut buf),
+            Sealing::Handshake
+        ));
+
+        let (hello, _) = client.hello(server_addr).unwrap();
+        let Opening::Handshake(Some(welcome)) = server.open(client_addr, &hello) else {
+            panic!("Hello not accepted.");
+        };
+        // Replayed Hello.
+        assert!(matches!(
+            server.open(client_addr, &hello),
+            Opening::Rejected
+        ));
+        // Hello replayed from a spoofed address.
+        assert!(matches!(
+            server.open("127.0.0.1:3333".parse().unwrap(), &hello),
+            Opening::Rejected
+        ));
+
+        // Welcome got lost, the client retries the handshake.
+        drop(welcome);
+        let (hello, established) = client.hello(server_addr).unwrap();
+        let Opening::Handshake(Some(welcome)) = server.open(client_addr, &hello) else {
+            panic!("Repeated Hello not accepted.");
+        };
+
+        assert!(!established.is_closed());
+        assert!(matches!(
+            client.open(server_addr, &welcome),
+            Opening::Handshake(None)
+        ));
+        assert!(established.is_closed());
+        assert!(client.hello(server_addr).is_none());
+
+        let Sealing::Sealed(len) = client.seal(server_addr, &data, &mut buf) else {
+            panic!("Datagram not sealed.");
+        };
+        assert_eq!(len, data.len() + SEAL_SIZE);
+        assert_eq!(&buf[..data.len()], &data);
+        let first = buf[..len].to_vec();
+
+        let Sealing::Sealed(len) = client.seal(server_addr, &data, &mut buf) else {
+            panic!("Datagram not sealed.");
+        };
+        assert!(matches!(
+            server.open(client_addr, &buf[..len]),
+            Opening::Authentic(5)
+        ));
+        // Replayed datagram.
+        assert!(matches!(
+            server.open(client_addr, &buf[..len]),
+            Opening::Rejected
+        ));
+        // Reordered datagram.
+        assert!(matches!(
+            server.open(client_addr, &first),
+            Opening::Authentic(5)
+        ));
+        assert!(matches!(
+            server.open(client_addr, &first),
+            Opening::Rejected
+        ));
+
+        // Handshake of anybody knowing the secret must not replace the
+        // confirmed session.
+        let intruder = Authentication::client();
+        intruder.register(server_addr, secret);
+        let (hello, _) = intruder.hello(server_addr).unwrap();
+        assert!(matches!(
+            server.open(client_addr, &hello),
+            Opening::Rejected
+        ));
+        assert!(matches!(
+            server.open(client_addr, &buf[..len]),
+            Opening::Rejected
+        ));
+        // Reflected datagram.
+        assert!(matches!(
+            client.open(server_addr, &buf[..len]),
+            Opening::Rejected
+        ));
+
+        buf[4] = 43;
+        assert!(matches!(
+            server.open(client_addr, &buf[..len]),
+            Opening::Rejected
+        ));
+
+        let Sealing::Sealed(len) = server.seal(client_addr, &data, &mut buf) else {
+            panic!("Datagram not sealed.");
+        };
+        assert!(matches!(
+            client.open(server_addr, &buf[..len]),
+            Opening::Authentic(5)
+        ));
+
+        let other = Authentication::client();
+        other.register(server_addr, SessionSecret::new([8; 32]));
+        let (hello, _) = other.hello(server_addr).unwrap();
+        assert!(matches!(
+            server.open("127.0.0.1:2222".parse().unwrap(), &hello),
+            Opening::Rejected
+        ));
+    }
+
+    #[test]
+    fn test_used_nonces() {
+        let mut nonces = UsedNonces::default();
+        nonces.insert([1; NONCE_SIZE]);
+        assert!(nonces.contains(&[1; NONCE_SIZE]));
+        assert!(!nonces.contains(&[2; NONCE_SIZE]));
+
+        for i in 0..MAX_USED_NONCES {
+            let mut nonce = [0; NONCE_SIZE];
+            nonce[..8].copy_from_slice(&(i as u64).to_be_bytes());
+            nonces.insert(nonce);
+        }
+        assert!(!nonces.contains(&[1; NONCE_SIZE]));
+        assert_eq!(nonces.set.len(), MAX_USED_NONCES);
+        assert_eq!(nonces.order.len(), MAX_USED_NONCES);
+    }
+
+    #[test]
+    fn test_replay_window() {
+        let mut window = ReplayWindow::default();
+        assert!(!window.accept(0));
+        assert!(window.accept(1));
+        assert!(!window.accept(1));
+        assert!(window.accept(3));
+        assert!(window.accept(2));
+        assert!(!window.accept(2));
+
+        assert!(window.accept(100));
+        assert!(!window.accept(3));
+        assert!(window.accept(37));
+        assert!(!window.accept(36));
+        assert!(!window.accept(37));
+        assert!(window.accept(99));
+    }
+}
@@ -1,10 +1,14 @@
 use std::{
     io,
     net::{IpAddr, Ipv4Addr},
+    sync::Arc,
 };
 
 use async_std::net::{SocketAddr, UdpSocket};
 use thiserror::Error;
+use tracing::info;
+
+use crate::impairment::{Impairer, Impairment};
 
 /// Maximum size of a UDP datagram which might be sent by this crate.
 ///
@@ -17,8 +21,9 @@ pub const MAX_DATAGRAM_SIZE: usize = 508;
 /// This struct represents a low level network socket. The socket is based on
 /// UDP and thus provides unreliable and unordered means of data delivery.
 pub struct Socket {
-    socket: UdpSocket,
+    socket: Arc<UdpSocket>,
     port: u16,
+    impairer: Option<Impairer>,
 }
 
 impl Socket {
@@ -37,11 +42,23 @@ impl Socket {
         }
 
         Ok(Self {
-            socket,
+            socket: Arc::new(socket),
             port: obtained_port,
+            impairer: None,
         })
     }
 
+    /// Simulates degraded network conditions on all datagrams subsequently
+    /// sent via the socket. This is intended for testing only.
+    pub fn with_impairment(mut self, impairment: Impairment) -> Self {
+        info!(
+            "Simulating network impairment on port {}: {impairment:?}",
+            self.port
+        );
+        self.impairer = Some(Impairer::new(impairment, Arc::clone(&self.socket)));
+        self
+    }
+
     pub fn port(&self) -> u16 {
         self.port
     }
@@ -66,6 +83,9 @@ impl Socket {
 
     /// Send data to a single target.
     ///
+    /// If the socket is impaired (see [`Self::with_impairment`]), the data
+    /// might be dropped, duplicated or sent later.
+    ///
     /// # Panics
     ///
     /// This method panics if `data` have more than [`MAX_DATAGRAM_SIZE`]
@@ -79,6 +99,21 @@ impl Socket {
             );
         }
 
+        let Some(impairer) = self.impairer.as_ref() else {
+            return self.send_now(target, data).await;
+        };
+
+        for delay in impairer.delays() {
+            if delay.is_zero() {
+                self.send_now(target, data).await?;
+            } else {
+                impairer.delay(target, data, delay);
+            }
+        }
+        Ok(())
+    }
+
+    async fn send_now(&self, target: SocketAddr, data: &[u8]) -> Result<(), SendError> {
         let n = self
             .socket
             .send_to(data, target)
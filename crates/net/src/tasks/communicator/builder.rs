@@ -5,7 +5,7 @@ use bincode::{encode_into_slice, error::EncodeError};
 use crate::{
     header::{Peers, Reliability, HEADER_SIZE},
     tasks::communicator::BINCODE_CONF,
-    OutPackage, MAX_DATAGRAM_SIZE,
+    OutPackage, MAX_PACKAGE_SIZE,
 };
 
 /// It cumulatively builds output packages from individual messages.
@@ -79,7 +79,12 @@ impl PackageBuilder {
         match self.push_inner(message, time) {
             Err(EncodeError::UnexpectedEnd) => {
                 self.build_package(false);
-                self.push_inner(message, time)
+                match self.push_inner(message, time) {
+                    Err(EncodeError::UnexpectedEnd) if self.reliability.is_reliable() => {
+                        self.push_large(message)
+                    }
+                    result => result,
+                }
             }
             Err(err) => Err(err),
             Ok(()) => Ok(()),
@@ -95,6 +100,24 @@ impl PackageBuilder {
         Ok(())
     }
 
+    /// Stores a message, which does not fit into a single datagram, as a
+    /// standalone package. The package is fragmented by the networking stack.
+    ///
+    /// The buffer must be empty so that the ordering of messages is kept.
+    fn push_large<E>(&mut self, message: &E) -> Result<(), EncodeError>
+    where
+        E: bincode::Encode,
+    {
+        debug_assert!(self.buffer.empty());
+        self.packages.push_back(OutPackage::encode_single(
+            message,
+            self.reliability,
+            self.peers,
+            self.target,
+        )?);
+        Ok(())
+    }
+
     /// Build and store another package from already buffered data (if there is
     /// any).
     ///
@@ -127,7 +150,7 @@ impl Buffer {
     fn new() -> Self {
         Self {
             birth: None,
-            data: vec![0; MAX_DATAGRAM_SIZE],
+            data: vec![0; HEADER_SIZE + MAX_PACKAGE_SIZE],
             used: HEADER_SIZE,
         }
     }
@@ -156,7 +179,7 @@ impl Buffer {
         let (mut data, used) = if empty {
             (Vec::new(), 0)
         } else {
-            (vec![0; MAX_DATAGRAM_SIZE], HEADER_SIZE)
+            (vec![0; HEADER_SIZE + MAX_PACKAGE_SIZE], HEADER_SIZE)
         };
 
         mem::swap(&mut data, &mut self.data);
@@ -265,4 +288,25 @@ mod tests {
         assert!(packages[3].data_slice().len() >= 128);
         assert!(packages[3].data_slice().len() < 128 * 2);
     }
+
+    #[test]
+    fn test_large_message() {
+        let target = "127.0.0.1:1111".parse::<SocketAddr>().unwrap();
+        let small = [u64::MAX; 4];
+        let large = vec![u64::MAX; 1000];
+
+        let mut builder = PackageBuilder::new(Reliability::SemiOrdered, Peers::Players, target);
+        builder.push(&small, Instant::now()).unwrap();
+        builder.push(&large, Instant::now()).unwrap();
+        builder.push(&small, Instant::now()).unwrap();
+
+        let packages: Vec<OutPackage> = builder.build_all().collect();
+        assert_eq!(packages.len(), 3);
+        assert!(packages[0].data_slice().len() < MAX_PACKAGE_SIZE);
+        assert!(packages[1].data_slice().len() > 8 * 1000);
+        assert!(packages[2].data_slice().len() < MAX_PACKAGE_SIZE);
+
+        let mut builder = PackageBuilder::new(Reliability::Unreliable, Peers::Players, target);
+        assert!(builder.push(&large, Instant::now()).is_err());
+    }
 }
@@ -3,18 +3,56 @@ use std::{net::SocketAddr, ops::Deref};
 use async_std::channel::{Receiver, Sender};
 
 use super::{decode::InPackage, encode::OutPackage};
+use crate::connection::{Compression, CompressionStats, PeerStats, SharedStats};
 
 /// Channel into networking stack tasks, used for data sending.
 ///
 /// The data-sending components of the networking stack are halted when this
 /// channel is closed (dropped).
-pub struct PackageSender(pub(crate) Sender<OutPackage>);
+#[derive(Clone)]
+pub struct PackageSender {
+    sender: Sender<OutPackage>,
+    stats: SharedStats,
+    compression: Compression,
+}
+
+impl PackageSender {
+    pub(crate) fn new(
+        sender: Sender<OutPackage>,
+        stats: SharedStats,
+        compression: Compression,
+    ) -> Self {
+        Self {
+            sender,
+            stats,
+            compression,
+        }
+    }
+
+    /// Returns estimated network conditions of the connection to a peer or
+    /// None if nothing has been sent to the peer recently.
+    ///
+    /// The networking stack does not throttle sent packages. It is up to the
+    /// user to keep within [`PeerStats::budget`], for example by dropping
+    /// unreliable packages which would exceed it.
+    pub fn stats(&self, target: SocketAddr) -> Option<PeerStats> {
+        self.stats.get(target)
+    }
+
+    /// Returns total payload sizes of all packages sent and received by the
+    /// networking stack before and after compression.
+    ///
+    /// Payloads are compressed only when sent to peers which support it.
+    pub fn compression_stats(&self) -> CompressionStats {
+        self.compression.stats()
+    }
+}
 
 impl Deref for PackageSender {
     type Target = Sender<OutPackage>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.sender
     }
 }
 
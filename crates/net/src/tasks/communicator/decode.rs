@@ -1,6 +1,13 @@
-use std::{marker::PhantomData, net::SocketAddr, time::Instant};
+use std::{
+    io::{self, Read},
+    marker::PhantomData,
+    net::SocketAddr,
+    time::Instant,
+};
 
 use bincode::{decode_from_slice, error::DecodeError};
+use flate2::read::DeflateDecoder;
+use thiserror::Error;
 
 use crate::{tasks::communicator::BINCODE_CONF, Peers, Reliability};
 
@@ -64,6 +71,35 @@ impl InPackage {
     }
 }
 
+/// Decompresses a package payload.
+///
+/// # Arguments
+///
+/// * `data` - compressed payload.
+///
+/// * `max_size` - maximum size of the decompressed payload. An error is
+///   returned if the payload decompresses to more bytes.
+pub(crate) fn decompress(data: &[u8], max_size: usize) -> Result<Vec<u8>, DecompressError> {
+    let mut decompressed = Vec::new();
+    DeflateDecoder::new(data)
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut decompressed)?;
+
+    if decompressed.len() > max_size {
+        Err(DecompressError::TooLarge(max_size))
+    } else {
+        Ok(decompressed)
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum DecompressError {
+    #[error("invalid compressed data: {0}")]
+    Invalid(#[from] io::Error),
+    #[error("decompressed payload is larger than {0} bytes")]
+    TooLarge(usize),
+}
+
 /// An iterator which decodes binary input data item by item.
 pub struct MessageDecoder<'a, E>
 where
@@ -1,14 +1,18 @@
-use std::net::SocketAddr;
+use std::{io::Write, net::SocketAddr};
 
 use bincode::{encode_into_std_write, error::EncodeError};
+use flate2::{write::DeflateEncoder, Compression};
 
 use crate::{
     header::{Peers, Reliability, HEADER_SIZE},
-    protocol::MAX_PACKAGE_SIZE,
+    protocol::{MAX_PACKAGE_SIZE, MAX_RELIABLE_PACKAGE_SIZE},
     tasks::communicator::BINCODE_CONF,
-    MAX_DATAGRAM_SIZE,
 };
 
+/// Payloads shorter than this are never compressed because the compression
+/// would hardly save any bytes.
+const MIN_COMPRESSED_SIZE: usize = 64;
+
 /// A package to be send.
 pub struct OutPackage {
     /// First [`HEADER_SIZE`] bytes are reserved for the header. Payload must
@@ -21,6 +25,9 @@ pub struct OutPackage {
 
 impl OutPackage {
     /// Creates a package from a single message.
+    ///
+    /// An error is returned if the encoded message does not fit into a
+    /// package, see [`Self::max_size`].
     pub fn encode_single<E>(
         message: &E,
         reliability: Reliability,
@@ -33,19 +40,35 @@ impl OutPackage {
         let mut data = Vec::with_capacity(HEADER_SIZE + 1);
         data.extend([0; HEADER_SIZE]);
         encode_into_std_write(message, &mut data, BINCODE_CONF)?;
+        if data.len() > HEADER_SIZE + Self::max_size(reliability) {
+            return Err(EncodeError::Other("message is too large for a package"));
+        }
         Ok(Self::new(data, reliability, peers, target))
     }
 
+    /// Maximum payload size of a package sent with a given reliability.
+    ///
+    /// Reliable packages larger than [`MAX_PACKAGE_SIZE`] are split into
+    /// multiple datagrams by the networking stack and reassembled by the
+    /// receiver.
+    pub fn max_size(reliability: Reliability) -> usize {
+        if reliability.is_reliable() {
+            MAX_RELIABLE_PACKAGE_SIZE
+        } else {
+            MAX_PACKAGE_SIZE
+        }
+    }
+
     /// # Panics
     ///
-    /// If `data` is longer than [`MAX_PACKAGE_SIZE`].
+    /// If `data` is longer than [`Self::max_size`].
     pub fn from_slice(
         data: &[u8],
         reliability: Reliability,
         peers: Peers,
         target: SocketAddr,
     ) -> Self {
-        assert!(data.len() <= MAX_PACKAGE_SIZE);
+        assert!(data.len() <= Self::max_size(reliability));
 
         let mut full_data = Vec::with_capacity(HEADER_SIZE + data.len());
         full_data.extend([0; HEADER_SIZE]);
@@ -67,7 +90,7 @@ impl OutPackage {
     ///
     /// * If data length is smaller or equal to header size..
     ///
-    /// * If data is longer than [`MAX_DATAGRAM_SIZE`].
+    /// * If data payload is longer than [`Self::max_size`].
     pub(super) fn new(
         data: Vec<u8>,
         reliability: Reliability,
@@ -75,7 +98,7 @@ impl OutPackage {
         target: SocketAddr,
     ) -> Self {
         assert!(data.len() > HEADER_SIZE);
-        assert!(data.len() <= MAX_DATAGRAM_SIZE);
+        assert!(data.len() <= HEADER_SIZE + Self::max_size(reliability));
         Self {
             data,
             reliability,
@@ -92,20 +115,71 @@ impl OutPackage {
         self.data
     }
 
+    /// Returns package data (see [`Self::data`]) with compressed payload.
+    ///
+    /// None is returned if the payload is too short to be compressed or if the
+    /// compression does not make it any shorter.
+    pub(crate) fn compressed(&self) -> Option<Vec<u8>> {
+        let payload = self.data_slice();
+        if payload.len() < MIN_COMPRESSED_SIZE {
+            return None;
+        }
+
+        let mut data = Vec::with_capacity(self.data.len());
+        data.extend([0; HEADER_SIZE]);
+        let mut encoder = DeflateEncoder::new(data, Compression::fast());
+        encoder.write_all(payload).unwrap();
+        let data = encoder.finish().unwrap();
+
+        if data.len() < self.data.len() {
+            Some(data)
+        } else {
+            None
+        }
+    }
+
     /// Returns slice to the payload part (without header) of the data.
-    pub(crate) fn data_slice(&self) -> &[u8] {
+    pub fn data_slice(&self) -> &[u8] {
         &self.data[HEADER_SIZE..]
     }
 
-    pub(crate) fn reliability(&self) -> Reliability {
+    pub fn reliability(&self) -> Reliability {
         self.reliability
     }
 
-    pub(crate) fn peers(&self) -> Peers {
+    pub fn peers(&self) -> Peers {
         self.peers
     }
 
-    pub(crate) fn target(&self) -> SocketAddr {
+    pub fn target(&self) -> SocketAddr {
         self.target
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::communicator::decompress;
+
+    #[test]
+    fn test_compressed() {
+        let target = "127.0.0.1:1111".parse::<SocketAddr>().unwrap();
+
+        let package =
+            OutPackage::encode_single(&[1u8; 16], Reliability::Unreliable, Peers::Players, target)
+                .unwrap();
+        assert!(package.compressed().is_none());
+
+        let message: Vec<u32> = (0..100).map(|i| i % 7).collect();
+        let package =
+            OutPackage::encode_single(&message, Reliability::Unordered, Peers::Players, target)
+                .unwrap();
+        let compressed = package.compressed().unwrap();
+        assert!(compressed.len() < package.data_slice().len());
+        assert_eq!(
+            decompress(&compressed[HEADER_SIZE..], MAX_PACKAGE_SIZE).unwrap(),
+            package.data_slice()
+        );
+        assert!(decompress(&compressed[HEADER_SIZE..], 10).is_err());
+    }
+}
@@ -48,6 +48,10 @@ pub(super) async fn run(
                 warn!("Invalid datagram received on port {port}: {err:?}");
                 continue;
             }
+            Err(err @ MsgRecvError::Unauthenticated(_)) => {
+                warn!("Datagram dropped on port {port}: {err}");
+                continue;
+            }
             Err(err @ MsgRecvError::RecvError(_)) => {
                 error!("Data receiving failed on port {port}: {err:?}");
                 break;
@@ -1,12 +1,12 @@
 use std::net::SocketAddr;
 
 use async_std::channel::Receiver;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
     header::{DatagramHeader, HEADER_SIZE},
-    protocol::ProtocolSocket,
-    MAX_DATAGRAM_SIZE, MAX_PACKAGE_SIZE,
+    protocol::{MsgSendError, ProtocolSocket},
+    MAX_PACKAGE_SIZE,
 };
 
 pub(crate) struct OutDatagram {
@@ -44,10 +44,10 @@ impl OutDatagram {
     ///
     /// * If `data` length is smaller or equal to [`HEADER_SIZE`].
     ///
-    /// * If `data` is larger than [`MAX_DATAGRAM_SIZE`].
+    /// * If `data` payload is larger than [`MAX_PACKAGE_SIZE`].
     pub(crate) fn new(header: DatagramHeader, data: Vec<u8>, target: SocketAddr) -> Self {
         assert!(data.len() > HEADER_SIZE);
-        assert!(data.len() <= MAX_DATAGRAM_SIZE);
+        assert!(data.len() <= HEADER_SIZE + MAX_PACKAGE_SIZE);
 
         Self {
             header,
@@ -64,12 +64,19 @@ pub(super) async fn run(port: u16, datagrams: Receiver<OutDatagram>, socket: Pro
         let Ok(mut datagram) = datagrams.recv().await else {
             break;
         };
-        if let Err(err) = socket
+        match socket
             .send(datagram.header, &mut datagram.data, datagram.target)
             .await
         {
-            error!("Error while sending a datagram: {err:?}");
-            break;
+            Ok(()) => (),
+            // Reliably sent datagrams are redelivered later.
+            Err(err @ (MsgSendError::Handshake(_) | MsgSendError::NoSession(_))) => {
+                warn!("Datagram not sent from port {port}: {err}");
+            }
+            Err(err @ MsgSendError::SendError(_)) => {
+                error!("Error while sending a datagram: {err:?}");
+                break;
+            }
         }
     }
 
@@ -0,0 +1,466 @@
+use std::{
+    mem::size_of,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use thiserror::Error;
+
+use crate::{
+    header::{PackageHeader, PackageId, HEADER_SIZE},
+    protocol::{FRAGMENT_PREFIX_SIZE, MAX_FRAGMENTS, MAX_RELIABLE_PACKAGE_SIZE},
+    record::DeliveryRecord,
+    MAX_PACKAGE_SIZE,
+};
+
+/// Maximum number of payload bytes of a single fragment.
+const MAX_FRAGMENT_SIZE: usize = MAX_PACKAGE_SIZE - FRAGMENT_PREFIX_SIZE;
+/// Maximum number of bytes (see [`Partial::bytes`]) of incomplete packages
+/// buffered for a single source.
+const MAX_SOURCE_BYTES: usize = 2 * (MAX_RELIABLE_PACKAGE_SIZE + Partial::overhead(MAX_FRAGMENTS));
+/// Maximum number of bytes of incomplete packages buffered for all sources
+/// combined.
+const MAX_TOTAL_BYTES: usize = 32 * MAX_SOURCE_BYTES;
+/// Maximum number of incomplete packages buffered for a single source.
+const MAX_SOURCE_PARTIALS: usize = 16;
+/// Incomplete packages are dropped after this time since reception of their
+/// first fragment.
+const MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Splits package payload into fragment datagram payloads.
+///
+/// Each of the returned vectors starts with [`HEADER_SIZE`] bytes reserved for
+/// the header, followed by the fragment index, the fragment count and finally
+/// the fragment data.
+///
+/// The fragments must be sent with consecutive package IDs in the order of the
+/// returned vectors.
+///
+/// # Panics
+///
+/// If `data` is longer than [`MAX_RELIABLE_PACKAGE_SIZE`].
+pub(super) fn split(data: &[u8]) -> impl Iterator<Item = Vec<u8>> + '_ {
+    assert!(data.len() <= MAX_RELIABLE_PACKAGE_SIZE);
+
+    let chunks = data.chunks(MAX_FRAGMENT_SIZE);
+    let count = chunks.len() as u8;
+    chunks.enumerate().map(move |(index, chunk)| {
+        let mut fragment = Vec::with_capacity(HEADER_SIZE + FRAGMENT_PREFIX_SIZE + chunk.len());
+        fragment.extend([0; HEADER_SIZE]);
+        fragment.extend([index as u8, count]);
+        fragment.extend(chunk);
+        fragment
+    })
+}
+
+/// Reassembler of fragmented packages.
+///
+/// Fragments of a package are expected to have consecutive package IDs, thus
+/// ID of the first fragment is used to identify the package.
+pub(super) struct Fragments {
+    sources: AHashMap<SocketAddr, Source>,
+    /// Number of buffered bytes from all sources.
+    bytes: usize,
+}
+
+impl Fragments {
+    pub(super) fn new() -> Self {
+        Self {
+            sources: AHashMap::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Processes a single delivered fragment datagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - sender of the fragment.
+    ///
+    /// * `record` - delivery record of the fragment datagram.
+    ///
+    /// * `data` - payload of the fragment datagram (including the fragment
+    ///   prefix).
+    ///
+    /// # Returns
+    ///
+    /// Payload of the whole package once its last fragment is received. None
+    /// if some fragments of the package are still missing.
+    ///
+    /// # Panics
+    ///
+    /// If the datagram is not a fragment.
+    pub(super) fn push(
+        &mut self,
+        source: SocketAddr,
+        record: &DeliveryRecord,
+        data: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, FragmentError> {
+        let header = record.header();
+        assert!(header.is_fragment());
+
+        if data.len() < FRAGMENT_PREFIX_SIZE {
+            return Err(FragmentError::Invalid);
+        }
+        let (index, count) = (data[0] as usize, data[1] as usize);
+        if index >= count || count > MAX_FRAGMENTS {
+            return Err(FragmentError::Invalid);
+        }
+
+        let size = data.len() - FRAGMENT_PREFIX_SIZE;
+        // All but the last fragment are of the maximum size (see `split`).
+        let expected = if index + 1 < count {
+            size == MAX_FRAGMENT_SIZE
+        } else {
+            size > 0 && size <= MAX_FRAGMENT_SIZE
+        };
+        if !expected {
+            return Err(FragmentError::Invalid);
+        }
+
+        let key = header.id().preceding(index as u32);
+        let source_state = self.sources.entry(source).or_insert_with(Source::new);
+
+        let new = !source_state.partials.contains_key(&key);
+        // Even a package with a single received fragment occupies memory
+        // proportional to its fragment count.
+        let charge = if new {
+            size + Partial::overhead(count)
+        } else {
+            size
+        };
+        if (new && source_state.partials.len() >= MAX_SOURCE_PARTIALS)
+            || source_state.bytes + charge > MAX_SOURCE_BYTES
+            || self.bytes + charge > MAX_TOTAL_BYTES
+        {
+            if let Some(partial) = source_state.partials.remove(&key) {
+                source_state.bytes -= partial.bytes;
+                self.bytes -= partial.bytes;
+            }
+            self.remove_empty(source);
+            return Err(FragmentError::Limit);
+        }
+
+        let partial = source_state
+            .partials
+            .entry(key)
+            .or_insert_with(|| Partial::new(record.time(), header, count));
+
+        if !partial.matches(header, count) || partial.parts[index].is_some() {
+            let partial = source_state.partials.remove(&key).unwrap();
+            source_state.bytes -= partial.bytes;
+            self.bytes -= partial.bytes;
+            self.remove_empty(source);
+            return Err(FragmentError::Inconsistent);
+        }
+
+        let mut data = data;
+        data.drain(..FRAGMENT_PREFIX_SIZE);
+        partial.parts[index] = Some(data);
+        partial.bytes += charge;
+        partial.missing -= 1;
+        source_state.bytes += charge;
+        self.bytes += charge;
+
+        if partial.missing > 0 {
+            return Ok(None);
+        }
+
+        let partial = source_state.partials.remove(&key).unwrap();
+        source_state.bytes -= partial.bytes;
+        self.bytes -= partial.bytes;
+        self.remove_empty(source);
+        Ok(Some(partial.join()))
+    }
+
+    /// Drops all incomplete packages older than a time limit.
+    ///
+    /// # Returns
+    ///
+    /// Number of dropped packages.
+    pub(super) fn clean(&mut self, now: Instant) -> usize {
+        let mut dropped = 0;
+        let mut bytes = 0;
+
+        self.sources.retain(|_, source| {
+            source.partials.retain(|_, partial| {
+                if now.saturating_duration_since(partial.created) > MAX_AGE {
+                    dropped += 1;
+                    bytes += partial.bytes;
+                    source.bytes -= partial.bytes;
+                    false
+                } else {
+                    true
+                }
+            });
+            !source.partials.is_empty()
+        });
+
+        self.bytes -= bytes;
+        dropped
+    }
+
+    fn remove_empty(&mut self, source: SocketAddr) {
+        if self
+            .sources
+            .get(&source)
+            .is_some_and(|s| s.partials.is_empty())
+        {
+            self.sources.remove(&source);
+        }
+    }
+}
+
+struct Source {
+    partials: AHashMap<PackageId, Partial>,
+    /// Number of buffered bytes from the source.
+    bytes: usize,
+}
+
+impl Source {
+    fn new() -> Self {
+        Self {
+            partials: AHashMap::new(),
+            bytes: 0,
+        }
+    }
+}
+
+/// A package whose fragments are being received.
+struct Partial {
+    /// Time of reception of the first fragment.
+    created: Instant,
+    header: PackageHeader,
+    parts: Vec<Option<Vec<u8>>>,
+    missing: usize,
+    /// Number of bytes charged against the buffering limits, i.e. received
+    /// fragment data and [`Self::overhead`].
+    bytes: usize,
+}
+
+impl Partial {
+    fn new(created: Instant, header: PackageHeader, count: usize) -> Self {
+        Self {
+            created,
+            header,
+            parts: vec![None; count],
+            missing: count,
+            bytes: 0,
+        }
+    }
+
+    /// Returns approximate number of bytes occupied by a package with a given
+    /// fragment count excluding the fragment data.
+    const fn overhead(count: usize) -> usize {
+        size_of::<PackageId>() + size_of::<Self>() + count * size_of::<Option<Vec<u8>>>()
+    }
+
+    /// Returns true if a fragment with a given header and fragment count
+    /// belongs to this package.
+    fn matches(&self, header: PackageHeader, count: usize) -> bool {
+        self.parts.len() == count
+            && self.header.reliability() == header.reliability()
+            && self.header.peers() == header.peers()
+            && self.header.is_compressed() == header.is_compressed()
+    }
+
+    fn join(self) -> Vec<u8> {
+        let len = self.parts.iter().flatten().map(Vec::len).sum();
+        let mut data = Vec::with_capacity(len);
+        for part in self.parts {
+            data.extend(part.unwrap());
+        }
+        data
+    }
+}
+
+#[derive(Error, Debug)]
+pub(super) enum FragmentError {
+    #[error("invalid fragment prefix or size")]
+    Invalid,
+    #[error("fragment is inconsistent with previously received fragments")]
+    Inconsistent,
+    #[error("too many bytes of incomplete packages are buffered")]
+    Limit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Peers, Reliability};
+
+    fn fragment_records(start: PackageId, fragments: &[Vec<u8>]) -> Vec<(DeliveryRecord, Vec<u8>)> {
+        let mut id = start;
+        fragments
+            .iter()
+            .map(|fragment| {
+                let header =
+                    PackageHeader::new(Reliability::SemiOrdered, Peers::Players, id).fragmented();
+                id = id.incremented();
+                (
+                    DeliveryRecord::now(header),
+                    fragment[HEADER_SIZE..].to_vec(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_and_join() {
+        let source = "127.0.0.1:1111".parse::<SocketAddr>().unwrap();
+        let data: Vec<u8> = (0..3000).map(|i| (i % 251) as u8).collect();
+
+        let fragments: Vec<Vec<u8>> = split(&data).collect();
+        assert_eq!(fragments.len(), 7);
+        for fragment in &fragments {
+            assert!(fragment.len() <= HEADER_SIZE + MAX_PACKAGE_SIZE);
+        }
+
+        // The first fragment has the maximum ID so that the IDs wrap around.
+        let start: PackageId = 0xffffff.try_into().unwrap();
+        let mut records = fragment_records(start, &fragments);
+        records.swap(0, 5);
+        records.swap(2, 6);
+
+        let mut reassembler = Fragments::new();
+        let last = records.pop().unwrap();
+        for (record, fragment) in records {
+            assert!(reassembler
+                .push(source, &record, fragment)
+                .unwrap()
+                .is_none());
+        }
+        let joined = reassembler.push(source, &last.0, last.1).unwrap().unwrap();
+        assert_eq!(joined, data);
+        assert_eq!(reassembler.bytes, 0);
+        assert!(reassembler.sources.is_empty());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let source = "127.0.0.1:1111".parse::<SocketAddr>().unwrap();
+        let header = PackageHeader::new(
+            Reliability::Unordered,
+            Peers::Server,
+            PackageId::from_bytes(&[0, 0, 8]),
+        )
+        .fragmented();
+        let record = DeliveryRecord::now(header);
+
+        let mut reassembler = Fragments::new();
+        assert!(matches!(
+            reassembler.push(source, &record, vec![1]),
+            Err(FragmentError::Invalid)
+        ));
+        assert!(matches!(
+            reassembler.push(source, &record, vec![3, 3, 1, 2]),
+            Err(FragmentError::Invalid)
+        ));
+        // Empty fragment.
+        assert!(matches!(
+            reassembler.push(source, &record, vec![2, 3]),
+            Err(FragmentError::Invalid)
+        ));
+        // Short non-final fragment.
+        assert!(matches!(
+            reassembler.push(source, &record, vec![1, 3, 1, 2]),
+            Err(FragmentError::Invalid)
+        ));
+
+        assert!(reassembler
+            .push(source, &record, vec![2, 3, 1, 2])
+            .unwrap()
+            .is_none());
+        // Fragment count differs from the previous fragment of the package.
+        let header = PackageHeader::new(
+            Reliability::Unordered,
+            Peers::Server,
+            PackageId::from_bytes(&[0, 0, 9]),
+        )
+        .fragmented();
+        assert!(matches!(
+            reassembler.push(source, &DeliveryRecord::now(header), vec![3, 4, 1, 2]),
+            Err(FragmentError::Inconsistent)
+        ));
+        assert_eq!(reassembler.bytes, 0);
+    }
+
+    #[test]
+    fn test_limits() {
+        let source = "127.0.0.1:1111".parse::<SocketAddr>().unwrap();
+        let data = vec![7; MAX_RELIABLE_PACKAGE_SIZE];
+        let fragments: Vec<Vec<u8>> = split(&data).collect();
+        assert_eq!(fragments.len(), MAX_FRAGMENTS);
+
+        let mut reassembler = Fragments::new();
+        // Leave out the last fragment of each package so that none is
+        // completed.
+        for i in 0..2u32 {
+            let start = (i * MAX_FRAGMENTS as u32).try_into().unwrap();
+            let records = fragment_records(start, &fragments[..MAX_FRAGMENTS - 1]);
+            for (record, fragment) in records {
+                assert!(reassembler
+                    .push(source, &record, fragment)
+                    .unwrap()
+                    .is_none());
+            }
+        }
+
+        // The remaining budget does not cover overhead of another package.
+        let start: PackageId = (2 * MAX_FRAGMENTS as u32).try_into().unwrap();
+        let records = fragment_records(start, &fragments[..1]);
+        let (record, fragment) = records.into_iter().next().unwrap();
+        assert!(matches!(
+            reassembler.push(source, &record, fragment),
+            Err(FragmentError::Limit)
+        ));
+
+        // Other sources are limited separately.
+        let other = "127.0.0.1:1112".parse::<SocketAddr>().unwrap();
+        let records = fragment_records(start, &fragments[..1]);
+        let (record, fragment) = records.into_iter().next().unwrap();
+        assert!(reassembler
+            .push(other, &record, fragment)
+            .unwrap()
+            .is_none());
+
+        assert_eq!(reassembler.clean(Instant::now()), 0);
+        assert_eq!(
+            reassembler.clean(Instant::now() + MAX_AGE + Duration::from_secs(1)),
+            3
+        );
+        assert_eq!(reassembler.bytes, 0);
+        assert!(reassembler.sources.is_empty());
+    }
+
+    #[test]
+    fn test_flood() {
+        let source = "127.0.0.1:1111".parse::<SocketAddr>().unwrap();
+        let mut reassembler = Fragments::new();
+
+        // Only the last out of 255 fragments of each package is sent.
+        let mut limited = false;
+        for i in 0..MAX_SOURCE_PARTIALS as u32 + 1 {
+            let id: PackageId = (i * MAX_FRAGMENTS as u32 + 254).try_into().unwrap();
+            let header =
+                PackageHeader::new(Reliability::SemiOrdered, Peers::Players, id).fragmented();
+            match reassembler.push(source, &DeliveryRecord::now(header), vec![254, 255, 1]) {
+                Ok(None) => (),
+                Err(FragmentError::Limit) => {
+                    limited = true;
+                    break;
+                }
+                _ => panic!("Unexpected result."),
+            }
+        }
+        assert!(limited);
+        assert!(reassembler.bytes <= MAX_SOURCE_BYTES);
+        assert_eq!(
+            reassembler.clean(Instant::now() + MAX_AGE + Duration::from_secs(1)),
+            MAX_SOURCE_PARTIALS
+        );
+        assert_eq!(reassembler.bytes, 0);
+        assert!(reassembler.sources.is_empty());
+    }
+}
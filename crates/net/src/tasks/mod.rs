@@ -53,7 +53,9 @@
 //!
 //! `usender` and `ureceiver` are responsible for sending and reception of user
 //! data. The user communicates with these via [`PackageSender`] and
-//! [`PackageReceiver`] respectively.
+//! [`PackageReceiver`] respectively. Reliable packages too large to fit into a
+//! single datagram are split into fragments by `usender` and reassembled by
+//! `ureceiver`.
 
 use async_std::channel::bounded;
 pub use communicator::{
@@ -67,6 +69,7 @@ use tracing::info;
 use crate::{
     connection::{DeliveryHandler, DispatchHandler},
     protocol::ProtocolSocket,
+    session::Authentication,
     tasks::cancellation::cancellation,
     Socket,
 };
@@ -76,6 +79,7 @@ mod communicator;
 mod confirmer;
 mod dreceiver;
 mod dsender;
+mod fragments;
 mod resender;
 mod sreceiver;
 mod ureceiver;
@@ -96,14 +100,20 @@ const CHANNEL_CAPACITY: usize = 1024;
 /// * `spawn` - async task spawner.
 ///
 /// * `socket` - network communication will happen over this socket.
-pub fn startup<S>(spawn: S, socket: Socket) -> (PackageSender, PackageReceiver, ConnErrorReceiver)
+///
+/// * `authentication` - authentication of datagrams exchanged with the peers.
+pub fn startup<S>(
+    spawn: S,
+    socket: Socket,
+    authentication: Authentication,
+) -> (PackageSender, PackageReceiver, ConnErrorReceiver)
 where
     S: Fn(BoxFuture<'static, ()>),
 {
     let port = socket.port();
     info!("Starting up network stack on port {port}...");
 
-    let protocol_socket = ProtocolSocket::new(socket);
+    let protocol_socket = ProtocolSocket::new(socket, authentication);
 
     let (out_datagrams_sender, out_datagrams_receiver) = bounded(16);
     spawn(Box::pin(dsender::run(
@@ -122,6 +132,8 @@ where
     )));
 
     let dispatch_handler = DispatchHandler::new();
+    let stats = dispatch_handler.stats();
+    let compression = dispatch_handler.compression();
     let (sreceiver_cancellation_sender, sreceiver_cancellation_receiver) = cancellation();
     spawn(Box::pin(sreceiver::run(
         port,
@@ -139,6 +151,7 @@ where
         in_user_datagrams_receiver,
         inputs_sender,
         delivery_handler.clone(),
+        compression.clone(),
     )));
 
     let (outputs_sender, outputs_receiver) = bounded(CHANNEL_CAPACITY);
@@ -168,7 +181,7 @@ where
     )));
 
     (
-        PackageSender(outputs_sender),
+        PackageSender::new(outputs_sender, stats, compression),
         PackageReceiver(inputs_receiver),
         ConnErrorReceiver(errors_receiver),
     )
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_std::{
     channel::{Receiver, Sender},
@@ -6,11 +6,17 @@ use async_std::{
 };
 use tracing::{error, info, trace, warn};
 
-use super::{cancellation::CancellationSender, dreceiver::InPackageDatagram};
+use super::{
+    cancellation::CancellationSender,
+    communicator::{decompress, DecompressError},
+    dreceiver::InPackageDatagram,
+    fragments::Fragments,
+};
 use crate::{
-    connection::{DeliveryHandler, ReceivedIdError},
+    connection::{Compression, DeliveryHandler, ReceivedIdError},
+    header::PackageHeader,
     record::DeliveryRecord,
-    InPackage, MAX_PACKAGE_SIZE,
+    InPackage, OutPackage, MAX_PACKAGE_SIZE,
 };
 
 /// Handler of user datagrams, i.e. datagrams with user data targeted to
@@ -24,12 +30,19 @@ pub(super) async fn run(
     datagrams: Receiver<InPackageDatagram>,
     packages: Sender<InPackage>,
     mut delivery_handler: DeliveryHandler,
+    compression: Compression,
 ) {
     info!("Starting package receiver on port {port}...");
 
     let mut buf = vec![0; MAX_PACKAGE_SIZE];
+    let mut fragments = Fragments::new();
 
     'main: loop {
+        let dropped = fragments.clean(Instant::now());
+        if dropped > 0 {
+            warn!("Dropped {dropped} incomplete fragmented packages on port {port}.");
+        }
+
         let Ok(result) = timeout(Duration::from_millis(500), datagrams.recv()).await else {
             if packages.is_closed() {
                 // This must be here in case of no incoming packages to ensure
@@ -52,12 +65,33 @@ pub(super) async fn run(
             break;
         };
         let record = DeliveryRecord::now(datagram.header);
+        compression.negotiate(datagram.source, datagram.header.supports_compression());
 
         if datagram.header.reliability().is_reliable() {
             let mut guard = delivery_handler.lock().await;
             match guard.received(datagram.source, record, datagram.data, &mut buf) {
                 Ok(deliveries) => {
                     for (record, data) in deliveries {
+                        let data = if record.header().is_fragment() {
+                            match fragments.push(datagram.source, &record, data) {
+                                Ok(Some(data)) => data,
+                                Ok(None) => continue,
+                                Err(err) => {
+                                    warn!("Dropping fragment from {:?}: {err}", datagram.source);
+                                    continue;
+                                }
+                            }
+                        } else {
+                            data
+                        };
+                        let data = match decompressed(&compression, record.header(), data) {
+                            Ok(data) => data,
+                            Err(err) => {
+                                warn!("Dropping package from {:?}: {err}", datagram.source);
+                                continue;
+                            }
+                        };
+
                         let result = packages
                             .send(InPackage::new(
                                 data,
@@ -84,9 +118,17 @@ pub(super) async fn run(
                 }
             }
         } else {
+            let data = match decompressed(&compression, datagram.header, datagram.data) {
+                Ok(data) => data,
+                Err(err) => {
+                    warn!("Dropping package from {:?}: {err}", datagram.source);
+                    continue;
+                }
+            };
+
             let result = packages
                 .send(InPackage::new(
-                    datagram.data,
+                    data,
                     datagram.header.reliability(),
                     datagram.header.peers(),
                     datagram.source,
@@ -101,3 +143,20 @@ pub(super) async fn run(
 
     info!("Package receiver on port {port} finished.");
 }
+
+/// Decompresses package payload if it is compressed and records the package
+/// in compression statistics.
+fn decompressed(
+    compression: &Compression,
+    header: PackageHeader,
+    data: Vec<u8>,
+) -> Result<Vec<u8>, DecompressError> {
+    let received = data.len();
+    let data = if header.is_compressed() {
+        decompress(&data, OutPackage::max_size(header.reliability()))?
+    } else {
+        data
+    };
+    compression.received(data.len(), received);
+    Ok(data)
+}
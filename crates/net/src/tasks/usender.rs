@@ -3,11 +3,11 @@ use std::time::Instant;
 use async_std::channel::{Receiver, Sender};
 use tracing::{error, info};
 
-use super::{cancellation::CancellationSender, dsender::OutDatagram};
+use super::{cancellation::CancellationSender, dsender::OutDatagram, fragments};
 use crate::{
     connection::DispatchHandler,
-    header::{DatagramHeader, PackageHeader, PackageIdRange},
-    OutPackage,
+    header::{DatagramHeader, PackageHeader, PackageIdRange, HEADER_SIZE},
+    OutPackage, MAX_PACKAGE_SIZE,
 };
 
 /// Handler & scheduler of datagram resends.
@@ -21,32 +21,82 @@ pub(super) async fn run(
     info!("Starting package sender on port {port}...");
 
     let mut counter_unreliable = PackageIdRange::counter();
+    let compression = dispatch_handler.compression();
 
-    loop {
+    'main: loop {
         let Ok(package) = packages.recv().await else {
             break;
         };
 
         let time = Instant::now();
         let target = package.target();
+        let reliability = package.reliability();
+        let peers = package.peers();
 
-        let package_id = if package.reliability().is_reliable() {
+        let raw_len = package.data_slice().len();
+        let (data, compressed) = match compression
+            .supported(target)
+            .then(|| package.compressed())
+            .flatten()
+        {
+            Some(data) => (data, true),
+            None => (package.data(), false),
+        };
+        compression.sent(raw_len, data.len() - HEADER_SIZE);
+
+        let package_header = |id| {
+            let header = PackageHeader::new(reliability, peers, id).supporting_compression();
+            if compressed {
+                header.compressed()
+            } else {
+                header
+            }
+        };
+
+        if data.len() - HEADER_SIZE > MAX_PACKAGE_SIZE {
+            for fragment in fragments::split(&data[HEADER_SIZE..]) {
+                let package_id = dispatch_handler.next_package_id(time, target).await;
+                let package_header = package_header(package_id).fragmented();
+
+                dispatch_handler
+                    .sent(time, target, package_header, &fragment[HEADER_SIZE..])
+                    .await;
+
+                let header = DatagramHeader::Package(package_header);
+                if datagrams
+                    .send(OutDatagram::new(header, fragment, target))
+                    .await
+                    .is_err()
+                {
+                    error!("Datagram sender channel on port {port} is unexpectedly closed. ");
+                    break 'main;
+                }
+            }
+
+            continue;
+        }
+
+        let package_id = if reliability.is_reliable() {
             dispatch_handler.next_package_id(time, target).await
         } else {
             counter_unreliable.next().unwrap()
         };
 
-        let package_header = PackageHeader::new(package.reliability(), package.peers(), package_id);
+        let package_header = package_header(package_id);
         let header = DatagramHeader::Package(package_header);
 
-        if package_header.reliability().is_reliable() {
+        if reliability.is_reliable() {
+            dispatch_handler
+                .sent(time, target, package_header, &data[HEADER_SIZE..])
+                .await;
+        } else {
             dispatch_handler
-                .sent(time, target, package_header, package.data_slice())
+                .sent_unreliable(time, target, data.len() - HEADER_SIZE)
                 .await;
         }
 
         let closed = datagrams
-            .send(OutDatagram::new(header, package.data(), target))
+            .send(OutDatagram::new(header, data, target))
             .await
             .is_err();
 
@@ -64,6 +64,12 @@ impl Health {
         self.health.clamp(0., self.max) / self.max
     }
 
+    /// Returns current health. Contrary to [`Self::fraction`], the returned
+    /// value is not clamped.
+    pub fn current(&self) -> f32 {
+        self.health
+    }
+
     /// # Arguments
     ///
     /// * `delta` - amount of change, i.e. by how much is the health increased.
@@ -42,7 +42,13 @@ impl Plugin for FinderPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<PathFinderUpdatedEvent>()
             .add_systems(OnEnter(AppState::InGame), setup_loading)
-            .add_systems(OnEnter(GameState::Playing), setup_playing)
+            .add_systems(
+                OnTransition {
+                    from: GameState::Waiting,
+                    to: GameState::Playing,
+                },
+                setup_playing,
+            )
             .add_systems(OnExit(AppState::InGame), cleanup)
             .add_systems(
                 PostUpdate,
@@ -30,6 +30,36 @@ impl ScheduledPath {
         self.path.waypoints()[0]
     }
 
+    /// Returns the number of path waypoints not yet reached.
+    ///
+    /// The number is the same for the path and for a remaining part of the
+    /// path (see [`Self::remaining`]) scheduled at any point of the current
+    /// path segment.
+    pub fn progress(&self) -> usize {
+        self.current
+    }
+
+    /// Overrides the number of path waypoints not yet reached. This is useful
+    /// to catch up with a path schedule of a remotely simulated entity.
+    ///
+    /// The progress is clamped so that the path is not extended beyond its
+    /// starting point.
+    pub fn set_progress(&mut self, progress: usize) {
+        self.current = progress.min(self.path.waypoints().len() - 1);
+    }
+
+    /// Returns the not yet followed part of the path starting at `position`.
+    pub fn remaining(&self, position: Vec2) -> Path {
+        let mut waypoints = self.path.waypoints()[..self.current.max(1)].to_vec();
+        waypoints.push(position);
+
+        let length = waypoints
+            .windows(2)
+            .map(|segment| segment[0].distance(segment[1]))
+            .sum();
+        Path::new(length, waypoints)
+    }
+
     /// Advances the path schedule by a given distance and returns the
     /// corresponding point on the path.
     ///
@@ -133,6 +163,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_schedule_remaining() {
+        let mut schedule = ScheduledPath::new(Path::new(
+            7.,
+            vec![Vec2::new(4., 6.), Vec2::new(4., 1.), Vec2::new(2., 1.)],
+        ));
+
+        let remaining = schedule.remaining(Vec2::new(3., 1.));
+        assert_eq!(
+            remaining.waypoints(),
+            &[Vec2::new(4., 6.), Vec2::new(4., 1.), Vec2::new(3., 1.)]
+        );
+        assert!((remaining.length() - 6.).abs() < 0.001);
+
+        schedule.advance(Vec2::new(4., 5.), 10.);
+        let remaining = schedule.remaining(Vec2::new(4., 5.5));
+        assert_eq!(
+            remaining.waypoints(),
+            &[Vec2::new(4., 6.), Vec2::new(4., 5.5)]
+        );
+    }
+
+    #[test]
+    fn test_schedule_progress() {
+        let mut schedule = ScheduledPath::new(Path::new(
+            7.,
+            vec![Vec2::new(4., 6.), Vec2::new(4., 1.), Vec2::new(2., 1.)],
+        ));
+        assert_eq!(schedule.progress(), 2);
+        assert_eq!(
+            ScheduledPath::new(schedule.remaining(Vec2::new(3., 1.))).progress(),
+            2
+        );
+
+        schedule.set_progress(1);
+        assert_eq!(schedule.progress(), 1);
+        assert_eq!(
+            schedule.remaining(Vec2::new(4., 3.)).waypoints(),
+            &[Vec2::new(4., 6.), Vec2::new(4., 3.)]
+        );
+
+        schedule.set_progress(7);
+        assert_eq!(schedule.progress(), 2);
+    }
+
     #[test]
     fn test_schedule_project() {
         let schedule = ScheduledPath::new(Path::new(
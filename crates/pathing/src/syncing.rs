@@ -54,8 +54,11 @@ fn send_new_paths(
     mut net_events: EventWriter<ToPlayersEvent>,
 ) {
     for event in path_events.read() {
+        let Some(entity) = net_entities.local_net_id(event.entity()) else {
+            continue;
+        };
         net_events.send(ToPlayersEvent::new(ToPlayers::SetPath {
-            entity: net_entities.local_net_id(event.entity()),
+            entity,
             waypoints: event.path().map(|p| p.try_into().unwrap()),
         }));
     }
@@ -75,9 +75,9 @@ fn despawn_active_local(
         event_writer.send(DespawnActiveEvent(event.0));
 
         if config.multiplayer() {
-            net_events.send(ToPlayersEvent::new(ToPlayers::Despawn {
-                entity: net_entities.local_net_id(event.0),
-            }));
+            if let Some(entity) = net_entities.local_net_id(event.0) {
+                net_events.send(ToPlayersEvent::new(ToPlayers::Despawn { entity }));
+            }
         }
     }
 }
@@ -20,6 +20,10 @@ fn game_end_detection_system(
     conf: Res<GameConfig>,
     counter: Res<ObjectCounter>,
 ) {
+    if conf.locals().is_observer() {
+        return;
+    }
+
     let mut result = None;
 
     let (playable, others) =
@@ -167,8 +167,11 @@ fn spawn_local_active(
         }
 
         if config.multiplayer() {
+            let Some(net_entity) = net_entities.local_net_id(entity) else {
+                continue;
+            };
             net_events.send(ToPlayersEvent::new(ToPlayers::Spawn {
-                entity: net_entities.local_net_id(entity),
+                entity: net_entity,
                 player: event.player,
                 object_type: event.object_type,
                 transform: event.transform.into(),
@@ -178,10 +181,20 @@ fn spawn_local_active(
 }
 
 fn spawn_remote_active(
+    mut commands: Commands,
+    config: Res<GameConfig>,
     mut event_reader: EventReader<NetRecvSpawnActiveEvent>,
     mut event_writer: EventWriter<SpawnActiveEvent>,
 ) {
     for event in event_reader.read() {
+        if event.is_local() {
+            let mut entity_commands = commands.entity(event.entity());
+            entity_commands.insert(Local);
+            if config.locals().is_playable(event.player()) || cfg!(feature = "godmode") {
+                entity_commands.insert(Playable);
+            }
+        }
+
         event_writer.send(SpawnActiveEvent::new(
             event.entity(),
             event.object_type(),